@@ -0,0 +1,31 @@
+#!/usr/bin/env bash
+# Fetches and builds the programs the Mollusk tests load:
+#   tests/fixtures/spl_token.so             SPL Token, dumped from mainnet
+#   tests/fixtures/solana_stake_program.so  Stake program, dumped from mainnet
+#   target/deploy/staking_platform.so       this program
+#   target/deploy/mock_swap_adapter.so      tests/programs/mock-swap-adapter
+#
+# Needs the Solana CLI (solana, cargo-build-sbf) on PATH. Set RPC_URL to dump from another cluster.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+RPC_URL="${RPC_URL:-https://api.mainnet-beta.solana.com}"
+TOKEN_PROGRAM_ID="TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+STAKE_PROGRAM_ID="Stake11111111111111111111111111111111111111"
+
+mkdir -p tests/fixtures
+
+echo "Dumping SPL Token from $RPC_URL"
+solana program dump -u "$RPC_URL" "$TOKEN_PROGRAM_ID" tests/fixtures/spl_token.so
+
+echo "Dumping the Stake program from $RPC_URL"
+solana program dump -u "$RPC_URL" "$STAKE_PROGRAM_ID" tests/fixtures/solana_stake_program.so
+
+echo "Building staking_platform"
+cargo build-sbf --manifest-path Cargo.toml --sbf-out-dir target/deploy
+
+echo "Building mock_swap_adapter"
+cargo build-sbf --manifest-path tests/programs/mock-swap-adapter/Cargo.toml --sbf-out-dir target/deploy
+
+echo "Fixtures ready, run: cargo test --features test-sbf"
@@ -0,0 +1,273 @@
+use pinocchio::program_error::ProgramError;
+use thiserror::Error;
+
+/// Program specific failures, returned as `ProgramError::Custom(code)`.
+/// Codes are grouped by area and must never be renumbered, clients match on them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Error)]
+pub enum StakingError {
+    // Positions and penalties
+    #[error("Stake position not found for this pool")]
+    PositionNotFound = 1001,
+    #[error("Stake position is no longer active")]
+    PositionInactive = 1002,
+    #[error("Penalty not found")]
+    PenaltyNotFound = 1003,
+    #[error("Penalty history is full of unsettled penalties")]
+    PenaltyHistoryFull = 1004,
+    #[error("Penalty has already been resolved")]
+    PenaltyAlreadyResolved = 1005,
+    #[error("Penalty has already been disputed")]
+    PenaltyAlreadyDisputed = 1006,
+    #[error("Penalty was not charged against this position")]
+    PenaltyPositionMismatch = 1007,
+    #[error("Penalty is not eligible for recovery")]
+    PenaltyNotRecoverable = 1008,
+    #[error("Penalty exceeds the remaining value of the position")]
+    PenaltyExceedsPosition = 1009,
+    #[error("Early unstake penalties are charged by RequestUnstake")]
+    InvalidPenaltyType = 1010,
+    #[error("Position holds fewer LST than requested")]
+    InsufficientPositionBalance = 1011,
+
+    // Emergency withdrawals
+    #[error("No emergency condition is active")]
+    NoEmergencyCondition = 2001,
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw = 2002,
+
+    // Rewards and pool registry
+    #[error("No rewards to claim")]
+    NoRewardsToClaim = 3001,
+    #[error("Reward vault can't cover the payout")]
+    InsufficientRewardVault = 3002,
+    #[error("Pool limit reached")]
+    PoolLimitReached = 3003,
+    #[error("Reward rate is set by the running campaign")]
+    RewardCampaignActive = 3004,
+    #[error("Campaign duration must be positive")]
+    InvalidCampaignDuration = 3005,
+
+    // Pause and pool status
+    #[error("User stake account is paused")]
+    UserPaused = 4001,
+    #[error("Protocol is in emergency pause")]
+    GlobalEmergencyPause = 4002,
+    #[error("Pool is in emergency pause")]
+    PoolEmergencyPause = 4003,
+    #[error("Pool is not active")]
+    PoolNotActive = 4004,
+    #[error("Pool status can't change from its current state")]
+    InvalidPoolStatusTransition = 4005,
+    #[error("Only pause updates are allowed during an emergency pause")]
+    UpdateBlockedByEmergencyPause = 4006,
+
+    // Auto-compounding
+    #[error("Auto-compound is not enabled for this position")]
+    AutoCompoundDisabled = 5001,
+    #[error("Rewards are below the position's minimum compound amount")]
+    BelowMinCompoundAmount = 5002,
+    #[error("Compound frequency has not elapsed")]
+    CompoundTooSoon = 5004,
+    #[error("Reward mint differs from the stake mint and the pool has no swap adapter")]
+    CrossMintCompound = 5005,
+    #[error("Compound tip exceeds the maximum")]
+    InvalidCompoundTip = 5006,
+    #[error("Compound frequency is below the pool's minimum")]
+    CompoundFrequencyTooLow = 5007,
+    #[error("Minimum compound amount is below the pool's minimum")]
+    CompoundAmountTooLow = 5008,
+
+    // Cooldowns and penalty windows
+    #[error("Withdrawal cooldown is still active")]
+    CooldownActive = 6001,
+    #[error("Penalty grace period is over")]
+    GracePeriodOver = 6002,
+    #[error("Penalty grace period is still active")]
+    GracePeriodActive = 6003,
+    #[error("Penalty recovery period is not over")]
+    RecoveryPeriodActive = 6004,
+    #[error("Position has an unresolved penalty")]
+    PenaltyPending = 6005,
+
+    // Slashing
+    #[error("Not enough evidence to execute a slash")]
+    NotEnoughEvidence = 7001,
+    #[error("Slashing is disabled for this pool")]
+    SlashingDisabled = 7002,
+    #[error("Evidence does not match the pool's slashing condition")]
+    SlashConditionMismatch = 7003,
+    #[error("Reporter is not registered for this pool")]
+    ReporterNotRegistered = 7004,
+
+    // Account validation
+    #[error("Account needs migration")]
+    AccountNeedsMigration = 8001,
+    #[error("Account discriminator does not match")]
+    InvalidDiscriminator = 8002,
+    #[error("Account version is newer than the program")]
+    UnsupportedAccountVersion = 8003,
+    #[error("Account is not the expected PDA")]
+    InvalidPda = 8004,
+    #[error("Signer is not the authority")]
+    Unauthorized = 8005,
+    #[error("Account does not belong to the signer")]
+    OwnerMismatch = 8006,
+    #[error("Account belongs to a different pool")]
+    PoolMismatch = 8007,
+    #[error("Mint does not match")]
+    InvalidMint = 8008,
+    #[error("Vault does not match the pool")]
+    InvalidVault = 8009,
+    #[error("Token account is owned by the wrong wallet")]
+    InvalidTokenAccountOwner = 8010,
+    #[error("Treasury account does not match")]
+    InvalidTreasury = 8011,
+    #[error("Withdrawal ticket was issued for another position")]
+    TicketPositionMismatch = 8012,
+    #[error("Token account is not initialized")]
+    UninitializedTokenAccount = 8013,
+
+    // Amounts and parameters
+    #[error("Amount must be greater than zero")]
+    ZeroAmount = 9001,
+    #[error("Amount is below the minimum stake")]
+    BelowMinimumStake = 9002,
+    #[error("Pool stake limit exceeded")]
+    StakeLimitExceeded = 9003,
+    #[error("Token balance is too low")]
+    InsufficientTokenBalance = 9004,
+    #[error("Fee rate above 10000 bps")]
+    InvalidFeeRate = 9005,
+    #[error("Max pools must be between 1 and the registry capacity")]
+    InvalidMaxPools = 9006,
+    #[error("Amount is too small to convert")]
+    AmountTooSmall = 9007,
+    #[error("Pool has LST outstanding with no backing")]
+    EmptyPoolBacking = 9008,
+
+    // Native stake pools
+    #[error("Instruction is not supported for this pool type")]
+    WrongPoolType = 10001,
+    #[error("Pool already has stake or LST outstanding")]
+    PoolNotEmpty = 10002,
+    #[error("Vote account is not the pool's validator")]
+    InvalidVoteAccount = 10003,
+    #[error("Transient stake account is still in use")]
+    TransientStakeInUse = 10004,
+    #[error("No transient stake to merge")]
+    NoTransientStake = 10005,
+    #[error("Reserve stake can't cover the amount")]
+    InsufficientReserve = 10006,
+    #[error("Validator is not in the pool's validator list")]
+    ValidatorNotFound = 10007,
+    #[error("Validator is already in the list")]
+    ValidatorAlreadyListed = 10008,
+    #[error("Validator list is full")]
+    ValidatorListFull = 10009,
+    #[error("Validator is being removed")]
+    ValidatorRemoving = 10010,
+    #[error("Pool balance was already updated this epoch")]
+    BalanceAlreadyUpdated = 10011,
+    #[error("Stake accounts don't match the validator list")]
+    StakeAccountsMismatch = 10012,
+    #[error("Pool balance hasn't been updated this epoch")]
+    BalanceNotUpdated = 10013,
+
+    // Liquidity reserve
+    #[error("Liquidity vault can't cover the amount")]
+    InsufficientLiquidity = 11001,
+    #[error("Fee curve is invalid")]
+    InvalidFeeCurve = 11002,
+    #[error("Position is still locked")]
+    PositionLocked = 11003,
+    #[error("Amount out is below the minimum")]
+    SlippageExceeded = 11004,
+
+    // Reward streams
+    #[error("Pool already runs the maximum number of reward streams")]
+    RewardStreamsFull = 12001,
+    #[error("Reward stream not found")]
+    RewardStreamNotFound = 12002,
+    #[error("Reward stream schedule is invalid")]
+    InvalidRewardSchedule = 12003,
+    #[error("Pool already has a reward stream for this mint")]
+    DuplicateRewardMint = 12004,
+    #[error("Reward stream accounts don't match the pool's streams")]
+    RewardStreamAccountsMismatch = 12005,
+
+    // Lock tiers
+    #[error("Lock tier not offered by the pool")]
+    InvalidLockTier = 13001,
+    #[error("Lock tiers are invalid")]
+    InvalidLockTiers = 13002,
+    #[error("Position's lock boost is already up to date")]
+    LockBoostCurrent = 13003,
+
+    // Swap adapter
+    #[error("Swap adapter is invalid or not configured")]
+    InvalidSwapAdapter = 14001,
+    #[error("Swap accounts don't match the pool's swap adapter")]
+    SwapAccountsMismatch = 14002,
+    #[error("Swap pool has no liquidity")]
+    EmptySwapPool = 14003,
+    #[error("Slippage bound is invalid")]
+    InvalidSlippage = 14004,
+    #[error("Swap adapter left part of the input unswapped")]
+    SwapInputNotConsumed = 14005,
+    #[error("Program swap adapter needs a reference rate")]
+    MissingSwapReferenceRate = 14006,
+
+    // Authority transfer
+    #[error("No authority transfer is pending")]
+    NoPendingAuthority = 15001,
+    #[error("Proposed authority is invalid")]
+    InvalidPendingAuthority = 15002,
+    #[error("Instruction has been retired, UpdateAuthority is now ProposeAuthority and AcceptAuthority")]
+    RetiredInstruction = 15003,
+
+    // Roles
+    #[error("Signer doesn't hold the required role")]
+    MissingRole = 16001,
+    #[error("Roles account has no free member slots")]
+    RoleMembersFull = 16002,
+    #[error("Role is invalid")]
+    InvalidRole = 16003,
+    #[error("Member doesn't hold this role")]
+    RoleNotHeld = 16004,
+
+    // Config timelock
+    #[error("Config change has to be queued and wait out the pool's delay")]
+    ConfigChangeTimelocked = 17001,
+    #[error("Queued config change can't execute yet")]
+    ConfigChangeNotReady = 17002,
+    #[error("Config change delay is below the minimum")]
+    InvalidConfigChangeDelay = 17003,
+    #[error("A change for this parameter is already queued")]
+    ConfigChangeAlreadyQueued = 17004,
+
+    // Multisig
+    #[error("Signer isn't a multisig member")]
+    NotMultisigMember = 18001,
+    #[error("Multisig members are invalid")]
+    InvalidMultisigMembers = 18002,
+    #[error("Multisig threshold is invalid")]
+    InvalidMultisigThreshold = 18003,
+    #[error("Member already approved this proposal")]
+    AlreadyApproved = 18004,
+    #[error("Proposal doesn't have enough approvals")]
+    ThresholdNotMet = 18005,
+    #[error("Proposal was already executed")]
+    ProposalAlreadyExecuted = 18006,
+    #[error("Proposal belongs to a previous member set")]
+    StaleProposal = 18007,
+    #[error("Accounts don't match the proposal")]
+    ProposalAccountsMismatch = 18008,
+    #[error("Proposed instruction is invalid")]
+    InvalidProposalInstruction = 18009,
+}
+
+impl From<StakingError> for ProgramError {
+    fn from(e: StakingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,537 @@
+use pinocchio::{log::sol_log_data, pubkey::Pubkey};
+use shank::ShankType;
+
+/// Events are logged with `sol_log_data` as two fields: the 8-byte discriminator and the payload.
+/// Payloads are packed little-endian, so they decode with the borsh layout of the IDL type.
+pub trait Event: Copy {
+    const DISCRIMINATOR: [u8; 8];
+
+    fn emit(&self) {
+        // Safe to view as bytes, every event is `repr(C, packed)` plain data without padding
+        let payload = unsafe {
+            core::slice::from_raw_parts(self as *const Self as *const u8, core::mem::size_of::<Self>())
+        };
+
+        sol_log_data(&[&Self::DISCRIMINATOR, payload]);
+    }
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct StakeEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub pool_id: u64,
+    pub amount: u64,
+    pub lst_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for StakeEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_STAKE";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct IncreaseStakeEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub pool_id: u64,
+    pub amount: u64,
+    pub lst_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for IncreaseStakeEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_INCST";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct UnstakeRequestedEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub withdrawal_ticket: Pubkey,
+    pub lst_burned: u64,
+    pub underlying_amount: u64, // Owed to the ticket, after penalties
+    pub penalty_amount: u64,    // Early-unstake penalty plus collected penalty debt
+    pub unlock_timestamp: i64,
+    pub timestamp: i64,
+}
+
+impl Event for UnstakeRequestedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_UNSRQ";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct UnstakeCompletedEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub withdrawal_ticket: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl Event for UnstakeCompletedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_UNSCM";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct UnstakeCancelledEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub withdrawal_ticket: Pubkey,
+    pub underlying_amount: u64,
+    pub lst_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for UnstakeCancelledEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_UNSCN";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct RewardsClaimedEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,   // Default for ClaimAllRewards, which pays out across positions
+    pub stake_position: Pubkey, // Default for ClaimAllRewards
+    pub amount: u64,       // Paid to the user
+    pub protocol_fee: u64, // Paid to the treasury
+    pub timestamp: i64,
+}
+
+impl Event for RewardsClaimedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_CLAIM";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct CompoundEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub executor: Pubkey,
+    pub rewards: u64,
+    pub protocol_fee: u64,
+    pub executor_tip: u64, // Paid to the executor out of the compounded rewards
+    pub compounded_amount: u64,
+    pub staked_amount: u64, // Stake tokens compounded, differs from compounded_amount after a swap
+    pub lst_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for CompoundEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_CMPND";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct EmergencyWithdrawEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub lst_burned: u64,
+    pub amount_returned: u64,
+    pub penalty_amount: u64,
+    pub penalty_debt_collected: u64,
+    pub timestamp: i64,
+}
+
+impl Event for EmergencyWithdrawEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_EMWDR";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct PoolStatusChangedEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub old_status: u8, // PoolStatusEnum
+    pub new_status: u8,
+    pub timestamp: i64,
+}
+
+impl Event for PoolStatusChangedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_PLSTS";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct PoolConfigUpdatedEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub update_type: u8,  // UpdatePoolConfig discriminator
+    pub value: [u8; 32],  // New value as sent in the instruction, zero-padded
+    pub timestamp: i64,
+}
+
+impl Event for PoolConfigUpdatedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_PLCFG";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct GlobalConfigUpdatedEvent {
+    pub global_config: Pubkey,
+    pub update_type: u8, // 0 = authority accepted, 1 = protocol fee rate, 2 = authority proposed, 3 = transfer cancelled
+    pub value: [u8; 32], // Authority key for 0, 2 and 3, or the fee rate as u16 zero-padded
+    pub timestamp: i64,
+}
+
+impl Event for GlobalConfigUpdatedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_GLCFG";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct TreasuryWithdrawnEvent {
+    pub global_config: Pubkey,
+    pub treasury: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl Event for TreasuryWithdrawnEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_TRSWD";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct SlashEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub penalty_type: u8,   // PenaltyType::Slashing, shared by every holder through the exchange rate
+    pub condition_type: u8, // SlashTypeEnum
+    pub slash_round: u64,   // Round the evidence was filed in
+    pub slash_amount: u64,
+    pub total_underlying: u64, // Pool backing left after the slash
+    pub timestamp: i64,
+}
+
+impl Event for SlashEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_SLASH";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct SolDepositEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub lamports: u64,
+    pub lst_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for SolDepositEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_SOLDP";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct StakeWithdrawEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_account: Pubkey, // Split-off stake account now authorized to the user
+    pub pool_id: u64,
+    pub lst_burned: u64,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+impl Event for StakeWithdrawEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_STKWD";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct ValidatorSlashEvent {
+    pub staking_pool: Pubkey,
+    pub validator: Pubkey,
+    pub pool_id: u64,
+    pub condition_type: u8, // SlashTypeEnum
+    pub slash_round: u64,
+    pub stake_lamports: u64, // Stake the pool still had with the validator, drained by the rebalance crank
+    pub timestamp: i64,
+}
+
+impl Event for ValidatorSlashEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_VSLSH";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct PoolBalanceUpdatedEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub epoch: u64,
+    pub previous_underlying: u64,
+    pub total_underlying: u64,
+    pub fee_lamports: u64,   // Protocol fee taken from the growth
+    pub fee_lst_minted: u64, // Minted to the treasury for fee_lamports
+    pub timestamp: i64,
+}
+
+impl Event for PoolBalanceUpdatedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_PLBAL";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct LiquidityAddedEvent {
+    pub provider: Pubkey,
+    pub staking_pool: Pubkey,
+    pub amount: u64,
+    pub lp_minted: u64,
+    pub timestamp: i64,
+}
+
+impl Event for LiquidityAddedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_LQADD";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct LiquidityRemovedEvent {
+    pub provider: Pubkey,
+    pub staking_pool: Pubkey,
+    pub amount: u64,
+    pub lp_burned: u64,
+    pub timestamp: i64,
+}
+
+impl Event for LiquidityRemovedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_LQREM";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct InstantUnstakeEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub lst_burned: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64, // Left in the liquidity vault for LPs
+    pub fee_bps: u16,
+    pub timestamp: i64,
+}
+
+impl Event for InstantUnstakeEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_INUNS";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct RewardStreamAddedEvent {
+    pub staking_pool: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stream_index: u8,
+    pub reward_rate_per_second: u64,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub timestamp: i64,
+}
+
+impl Event for RewardStreamAddedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_RSADD";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct RewardStreamClaimedEvent {
+    pub user: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub timestamp: i64,
+}
+
+impl Event for RewardStreamClaimedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_RSCLM";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct RewardCampaignFundedEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub amount: u64,
+    pub reward_rate_per_second: u64, // Remaining plus new rewards spread until reward_end_timestamp
+    pub reward_end_timestamp: i64,
+    pub timestamp: i64,
+}
+
+impl Event for RewardCampaignFundedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_RWCMP";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct RoleUpdatedEvent {
+    pub global_config: Pubkey,
+    pub member: Pubkey,
+    pub role: u8, // RoleEnum
+    pub granted: bool, // False when revoked
+    pub timestamp: i64,
+}
+
+impl Event for RoleUpdatedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_ROLES";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct PoolConfigChangeQueuedEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub update_type: u8, // UpdatePoolConfig discriminator
+    pub value: [u8; 32], // Queued value as sent in the instruction, zero-padded
+    pub execute_after: i64,
+    pub timestamp: i64,
+}
+
+impl Event for PoolConfigChangeQueuedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_CFGQU";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct PoolConfigChangeCancelledEvent {
+    pub staking_pool: Pubkey,
+    pub pool_id: u64,
+    pub update_type: u8, // UpdatePoolConfig discriminator
+    pub timestamp: i64,
+}
+
+impl Event for PoolConfigChangeCancelledEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_CFGCN";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct MultisigUpdatedEvent {
+    pub multisig: Pubkey,
+    pub global_config: Pubkey,
+    pub threshold: u8,
+    pub member_count: u8,
+    pub member_set_seq: u64,
+    pub timestamp: i64,
+}
+
+impl Event for MultisigUpdatedEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_MSUPD";
+}
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, ShankType)]
+pub struct MultisigProposalEvent {
+    pub multisig: Pubkey,
+    pub proposal: Pubkey,
+    pub proposal_index: u64,
+    pub member: Pubkey, // Proposer, approver or executor, per action
+    pub action: u8, // 0 = created, 1 = approved, 2 = executed
+    pub instruction: u8, // StakingInstructions discriminator of the proposed call
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+impl Event for MultisigProposalEvent {
+    const DISCRIMINATOR: [u8; 8] = *b"EV_MSPRP";
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+
+    use crate::states::staking_pool_account::PoolStatusEnum;
+    use crate::test_utils::*;
+
+    use super::*;
+
+    const EARLY_PENALTY_UPDATE: u8 = 3;
+    const POOL_STATUS_UPDATE: u8 = 13;
+
+    // Pool open for staking with a funded reward vault and treasuries for both mints
+    fn setup_pool() -> (Context, TestPool) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.stake_mint);
+        init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+        set_token_account(&context, pool.reward_vault, &pool.reward_mint, &global_config, 1_000_000);
+
+        (context, pool)
+    }
+
+    #[test]
+    fn test_stake_claim_and_unstake_emit_events() {
+        let (mut context, pool) = setup_pool();
+        let logs = capture_logs(&mut context);
+        let staker = create_staker(&context, &pool, 1000);
+        let reward_account = create_token_account(&context, &pool.reward_mint, &staker.user, 0);
+
+        let position = stake(&context, &pool, &staker, 1000, 0);
+
+        let [stake_event] = events::<StakeEvent>(&logs)[..] else { panic!("expected one stake event") };
+        assert_eq!({ stake_event.user }, staker.user.to_bytes());
+        assert_eq!({ stake_event.staking_pool }, pool.staking_pool.to_bytes());
+        assert_eq!({ stake_event.stake_position }, position.to_bytes());
+        assert_eq!({ stake_event.amount }, 1000);
+        assert_eq!({ stake_event.lst_minted }, 1000);
+        assert_eq!({ stake_event.timestamp }, START_TIMESTAMP);
+
+        advance_time(&mut context, 100);
+        claim_rewards(&context, &pool, &staker, &position, &reward_account, &[], Check::success());
+
+        let [claim_event] = events::<RewardsClaimedEvent>(&logs)[..] else { panic!("expected one claim event") };
+        assert_eq!({ claim_event.stake_position }, position.to_bytes());
+        assert_eq!({ claim_event.amount }, 9_900);
+        assert_eq!({ claim_event.protocol_fee }, 100);
+
+        let ticket = request_unstake(&context, &pool, &staker, &position, 400, 0, Check::success());
+
+        let [unstake_event] = events::<UnstakeRequestedEvent>(&logs)[..] else { panic!("expected one unstake event") };
+        assert_eq!({ unstake_event.withdrawal_ticket }, ticket.to_bytes());
+        assert_eq!({ unstake_event.lst_burned }, 400);
+        assert_eq!({ unstake_event.underlying_amount }, 400);
+        assert_eq!({ unstake_event.penalty_amount }, 0);
+        assert_eq!({ unstake_event.unlock_timestamp }, START_TIMESTAMP + 100 + 86_400);
+    }
+
+    #[test]
+    fn test_pool_config_changes_emit_events() {
+        let (mut context, pool) = setup_pool();
+        let logs = capture_logs(&mut context);
+
+        apply_config_change(&mut context, &pool, EARLY_PENALTY_UPDATE, &500u64.to_le_bytes());
+        apply_config_change(&mut context, &pool, POOL_STATUS_UPDATE, &[PoolStatusEnum::Paused as u8]);
+
+        let updates = events::<PoolConfigUpdatedEvent>(&logs);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].update_type, EARLY_PENALTY_UPDATE);
+        assert_eq!(updates[0].value[..8], 500u64.to_le_bytes());
+        assert_eq!(updates[0].value[8..], [0u8; 24]);
+        assert_eq!(updates[1].update_type, POOL_STATUS_UPDATE);
+
+        // Only the status change reports a status transition
+        let [status_event] = events::<PoolStatusChangedEvent>(&logs)[..] else { panic!("expected one status event") };
+        assert_eq!(status_event.old_status, PoolStatusEnum::Active as u8);
+        assert_eq!(status_event.new_status, PoolStatusEnum::Paused as u8);
+        assert_eq!({ status_event.pool_id }, pool.pool_id);
+    }
+}
@@ -1,27 +1,32 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, sysvars::{clock::Clock, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, *};
 use pinocchio_token::{state::{TokenAccount, Mint}, instructions::TransferChecked};
 
+use crate::error::StakingError;
+use crate::events::{Event, RewardStreamClaimedEvent, RewardsClaimedEvent};
+use crate::rewards::{calculate_protocol_fee, settle_position_rewards, update_pool_rewards};
 use crate::states::{
     helper::AccountData, 
     staking_pool_account::StakingPool, 
-    user_stake_account::{UserStakeAccount, ClaimEvent, MAX_HISTORY},
+    user_stake_account::{StakePosition, UserStakeAccount, ClaimEvent, MAX_HISTORY},
     global_config::GlobalConfig
 };
 
 pub fn process_claim_rewards(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,                      // User claiming rewards
-        authority,                 // Global config authority for PDA signing
+        _authority,                 // Global config authority for PDA signing
         reward_token_mint,         // Reward token mint
         reward_token_vault,        // Pool's reward vault
         global_config_account,     // Global config PDA
         staking_pool_account,      // Pool account
         user_reward_token_account, // User's reward token account
-        user_stake_account,        // User's stake position account
+        user_stake_account,        // User's aggregate stake account
+        stake_position_account,    // Position being claimed
         treasury_account,          // Treasury for protocol fees
-        token_program,             // Token program
+        _token_program,            // Token program
+        stream_accounts @ ..,      // (reward_mint, reward_vault, user_token_account, treasury_account) per reward stream
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -37,82 +42,72 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], instruction_data: &[u8])
     );
 
     // Verify PDAs
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
 
     // Validate user owns the stake account
     if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
     if user_stake.is_paused {
-        return Err(ProgramError::Custom(4001)); // User account is paused
+        return Err(StakingError::UserPaused.into());
     }
 
     if global_config.emergency_pause {
-        return Err(ProgramError::Custom(4002)); // Global emergency pause
+        return Err(StakingError::GlobalEmergencyPause.into());
     }
 
-    // Find user's position for this pool
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if position.is_active && position.pool_id == pool_id {
-            position_index = Some(i);
-            break;
-        }
-    }
+    position.check_owner(user.key(), staking_pool_account.key())?;
 
-    let position_idx = position_index.ok_or(ProgramError::Custom(1002))?; // Position not found
+    if position.pool_id != pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
 
     // Update pool rewards first
     update_pool_rewards(&mut staking_pool, current_timestamp)?;
 
-    // Calculate pending rewards for this specific position
-    let pending_rewards = calculate_position_rewards(
-        &mut user_stake.positions[position_idx],
-        &staking_pool,
-        current_timestamp
-    )?;
+    // Settle this position, pending_rewards then holds everything claimable
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
 
-    // Add any accumulated pending rewards
-    let total_claimable = pending_rewards.saturating_add(user_stake.positions[position_idx].pending_rewards);
+    let total_claimable = position.pending_rewards;
 
-    if total_claimable == 0 {
-        return Err(ProgramError::Custom(3001)); // No rewards to claim
+    // Every stream is paid in the same call, so the accounts have to cover all of them
+    if stream_accounts.len() != staking_pool.reward_stream_count as usize * 4 {
+        return Err(StakingError::RewardStreamAccountsMismatch.into());
+    }
+
+    if total_claimable == 0 && position.stream_pending_rewards.iter().all(|amount| *amount == 0) {
+        return Err(StakingError::NoRewardsToClaim.into());
     }
 
     // Validate accounts
-    let user_reward_token_info = TokenAccount::from_account_info(user_reward_token_account)?;
-    let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
-    let treasury_info = TokenAccount::from_account_info(treasury_account)?;
+    {
+        let user_reward_token_info = TokenAccount::from_account_info(user_reward_token_account)?;
+        let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
 
-    if *user_reward_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_reward_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_reward_token_info.mint() != *reward_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_reward_token_info.mint() != *reward_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    if *reward_vault_info.mint() != staking_pool.reward_token_mint {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *reward_vault_info.mint() != staking_pool.reward_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    // Check if reward vault has sufficient balance
-    if reward_vault_info.amount() < total_claimable {
-        return Err(ProgramError::Custom(3002)); // Insufficient rewards in vault
+        // Check if reward vault has sufficient balance
+        if reward_vault_info.amount() < total_claimable {
+            return Err(StakingError::InsufficientRewardVault.into());
+        }
     }
 
     // Calculate protocol fee
@@ -122,12 +117,12 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], instruction_data: &[u8])
     let global_config_bump_arr = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let reward_mint_info = Mint::from_account_info(reward_token_mint)?;
+    let reward_mint_decimals = Mint::from_account_info(reward_token_mint)?.decimals();
 
     let signer_seeds_clone = signer_seeds.clone();
     if user_rewards > 0 {
@@ -137,23 +132,104 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], instruction_data: &[u8])
             mint: reward_token_mint,
             authority: global_config_account,
             amount: user_rewards,
-            decimals: reward_mint_info.decimals(),
+            decimals: reward_mint_decimals,
         }.invoke_signed(&[signer_seeds_clone])?;
     }
 
     if protocol_fee > 0 {
+        global_config.check_treasury(treasury_account, &staking_pool.reward_token_mint)?;
+
         TransferChecked {
             from: reward_token_vault,
             to: treasury_account,
             mint: reward_token_mint,
             authority: global_config_account,
             amount: protocol_fee,
-            decimals: reward_mint_info.decimals(),
+            decimals: reward_mint_decimals,
         }.invoke_signed(&[signer_seeds])?;
     }
 
-    user_stake.positions[position_idx].pending_rewards = 0;
-    user_stake.positions[position_idx].last_reward_update = current_timestamp;
+    for (index, stream_accounts) in stream_accounts.chunks_exact(4).enumerate() {
+        let [stream_mint, stream_vault, user_stream_token_account, stream_treasury_account] = stream_accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let stream_claimable = position.stream_pending_rewards[index];
+
+        if stream_claimable == 0 {
+            continue;
+        }
+
+        let stream = staking_pool.reward_streams[index];
+
+        if *stream_mint.key() != stream.reward_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        if *stream_vault.key() != stream.reward_vault {
+            return Err(StakingError::InvalidVault.into());
+        }
+
+        {
+            let user_stream_token_info = TokenAccount::from_account_info(user_stream_token_account)?;
+
+            if *user_stream_token_info.owner() != *user.key() {
+                return Err(StakingError::InvalidTokenAccountOwner.into());
+            }
+
+            if *user_stream_token_info.mint() != stream.reward_mint {
+                return Err(StakingError::InvalidMint.into());
+            }
+
+            global_config.check_treasury(stream_treasury_account, &stream.reward_mint)?;
+
+            if TokenAccount::from_account_info(stream_vault)?.amount() < stream_claimable {
+                return Err(StakingError::InsufficientRewardVault.into());
+            }
+        }
+
+        let stream_fee = calculate_protocol_fee(stream_claimable, global_config.protocol_fee_rate)?;
+        let stream_user_rewards = stream_claimable.saturating_sub(stream_fee);
+        let stream_mint_decimals = Mint::from_account_info(stream_mint)?.decimals();
+
+        if stream_user_rewards > 0 {
+            TransferChecked {
+                from: stream_vault,
+                to: user_stream_token_account,
+                mint: stream_mint,
+                authority: global_config_account,
+                amount: stream_user_rewards,
+                decimals: stream_mint_decimals,
+            }.invoke_signed(&[Signer::from(&seeds)])?;
+        }
+
+        if stream_fee > 0 {
+            TransferChecked {
+                from: stream_vault,
+                to: stream_treasury_account,
+                mint: stream_mint,
+                authority: global_config_account,
+                amount: stream_fee,
+                decimals: stream_mint_decimals,
+            }.invoke_signed(&[Signer::from(&seeds)])?;
+        }
+
+        position.stream_pending_rewards[index] = 0;
+        staking_pool.reward_streams[index].total_distributed = stream.total_distributed
+            .saturating_add(stream_claimable);
+
+        RewardStreamClaimedEvent {
+            user: *user.key(),
+            staking_pool: *staking_pool_account.key(),
+            stake_position: *stake_position_account.key(),
+            reward_mint: stream.reward_mint,
+            amount: stream_user_rewards,
+            protocol_fee: stream_fee,
+            timestamp: current_timestamp,
+        }.emit();
+    }
+
+    position.pending_rewards = 0;
 
     user_stake.total_earned = user_stake.total_earned.saturating_add(total_claimable);
     user_stake.total_claimed = user_stake.total_claimed.saturating_add(user_rewards);
@@ -165,65 +241,91 @@ pub fn process_claim_rewards(accounts: &[AccountInfo], instruction_data: &[u8])
 
     staking_pool.total_reward_distributed = staking_pool.total_reward_distributed.saturating_add(total_claimable);
 
+    RewardsClaimedEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        amount: user_rewards,
+        protocol_fee,
+        timestamp: current_timestamp,
+    }.emit();
+
     Ok(())
 }
 
 pub fn process_claim_all_rewards(accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
     let [
         user,
-        authority,
+        _authority,
         reward_token_mint,
         reward_token_vault,
         global_config_account,
         user_reward_token_account,
         user_stake_account,
-        treasury_account,
-        token_program,
+        treasury_account,          // Treasury for protocol fees
+        _token_program,
+        position_accounts @ ..,    // (staking_pool_account, stake_position_account) pairs
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    if position_accounts.len() % 2 != 0 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
     if !user.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
 
     if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    if user_stake.is_paused || global_config.emergency_pause {
-        return Err(ProgramError::Custom(4002));
+    if user_stake.is_paused {
+        return Err(StakingError::UserPaused.into());
+    }
+
+    if global_config.emergency_pause {
+        return Err(StakingError::GlobalEmergencyPause.into());
     }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
     let mut total_claimable = 0u64;
 
-    for position in user_stake.positions.iter_mut() {
-        if position.is_active && position.staked_amount > 0 {
-            let position_rewards = position.pending_rewards;
-            total_claimable = total_claimable.saturating_add(position_rewards);
-            
-            position.pending_rewards = 0;
-            position.last_reward_update = current_timestamp;
+    for pair in position_accounts.chunks_exact(2) {
+        let [staking_pool_account, stake_position_account] = pair else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+        let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+        position.check_owner(user.key(), staking_pool_account.key())?;
+
+        // Every pool claimed from here has to pay out of the same vault
+        if staking_pool.reward_token_vault != *reward_token_vault.key() {
+            return Err(StakingError::InvalidVault.into());
         }
+
+        update_pool_rewards(&mut staking_pool, current_timestamp)?;
+        settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
+
+        total_claimable = total_claimable.saturating_add(position.pending_rewards);
+        staking_pool.total_reward_distributed = staking_pool.total_reward_distributed
+            .saturating_add(position.pending_rewards);
+
+        position.pending_rewards = 0;
     }
 
     total_claimable = total_claimable.saturating_add(user_stake.pending_rewards);
 
     if total_claimable == 0 {
-        return Err(ProgramError::Custom(3001));
+        return Err(StakingError::NoRewardsToClaim.into());
     }
 
     let protocol_fee = calculate_protocol_fee(total_claimable, global_config.protocol_fee_rate)?;
@@ -232,13 +334,14 @@ pub fn process_claim_all_rewards(accounts: &[AccountInfo], _instruction_data: &[
     let global_config_bump_arr = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let reward_mint_info = Mint::from_account_info(reward_token_mint)?;
+    let reward_mint_decimals = Mint::from_account_info(reward_token_mint)?.decimals();
 
+    let signer_seeds_clone = signer_seeds.clone();
     if user_rewards > 0 {
         TransferChecked {
             from: reward_token_vault,
@@ -246,7 +349,20 @@ pub fn process_claim_all_rewards(accounts: &[AccountInfo], _instruction_data: &[
             mint: reward_token_mint,
             authority: global_config_account,
             amount: user_rewards,
-            decimals: reward_mint_info.decimals(),
+            decimals: reward_mint_decimals,
+        }.invoke_signed(&[signer_seeds_clone])?;
+    }
+
+    if protocol_fee > 0 {
+        global_config.check_treasury(treasury_account, reward_token_mint.key())?;
+
+        TransferChecked {
+            from: reward_token_vault,
+            to: treasury_account,
+            mint: reward_token_mint,
+            authority: global_config_account,
+            amount: protocol_fee,
+            decimals: reward_mint_decimals,
         }.invoke_signed(&[signer_seeds])?;
     }
 
@@ -258,59 +374,16 @@ pub fn process_claim_all_rewards(accounts: &[AccountInfo], _instruction_data: &[
 
     add_claim_to_history(&mut user_stake, user_rewards, current_timestamp)?;
 
-    Ok(())
-}
-
-fn calculate_position_rewards(
-    position: &mut crate::states::user_stake_account::StakePosition,
-    pool: &StakingPool,
-    current_timestamp: i64
-) -> Result<u64, ProgramError> {
-    if position.staked_amount == 0 || !position.is_active {
-        return Ok(0);
-    }
+    RewardsClaimedEvent {
+        user: *user.key(),
+        staking_pool: Pubkey::default(),
+        stake_position: Pubkey::default(),
+        amount: user_rewards,
+        protocol_fee,
+        timestamp: current_timestamp,
+    }.emit();
 
-    let time_elapsed = current_timestamp.saturating_sub(position.last_reward_update);
-    if time_elapsed == 0 {
-        return Ok(0);
-    };
-
-    if time_elapsed <= 0 {
-        return Ok(0);
-    }
-
-    let base_rewards = (position.staked_amount as u128)
-        .saturating_mul(pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128)
-        .saturating_div(1_000_000_000_000u128) as u64;
-
-    let final_rewards = if position.lock_exipry_enable && current_timestamp < position.lock_expiry {
-        apply_reward_multiplier(base_rewards, pool.reward_multiplier)?
-    } else {
-        base_rewards
-    };
-
-    Ok(final_rewards)
-}
-
-fn apply_reward_multiplier(rewards: u64, multiplier: u16) -> Result<u64, ProgramError> {
-    if multiplier <= 100 {
-        return Ok(rewards);
-    }
-
-    let multiplied = (rewards as u128)
-        .saturating_mul(multiplier as u128)
-        .saturating_div(100u128) as u64;
-
-    Ok(multiplied)
-}
-
-fn calculate_protocol_fee(amount: u64, fee_rate: u16) -> Result<u64, ProgramError> {
-    let fee = (amount as u128)
-        .saturating_mul(fee_rate as u128)
-        .saturating_div(10000u128) as u64;
-
-    Ok(fee)
+    Ok(())
 }
 
 fn add_claim_to_history(
@@ -330,26 +403,137 @@ fn add_claim_to_history(
     Ok(())
 }
 
-fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
-    if pool.total_staked == 0 {
-        return Ok(());
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::test_utils::*;
+
+    const REWARD_BALANCE: u64 = 1_000_000;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        reward_account: Pubkey,
+        treasury: Pubkey,
     }
 
-    let time_elapsed = current_timestamp.saturating_sub(pool.creation_timestamp).max(0) as u64;
-    if time_elapsed == 0 {
-        return Ok(());
+    // One staker in a pool whose reward vault holds REWARD_BALANCE, with a treasury for the reward mint
+    fn setup_staker(stake_amount: u64) -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let treasury = init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+        set_token_account(&context, pool.reward_vault, &pool.reward_mint, &global_config, REWARD_BALANCE);
+
+        let staker = create_staker(&context, &pool, stake_amount);
+        let reward_account = create_token_account(&context, &pool.reward_mint, &staker.user, 0);
+
+        Setup { context, pool, staker, reward_account, treasury }
     }
 
-    let rewards_earned = (pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128);
-    
-    let scaling_factor = 1_000_000_000_000u128;
-    let reward_per_share = rewards_earned
-        .saturating_mul(scaling_factor)
-        .saturating_div(pool.total_staked as u128);
+    fn claim_all(setup: &Setup, treasury: &Pubkey, positions: &[Pubkey], check: Check) {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(setup.staker.user, true),
+            AccountMeta::new_readonly(setup.pool.authority, false),
+            AccountMeta::new_readonly(setup.pool.reward_mint, false),
+            AccountMeta::new(setup.pool.reward_vault, false),
+            AccountMeta::new_readonly(setup.pool.global_config, false),
+            AccountMeta::new(setup.reward_account, false),
+            AccountMeta::new(setup.staker.user_stake, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+
+        for position in positions {
+            accounts.push(AccountMeta::new(setup.pool.staking_pool, false));
+            accounts.push(AccountMeta::new(*position, false));
+        }
 
-    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
-        .saturating_add(reward_per_share);
+        setup.context.process_and_validate_instruction(
+            &instruction(StakingInstructions::ClaimAllRewards, accounts, &[]),
+            &[check],
+        );
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn test_claim_all_pays_the_protocol_fee_to_the_treasury() {
+        let mut setup = setup_staker(2000);
+        let positions = [
+            stake(&setup.context, &setup.pool, &setup.staker, 1000, 0),
+            stake(&setup.context, &setup.pool, &setup.staker, 1000, 1),
+        ];
+
+        advance_time(&mut setup.context, 100);
+
+        claim_all(&setup, &Pubkey::new_unique(), &positions, custom_error(StakingError::InvalidTreasury));
+        claim_all(&setup, &setup.treasury, &positions, Check::success());
+
+        // 100 tokens a second for 100 seconds, 1% of it kept as the protocol fee
+        let claimed = REWARD_BALANCE - token_amount(&setup.context, &setup.pool.reward_vault);
+        assert_eq!(claimed, 10_000);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 100);
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 9_900);
+    }
+
+    #[test]
+    fn test_rewards_split_by_stake_and_time() {
+        let mut setup = setup_staker(1000);
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+
+        advance_time(&mut setup.context, 100);
+
+        let other = create_staker(&setup.context, &setup.pool, 3000);
+        let other_reward_account = create_token_account(&setup.context, &setup.pool.reward_mint, &other.user, 0);
+        let other_position = stake(&setup.context, &setup.pool, &other, 3000, 0);
+
+        advance_time(&mut setup.context, 100);
+
+        // 10_000 alone, then a quarter of the next 10_000
+        claim_rewards(&setup.context, &setup.pool, &setup.staker, &position, &setup.reward_account, &[], Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 12_375);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 125);
+
+        // Three quarters of the second 10_000, nothing from before it joined
+        claim_rewards(&setup.context, &setup.pool, &other, &other_position, &other_reward_account, &[], Check::success());
+        assert_eq!(token_amount(&setup.context, &other_reward_account), 7_425);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 200);
+    }
+
+    #[test]
+    fn test_claims_only_pay_what_accrued_since_the_last_one() {
+        let mut setup = setup_staker(1000);
+
+        // Nobody staked, nothing is owed for this stretch
+        advance_time(&mut setup.context, 100);
+
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+        advance_time(&mut setup.context, 100);
+
+        claim_rewards(&setup.context, &setup.pool, &setup.staker, &position, &setup.reward_account, &[], Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 9_900);
+
+        claim_rewards(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &position,
+            &setup.reward_account,
+            &[],
+            custom_error(StakingError::NoRewardsToClaim),
+        );
+
+        advance_time(&mut setup.context, 50);
+
+        claim_rewards(&setup.context, &setup.pool, &setup.staker, &position, &setup.reward_account, &[], Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 9_900 + 4_950);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 150);
+    }
+}
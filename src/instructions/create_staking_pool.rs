@@ -1,9 +1,8 @@
-use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
 use pinocchio_system::instructions::CreateAccount;
 use pinocchio_token::{instructions::{InitializeAccount3, InitializeMint2}, state::{Mint, TokenAccount}};
-use crate::states::{global_config::GlobalConfig, helper::AccountData, staking_pool_account::{PoolStatusEnum, SlashTypeEnum, StakingPool}};
-
-const MAX_POOLS: usize = 10;
+use crate::error::StakingError;
+use crate::states::{global_config::GlobalConfig, helper::AccountData, roles::{RoleEnum, Roles}, staking_pool_account::{PenaltyDestinationEnum, PoolTypeEnum, StakingPool}};
 
 pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
@@ -17,16 +16,19 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         global_config_account, 
         liquid_stake_mint, 
         price_feed_account,
-        system_program,
-        token_program,
+        _system_program,
+        _token_program,
+        roles_account,
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !creator.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     };
 
+    Roles::check_role(global_config_account, roles_account, creator, RoleEnum::PoolCreator)?;
+
     if instruction_data.len() < 64 { 
         return Err(ProgramError::InvalidInstructionData);
     };
@@ -103,9 +105,18 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
     );
 
     if *staking_pool_account.key() != staking_pool_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
+    {
+        let global_config = GlobalConfig::from_account_info(global_config_account)?;
+
+        // Fail before any accounts are created if the registry is already full
+        if global_config.active_pools >= global_config.max_pools as u64 {
+            return Err(StakingError::PoolLimitReached.into());
+        }
+    }
+
     let (stake_token_vault_pda, stake_token_vault_bump) = pubkey::find_program_address(
         &[
             b"stake_token_vault",
@@ -116,7 +127,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
     );
 
     if *stake_token_vault.key() != stake_token_vault_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     let (reward_token_vault_pda, reward_token_vault_bump) = pubkey::find_program_address(
@@ -129,7 +140,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
     );
 
     if *reward_token_vault.key() != reward_token_vault_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     let (liquid_stake_mint_pda, liquid_stake_mint_bump) = pubkey::find_program_address(
@@ -138,7 +149,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
     );
 
     if *liquid_stake_mint.key() != liquid_stake_mint_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     if stake_token_vault.data_is_empty() {
@@ -164,7 +175,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         InitializeAccount3 {
             account: stake_token_vault,
             mint: stake_token_mint,
-            owner: &global_config_account.key()
+            owner: global_config_account.key()
         }.invoke()?;
     };
 
@@ -191,7 +202,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         InitializeAccount3 {
             account: reward_token_vault,
             mint: reward_token_mint,
-            owner: &global_config_account.key()
+            owner: global_config_account.key()
         }.invoke()?;
     };
 
@@ -217,8 +228,8 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         InitializeMint2 {
             mint: liquid_stake_mint,
             decimals: 9,
-            mint_authority: &global_config_account.key(),
-            freeze_authority: Some(&global_config_account.key()),
+            mint_authority: global_config_account.key(),
+            freeze_authority: Some(global_config_account.key()),
         }.invoke()?;
     };
 
@@ -246,7 +257,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         let lock_period_enabled_bool = lock_period_enabled != 0;
         let slashing_enabled_bool = slashing_enabled != 0;
 
-        let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
+        let mut staking_pool_account_info = StakingPool::initialize(staking_pool_account)?;
         staking_pool_account_info.authority = *authority.key();
         staking_pool_account_info.pool_id = pool_id;
         staking_pool_account_info.creation_timestamp = clock.unix_timestamp;
@@ -259,6 +270,7 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         staking_pool_account_info.total_reward_distributed = 0;
         staking_pool_account_info.reward_rate_per_second = reward_rate_per_second;
         staking_pool_account_info.accumulated_reward_per_share = 0;
+        staking_pool_account_info.last_update_timestamp = clock.unix_timestamp;
         staking_pool_account_info.lock_period_enabled = lock_period_enabled_bool;
         staking_pool_account_info.lock_period_duration = lock_period_duration;
         staking_pool_account_info.reward_multiplier = reward_multiplier;
@@ -267,14 +279,25 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         staking_pool_account_info.slashing_condition_type = slashing_condition_type;
         staking_pool_account_info.slash_percentage = slash_percentage;
         staking_pool_account_info.min_evidence_required = min_evidence_required;
+        staking_pool_account_info.slash_round = 0;
+        staking_pool_account_info.evidence_count = 0;
+        staking_pool_account_info.total_slashed = 0;
+        staking_pool_account_info.last_slash_timestamp = 0;
         staking_pool_account_info.cooldown_period = cooldown_period;
+        staking_pool_account_info.penalty_destination = PenaltyDestinationEnum::Treasury as u8;
+        staking_pool_account_info.penalty_grace_period = 0;
+        staking_pool_account_info.penalty_recovery_bps = 0;
         staking_pool_account_info.price_feed_account = *price_feed_account.key();
         staking_pool_account_info.maximum_stake_limit = maximum_stake_limit;
         staking_pool_account_info.minimum_stake_amount = minimum_stake_amount;
         staking_pool_account_info.liquid_stake_mint = *liquid_stake_mint.key();
         staking_pool_account_info.liquid_stake_supply = 0;
+        staking_pool_account_info.total_underlying = 0;
+        staking_pool_account_info.total_pending_unstake = 0;
+        staking_pool_account_info.penalty_reserve = 0;
         staking_pool_account_info.emergency_pause_flag = false;
         staking_pool_account_info.stake_pool_bump = staking_pool_bump;
+        staking_pool_account_info.pool_type = PoolTypeEnum::SplToken as u8;
     }
 
     let mut global_config_account_info = GlobalConfig::from_account_info_mut(global_config_account)?;
@@ -283,15 +306,97 @@ pub fn process_create_staking_pool(accounts: &[AccountInfo], instruction_data: &
         .checked_add(1)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
-    global_config_account_info.active_pools = global_config_account_info
-        .active_pools
-        .checked_add(1)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+    global_config_account_info.register_pool(*staking_pool_account.key())?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::global_config::GlobalConfig;
+    use crate::test_utils::*;
+
+    // Another pool from the same creator and mints under `pool_id`
+    fn create_another_pool(context: &Context, pool: &TestPool, pool_id: u64, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&pool_id.to_le_bytes());
+        data.extend_from_slice(&100u64.to_le_bytes()); // reward_rate_per_second
+        data.push(0); // lock_period_enabled
+        data.extend_from_slice(&0i64.to_le_bytes()); // lock_period_duration
+        data.extend_from_slice(&100u16.to_le_bytes()); // reward_multiplier
+        data.extend_from_slice(&0u64.to_le_bytes()); // early_withdraw_penalty
+        data.push(1); // slashing_enabled
+        data.push(0); // slashing_condition_type
+        data.extend_from_slice(&1000u16.to_le_bytes()); // slash_percentage
+        data.push(1); // min_evidence_required
+        data.extend_from_slice(&86_400i64.to_le_bytes()); // cooldown_period
+        data.extend_from_slice(&0u64.to_le_bytes()); // maximum_stake_limit
+        data.extend_from_slice(&1u64.to_le_bytes()); // minimum_stake_amount
+
+        let staking_pool = Pubkey::find_program_address(
+            &[b"staking_pool", pool.authority.as_ref(), &pool_id.to_le_bytes()],
+            &PROGRAM_ID
+        ).0;
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::CreateStakingPool,
+                vec![
+                    AccountMeta::new_readonly(pool.authority, false),
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new_readonly(pool.stake_mint, false),
+                    AccountMeta::new_readonly(pool.reward_mint, false),
+                    AccountMeta::new(pool.stake_vault, false),
+                    AccountMeta::new(pool.reward_vault, false),
+                    AccountMeta::new(staking_pool, false),
+                    AccountMeta::new(pool.global_config, false),
+                    AccountMeta::new(pool.liquid_stake_mint, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(roles_pda(&pool.global_config), false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_created_pool_is_registered_in_the_config() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
 
-    if global_config_account_info.active_pool_keys.len() >= MAX_POOLS {
-        return Err(ProgramError::AccountDataTooSmall);
+        let pool = create_pool(&context, &authority, &global_config, None);
+
+        let config: GlobalConfig = read(&context, &global_config);
+        assert_eq!(config.active_pools, 1);
+        assert_eq!(config.active_pool_keys[0], pool.staking_pool.to_bytes());
+        assert!(config.active_pool_keys[1..].iter().all(|key| *key == [0u8; 32]));
     }
-    global_config_account_info.active_pool_keys.push(*staking_pool_account.key());
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn test_pool_registry_stops_at_max_pools() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        // Nothing updates max_pools after init, so lower it in place
+        let mut config_account = account(&context, &global_config);
+        let offset = core::mem::offset_of!(GlobalConfig, max_pools);
+        config_account.data[offset..offset + 4].copy_from_slice(&1u32.to_le_bytes());
+        set_account(&context, global_config, config_account);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        create_another_pool(&context, &pool, 2, custom_error(StakingError::PoolLimitReached));
+
+        let config: GlobalConfig = read(&context, &global_config);
+        assert_eq!(config.active_pools, 1);
+    }
+}
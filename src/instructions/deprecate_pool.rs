@@ -1,17 +1,23 @@
-use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{rent::Rent, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
 
+use crate::error::StakingError;
+use crate::events::{Event, PoolStatusChangedEvent};
 use crate::states::{helper::AccountData, staking_pool_account::{PoolStatusEnum, StakingPool}};
 
 pub fn process_deprecate_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
     let [authority, staking_pool_account] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     };
 
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
     let pool_id = u64::from_le_bytes(
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
@@ -26,13 +32,13 @@ pub fn process_deprecate_pool(accounts: &[AccountInfo], instruction_data: &[u8])
     );
 
     if *staking_pool_account.key() != staking_pool_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
 
     if staking_pool_account_info.authority != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::Unauthorized.into());
     };
 
     let current_status = PoolStatusEnum::try_from(&staking_pool_account_info.pool_status)?;
@@ -43,8 +49,18 @@ pub fn process_deprecate_pool(accounts: &[AccountInfo], instruction_data: &[u8])
         PoolStatusEnum::Deprecated => {
             return Ok(()); // Already deprecated
         }
-        _ => {}
+        _ => {
+            return Ok(());
+        }
     }
 
+    PoolStatusChangedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        old_status: current_status as u8,
+        new_status: staking_pool_account_info.pool_status,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
     Ok(())
 }
\ No newline at end of file
@@ -1,12 +1,14 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
-use crate::states::{helper::AccountData, user_stake_account::UserStakeAccount};
+use crate::error::StakingError;
+use crate::states::{helper::AccountData, user_stake_account::{StakePosition, UserStakeAccount}};
 
 pub fn process_disable_auto_compound(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,
         user_stake_account,
+        stake_position_account,
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -22,25 +24,26 @@ pub fn process_disable_auto_compound(accounts: &[AccountInfo], instruction_data:
     );
 
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
 
     if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    // Find the position and disable auto-compound
-    let mut position_found = false;
-    for position in user_stake.positions.iter_mut() {
-        if position.is_active && position.pool_id == pool_id {
-            position.auto_compound_enabled = false;
-            position_found = true;
-            break;
-        }
+    if position.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    if !position_found {
-        return Err(ProgramError::Custom(1002));
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
     }
 
+    if position.pool_id != pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
+
+    position.auto_compound_enabled = false;
+
     let current_timestamp = Clock::get()?.unix_timestamp;
     user_stake.last_update_timestamp = current_timestamp;
 
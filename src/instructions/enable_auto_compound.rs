@@ -1,19 +1,116 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
-use crate::states::{helper::AccountData, user_stake_account::UserStakeAccount};
+use crate::error::StakingError;
+use crate::states::{
+    helper::AccountData,
+    staking_pool_account::{StakingPool, MAX_COMPOUND_TIP_BPS},
+    user_stake_account::{StakePosition, UserStakeAccount}
+};
 
 pub fn process_enable_auto_compound(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,
         user_stake_account,
+        stake_position_account,
+        staking_pool_account,
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if instruction_data.len() < 8 {
+    let settings = parse_compound_settings(instruction_data)?;
+
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
+    }
+
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
+    }
+
+    if position.pool_id != settings.pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
+
+    check_compound_settings(&settings, &staking_pool)?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    position.auto_compound_enabled = true;
+    position.compound_frequency_hours = settings.frequency_hours;
+    position.min_compound_amount = settings.min_amount;
+    position.max_compound_tip_bps = settings.max_tip_bps;
+    // The first compound waits a full interval like every later one
+    position.last_compound_timestamp = current_timestamp;
+
+    user_stake.last_update_timestamp = current_timestamp;
+
+    Ok(())
+}
+
+pub fn process_update_auto_compound(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,
+        user_stake_account,
+        stake_position_account,
+        staking_pool_account,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let settings = parse_compound_settings(instruction_data)?;
+
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
+    }
+
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    if position.pool_id != settings.pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
+
+    if !position.auto_compound_enabled {
+        return Err(StakingError::AutoCompoundDisabled.into());
+    }
+
+    check_compound_settings(&settings, &staking_pool)?;
+
+    position.compound_frequency_hours = settings.frequency_hours;
+    position.min_compound_amount = settings.min_amount;
+    position.max_compound_tip_bps = settings.max_tip_bps;
+
+    user_stake.last_update_timestamp = Clock::get()?.unix_timestamp;
+
+    Ok(())
+}
+
+struct CompoundSettings {
+    pool_id: u64,
+    frequency_hours: u32,
+    min_amount: u64,
+    max_tip_bps: u16,
+}
+
+// pool_id (8), frequency_hours (4), min_amount (8), then an optional max_tip_bps (2)
+fn parse_compound_settings(instruction_data: &[u8]) -> Result<CompoundSettings, ProgramError> {
+    if instruction_data.len() < 20 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -21,28 +118,136 @@ pub fn process_enable_auto_compound(accounts: &[AccountInfo], instruction_data:
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
-    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let frequency_hours = u32::from_le_bytes(
+        instruction_data[8..12].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
 
-    if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let min_amount = u64::from_le_bytes(
+        instruction_data[12..20].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    // Without a cap the owner accepts whatever tip the pool pays
+    let max_tip_bps = match instruction_data.get(20..22) {
+        Some(bytes) => u16::from_le_bytes(bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        None => MAX_COMPOUND_TIP_BPS,
+    };
 
-    // Find the position and enable auto-compound
-    let mut position_found = false;
-    for position in user_stake.positions.iter_mut() {
-        if position.is_active && position.pool_id == pool_id {
-            position.auto_compound_enabled = true;
-            position_found = true;
-            break;
-        }
+    Ok(CompoundSettings { pool_id, frequency_hours, min_amount, max_tip_bps })
+}
+
+fn check_compound_settings(settings: &CompoundSettings, staking_pool: &StakingPool) -> ProgramResult {
+    if settings.frequency_hours == 0 || settings.frequency_hours < staking_pool.compound_min_frequency_hours {
+        return Err(StakingError::CompoundFrequencyTooLow.into());
     }
 
-    if !position_found {
-        return Err(ProgramError::Custom(1002)); // Position not found
+    if settings.min_amount < staking_pool.compound_min_amount {
+        return Err(StakingError::CompoundAmountTooLow.into());
     }
 
-    let current_timestamp = Clock::get()?.unix_timestamp;
-    user_stake.last_update_timestamp = current_timestamp;
+    if settings.max_tip_bps > MAX_COMPOUND_TIP_BPS {
+        return Err(StakingError::InvalidCompoundTip.into());
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::test_utils::*;
+
+    const COMPOUND_MIN_FREQUENCY_UPDATE: u8 = 19;
+    const COMPOUND_MIN_AMOUNT_UPDATE: u8 = 20;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+    }
+
+    // Pool paying rewards in its stake mint, with one staked position
+    fn setup_position() -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let mint = create_mint(&context, &authority, 9);
+        let pool = create_pool_with_mints(&context, &authority, &global_config, mint, mint);
+        init_treasury(&context, &authority, &global_config, &mint);
+        set_token_account(&context, pool.reward_vault, &mint, &global_config, 1_000_000);
+
+        let staker = create_staker(&context, &pool, 1000);
+        let position = stake(&context, &pool, &staker, 1000, 0);
+
+        Setup { context, pool, staker, position }
+    }
+
+    fn set_compound(setup: &Setup, instruction_type: StakingInstructions, settings: &[u8], check: Check) {
+        setup.context.process_and_validate_instruction(
+            &auto_compound_instruction(instruction_type, &setup.pool, &setup.staker, &setup.position, settings),
+            &[check],
+        );
+    }
+
+    // Enable and update take the same settings and check them the same way
+    fn assert_held_to_pool_bounds(setup: &Setup, instruction_type: fn() -> StakingInstructions) {
+        let settings = |frequency_hours, min_amount, max_tip_bps| {
+            compound_settings(&setup.pool, frequency_hours, min_amount, max_tip_bps)
+        };
+
+        set_compound(setup, instruction_type(), &settings(0, 1000, None), custom_error(StakingError::CompoundFrequencyTooLow));
+        set_compound(setup, instruction_type(), &settings(12, 1000, None), custom_error(StakingError::CompoundFrequencyTooLow));
+        set_compound(setup, instruction_type(), &settings(24, 500, None), custom_error(StakingError::CompoundAmountTooLow));
+        set_compound(setup, instruction_type(), &settings(24, 1000, Some(1001)), custom_error(StakingError::InvalidCompoundTip));
+        set_compound(setup, instruction_type(), &settings(24, 1000, Some(1000)), Check::success());
+    }
+
+    #[test]
+    fn test_settings_are_held_to_the_pool_bounds() {
+        let mut setup = setup_position();
+        apply_config_change(&mut setup.context, &setup.pool, COMPOUND_MIN_FREQUENCY_UPDATE, &24u32.to_le_bytes());
+        apply_config_change(&mut setup.context, &setup.pool, COMPOUND_MIN_AMOUNT_UPDATE, &1000u64.to_le_bytes());
+
+        assert_held_to_pool_bounds(&setup, || StakingInstructions::EnableAutoCompound);
+        assert_held_to_pool_bounds(&setup, || StakingInstructions::UpdateAutoCompound);
+    }
+
+    #[test]
+    fn test_update_needs_auto_compound_enabled() {
+        let setup = setup_position();
+        let settings = compound_settings(&setup.pool, 1, 0, None);
+
+        set_compound(&setup, StakingInstructions::UpdateAutoCompound, &settings, custom_error(StakingError::AutoCompoundDisabled));
+        set_compound(&setup, StakingInstructions::EnableAutoCompound, &settings, Check::success());
+        set_compound(&setup, StakingInstructions::UpdateAutoCompound, &settings, Check::success());
+    }
+
+    #[test]
+    fn test_compound_follows_the_position_settings() {
+        let mut setup = setup_position();
+        let executor = funded_wallet(&setup.context);
+        let executor_token_account = create_token_account(&setup.context, &setup.pool.reward_mint, &executor, 0);
+        let execute = |setup: &Setup, check| {
+            execute_auto_compound(&setup.context, &setup.pool, &setup.staker, &setup.position, &executor, &executor_token_account, check);
+        };
+
+        // Every two hours, and only once more than two hours of rewards have built up
+        set_compound(&setup, StakingInstructions::EnableAutoCompound, &compound_settings(&setup.pool, 2, 720_001, None), Check::success());
+
+        advance_time(&mut setup.context, 3600);
+        execute(&setup, custom_error(StakingError::CompoundTooSoon));
+
+        advance_time(&mut setup.context, 3600);
+        execute(&setup, custom_error(StakingError::BelowMinCompoundAmount));
+
+        set_compound(&setup, StakingInstructions::UpdateAutoCompound, &compound_settings(&setup.pool, 2, 720_000, None), Check::success());
+        execute(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 1000 + 712_800);
+    }
+}
@@ -10,18 +10,22 @@ use pinocchio_token::{
     instructions::{TransferChecked, MintToChecked}
 };
 
+use crate::error::StakingError;
+use crate::events::{CompoundEvent, Event};
+use crate::rewards::{calculate_protocol_fee, reset_reward_debt, settle_position_rewards, update_pool_rewards};
 use crate::states::{
     helper::AccountData, 
-    staking_pool_account::StakingPool, 
-    user_stake_account::{UserStakeAccount, StakePosition},
+    staking_pool_account::{PoolTypeEnum, StakingPool}, 
+    user_stake_account::{StakePosition, UserStakeAccount},
     global_config::GlobalConfig
 };
+use crate::swap_adapter::CompoundSwap;
 
 pub fn process_execute_auto_compound(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         executor,                  
         position_owner,            
-        authority,                 
+        _authority,                 
         reward_token_mint,         
         stake_token_mint,          
         reward_token_vault,        
@@ -30,13 +34,20 @@ pub fn process_execute_auto_compound(accounts: &[AccountInfo], instruction_data:
         global_config_account,      
         staking_pool_account,       
         user_stake_account,         
+        stake_position_account,     
         user_lst_token_account,     
         treasury_account,           
+        executor_token_account,     // Executor's reward token account, receives the tip
         token_program,              
+        swap_accounts @ ..          // Swap adapter accounts, only read when the mints differ
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    if !executor.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     if instruction_data.len() < 8 {
         return Err(ProgramError::InvalidInstructionData);
     }
@@ -45,48 +56,54 @@ pub fn process_execute_auto_compound(accounts: &[AccountInfo], instruction_data:
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
+    // Executor's bound on a cross-mint swap, required there. The pool's slippage bound applies on top
+    let min_amount_out = match instruction_data.get(8..16) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        None => 0,
+    };
 
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
 
     if user_stake.owner != *position_owner.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
     if user_stake.is_paused {
-        return Err(ProgramError::Custom(4001)); 
+        return Err(StakingError::UserPaused.into());
     }
 
     if global_config.emergency_pause {
-        return Err(ProgramError::Custom(4002)); 
+        return Err(StakingError::GlobalEmergencyPause.into());
     }
 
     if staking_pool.emergency_pause_flag {
-        return Err(ProgramError::Custom(4003)); 
+        return Err(StakingError::PoolEmergencyPause.into());
     }
 
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if position.is_active && position.pool_id == pool_id {
-            position_index = Some(i);
-            break;
-        }
+    position.check_owner(position_owner.key(), staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
     }
 
-    let position_idx = position_index.ok_or(ProgramError::Custom(1002))?; 
-    let position = &user_stake.positions[position_idx];
+    if position.pool_id != pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
 
     if !position.auto_compound_enabled {
-        return Err(ProgramError::Custom(5001)); 
+        return Err(StakingError::AutoCompoundDisabled.into());
+    }
+
+    // Enabled before compound settings were taken, the owner has to set them with UpdateAutoCompound
+    if position.compound_frequency_hours == 0 {
+        return Err(StakingError::CompoundFrequencyTooLow.into());
     }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
@@ -95,55 +112,83 @@ pub fn process_execute_auto_compound(accounts: &[AccountInfo], instruction_data:
     let required_interval = (position.compound_frequency_hours as i64) * 3600; 
 
     if time_since_last_compound < required_interval {
-        return Err(ProgramError::Custom(5004)); 
+        return Err(StakingError::CompoundTooSoon.into());
     }
 
     update_pool_rewards(&mut staking_pool, current_timestamp)?;
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
 
-    let pending_rewards = calculate_position_rewards(
-        &user_stake.positions[position_idx],
-        &staking_pool,
-        current_timestamp
-    )?;
-
-    let total_rewards = pending_rewards.saturating_add(position.pending_rewards);
+    let total_rewards = position.pending_rewards;
 
     if total_rewards < position.min_compound_amount {
-        return Err(ProgramError::Custom(5002)); 
+        return Err(StakingError::BelowMinCompoundAmount.into());
     }
 
     if total_rewards == 0 {
-        return Err(ProgramError::Custom(3001)); 
+        return Err(StakingError::NoRewardsToClaim.into());
     }
 
-    let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
-    let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+    {
+        let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
 
-    if reward_vault_info.amount() < total_rewards {
-        return Err(ProgramError::Custom(3002)); 
-    }
+        if reward_vault_info.amount() < total_rewards {
+            return Err(StakingError::InsufficientRewardVault.into());
+        }
 
-    if *user_lst_token_info.owner() != *position_owner.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *reward_token_vault.key() != staking_pool.reward_token_vault {
+            return Err(StakingError::InvalidVault.into());
+        }
 
-    if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
+        if *stake_token_vault.key() != staking_pool.stake_token_vault {
+            return Err(StakingError::InvalidVault.into());
+        }
+
+        if *reward_token_mint.key() != staking_pool.reward_token_mint
+            || *stake_token_mint.key() != staking_pool.stake_token_mint
+            || *liquid_stake_mint.key() != staking_pool.liquid_stake_mint
+        {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        if *user_lst_token_info.owner() != *position_owner.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        global_config.check_treasury(treasury_account, reward_token_mint.key())?;
+
+        let executor_token_info = TokenAccount::from_account_info(executor_token_account)?;
+
+        if *executor_token_info.owner() != *executor.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *executor_token_info.mint() != *reward_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
     }
 
     let protocol_fee = calculate_protocol_fee(total_rewards, global_config.protocol_fee_rate)?;
-    let compound_amount = total_rewards.saturating_sub(protocol_fee);
+    let tip_bps = staking_pool.compound_tip_bps.min(position.max_compound_tip_bps);
+    let executor_tip = calculate_protocol_fee(total_rewards.saturating_sub(protocol_fee), tip_bps)?;
+    let compound_amount = total_rewards
+        .saturating_sub(protocol_fee)
+        .saturating_sub(executor_tip);
 
     let global_config_bump_arr = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
     if protocol_fee > 0 {
-        let reward_mint_info = Mint::from_account_info(reward_token_mint)?;
+        let reward_mint_decimals = Mint::from_account_info(reward_token_mint)?.decimals();
         let signer_seeds_clone = signer_seeds.clone();
         TransferChecked {
             from: reward_token_vault,
@@ -151,125 +196,226 @@ pub fn process_execute_auto_compound(accounts: &[AccountInfo], instruction_data:
             mint: reward_token_mint,
             authority: global_config_account,
             amount: protocol_fee,
-            decimals: reward_mint_info.decimals(),
+            decimals: reward_mint_decimals,
         }.invoke_signed(&[signer_seeds_clone])?;
     }
 
-    if *reward_token_mint.key() == *stake_token_mint.key() {
-        let lst_tokens = compound_amount; 
+    if executor_tip > 0 {
+        let reward_mint_decimals = Mint::from_account_info(reward_token_mint)?.decimals();
+        let signer_seeds_clone = signer_seeds.clone();
+        TransferChecked {
+            from: reward_token_vault,
+            to: executor_token_account,
+            mint: reward_token_mint,
+            authority: global_config_account,
+            amount: executor_tip,
+            decimals: reward_mint_decimals,
+        }.invoke_signed(&[signer_seeds_clone])?;
+    }
+
+    let stake_amount = if *reward_token_mint.key() == *stake_token_mint.key() {
+        // Compounded rewards must back the newly minted LST
+        let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
+        let signer_seeds_clone = signer_seeds.clone();
+        TransferChecked {
+            from: reward_token_vault,
+            to: stake_token_vault,
+            mint: stake_token_mint,
+            authority: global_config_account,
+            amount: compound_amount,
+            decimals: stake_mint_decimals,
+        }.invoke_signed(&[signer_seeds_clone])?;
+
+        compound_amount
+    } else {
+        let signer_seeds_clone = signer_seeds.clone();
+        CompoundSwap {
+            staking_pool_account,
+            global_config_account,
+            reward_token_mint,
+            stake_token_mint,
+            reward_token_vault,
+            stake_token_vault,
+            token_program,
+            swap_accounts,
+            amount_in: compound_amount,
+            min_amount_out,
+        }.invoke_signed(&staking_pool, &[signer_seeds_clone])?
+    };
 
-        let mint_info = Mint::from_account_info(liquid_stake_mint)?;
-        
-        MintToChecked {
-            mint: liquid_stake_mint,
-            account: user_lst_token_account,
-            mint_authority: global_config_account,
-            amount: lst_tokens,
-            decimals: mint_info.decimals()
-        }.invoke_signed(&[signer_seeds])?;
+    let lst_tokens = staking_pool.underlying_to_lst(stake_amount)?;
+    let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
 
-        user_stake.positions[position_idx].staked_amount = 
-            user_stake.positions[position_idx].staked_amount.saturating_add(compound_amount);
-        user_stake.positions[position_idx].lst_tokens = 
-            user_stake.positions[position_idx].lst_tokens.saturating_add(lst_tokens);
+    MintToChecked {
+        mint: liquid_stake_mint,
+        account: user_lst_token_account,
+        mint_authority: global_config_account,
+        amount: lst_tokens,
+        decimals: lst_mint_decimals
+    }.invoke_signed(&[signer_seeds])?;
 
-        staking_pool.total_staked = staking_pool.total_staked.saturating_add(compound_amount);
-        staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+    position.staked_amount = position.staked_amount.saturating_add(stake_amount);
+    position.lst_tokens = position.lst_tokens.saturating_add(lst_tokens);
 
-    } else {
-        return Err(ProgramError::Custom(5005)); 
-    }
+    staking_pool.total_staked = staking_pool.total_staked.saturating_add(stake_amount);
+    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_add(stake_amount);
 
-    user_stake.positions[position_idx].pending_rewards = 0;
-    user_stake.positions[position_idx].last_reward_update = current_timestamp;
-    user_stake.positions[position_idx].last_compound_timestamp = current_timestamp;
-    user_stake.positions[position_idx].compound_count = user_stake.positions[position_idx].compound_count.saturating_add(1);
+    user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_add(lst_tokens);
 
-    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_add(compound_amount);
-    user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_add(compound_amount);
+    position.pending_rewards = 0;
+    reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+    position.last_compound_timestamp = current_timestamp;
+    position.compound_count = position.compound_count.saturating_add(1);
+
+    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_add(stake_amount);
     user_stake.total_earned = user_stake.total_earned.saturating_add(total_rewards);
     user_stake.last_update_timestamp = current_timestamp;
 
     staking_pool.total_reward_distributed = staking_pool.total_reward_distributed.saturating_add(total_rewards);
 
+    CompoundEvent {
+        user: *position_owner.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        executor: *executor.key(),
+        rewards: total_rewards,
+        protocol_fee,
+        executor_tip,
+        compounded_amount: compound_amount,
+        staked_amount: stake_amount,
+        lst_minted: lst_tokens,
+        timestamp: current_timestamp,
+    }.emit();
+
     Ok(())
 }
 
-fn calculate_position_rewards(
-    position: &StakePosition,
-    pool: &StakingPool,
-    current_timestamp: i64
-) -> Result<u64, ProgramError> {
-    if position.staked_amount == 0 || !position.is_active {
-        return Ok(0);
-    }
-
-    let time_elapsed = current_timestamp.saturating_sub(position.last_reward_update);
-    
-    if time_elapsed <= 0 {
-        return Ok(0);
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::test_utils::*;
+
+    const COMPOUND_TIP_UPDATE: u8 = 18;
+    const REWARD_BALANCE: u64 = 1_000_000;
+    const STAKE_AMOUNT: u64 = 1000;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+        treasury: Pubkey,
+        executor: Pubkey,
+        executor_token_account: Pubkey,
     }
 
-    let user_share = (position.staked_amount as u128)
-        .saturating_mul(pool.accumulated_reward_per_share)
-        .saturating_div(1_000_000_000_000u128) as u64;
+    // Pool paying rewards in its stake mint with a `tip_bps` compound tip, and one staked position
+    fn setup_position(tip_bps: u16) -> Setup {
+        let mut context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
 
-    let time_rewards = (position.staked_amount as u128)
-        .saturating_mul(pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128)
-        .saturating_div(1_000_000_000_000u128) as u64;
+        let mint = create_mint(&context, &authority, 9);
+        let pool = create_pool_with_mints(&context, &authority, &global_config, mint, mint);
+        let treasury = init_treasury(&context, &authority, &global_config, &mint);
+        set_token_account(&context, pool.reward_vault, &mint, &global_config, REWARD_BALANCE);
 
-    let base_rewards = user_share.saturating_add(time_rewards);
+        apply_config_change(&mut context, &pool, COMPOUND_TIP_UPDATE, &tip_bps.to_le_bytes());
 
-    let final_rewards = if position.lock_exipry_enable && current_timestamp < position.lock_expiry {
-        apply_reward_multiplier(base_rewards, pool.reward_multiplier)?
-    } else {
-        base_rewards
-    };
+        let staker = create_staker(&context, &pool, STAKE_AMOUNT);
+        let position = stake(&context, &pool, &staker, STAKE_AMOUNT, 0);
 
-    Ok(final_rewards)
-}
+        let executor = funded_wallet(&context);
+        let executor_token_account = create_token_account(&context, &mint, &executor, 0);
 
-fn apply_reward_multiplier(rewards: u64, multiplier: u16) -> Result<u64, ProgramError> {
-    if multiplier <= 100 {
-        return Ok(rewards);
+        Setup { context, pool, staker, position, treasury, executor, executor_token_account }
     }
 
-    let multiplied = (rewards as u128)
-        .saturating_mul(multiplier as u128)
-        .saturating_div(100u128) as u64;
+    fn enable(setup: &Setup, max_tip_bps: Option<u16>) {
+        setup.context.process_and_validate_instruction(
+            &auto_compound_instruction(
+                StakingInstructions::EnableAutoCompound,
+                &setup.pool,
+                &setup.staker,
+                &setup.position,
+                &compound_settings(&setup.pool, 1, 0, max_tip_bps),
+            ),
+            &[Check::success()],
+        );
+    }
 
-    Ok(multiplied)
-}
+    fn execute(setup: &Setup, check: Check) {
+        execute_auto_compound(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &setup.position,
+            &setup.executor,
+            &setup.executor_token_account,
+            check,
+        );
+    }
 
-fn calculate_protocol_fee(amount: u64, fee_rate: u16) -> Result<u64, ProgramError> {
-    let fee = (amount as u128)
-        .saturating_mul(fee_rate as u128)
-        .saturating_div(10000u128) as u64; 
+    #[test]
+    fn test_executor_is_paid_the_pool_tip() {
+        let mut setup = setup_position(500);
+        enable(&setup, None);
 
-    Ok(fee)
-}
+        advance_time(&mut setup.context, 3600);
+        execute(&setup, Check::success());
 
-fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
-    if pool.total_staked == 0 {
-        return Ok(());
+        // 360_000 of rewards, 1% protocol fee, then 5% of the rest to the executor
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 3_600);
+        assert_eq!(token_amount(&setup.context, &setup.executor_token_account), 17_820);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT + 338_580);
+        assert_eq!(token_amount(&setup.context, &setup.staker.lst_account), STAKE_AMOUNT + 338_580);
+        assert_eq!(token_amount(&setup.context, &setup.pool.reward_vault), REWARD_BALANCE - 360_000);
     }
 
-    let time_elapsed = current_timestamp.saturating_sub(pool.creation_timestamp).max(0) as u64;
-    if time_elapsed == 0 {
-        return Ok(());
-    }
+    #[test]
+    fn test_owner_caps_the_tip() {
+        let mut setup = setup_position(500);
+        enable(&setup, Some(100));
 
-    let rewards_earned = (pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128);
-    
-    let scaling_factor = 1_000_000_000_000u128;
-    let reward_per_share = rewards_earned
-        .saturating_mul(scaling_factor)
-        .saturating_div(pool.total_staked as u128);
+        advance_time(&mut setup.context, 3600);
+        execute(&setup, Check::success());
 
-    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
-        .saturating_add(reward_per_share);
+        assert_eq!(token_amount(&setup.context, &setup.executor_token_account), 3_564);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT + 352_836);
+    }
 
-    Ok(())
+    #[test]
+    fn test_anyone_can_crank_once_the_interval_passed() {
+        let mut setup = setup_position(500);
+
+        advance_time(&mut setup.context, 3600);
+        execute(&setup, custom_error(StakingError::AutoCompoundDisabled));
+
+        enable(&setup, None);
+
+        advance_time(&mut setup.context, 3599);
+        execute(&setup, custom_error(StakingError::CompoundTooSoon));
+
+        // The tip can only go to an account the executor owns
+        let other_token_account = create_token_account(&setup.context, &setup.pool.reward_mint, &Pubkey::new_unique(), 0);
+        advance_time(&mut setup.context, 1);
+        execute_auto_compound(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &setup.position,
+            &setup.executor,
+            &other_token_account,
+            custom_error(StakingError::InvalidTokenAccountOwner),
+        );
+
+        execute(&setup, Check::success());
+        execute(&setup, custom_error(StakingError::CompoundTooSoon));
+    }
 }
@@ -1,6 +1,9 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError ,*};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
 use pinocchio_token::{instructions::TransferChecked, state::{Mint, TokenAccount}};
-use crate::states::{global_config::GlobalConfig, helper::AccountData, staking_pool_account::StakingPool};
+use crate::error::StakingError;
+use crate::events::{Event, RewardCampaignFundedEvent};
+use crate::rewards::update_pool_rewards;
+use crate::states::{helper::AccountData, roles::{RoleEnum, Roles}, staking_pool_account::StakingPool};
 
 pub fn process_fund_reward_vault(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
@@ -10,16 +13,18 @@ pub fn process_fund_reward_vault(accounts: &[AccountInfo], instruction_data: &[u
         reward_token_mint,
         reward_token_vault,
         staking_pool_account,
-        token_program
+        _token_program,
+        global_config_account,
+        roles_account
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     };
 
-    if instruction_data.len() < 8 {
+    if instruction_data.len() < 16 {
         return Err(ProgramError::InvalidInstructionData);
     };
 
@@ -27,46 +32,59 @@ pub fn process_fund_reward_vault(accounts: &[AccountInfo], instruction_data: &[u
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
+    // Seconds from now until the campaign ends, the remaining and new rewards are spread over it
+    let campaign_duration = i64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
     if reward_amount == 0 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::ZeroAmount.into());
+    };
+
+    if campaign_duration <= 0 {
+        return Err(StakingError::InvalidCampaignDuration.into());
     };
 
-    let staking_pool_info = StakingPool::from_account_info(staking_pool_account)?;
+    let mut staking_pool_info = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    Roles::check_pool_role(global_config_account, roles_account, authority, staking_pool_account.key(), RoleEnum::RewardFunder)?;
 
     if staking_pool_info.reward_token_mint != *reward_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidMint.into());
     };
 
     if staking_pool_info.reward_token_vault != *reward_token_vault.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidVault.into());
     };
 
     if staking_pool_info.emergency_pause_flag {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::PoolEmergencyPause.into());
     };
 
-    let authority_token_info = TokenAccount::from_account_info(authority_token_account)?;
-    let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
+    {
+        let authority_token_info = TokenAccount::from_account_info(authority_token_account)?;
+        let reward_vault_info = TokenAccount::from_account_info(reward_token_vault)?;
 
-    if !authority_token_info.is_initialized() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+        if !authority_token_info.is_initialized() {
+            return Err(StakingError::UninitializedTokenAccount.into());
+        };
 
-    if *authority_token_info.mint() != *reward_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+        if *authority_token_info.mint() != *reward_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        };
 
-    if *authority_token_info.owner() != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+        if *authority_token_info.owner() != *authority.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        };
 
-    if *reward_vault_info.mint() != *reward_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+        if *reward_vault_info.mint() != *reward_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        };
 
-    if authority_token_info.amount() < reward_amount {
-        return Err(ProgramError::InsufficientFunds);
-    };
+        if authority_token_info.amount() < reward_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        };
+    }
 
     let reward_mint_account_info = Mint::from_account_info(reward_token_mint)?;
 
@@ -74,10 +92,205 @@ pub fn process_fund_reward_vault(accounts: &[AccountInfo], instruction_data: &[u
         from: authority_token_account,
         to: reward_token_vault,
         mint: reward_token_mint,
-        authority: authority,
+        authority,
         amount: reward_amount,
         decimals: reward_mint_account_info.decimals(),
     }.invoke()?;
 
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    // Settle emissions at the old rate before it changes
+    update_pool_rewards(&mut staking_pool_info, current_timestamp)?;
+
+    // Only what the running campaign still owes rolls over, the vault may be shared with other pools
+    let remaining_rewards = if current_timestamp < staking_pool_info.reward_end_timestamp {
+        (staking_pool_info.reward_rate_per_second as u128)
+            .saturating_mul(staking_pool_info.reward_end_timestamp.saturating_sub(current_timestamp) as u128)
+    } else {
+        0
+    };
+
+    let reward_rate_per_second = remaining_rewards
+        .saturating_add(reward_amount as u128)
+        / campaign_duration as u128;
+
+    staking_pool_info.reward_rate_per_second = u64::try_from(reward_rate_per_second)
+        .map_err(|_| ProgramError::ArithmeticOverflow)?;
+    staking_pool_info.reward_end_timestamp = current_timestamp.saturating_add(campaign_duration);
+
+    RewardCampaignFundedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id: staking_pool_info.pool_id,
+        amount: reward_amount,
+        reward_rate_per_second: staking_pool_info.reward_rate_per_second,
+        reward_end_timestamp: staking_pool_info.reward_end_timestamp,
+        timestamp: current_timestamp,
+    }.emit();
+
     Ok(())
 }
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::staking_pool_account::{StakingPool, MIN_CONFIG_CHANGE_DELAY};
+    use crate::test_utils::*;
+
+    const REWARD_RATE_UPDATE: u8 = 0;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        reward_account: Pubkey,
+        treasury: Pubkey,
+    }
+
+    // Pool with an empty reward vault and one staker, the config authority funds campaigns
+    fn setup_pool() -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let treasury = init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+
+        let staker = create_staker(&context, &pool, 1000);
+        let reward_account = create_token_account(&context, &pool.reward_mint, &staker.user, 0);
+
+        Setup { context, pool, staker, reward_account, treasury }
+    }
+
+    fn fund(setup: &Setup, amount: u64, campaign_duration: i64, check: Check) {
+        let funder_token_account = create_token_account(&setup.context, &setup.pool.reward_mint, &setup.pool.authority, amount);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&campaign_duration.to_le_bytes());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::FundRewardVault,
+                vec![
+                    AccountMeta::new(setup.pool.authority, true),
+                    AccountMeta::new(funder_token_account, false),
+                    AccountMeta::new_readonly(setup.pool.reward_mint, false),
+                    AccountMeta::new(setup.pool.reward_vault, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new_readonly(roles_pda(&setup.pool.global_config), false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn reward_rate(setup: &Setup) -> u64 {
+        read_at(&setup.context, &setup.pool.staking_pool, core::mem::offset_of!(StakingPool, reward_rate_per_second))
+    }
+
+    #[test]
+    fn test_funding_spreads_rewards_until_the_campaign_ends() {
+        let mut setup = setup_pool();
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+
+        fund(&setup, 50_000, 1000, Check::success());
+        assert_eq!(reward_rate(&setup), 50);
+
+        advance_time(&mut setup.context, 2000);
+        claim_rewards(&setup.context, &setup.pool, &setup.staker, &position, &setup.reward_account, &[], Check::success());
+
+        // Emission stopped at the end, so exactly what was funded is paid out
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 49_500);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 500);
+        assert_eq!(token_amount(&setup.context, &setup.pool.reward_vault), 0);
+    }
+
+    #[test]
+    fn test_top_up_rolls_over_what_the_campaign_still_owes() {
+        let mut setup = setup_pool();
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+
+        fund(&setup, 50_000, 1000, Check::success());
+        advance_time(&mut setup.context, 500);
+
+        // 25_000 still owed plus 35_000 new over 2000 seconds
+        fund(&setup, 35_000, 2000, Check::success());
+        assert_eq!(reward_rate(&setup), 30);
+
+        advance_time(&mut setup.context, 3000);
+        claim_rewards(&setup.context, &setup.pool, &setup.staker, &position, &setup.reward_account, &[], Check::success());
+
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 84_150);
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 850);
+        assert_eq!(token_amount(&setup.context, &setup.pool.reward_vault), 0);
+    }
+
+    #[test]
+    fn test_reward_rate_is_locked_while_a_campaign_runs() {
+        let mut setup = setup_pool();
+        let campaign_duration = 2 * MIN_CONFIG_CHANGE_DELAY;
+        fund(&setup, 10 * campaign_duration as u64, campaign_duration, Check::success());
+        assert_eq!(reward_rate(&setup), 10);
+
+        let config_change = Pubkey::find_program_address(
+            &[b"pool_config_change", setup.pool.staking_pool.as_ref(), &[REWARD_RATE_UPDATE]],
+            &PROGRAM_ID
+        ).0;
+
+        let mut data = vec![REWARD_RATE_UPDATE];
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&500u64.to_le_bytes());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::QueuePoolConfigChange,
+                vec![
+                    AccountMeta::new(setup.pool.authority, true),
+                    AccountMeta::new_readonly(setup.pool.staking_pool, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                    AccountMeta::new(config_change, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+
+        let execute = instruction(
+            StakingInstructions::ExecutePoolConfigChange,
+            vec![
+                AccountMeta::new(setup.pool.authority, true),
+                AccountMeta::new(setup.pool.staking_pool, false),
+                AccountMeta::new(config_change, false),
+            ],
+            &[],
+        );
+
+        advance_time(&mut setup.context, MIN_CONFIG_CHANGE_DELAY);
+        setup.context.process_and_validate_instruction(&execute, &[custom_error(StakingError::RewardCampaignActive)]);
+        assert_eq!(reward_rate(&setup), 10);
+
+        // Once the campaign is over the rate is set by hand again
+        set_time(&mut setup.context, START_TIMESTAMP + campaign_duration);
+        setup.context.process_and_validate_instruction(&execute, &[Check::success()]);
+        assert_eq!(reward_rate(&setup), 500);
+    }
+
+    #[test]
+    fn test_funding_needs_an_amount_and_a_duration() {
+        let setup = setup_pool();
+
+        fund(&setup, 0, 1000, custom_error(StakingError::ZeroAmount));
+        fund(&setup, 50_000, 0, custom_error(StakingError::InvalidCampaignDuration));
+        fund(&setup, 50_000, -1, custom_error(StakingError::InvalidCampaignDuration));
+        assert_eq!(token_amount(&setup.context, &setup.pool.reward_vault), 0);
+    }
+}
@@ -1,12 +1,15 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, sysvars::{clock::Clock, Sysvar}, *};
 use pinocchio_token::{state::{TokenAccount, Mint}, instructions::{TransferChecked, MintToChecked}};
 
-use crate::states::{helper::AccountData, staking_pool_account::StakingPool, user_stake_account::UserStakeAccount, global_config::GlobalConfig};
+use crate::error::StakingError;
+use crate::events::{Event, IncreaseStakeEvent};
+use crate::rewards::{reset_reward_debt, settle_position_rewards, update_pool_rewards};
+use crate::states::{helper::AccountData, staking_pool_account::{PoolTypeEnum, StakingPool}, user_stake_account::{StakePosition, UserStakeAccount}, global_config::GlobalConfig};
 
 pub fn process_increase_stake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,  
-        authority,              // Add this for global config authority
+        _authority,              // Add this for global config authority
         stake_token_mint,
         stake_token_vault,
         liquid_stake_mint,
@@ -14,10 +17,11 @@ pub fn process_increase_stake(accounts: &[AccountInfo], instruction_data: &[u8])
         staking_pool_account,
         user_token_account,
         user_stake_account,
+        stake_position_account,
         user_lst_token_account,
-        token_program
+        _token_program
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -37,98 +41,100 @@ pub fn process_increase_stake(accounts: &[AccountInfo], instruction_data: &[u8])
     );
 
     if stake_amount == 0 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::ZeroAmount.into());
     }
 
     // Get global config for mint authority
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
 
     if staking_pool.pool_status != 0 {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::PoolNotActive.into());
     }
 
     if staking_pool.emergency_pause_flag {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::PoolEmergencyPause.into());
     }
 
     if global_config.emergency_pause {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::GlobalEmergencyPause.into());
     }
 
     if stake_amount < staking_pool.minimum_stake_amount {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::BelowMinimumStake.into());
     }
 
     if staking_pool.maximum_stake_limit > 0 && 
        staking_pool.total_staked.saturating_add(stake_amount) > staking_pool.maximum_stake_limit {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::StakeLimitExceeded.into());
     }
 
-    let user_token_info = TokenAccount::from_account_info(user_token_account)?;
-    let stake_vault_info = TokenAccount::from_account_info(stake_token_vault)?;
-    let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+    {
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+        let stake_vault_info = TokenAccount::from_account_info(stake_token_vault)?;
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
 
-    // Validation checks
-    if *user_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        // Validation checks
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_token_info.mint() != *stake_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_token_info.mint() != *stake_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    if user_token_info.amount() < stake_amount {
-        return Err(ProgramError::InsufficientFunds);
-    }
+        if user_token_info.amount() < stake_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
 
-    if *stake_vault_info.mint() != staking_pool.stake_token_mint {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *stake_vault_info.mint() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    if *user_lst_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
+        if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
     }
 
     if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    // Find the existing position for this pool
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if position.is_active && position.pool_id == pool_id {
-            position_index = Some(i);
-            break;
-        }
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
     }
 
-    let position_idx = position_index.ok_or(ProgramError::Custom(1001))?; // Position not found
+    if position.pool_id != pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
     
-    // Update pool rewards before modifying stakes
+    // Update pool rewards and settle the position before modifying stakes
     update_pool_rewards(&mut staking_pool, current_timestamp)?;
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
 
-    // Calculate LST tokens to mint (1:1 ratio in this case)
-    let lst_tokens = stake_amount;
+    // Calculate LST tokens to mint at the pool's current exchange rate
+    let lst_tokens = staking_pool.underlying_to_lst(stake_amount)?;
+
+    if lst_tokens == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
 
     // Transfer stake tokens from user to vault
-    let stake_mint_info = Mint::from_account_info(stake_token_mint)?;
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
     
     TransferChecked {
         from: user_token_account,
@@ -136,34 +142,32 @@ pub fn process_increase_stake(accounts: &[AccountInfo], instruction_data: &[u8])
         mint: stake_token_mint,
         authority: user,
         amount: stake_amount,
-        decimals: stake_mint_info.decimals(),
+        decimals: stake_mint_decimals,
     }.invoke()?;
 
     // Mint LST tokens to user
     let global_config_bump_arr = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let mint_account_info = Mint::from_account_info(liquid_stake_mint)?;
+    let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
 
     MintToChecked {
         mint: liquid_stake_mint,
         account: user_lst_token_account,
         mint_authority: global_config_account,
         amount: lst_tokens,
-        decimals: mint_account_info.decimals()
+        decimals: lst_mint_decimals
     }.invoke_signed(&[signer_seeds])?;
 
     // Update the existing position
-    user_stake.positions[position_idx].staked_amount = 
-        user_stake.positions[position_idx].staked_amount.saturating_add(stake_amount);
-    user_stake.positions[position_idx].lst_tokens = 
-        user_stake.positions[position_idx].lst_tokens.saturating_add(lst_tokens);
-    user_stake.positions[position_idx].last_reward_update = current_timestamp;
+    position.staked_amount = position.staked_amount.saturating_add(stake_amount);
+    position.lst_tokens = position.lst_tokens.saturating_add(lst_tokens);
+    reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
 
     // Update user stake totals
     user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_add(stake_amount);
@@ -173,30 +177,17 @@ pub fn process_increase_stake(accounts: &[AccountInfo], instruction_data: &[u8])
     // Update pool totals
     staking_pool.total_staked = staking_pool.total_staked.saturating_add(stake_amount);
     staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_add(stake_amount);
 
-    Ok(())
-}
-
-fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
-    if pool.total_staked == 0 {
-        return Ok(());
-    }
-
-    let time_elapsed = current_timestamp.saturating_sub(pool.creation_timestamp).max(0) as u64;
-    if time_elapsed == 0 {
-        return Ok(());
-    }
-
-    let rewards_earned = (pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128);
-    
-    let scaling_factor = 1_000_000_000_000u128; 
-    let reward_per_share = rewards_earned
-        .saturating_mul(scaling_factor)
-        .saturating_div(pool.total_staked as u128);
-
-    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
-        .saturating_add(reward_per_share);
+    IncreaseStakeEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        pool_id,
+        amount: stake_amount,
+        lst_minted: lst_tokens,
+        timestamp: current_timestamp,
+    }.emit();
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,17 +1,19 @@
-use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{rent::Rent, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::{instructions::InitializeAccount3, state::TokenAccount};
+use pinocchio_token::{instructions::{InitializeAccount3, Transfer}, state::TokenAccount};
 
-use crate::states::{global_config::GlobalConfig, helper::AccountData};
+use crate::error::StakingError;
+use crate::events::{Event, TreasuryWithdrawnEvent};
+use crate::states::{global_config::{GlobalConfig, MAX_POOLS}, helper::AccountData, roles::{RoleEnum, Roles}};
 
 pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     
-    let [authority, mint, global_config_account, treasury_account, system_program, token_program] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+    let [authority, mint, global_config_account, treasury_account, _system_program, _token_program, config_id_account @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::MissingRequiredSignature);
     };
 
     if instruction_data.len() < 14 {
@@ -30,34 +32,47 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
         instruction_data[10..14].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
+    // The PDA's seed for life, defaults to the creating authority like configs made before it existed.
+    // Any other id has to sign, so nobody can claim the config PDA of an id they don't hold
+    let config_id = match config_id_account.first() {
+        Some(config_id_account) => {
+            if !config_id_account.is_signer() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            *config_id_account.key()
+        },
+        None => *authority.key(),
+    };
+
     if protocol_fee_rate > 10000 { 
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::InvalidFeeRate.into());
     };
 
     if min_stake_amount == 0 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::ZeroAmount.into());
     };
 
-    if max_pools == 0 {
-        return Err(ProgramError::InvalidArgument);
+    if max_pools == 0 || max_pools as usize > MAX_POOLS {
+        return Err(StakingError::InvalidMaxPools.into());
     };
 
     let (global_config_pda, bump1) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
+        &[b"global_config_account", config_id.as_ref()],
         &crate::ID
     );
 
     let (treasury_account_pda, bump2) = pubkey::find_program_address(
-        &[b"treasury_account", mint.key().as_ref(), authority.key().as_ref()],
+        &[b"treasury_account", mint.key().as_ref(), config_id.as_ref()],
         &crate::ID
     );
 
     if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     if *treasury_account.key() != treasury_account_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     if treasury_account.data_is_empty() {
@@ -67,7 +82,7 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
         let seeds = seeds!(
             b"treasury_account", 
             mint.key().as_ref(), 
-            authority.key().as_ref(),
+            config_id.as_ref(),
             bump_ref
         );
         let signer_seeds = Signer::from(&seeds);
@@ -83,7 +98,7 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
         InitializeAccount3 {
             account: treasury_account,
             mint,
-            owner: &authority.key()
+            owner: &global_config_pda
         }.invoke()?;
     } else {
         return Err(ProgramError::AccountAlreadyInitialized);
@@ -95,7 +110,7 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
         let bump_ref = &[bump1];
         let seeds = seeds!(
             b"global_config_account", 
-            authority.key().as_ref(),
+            config_id.as_ref(),
             bump_ref
         );
         let signer_seeds = Signer::from(&seeds);
@@ -108,7 +123,7 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
             owner: &crate::ID
         }.invoke_signed(&[signer_seeds])?;
 
-        let mut global_config_account_info = GlobalConfig::from_account_info_mut(global_config_account)?;
+        let mut global_config_account_info = GlobalConfig::initialize(global_config_account)?;
         global_config_account_info.authority = *authority.key();
         global_config_account_info.treasury = *treasury_account.key();
         global_config_account_info.protocol_fee_rate = protocol_fee_rate;
@@ -117,9 +132,11 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
         global_config_account_info.emergency_pause = true;
         global_config_account_info.total_pools_created = 0;
         global_config_account_info.active_pools = 0;
-        global_config_account_info.active_pool_keys = Vec::new();
+        global_config_account_info.active_pool_keys = [Pubkey::default(); MAX_POOLS];
         global_config_account_info.bump = bump1;
         global_config_account_info.treasury_bump = bump2;
+        global_config_account_info.config_id = config_id;
+        global_config_account_info.pending_authority = Pubkey::default();
     } else {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
@@ -127,124 +144,318 @@ pub fn process_initialize_global_config(accounts: &[AccountInfo], instruction_da
     Ok(())
 }
 
+/// Creates the config's treasury for a mint other than the one it was initialized with. Anyone can
+/// pay for it, fees only ever land in `GlobalConfig::check_treasury` accounts.
+///
+/// Treasuries are owned by the config PDA, so they follow the config through authority transfers
+/// and only move through WithdrawTreasury.
+pub fn process_init_treasury_account(accounts: &[AccountInfo]) -> ProgramResult {
+    let [
+        payer,                 // Pays for the treasury
+        global_config_account, // Global config PDA
+        mint,                  // Mint the treasury holds
+        treasury_account,      // Treasury PDA, created here
+        _system_program,       // System program
+        _token_program,        // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !payer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (config_id, _global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let (treasury_account_pda, treasury_bump) = pubkey::find_program_address(
+        &[b"treasury_account", mint.key().as_ref(), config_id.as_ref()],
+        &crate::ID
+    );
+
+    if *treasury_account.key() != treasury_account_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !treasury_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let bump_ref = &[treasury_bump];
+    let seeds = seeds!(
+        b"treasury_account",
+        mint.key().as_ref(),
+        config_id.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: payer,
+        to: treasury_account,
+        lamports: Rent::get()?.minimum_balance(TokenAccount::LEN),
+        space: TokenAccount::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeAccount3 {
+        account: treasury_account,
+        mint,
+        owner: global_config_account.key()
+    }.invoke()
+}
+
+/// Moves collected fees out of one of the config's treasuries, signed by the config PDA that owns them.
+pub fn process_withdraw_treasury(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        treasury_manager,      // Holder of the TreasuryManager role
+        global_config_account, // Global config PDA, owns the treasury
+        roles_account,         // Roles PDA of the global config
+        treasury_account,      // Treasury to withdraw from
+        destination_account,   // Token account receiving the withdrawal
+        _token_program,        // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let amount = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    Roles::check_role(global_config_account, roles_account, treasury_manager, RoleEnum::TreasuryManager)?;
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let treasury_mint = {
+        let treasury_info = TokenAccount::from_account_info(treasury_account)?;
+
+        if treasury_info.amount() < amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+
+        *treasury_info.mint()
+    };
+
+    GlobalConfig::from_account_info(global_config_account)?.check_treasury(treasury_account, &treasury_mint)?;
+
+    let bump_ref = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    Transfer {
+        from: treasury_account,
+        to: destination_account,
+        authority: global_config_account,
+        amount,
+    }.invoke_signed(&[signer_seeds])?;
+
+    TreasuryWithdrawnEvent {
+        global_config: *global_config_account.key(),
+        treasury: *treasury_account.key(),
+        destination: *destination_account.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
 // ====================== TESTING process_initialize_global_config ======================
-// #[cfg(test)]
-// mod testing {
-//     use std::vec;
-
-//     use super::*;
-//     use mollusk_svm::{program, Mollusk, result::Check};
-//     use pinocchio_token::state::Mint;
-//     use solana_sdk::{
-//         account::Account,
-//         instruction::{AccountMeta, Instruction},
-//         pubkey::Pubkey,
-//         pubkey,
-//         system_program
-//     };
-
-//     const PROGRAM_ID: Pubkey = pubkey!("HP1VTBW6YdDjLDYNpn94EpaHz6QJ2LHKjBuGeuJJGjES");
-//     const AUTHORITY: Pubkey = Pubkey::new_from_array([9u8; 32]);
-//     const MINT: Pubkey = Pubkey::new_from_array([7u8; 32]);
-
-//     #[test]
-//     fn test_process_initialize_global_config() {
-//         // Correct path to the BPF program
-//         let mollusk = Mollusk::new(&PROGRAM_ID, "target/deploy/staking_platform");
-
-//         let (global_config_pda, _bump1) = Pubkey::find_program_address(
-//             &[b"global_config_account", AUTHORITY.as_ref()],
-//             &PROGRAM_ID
-//         );  
-
-//         let (treasury_account_pda, _bump2) = Pubkey::find_program_address(
-//             &[b"treasury_account", MINT.as_ref(), AUTHORITY.as_ref()],
-//             &PROGRAM_ID
-//         );   
-
-//         // Use mollusk's built-in program support for system program
-//         let (system_program_id, system_account) = program::keyed_account_for_system_program();
-
-//         let token_program_account = Account {
-//             lamports: 0,
-//             data: vec![],
-//             owner: Pubkey::default(),
-//             executable: true,
-//             rent_epoch: 0,
-//         };
-
-//         let protocol_fee_rate: u16 = 500; // 5%
-//         let min_stake_amount: u64 = 1000000; // 1 SOL in lamports
-//         let max_pools: u32 = 100;
-
-//         // Create instruction data with discriminator byte first
-//         let mut instruction_data = vec![0u8; 15]; // 1 byte discriminator + 14 bytes data
-//         instruction_data[0] = 0; // Discriminator for InitConfigAccount instruction
-//         instruction_data[1..3].copy_from_slice(&protocol_fee_rate.to_le_bytes());
-//         instruction_data[3..11].copy_from_slice(&min_stake_amount.to_le_bytes());
-//         instruction_data[11..15].copy_from_slice(&max_pools.to_le_bytes());
-
-//         let instruction = Instruction {
-//             program_id: PROGRAM_ID,
-//             accounts: vec![
-//                 AccountMeta::new(AUTHORITY, true),
-//                 AccountMeta::new_readonly(MINT, false),
-//                 AccountMeta::new(global_config_pda, false),
-//                 AccountMeta::new(treasury_account_pda, false),
-//                 AccountMeta::new_readonly(system_program_id, false),
-//                 AccountMeta::new_readonly(pinocchio_token::id().into(), false),
-//             ],
-//             data: instruction_data
-//         };
-
-//         let authority_account = Account {
-//             lamports: 10_000_000,
-//             data: vec![],
-//             owner: solana_sdk::system_program::id(),
-//             executable: false,
-//             rent_epoch: 0,
-//         };
-
-//         let mut mint_data = vec![0u8; Mint::LEN];
-//         mint_data[0] = 1; 
-//         mint_data[36] = 1; 
-//         mint_data[44] = 9; 
-
-//         let mint_account = Account {
-//             lamports: 1000000,
-//             data: mint_data,
-//             owner: pinocchio_token::id().into(),
-//             executable: false,
-//             rent_epoch: 0,
-//         };
-
-//         let global_config_account = Account {
-//             lamports: 0,
-//             data: vec![],
-//             owner: system_program::id(),
-//             executable: false,
-//             rent_epoch: 0,
-//         };
-
-//         let treasury_account = Account {
-//             lamports: 0,
-//             data: vec![],
-//             owner: system_program::id(), 
-//             executable: false,
-//             rent_epoch: 0,
-//         };
-
-//         mollusk.process_and_validate_instruction(
-//             &instruction,
-//             &vec![
-//                 (AUTHORITY, authority_account),
-//                 (MINT, mint_account),
-//                 (global_config_pda, global_config_account),
-//                 (treasury_account_pda, treasury_account),
-//                 (system_program_id, system_account),
-//                 (pinocchio_token::id().into(), token_program_account),
-//             ],
-//             &[Check::success()],
-//         );
-//     }
-// }
\ No newline at end of file
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::global_config::GlobalConfig;
+    use crate::test_utils::*;
+
+    fn config_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&500u16.to_le_bytes()); // protocol_fee_rate
+        data.extend_from_slice(&1_000_000u64.to_le_bytes()); // min_stake_amount
+        data.extend_from_slice(&100u32.to_le_bytes()); // max_pools
+        data
+    }
+
+    fn init_with_config_id(context: &Context, authority: &Pubkey, mint: &Pubkey, config_id: &Pubkey, config_id_signs: bool, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::InitConfigAccount,
+                vec![
+                    AccountMeta::new(*authority, true),
+                    AccountMeta::new_readonly(*mint, false),
+                    AccountMeta::new(config_pda(config_id), false),
+                    AccountMeta::new(treasury_pda(mint, config_id), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(*config_id, config_id_signs),
+                ],
+                &config_data(),
+            ),
+            &[check],
+        );
+    }
+
+    fn init_treasury(context: &Context, payer: &Pubkey, global_config: &Pubkey, mint: &Pubkey, treasury: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::InitTreasuryAccount,
+                vec![
+                    AccountMeta::new(*payer, true),
+                    AccountMeta::new_readonly(*global_config, false),
+                    AccountMeta::new_readonly(*mint, false),
+                    AccountMeta::new(*treasury, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    fn withdraw(context: &Context, signer: &Pubkey, global_config: &Pubkey, treasury: &Pubkey, destination: &Pubkey, amount: u64, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::WithdrawTreasury,
+                vec![
+                    AccountMeta::new_readonly(*signer, true),
+                    AccountMeta::new_readonly(*global_config, false),
+                    AccountMeta::new_readonly(roles_pda(global_config), false),
+                    AccountMeta::new(*treasury, false),
+                    AccountMeta::new(*destination, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &amount.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn transfer_authority(context: &Context, authority: &Pubkey, global_config: &Pubkey, new_authority: &Pubkey) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ProposeAuthority,
+                vec![AccountMeta::new_readonly(*authority, true), AccountMeta::new(*global_config, false)],
+                new_authority.as_ref(),
+            ),
+            &[Check::success()],
+        );
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::AcceptAuthority,
+                vec![AccountMeta::new_readonly(*new_authority, true), AccountMeta::new(*global_config, false)],
+                &[],
+            ),
+            &[Check::success()],
+        );
+    }
+
+    fn token_owner(context: &Context, key: &Pubkey) -> Pubkey {
+        Pubkey::try_from(&account(context, key).data[32..64]).unwrap()
+    }
+
+    #[test]
+    fn test_process_initialize_global_config() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, mint) = init_config(&context, &authority);
+
+        let config = read::<GlobalConfig>(&context, &global_config);
+        assert_eq!(config.authority, authority.to_bytes());
+        assert_eq!(config.config_id, authority.to_bytes());
+        assert_eq!(config.treasury, treasury_pda(&mint, &authority).to_bytes());
+        assert_eq!(config.pending_authority, [0u8; 32]);
+        assert!(config.emergency_pause);
+        assert_eq!(token_owner(&context, &treasury_pda(&mint, &authority)), global_config);
+
+        init_with_config_id(&context, &authority, &mint, &authority, true, Check::err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_config_id_has_to_sign() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let mint = create_mint(&context, &authority, 9);
+        let config_id = Pubkey::new_unique();
+
+        // Otherwise anyone could take the config PDA of an id they don't hold
+        init_with_config_id(&context, &authority, &mint, &config_id, false, Check::err(ProgramError::MissingRequiredSignature));
+
+        init_with_config_id(&context, &authority, &mint, &config_id, true, Check::success());
+
+        let config = read::<GlobalConfig>(&context, &config_pda(&config_id));
+        assert_eq!(config.authority, authority.to_bytes());
+        assert_eq!(config.config_id, config_id.to_bytes());
+        assert_eq!(config.treasury, treasury_pda(&mint, &config_id).to_bytes());
+    }
+
+    #[test]
+    fn test_treasury_for_another_mint() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let payer = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        let reward_mint = create_mint(&context, &authority, 6);
+        let treasury = treasury_pda(&reward_mint, &authority);
+
+        init_treasury(&context, &payer, &global_config, &reward_mint, &Pubkey::new_unique(), custom_error(StakingError::InvalidPda));
+
+        // Anyone can pay, the config PDA owns what lands there
+        init_treasury(&context, &payer, &global_config, &reward_mint, &treasury, Check::success());
+        assert_eq!(token_owner(&context, &treasury), global_config);
+
+        init_treasury(&context, &payer, &global_config, &reward_mint, &treasury, Check::err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_treasury_follows_authority_transfer() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let new_authority = funded_wallet(&context);
+        let outsider = funded_wallet(&context);
+        let (global_config, mint) = init_config(&context, &authority);
+        let treasury = treasury_pda(&mint, &authority);
+        set_token_account(&context, treasury, &mint, &global_config, 1_000);
+        let destination = create_token_account(&context, &mint, &new_authority, 0);
+
+        // No roles account yet, only the config authority gets through
+        withdraw(&context, &outsider, &global_config, &treasury, &destination, 100, Check::err(ProgramError::InvalidAccountOwner));
+
+        transfer_authority(&context, &authority, &global_config, &new_authority);
+
+        // The treasury stays with the config, not with the key that created it
+        withdraw(&context, &authority, &global_config, &treasury, &destination, 100, Check::err(ProgramError::InvalidAccountOwner));
+        withdraw(&context, &new_authority, &global_config, &treasury, &destination, 0, custom_error(StakingError::ZeroAmount));
+        withdraw(&context, &new_authority, &global_config, &treasury, &destination, 1_001, custom_error(StakingError::InsufficientTokenBalance));
+        withdraw(&context, &new_authority, &global_config, &treasury, &destination, 400, Check::success());
+
+        assert_eq!(token_amount(&context, &treasury), 600);
+        assert_eq!(token_amount(&context, &destination), 400);
+    }
+
+    #[test]
+    fn test_withdraw_only_from_config_treasuries() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, mint) = init_config(&context, &authority);
+        let destination = create_token_account(&context, &mint, &authority, 0);
+
+        // A token account the config happens to own is still not one of its treasuries
+        let stray = create_token_account(&context, &mint, &global_config, 1_000);
+        withdraw(&context, &authority, &global_config, &stray, &destination, 100, custom_error(StakingError::InvalidPda));
+    }
+}
@@ -2,12 +2,13 @@ use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::P
 use pinocchio_system::instructions::CreateAccount;
 use pinocchio_log::log;
 
-use crate::states::{helper::AccountData, user_stake_account::{ClaimEvent, PenaltyEvent, StakePosition, UserStakeAccount, MAX_HISTORY, MAX_POSITIONS}};
+use crate::error::StakingError;
+use crate::states::{global_config::GlobalConfig, helper::AccountData, user_stake_account::{ClaimEvent, PenaltyEvent, UserStakeAccount, MAX_HISTORY}};
 
 pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> ProgramResult {
 
-    let [user, authority, global_config_account, user_token_account, user_stake_account, _system_program] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+    let [user, _authority, global_config_account, user_token_account, user_stake_account, _system_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -22,14 +23,7 @@ pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> Progra
         return Err(ProgramError::UninitializedAccount);
     };
 
-    let (global_config_pda, _bump1) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    GlobalConfig::check_pda(global_config_account)?;
 
     let (user_stake_account_pda, user_stake_account_bump) = pubkey::find_program_address(
         &[b"user_stake_account", user.key().as_ref(), global_config_account.key().as_ref()],
@@ -37,7 +31,7 @@ pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> Progra
     );
 
     if *user_stake_account.key() != user_stake_account_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     let lamports = Rent::get()?.minimum_balance(UserStakeAccount::SIZE);
@@ -59,7 +53,7 @@ pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> Progra
         owner: &crate::ID,
     }.invoke_signed(&[signer_seeds])?;
 
-    let mut user_stake_account_info = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut user_stake_account_info = UserStakeAccount::initialize(user_stake_account)?;
 
     user_stake_account_info.owner = *user.key();
     user_stake_account_info.global_config = *global_config_account.key();
@@ -70,7 +64,7 @@ pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> Progra
     user_stake_account_info.active_positions = 0;
     user_stake_account_info.creation_timestamp = Clock::get()?.unix_timestamp;
     user_stake_account_info.is_paused = false;
-    user_stake_account_info.positions = [StakePosition::default(); MAX_POSITIONS];
+    user_stake_account_info.next_position_index = 0;
     
     user_stake_account_info.total_earned = 0;
     user_stake_account_info.total_claimed = 0;
@@ -81,6 +75,7 @@ pub fn process_initialize_user_stake_account(accounts: &[AccountInfo]) -> Progra
 
     user_stake_account_info.total_penalties = 0;
     user_stake_account_info.active_penalties = 0;
+    user_stake_account_info.next_penalty_id = 0;
     user_stake_account_info.penalty_type_count = 0;
     user_stake_account_info.penalty_history = [PenaltyEvent::default(); MAX_HISTORY];
 
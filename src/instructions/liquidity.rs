@@ -0,0 +1,924 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{instructions::{BurnChecked, InitializeAccount3, InitializeMint2, MintToChecked, TransferChecked}, state::{Mint, TokenAccount}};
+
+use crate::error::StakingError;
+use crate::events::{Event, InstantUnstakeEvent, LiquidityAddedEvent, LiquidityRemovedEvent};
+use crate::penalty_payout::PenaltyPayout;
+use crate::rewards::{reset_reward_debt, settle_position_rewards, update_pool_rewards};
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    liquidity_pool::LiquidityPool,
+    staking_pool_account::{PoolTypeEnum, StakingPool},
+    user_stake_account::{StakePosition, UserStakeAccount}
+};
+
+pub fn process_initialize_liquidity_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,              // Pool authority, pays for the new accounts
+        stake_token_mint,       // Token being staked
+        global_config_account,  // Global config PDA, owns the vault and mints LP shares
+        staking_pool_account,   // Pool account
+        liquidity_pool_account, // Liquidity pool PDA, created here
+        lp_mint,                // LP mint PDA, created here
+        liquidity_vault,        // Liquidity vault PDA, created here
+        _system_program,        // System program
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 20 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let target_liquidity = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let min_fee_bps = u16::from_le_bytes(
+        instruction_data[16..18].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let max_fee_bps = u16::from_le_bytes(
+        instruction_data[18..20].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if min_fee_bps > max_fee_bps || max_fee_bps > 10000 {
+        return Err(StakingError::InvalidFeeCurve.into());
+    }
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+        // The fee curve and target are fixed here, only the pool authority gets to pick them
+        if staking_pool.authority != *authority.key() {
+            return Err(StakingError::Unauthorized.into());
+        }
+
+        if *stake_token_mint.key() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let (liquidity_pool_pda, liquidity_pool_bump) = pubkey::find_program_address(
+        &[b"liquidity_pool", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *liquidity_pool_account.key() != liquidity_pool_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (lp_mint_pda, lp_mint_bump) = pubkey::find_program_address(
+        &[b"lp_mint", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *lp_mint.key() != lp_mint_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (liquidity_vault_pda, liquidity_vault_bump) = pubkey::find_program_address(
+        &[b"liquidity_vault", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *liquidity_vault.key() != liquidity_vault_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !liquidity_pool_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = Rent::get()?;
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
+
+    let bump_ref = &[lp_mint_bump];
+    let seeds = seeds!(
+        b"lp_mint",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: lp_mint,
+        lamports: rent.minimum_balance(Mint::LEN),
+        space: Mint::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    // Shares track the stake token one to one until fees accrue
+    InitializeMint2 {
+        mint: lp_mint,
+        decimals: stake_mint_decimals,
+        mint_authority: global_config_account.key(),
+        freeze_authority: Some(global_config_account.key()),
+    }.invoke()?;
+
+    let bump_ref = &[liquidity_vault_bump];
+    let seeds = seeds!(
+        b"liquidity_vault",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: liquidity_vault,
+        lamports: rent.minimum_balance(TokenAccount::LEN),
+        space: TokenAccount::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeAccount3 {
+        account: liquidity_vault,
+        mint: stake_token_mint,
+        owner: global_config_account.key()
+    }.invoke()?;
+
+    let bump_ref = &[liquidity_pool_bump];
+    let seeds = seeds!(
+        b"liquidity_pool",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: liquidity_pool_account,
+        lamports: rent.minimum_balance(LiquidityPool::SIZE),
+        space: LiquidityPool::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut liquidity_pool = LiquidityPool::initialize(liquidity_pool_account)?;
+    liquidity_pool.staking_pool = *staking_pool_account.key();
+    liquidity_pool.lp_mint = lp_mint_pda;
+    liquidity_pool.liquidity_vault = liquidity_vault_pda;
+    liquidity_pool.target_liquidity = target_liquidity;
+    liquidity_pool.min_fee_bps = min_fee_bps;
+    liquidity_pool.max_fee_bps = max_fee_bps;
+    liquidity_pool.bump = liquidity_pool_bump;
+
+    Ok(())
+}
+
+pub fn process_add_liquidity(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        provider,               // LP (signer)
+        _authority,              // Global config authority
+        stake_token_mint,       // Token being staked
+        global_config_account,  // Global config PDA
+        staking_pool_account,   // Pool account
+        liquidity_pool_account, // Liquidity pool PDA
+        lp_mint,                // LP mint
+        liquidity_vault,        // Liquidity vault
+        provider_token_account, // LP's stake token account
+        provider_lp_account,    // LP's share token account
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !provider.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let amount = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let mut liquidity_pool = LiquidityPool::from_account_info_mut(liquidity_pool_account)?;
+
+    check_liquidity_accounts(&liquidity_pool, staking_pool_account, lp_mint, liquidity_vault)?;
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if *stake_token_mint.key() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    {
+        let provider_token_info = TokenAccount::from_account_info(provider_token_account)?;
+
+        if *provider_token_info.owner() != *provider.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if provider_token_info.amount() < amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+
+        let provider_lp_info = TokenAccount::from_account_info(provider_lp_account)?;
+
+        if *provider_lp_info.owner() != *provider.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *provider_lp_info.mint() != *lp_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    // Priced off the tracked liquidity, a donation to the vault can't round later deposits down to nothing
+    let total_value = liquidity_pool.total_value();
+
+    // Shares minted at the current value per share, rounded down in favour of existing LPs
+    let lp_amount = if liquidity_pool.lp_supply == 0 || total_value == 0 {
+        amount
+    } else {
+        ((amount as u128)
+            .saturating_mul(liquidity_pool.lp_supply as u128)
+            / total_value as u128) as u64
+    };
+
+    if lp_amount == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    TransferChecked {
+        from: provider_token_account,
+        to: liquidity_vault,
+        mint: stake_token_mint,
+        authority: provider,
+        amount,
+        decimals: Mint::from_account_info(stake_token_mint)?.decimals(),
+    }.invoke()?;
+
+    let global_config_bump = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    MintToChecked {
+        mint: lp_mint,
+        account: provider_lp_account,
+        mint_authority: global_config_account,
+        amount: lp_amount,
+        decimals: Mint::from_account_info(lp_mint)?.decimals(),
+    }.invoke_signed(&[signer_seeds])?;
+
+    liquidity_pool.lp_supply = liquidity_pool.lp_supply.saturating_add(lp_amount);
+    liquidity_pool.liquidity = liquidity_pool.liquidity.saturating_add(amount);
+
+    LiquidityAddedEvent {
+        provider: *provider.key(),
+        staking_pool: *staking_pool_account.key(),
+        amount,
+        lp_minted: lp_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_remove_liquidity(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        provider,               // LP (signer)
+        _authority,              // Global config authority
+        stake_token_mint,       // Token being staked
+        global_config_account,  // Global config PDA
+        staking_pool_account,   // Pool account
+        liquidity_pool_account, // Liquidity pool PDA
+        lp_mint,                // LP mint
+        liquidity_vault,        // Liquidity vault
+        provider_token_account, // LP's stake token account
+        provider_lp_account,    // LP's share token account
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !provider.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let lp_amount = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if lp_amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let mut liquidity_pool = LiquidityPool::from_account_info_mut(liquidity_pool_account)?;
+
+    check_liquidity_accounts(&liquidity_pool, staking_pool_account, lp_mint, liquidity_vault)?;
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if *stake_token_mint.key() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    {
+        let provider_token_info = TokenAccount::from_account_info(provider_token_account)?;
+
+        if *provider_token_info.owner() != *provider.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *provider_token_info.mint() != *stake_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        let provider_lp_info = TokenAccount::from_account_info(provider_lp_account)?;
+
+        if *provider_lp_info.owner() != *provider.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if provider_lp_info.amount() < lp_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+    }
+
+    if liquidity_pool.lp_supply == 0 {
+        return Err(StakingError::InsufficientLiquidity.into());
+    }
+
+    let amount = ((lp_amount as u128)
+        .saturating_mul(liquidity_pool.total_value() as u128)
+        / liquidity_pool.lp_supply as u128) as u64;
+
+    if amount == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    // Pending backing can't be paid out until ReplenishLiquidity has moved it into the vault
+    if amount > liquidity_pool.liquidity {
+        return Err(StakingError::InsufficientLiquidity.into());
+    }
+
+    BurnChecked {
+        mint: lp_mint,
+        account: provider_lp_account,
+        authority: provider,
+        amount: lp_amount,
+        decimals: Mint::from_account_info(lp_mint)?.decimals(),
+    }.invoke()?;
+
+    let global_config_bump = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    TransferChecked {
+        from: liquidity_vault,
+        to: provider_token_account,
+        mint: stake_token_mint,
+        authority: global_config_account,
+        amount,
+        decimals: Mint::from_account_info(stake_token_mint)?.decimals(),
+    }.invoke_signed(&[signer_seeds])?;
+
+    liquidity_pool.lp_supply = liquidity_pool.lp_supply.saturating_sub(lp_amount);
+    liquidity_pool.liquidity = liquidity_pool.liquidity.saturating_sub(amount);
+
+    LiquidityRemovedEvent {
+        provider: *provider.key(),
+        staking_pool: *staking_pool_account.key(),
+        amount,
+        lp_burned: lp_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_instant_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,                   // Staker (signer)
+        _authority,              // Global config authority
+        stake_token_mint,       // Token being staked
+        stake_token_vault,      // Vault holding staked tokens
+        liquid_stake_mint,      // LST mint
+        global_config_account,  // Global config PDA
+        staking_pool_account,   // Pool account
+        liquidity_pool_account, // Liquidity pool PDA
+        lp_mint,                // LP mint
+        liquidity_vault,        // Liquidity vault paying the user
+        user_stake_account,     // User's aggregate stake account
+        stake_position_account, // Position the LST comes from
+        user_lst_token_account, // User's LST token account
+        user_token_account,     // User's stake token account
+        treasury_account,       // Receives collected penalty debt routed to the treasury
+        reward_token_vault,     // Receives collected penalty debt routed to the reward vault
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 24 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let lst_amount = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    // Slippage guard, the fee moves with every unstake ahead of this one
+    let min_amount_out = u64::from_le_bytes(
+        instruction_data[16..24].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if lst_amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let global_config = GlobalConfig::from_account_info(global_config_account)?;
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+    let mut liquidity_pool = LiquidityPool::from_account_info_mut(liquidity_pool_account)?;
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    check_liquidity_accounts(&liquidity_pool, staking_pool_account, lp_mint, liquidity_vault)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if global_config.emergency_pause {
+        return Err(StakingError::GlobalEmergencyPause.into());
+    }
+
+    if staking_pool.emergency_pause_flag {
+        return Err(StakingError::PoolEmergencyPause.into());
+    }
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
+    }
+
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
+    }
+
+    if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if *stake_token_mint.key() != staking_pool.stake_token_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if *stake_token_vault.key() != staking_pool.stake_token_vault {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    if user_stake.has_open_penalty(stake_position_account.key()) {
+        return Err(StakingError::PenaltyPending.into());
+    }
+
+    if position.lst_tokens < lst_amount {
+        return Err(StakingError::InsufficientPositionBalance.into());
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    // Locked stake exits through RequestUnstake, where the early-unstake penalty applies
+    if position.lock_exipry_enable && current_timestamp < position.lock_expiry {
+        return Err(StakingError::PositionLocked.into());
+    }
+
+    {
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if user_lst_token_info.amount() < lst_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_token_info.mint() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let available_liquidity = liquidity_pool.liquidity;
+
+    let underlying_tokens = staking_pool.lst_to_underlying(lst_amount)?;
+
+    if underlying_tokens == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    // Upheld penalties still owed on the position come out first, as they do on RequestUnstake
+    let penalty_debt_collected = position.penalty_debt.min(underlying_tokens);
+    let net_amount = underlying_tokens.saturating_sub(penalty_debt_collected);
+
+    let fee_bps = liquidity_pool.unstake_fee_bps(available_liquidity.saturating_sub(net_amount));
+    let fee_amount = ((net_amount as u128).saturating_mul(fee_bps as u128) / 10000u128) as u64;
+    let payout_amount = net_amount.saturating_sub(fee_amount);
+
+    if payout_amount < min_amount_out {
+        return Err(StakingError::SlippageExceeded.into());
+    }
+
+    if payout_amount > available_liquidity {
+        return Err(StakingError::InsufficientLiquidity.into());
+    }
+
+    let principal_amount = ((position.staked_amount as u128)
+        .saturating_mul(lst_amount as u128)
+        / position.lst_tokens as u128) as u64;
+
+    update_pool_rewards(&mut staking_pool, current_timestamp)?;
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
+
+    BurnChecked {
+        mint: liquid_stake_mint,
+        account: user_lst_token_account,
+        authority: user,
+        amount: lst_amount,
+        decimals: Mint::from_account_info(liquid_stake_mint)?.decimals(),
+    }.invoke()?;
+
+    let global_config_bump = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump
+    );
+
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
+
+    // Backing that already finished its cooldown goes in first
+    let unlocked_underlying = liquidity_pool.take_unlocked(current_timestamp);
+
+    if unlocked_underlying > 0 {
+        TransferChecked {
+            from: stake_token_vault,
+            to: liquidity_vault,
+            mint: stake_token_mint,
+            authority: global_config_account,
+            amount: unlocked_underlying,
+            decimals: stake_mint_decimals,
+        }.invoke_signed(&[Signer::from(&seeds)])?;
+
+        staking_pool.total_pending_unstake = staking_pool.total_pending_unstake
+            .saturating_sub(unlocked_underlying);
+        liquidity_pool.liquidity = liquidity_pool.liquidity.saturating_add(unlocked_underlying);
+    }
+
+    TransferChecked {
+        from: liquidity_vault,
+        to: user_token_account,
+        mint: stake_token_mint,
+        authority: global_config_account,
+        amount: payout_amount,
+        decimals: stake_mint_decimals,
+    }.invoke_signed(&[Signer::from(&seeds)])?;
+
+    // The burned LST's backing is owed to the LPs after the same cooldown a ticket would wait
+    liquidity_pool.liquidity = liquidity_pool.liquidity.saturating_sub(payout_amount);
+    liquidity_pool.queue_pending(net_amount, current_timestamp.saturating_add(staking_pool.cooldown_period));
+    liquidity_pool.total_fees_earned = liquidity_pool.total_fees_earned.saturating_add(fee_amount);
+
+    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_sub(lst_amount);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_sub(underlying_tokens);
+    staking_pool.total_pending_unstake = staking_pool.total_pending_unstake.saturating_add(net_amount);
+    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(principal_amount);
+
+    // Debt held for a later recovery stays in the vault, the rest goes where the pool routes penalties
+    let reserved_debt = user_stake.collect_penalty_debt(stake_position_account.key(), penalty_debt_collected);
+    staking_pool.penalty_reserve = staking_pool.penalty_reserve.saturating_add(reserved_debt);
+    position.penalty_debt = position.penalty_debt.saturating_sub(penalty_debt_collected);
+
+    PenaltyPayout {
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        treasury_account,
+        reward_token_vault,
+        amount: penalty_debt_collected.saturating_sub(reserved_debt),
+    }.invoke_signed(&mut staking_pool, &global_config, &[Signer::from(&seeds)])?;
+
+    position.staked_amount = position.staked_amount.saturating_sub(principal_amount);
+    position.lst_tokens = position.lst_tokens.saturating_sub(lst_amount);
+    reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+
+    if position.lst_tokens == 0 {
+        position.is_active = false;
+        user_stake.active_positions = user_stake.active_positions.saturating_sub(1);
+    }
+
+    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_sub(principal_amount);
+    user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_sub(lst_amount);
+    user_stake.last_update_timestamp = current_timestamp;
+
+    InstantUnstakeEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        lst_burned: lst_amount,
+        amount_out: payout_amount,
+        fee_amount,
+        fee_bps,
+        timestamp: current_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_replenish_liquidity(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        _authority,              // Global config authority
+        stake_token_mint,       // Token being staked
+        stake_token_vault,      // Vault holding the pending backing
+        global_config_account,  // Global config PDA
+        staking_pool_account,   // Pool account
+        liquidity_pool_account, // Liquidity pool PDA
+        lp_mint,                // LP mint
+        liquidity_vault,        // Liquidity vault
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+    let mut liquidity_pool = LiquidityPool::from_account_info_mut(liquidity_pool_account)?;
+
+    check_liquidity_accounts(&liquidity_pool, staking_pool_account, lp_mint, liquidity_vault)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if *stake_token_vault.key() != staking_pool.stake_token_vault {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    if *stake_token_mint.key() != staking_pool.stake_token_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if liquidity_pool.pending_underlying == 0 {
+        return Err(StakingError::NothingToWithdraw.into());
+    }
+
+    let amount = liquidity_pool.take_unlocked(Clock::get()?.unix_timestamp);
+
+    if amount == 0 {
+        return Err(StakingError::CooldownActive.into());
+    }
+
+    let global_config_bump = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    TransferChecked {
+        from: stake_token_vault,
+        to: liquidity_vault,
+        mint: stake_token_mint,
+        authority: global_config_account,
+        amount,
+        decimals: Mint::from_account_info(stake_token_mint)?.decimals(),
+    }.invoke_signed(&[signer_seeds])?;
+
+    staking_pool.total_pending_unstake = staking_pool.total_pending_unstake.saturating_sub(amount);
+    liquidity_pool.liquidity = liquidity_pool.liquidity.saturating_add(amount);
+
+    Ok(())
+}
+
+fn check_liquidity_accounts(
+    liquidity_pool: &LiquidityPool,
+    staking_pool_account: &AccountInfo,
+    lp_mint: &AccountInfo,
+    liquidity_vault: &AccountInfo,
+) -> ProgramResult {
+    if liquidity_pool.staking_pool != *staking_pool_account.key() {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if liquidity_pool.lp_mint != *lp_mint.key() {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if liquidity_pool.liquidity_vault != *liquidity_vault.key() {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::instructions::StakingInstructions;
+    use crate::states::liquidity_pool::LiquidityPool;
+    use crate::test_utils::*;
+
+    struct TestLiquidity {
+        liquidity_pool: Pubkey,
+        lp_mint: Pubkey,
+        liquidity_vault: Pubkey,
+    }
+
+    fn init_liquidity(context: &Context, pool: &TestPool) -> TestLiquidity {
+        let seeded = |seed: &[u8]| Pubkey::find_program_address(&[seed, pool.staking_pool.as_ref()], &PROGRAM_ID).0;
+        let liquidity = TestLiquidity {
+            liquidity_pool: seeded(b"liquidity_pool"),
+            lp_mint: seeded(b"lp_mint"),
+            liquidity_vault: seeded(b"liquidity_vault"),
+        };
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&1_000_000u64.to_le_bytes()); // target_liquidity
+        data.extend_from_slice(&10u16.to_le_bytes()); // min_fee_bps
+        data.extend_from_slice(&300u16.to_le_bytes()); // max_fee_bps
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::InitializeLiquidityPool,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new_readonly(pool.stake_mint, false),
+                    AccountMeta::new_readonly(pool.global_config, false),
+                    AccountMeta::new_readonly(pool.staking_pool, false),
+                    AccountMeta::new(liquidity.liquidity_pool, false),
+                    AccountMeta::new(liquidity.lp_mint, false),
+                    AccountMeta::new(liquidity.liquidity_vault, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+
+        liquidity
+    }
+
+    struct TestProvider {
+        provider: Pubkey,
+        token_account: Pubkey,
+        lp_account: Pubkey,
+    }
+
+    // Wallet holding `balance` stake tokens and an empty LP share account
+    fn create_provider(context: &Context, pool: &TestPool, liquidity: &TestLiquidity, balance: u64) -> TestProvider {
+        let provider = funded_wallet(context);
+
+        TestProvider {
+            provider,
+            token_account: create_token_account(context, &pool.stake_mint, &provider, balance),
+            lp_account: create_token_account(context, &liquidity.lp_mint, &provider, 0),
+        }
+    }
+
+    fn move_liquidity(
+        context: &Context,
+        instruction_type: StakingInstructions,
+        pool: &TestPool,
+        liquidity: &TestLiquidity,
+        provider: &TestProvider,
+        amount: u64,
+        check: Check
+    ) {
+        context.process_and_validate_instruction(
+            &instruction(
+                instruction_type,
+                vec![
+                    AccountMeta::new(provider.provider, true),
+                    AccountMeta::new_readonly(pool.authority, false),
+                    AccountMeta::new_readonly(pool.stake_mint, false),
+                    AccountMeta::new_readonly(pool.global_config, false),
+                    AccountMeta::new_readonly(pool.staking_pool, false),
+                    AccountMeta::new(liquidity.liquidity_pool, false),
+                    AccountMeta::new(liquidity.lp_mint, false),
+                    AccountMeta::new(liquidity.liquidity_vault, false),
+                    AccountMeta::new(provider.token_account, false),
+                    AccountMeta::new(provider.lp_account, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &amount.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_vault_donation_does_not_move_the_share_price() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let liquidity = init_liquidity(&context, &pool);
+
+        let attacker = create_provider(&context, &pool, &liquidity, 1);
+        let victim = create_provider(&context, &pool, &liquidity, 500_000);
+
+        move_liquidity(&context, StakingInstructions::AddLiquidity, &pool, &liquidity, &attacker, 1, Check::success());
+
+        // Sent straight to the vault, so it isn't LP liquidity and doesn't price the next deposit
+        set_token_account(&context, liquidity.liquidity_vault, &pool.stake_mint, &global_config, 1 + 1_000_000);
+
+        move_liquidity(&context, StakingInstructions::AddLiquidity, &pool, &liquidity, &victim, 500_000, Check::success());
+        assert_eq!(token_amount(&context, &victim.lp_account), 500_000);
+
+        let liquidity_pool = read::<LiquidityPool>(&context, &liquidity.liquidity_pool);
+        assert_eq!(liquidity_pool.liquidity, 500_001);
+        assert_eq!(liquidity_pool.lp_supply, 500_001);
+
+        // The attacker's single share is still worth a single token
+        move_liquidity(&context, StakingInstructions::RemoveLiquidity, &pool, &liquidity, &attacker, 1, Check::success());
+        assert_eq!(token_amount(&context, &attacker.token_account), 1);
+
+        move_liquidity(&context, StakingInstructions::RemoveLiquidity, &pool, &liquidity, &victim, 500_000, Check::success());
+        assert_eq!(token_amount(&context, &victim.token_account), 500_000);
+
+        let liquidity_pool = read::<LiquidityPool>(&context, &liquidity.liquidity_pool);
+        assert_eq!(liquidity_pool.liquidity, 0);
+        assert_eq!(liquidity_pool.lp_supply, 0);
+    }
+}
@@ -0,0 +1,274 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
+
+use crate::error::StakingError;
+use crate::rewards::{settle_position_rewards, update_pool_rewards};
+use crate::states::{
+    helper::AccountData,
+    staking_pool_account::{LockTier, PoolTypeEnum, StakingPool, MAX_LOCK_TIERS},
+    user_stake_account::StakePosition
+};
+
+const LOCK_TIER_LEN: usize = 10;
+
+pub fn process_set_lock_tiers(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 9 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let tier_count = instruction_data[8] as usize;
+
+    if tier_count > MAX_LOCK_TIERS {
+        return Err(StakingError::InvalidLockTiers.into());
+    }
+
+    // Followed by tier_count (duration: i64, multiplier: u16) entries
+    if instruction_data.len() < 9 + tier_count * LOCK_TIER_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut lock_tiers = [LockTier::default(); MAX_LOCK_TIERS];
+
+    for (index, tier) in lock_tiers.iter_mut().take(tier_count).enumerate() {
+        let offset = 9 + index * LOCK_TIER_LEN;
+
+        tier.duration = i64::from_le_bytes(
+            instruction_data[offset..offset + 8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+        );
+
+        tier.multiplier = u16::from_le_bytes(
+            instruction_data[offset + 8..offset + 10].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+        );
+    }
+
+    // Longer locks can't earn a smaller boost than shorter ones
+    let mut previous = LockTier { duration: 0, multiplier: 100 };
+
+    for tier in &lock_tiers[..tier_count] {
+        if tier.duration <= previous.duration || tier.multiplier < previous.multiplier {
+            return Err(StakingError::InvalidLockTiers.into());
+        }
+
+        previous = *tier;
+    }
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    // Open positions keep the multiplier of the tier they picked
+    staking_pool.lock_tiers = lock_tiers;
+    staking_pool.lock_tier_count = tier_count as u8;
+
+    Ok(())
+}
+
+/// Drops the boost of a position whose lock has expired. Permissionless, so the boosted weight of
+/// idle positions doesn't keep diluting everyone else.
+pub fn process_refresh_lock_boost(accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    let [staking_pool_account, stake_position_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    if position.staking_pool != *staking_pool_account.key() {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    if position.boost_weight == 0 || current_timestamp < position.lock_expiry {
+        return Err(StakingError::LockBoostCurrent.into());
+    }
+
+    update_pool_rewards(&mut staking_pool, current_timestamp)?;
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::staking_pool_account::NO_LOCK_TIER;
+    use crate::test_utils::*;
+
+    const TIER_DURATION: i64 = 1000;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+    }
+
+    // Pool whose reward vault covers every test, with a treasury for the reward mint
+    fn setup_pool() -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+        set_token_account(&context, pool.reward_vault, &pool.reward_mint, &global_config, 1_000_000);
+
+        Setup { context, pool }
+    }
+
+    fn set_lock_tiers(setup: &Setup, authority: &Pubkey, tiers: &[(i64, u16)], check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.push(tiers.len() as u8);
+
+        for (duration, multiplier) in tiers {
+            data.extend_from_slice(&duration.to_le_bytes());
+            data.extend_from_slice(&multiplier.to_le_bytes());
+        }
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::SetLockTiers,
+                vec![
+                    AccountMeta::new_readonly(*authority, true),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn refresh_lock_boost(setup: &Setup, stake_position: &Pubkey, check: Check) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RefreshLockBoost,
+                vec![
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(*stake_position, false),
+                ],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    // Stakes 1000 at position 0 under `tier`, returns the staker, its position and an empty reward account
+    fn stake_in_tier(setup: &Setup, tier: u8) -> (TestStaker, Pubkey, Pubkey) {
+        let staker = create_staker(&setup.context, &setup.pool, 1000);
+        let reward_account = create_token_account(&setup.context, &setup.pool.reward_mint, &staker.user, 0);
+
+        setup.context.process_and_validate_instruction(
+            &stake_instruction(&setup.pool, &staker, 1000, 0, &[tier]),
+            &[Check::success()],
+        );
+
+        let position = position_pda(&staker.user, &setup.pool.staking_pool, 0);
+        (staker, position, reward_account)
+    }
+
+    fn claim(setup: &Setup, (staker, position, reward_account): &(TestStaker, Pubkey, Pubkey)) {
+        claim_rewards(&setup.context, &setup.pool, staker, position, reward_account, &[], Check::success());
+    }
+
+    #[test]
+    fn test_longer_tier_earns_its_boosted_share() {
+        let mut setup = setup_pool();
+        set_lock_tiers(&setup, &setup.pool.authority, &[(TIER_DURATION, 150), (3 * TIER_DURATION, 300)], Check::success());
+
+        let unlocked = stake_in_tier(&setup, NO_LOCK_TIER);
+        let locked = stake_in_tier(&setup, 1);
+
+        advance_time(&mut setup.context, 100);
+        claim(&setup, &unlocked);
+        claim(&setup, &locked);
+
+        // Same stake, three times the weight, and the two shares still add up to what was emitted
+        assert_eq!(token_amount(&setup.context, &unlocked.2), 2_475);
+        assert_eq!(token_amount(&setup.context, &locked.2), 7_425);
+        assert_eq!(1_000_000 - token_amount(&setup.context, &setup.pool.reward_vault), 10_000);
+    }
+
+    #[test]
+    fn test_expired_boost_can_be_dropped_by_anyone() {
+        let mut setup = setup_pool();
+        set_lock_tiers(&setup, &setup.pool.authority, &[(TIER_DURATION, 300)], Check::success());
+
+        let unlocked = stake_in_tier(&setup, NO_LOCK_TIER);
+        let locked = stake_in_tier(&setup, 0);
+
+        refresh_lock_boost(&setup, &locked.1, custom_error(StakingError::LockBoostCurrent));
+        refresh_lock_boost(&setup, &unlocked.1, custom_error(StakingError::LockBoostCurrent));
+
+        advance_time(&mut setup.context, TIER_DURATION);
+        refresh_lock_boost(&setup, &locked.1, Check::success());
+        refresh_lock_boost(&setup, &locked.1, custom_error(StakingError::LockBoostCurrent));
+
+        advance_time(&mut setup.context, 100);
+        claim(&setup, &unlocked);
+        claim(&setup, &locked);
+
+        // 100_000 split 1:3 while locked, then 10_000 split evenly
+        assert_eq!(token_amount(&setup.context, &unlocked.2), 29_700);
+        assert_eq!(token_amount(&setup.context, &locked.2), 79_200);
+    }
+
+    #[test]
+    fn test_tiers_have_to_grow_with_their_duration() {
+        let setup = setup_pool();
+        let outsider = funded_wallet(&setup.context);
+
+        set_lock_tiers(&setup, &outsider, &[(TIER_DURATION, 150)], custom_error(StakingError::Unauthorized));
+        set_lock_tiers(&setup, &setup.pool.authority, &[(TIER_DURATION, 50)], custom_error(StakingError::InvalidLockTiers));
+        set_lock_tiers(&setup, &setup.pool.authority, &[(0, 150)], custom_error(StakingError::InvalidLockTiers));
+        set_lock_tiers(
+            &setup,
+            &setup.pool.authority,
+            &[(TIER_DURATION, 200), (2 * TIER_DURATION, 150)],
+            custom_error(StakingError::InvalidLockTiers),
+        );
+        set_lock_tiers(
+            &setup,
+            &setup.pool.authority,
+            &[(TIER_DURATION, 150), (TIER_DURATION, 200)],
+            custom_error(StakingError::InvalidLockTiers),
+        );
+        set_lock_tiers(
+            &setup,
+            &setup.pool.authority,
+            &[(1, 110), (2, 120), (3, 130), (4, 140), (5, 150)],
+            custom_error(StakingError::InvalidLockTiers),
+        );
+
+        set_lock_tiers(&setup, &setup.pool.authority, &[(TIER_DURATION, 150)], Check::success());
+
+        let staker = create_staker(&setup.context, &setup.pool, 1000);
+        setup.context.process_and_validate_instruction(
+            &stake_instruction(&setup.pool, &staker, 1000, 0, &[1]),
+            &[custom_error(StakingError::InvalidLockTier)],
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::{CreateAccount, Transfer};
+
+use crate::error::StakingError;
+use crate::rewards::{pending_rewards, reset_reward_debt, update_pool_rewards};
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::{AccountData, VERSION_OFFSET},
+    liquidity_pool::LiquidityPool,
+    multisig::{Multisig, MultisigProposal},
+    oracle_config::OracleConfigInfo,
+    pool_config_change::PoolConfigChange,
+    roles::Roles,
+    slashing::{SlashEvidence, SlashReporter},
+    staking_pool_account::StakingPool,
+    swap_pool::SwapPool,
+    user_stake_account::{StakePosition, UserStakeAccount, UserStakeAccountV1, MAX_POSITIONS_V1},
+    validator_list::ValidatorList,
+    withdrawal_ticket::WithdrawalTicket
+};
+
+pub fn process_migrate_account(accounts: &[AccountInfo]) -> ProgramResult {
+    let [payer, account, _system_program, position_accounts @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !payer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !account.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let discriminator: [u8; 8] = {
+        let data = account.try_borrow_data()?;
+
+        if data.len() <= VERSION_OFFSET {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        data[..VERSION_OFFSET].try_into().map_err(|_| ProgramError::InvalidAccountData)?
+    };
+
+    match discriminator {
+        GlobalConfig::DISCRIMINATOR => migrate::<GlobalConfig>(payer, account),
+        StakingPool::DISCRIMINATOR => migrate::<StakingPool>(payer, account),
+        UserStakeAccount::DISCRIMINATOR => migrate_user_stake_account(payer, account, position_accounts),
+        StakePosition::DISCRIMINATOR => migrate::<StakePosition>(payer, account),
+        OracleConfigInfo::DISCRIMINATOR => migrate::<OracleConfigInfo>(payer, account),
+        WithdrawalTicket::DISCRIMINATOR => migrate::<WithdrawalTicket>(payer, account),
+        SlashReporter::DISCRIMINATOR => migrate::<SlashReporter>(payer, account),
+        SlashEvidence::DISCRIMINATOR => migrate::<SlashEvidence>(payer, account),
+        ValidatorList::DISCRIMINATOR => migrate::<ValidatorList>(payer, account),
+        LiquidityPool::DISCRIMINATOR => migrate::<LiquidityPool>(payer, account),
+        SwapPool::DISCRIMINATOR => migrate::<SwapPool>(payer, account),
+        Roles::DISCRIMINATOR => migrate::<Roles>(payer, account),
+        PoolConfigChange::DISCRIMINATOR => migrate::<PoolConfigChange>(payer, account),
+        Multisig::DISCRIMINATOR => migrate::<Multisig>(payer, account),
+        MultisigProposal::DISCRIMINATOR => migrate::<MultisigProposal>(payer, account),
+        _ => Err(StakingError::InvalidDiscriminator.into()),
+    }
+}
+
+// Grows the account to the current layout, topping up rent from the payer, then bumps its version
+fn migrate<T: AccountData>(payer: &AccountInfo, account: &AccountInfo) -> ProgramResult {
+    let from_version = account.try_borrow_data()?[VERSION_OFFSET];
+
+    if from_version == T::VERSION {
+        return Ok(());
+    }
+
+    if from_version > T::VERSION {
+        return Err(StakingError::UnsupportedAccountVersion.into());
+    }
+
+    if account.data_len() < T::SIZE {
+        let required_lamports = Rent::get()?.minimum_balance(T::SIZE);
+        let top_up = required_lamports.saturating_sub(account.lamports());
+
+        if top_up > 0 {
+            Transfer {
+                from: payer,
+                to: account,
+                lamports: top_up,
+            }.invoke()?;
+        }
+
+        // New bytes are zero-filled
+        account.resize(T::SIZE)?;
+    }
+
+    let mut data = account.try_borrow_mut_data()?;
+    T::migrate(&mut data, from_version)?;
+    data[VERSION_OFFSET] = T::VERSION;
+
+    Ok(())
+}
+
+// Version 1 kept positions inline, so this one can't grow in place. Every active inline position
+// moves to its StakePosition PDA at its old slot index, taking a (staking_pool, stake_position) pair
+// from `position_accounts` in slot order
+fn migrate_user_stake_account(payer: &AccountInfo, account: &AccountInfo, position_accounts: &[AccountInfo]) -> ProgramResult {
+    let from_version = account.try_borrow_data()?[VERSION_OFFSET];
+
+    if from_version == UserStakeAccount::VERSION {
+        return Ok(());
+    }
+
+    if from_version != 1 {
+        return Err(StakingError::UnsupportedAccountVersion.into());
+    }
+
+    let legacy = {
+        let data = account.try_borrow_data()?;
+
+        if data.len() < UserStakeAccountV1::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        unsafe { core::ptr::read_unaligned(data.as_ptr() as *const UserStakeAccountV1) }
+    };
+
+    let mut position_keys = [Pubkey::default(); MAX_POSITIONS_V1];
+    let mut position_pairs = position_accounts.chunks_exact(2);
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    for (position_index, inline_position) in legacy.positions.iter().enumerate() {
+        if !inline_position.is_active {
+            continue;
+        }
+
+        let [staking_pool_account, stake_position_account] = position_pairs.next().ok_or(ProgramError::NotEnoughAccountKeys)? else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if *staking_pool_account.key() != inline_position.staking_pool {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        let position_index_ref = (position_index as u64).to_le_bytes();
+
+        let (stake_position_pda, stake_position_bump) = pubkey::find_program_address(
+            &[
+                b"stake_position",
+                legacy.owner.as_ref(),
+                staking_pool_account.key().as_ref(),
+                position_index_ref.as_ref(),
+            ],
+            &crate::ID
+        );
+
+        if *stake_position_account.key() != stake_position_pda {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        let bump_ref = &[stake_position_bump];
+        let seeds = seeds!(
+            b"stake_position",
+            legacy.owner.as_ref(),
+            staking_pool_account.key().as_ref(),
+            position_index_ref.as_ref(),
+            bump_ref
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        CreateAccount {
+            from: payer,
+            to: stake_position_account,
+            lamports: Rent::get()?.minimum_balance(StakePosition::SIZE),
+            space: StakePosition::SIZE as u64,
+            owner: &crate::ID
+        }.invoke_signed(&[signer_seeds])?;
+
+        let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+        let mut position = StakePosition::initialize(stake_position_account)?;
+
+        *position = StakePosition {
+            owner: legacy.owner,
+            position_index: position_index as u64,
+            pool_id: inline_position.pool_id,
+            staking_pool: inline_position.staking_pool,
+            lst_token_account: inline_position.lst_token_account,
+            staked_amount: inline_position.staked_amount,
+            lst_tokens: inline_position.lst_tokens,
+            last_reward_update: inline_position.last_reward_update,
+            pending_rewards: inline_position.pending_rewards,
+            reward_debt: inline_position.reward_debt,
+            penalty_debt: inline_position.penalty_debt,
+            stake_timestamp: inline_position.stake_timestamp,
+            lock_exipry_enable: inline_position.lock_exipry_enable,
+            lock_expiry: inline_position.lock_expiry,
+            is_active: true,
+            auto_compound_enabled: inline_position.auto_compound_enabled,
+            min_compound_amount: inline_position.min_compound_amount,
+            compound_frequency_hours: inline_position.compound_frequency_hours,
+            last_compound_timestamp: inline_position.last_compound_timestamp,
+            compound_count: inline_position.compound_count,
+            bump: stake_position_bump,
+            ..*position
+        };
+
+        // Primary rewards carry over through the old reward debt, reward streams start from now
+        update_pool_rewards(&mut staking_pool, current_timestamp)?;
+        let earned = pending_rewards(&position, &staking_pool)?;
+        position.pending_rewards = position.pending_rewards.saturating_add(earned);
+        position.last_reward_update = current_timestamp;
+        reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+
+        position_keys[position_index] = *stake_position_account.key();
+    }
+
+    // Rent freed by the smaller layout stays with the account
+    account.resize(UserStakeAccount::SIZE)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    data.fill(0);
+
+    let user_stake = unsafe { &mut *(data.as_mut_ptr() as *mut UserStakeAccount) };
+    legacy.to_current(user_stake, &position_keys);
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{account::Account, instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::{
+        helper::{AccountData, VERSION_OFFSET},
+        staking_pool_account::StakingPool,
+        user_stake_account::{StakePosition, UserStakeAccount, UserStakeAccountV1, MAX_POSITIONS_V1}
+    };
+    use crate::test_utils::*;
+
+    fn migrate(context: &Context, payer: &Pubkey, account: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::MigrateAccount,
+                vec![
+                    AccountMeta::new(*payer, true),
+                    AccountMeta::new(*account, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    fn write_field(data: &mut [u8], offset: usize, value: &[u8]) {
+        data[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    // Opens a pool and a staker whose user stake account is rewritten as version 1 with no inline positions
+    fn setup_v1_staker() -> (Context, TestPool, TestStaker) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let staker = create_staker(&context, &pool, 1000);
+
+        // Only the header ahead of the inline positions is written, those hold a u128 the host pads differently
+        let mut data = vec![0u8; UserStakeAccountV1::SIZE];
+        write_field(&mut data, 0, &UserStakeAccount::DISCRIMINATOR);
+        write_field(&mut data, VERSION_OFFSET, &[1]);
+        write_field(&mut data, core::mem::offset_of!(UserStakeAccountV1, owner), staker.user.as_ref());
+        write_field(&mut data, core::mem::offset_of!(UserStakeAccountV1, global_config), global_config.as_ref());
+        write_field(&mut data, core::mem::offset_of!(UserStakeAccountV1, user_token_account), staker.token_account.as_ref());
+        write_field(&mut data, core::mem::offset_of!(UserStakeAccountV1, creation_timestamp), &START_TIMESTAMP.to_le_bytes());
+
+        set_account(&context, staker.user_stake, Account { lamports: 1_000_000_000, data, owner: PROGRAM_ID, executable: false, rent_epoch: 0 });
+
+        (context, pool, staker)
+    }
+
+    #[test]
+    fn test_version_1_user_stake_account_migrates_to_position_pdas() {
+        let (context, pool, staker) = setup_v1_staker();
+
+        let position = position_pda(&staker.user, &pool.staking_pool, MAX_POSITIONS_V1 as u64);
+        let stake_instruction = stake_instruction(&pool, &staker, 1000, MAX_POSITIONS_V1 as u64, &[]);
+
+        context.process_and_validate_instruction(&stake_instruction, &[custom_error(StakingError::AccountNeedsMigration)]);
+
+        migrate(&context, &staker.user, &staker.user_stake, Check::success());
+
+        assert_eq!(account(&context, &staker.user_stake).data.len(), UserStakeAccount::SIZE);
+
+        let user_stake: UserStakeAccount = read(&context, &staker.user_stake);
+        assert_eq!(user_stake.version, UserStakeAccount::VERSION);
+        assert_eq!(user_stake.owner, staker.user.to_bytes());
+        assert_eq!(user_stake.global_config, pool.global_config.to_bytes());
+        assert_eq!(user_stake.creation_timestamp, START_TIMESTAMP);
+
+        // New positions are numbered after the inline slots so none can collide with a migrated one
+        assert_eq!(user_stake.next_position_index, MAX_POSITIONS_V1 as u64);
+
+        context.process_and_validate_instruction(&stake_instruction, &[Check::success()]);
+
+        let position_index: u64 = read_at(&context, &position, core::mem::offset_of!(StakePosition, position_index));
+        assert_eq!(position_index, MAX_POSITIONS_V1 as u64);
+
+        // Already current, a second run changes nothing
+        let migrated = account(&context, &staker.user_stake).data;
+        migrate(&context, &staker.user, &staker.user_stake, Check::success());
+        assert_eq!(account(&context, &staker.user_stake).data, migrated);
+    }
+
+    #[test]
+    fn test_migrate_account_checks_owner_discriminator_and_version() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        let pool = create_pool(&context, &authority, &global_config, None);
+
+        migrate(&context, &authority, &authority, Check::err(ProgramError::InvalidAccountOwner));
+
+        let unknown = Pubkey::new_unique();
+        set_account(&context, unknown, Account { lamports: 1_000_000_000, data: vec![9u8; 64], owner: PROGRAM_ID, executable: false, rent_epoch: 0 });
+        migrate(&context, &authority, &unknown, custom_error(StakingError::InvalidDiscriminator));
+
+        // Written by a newer program than this one
+        let mut pool_account = account(&context, &pool.staking_pool);
+        pool_account.data[VERSION_OFFSET] = StakingPool::VERSION + 1;
+        set_account(&context, pool.staking_pool, pool_account);
+        migrate(&context, &authority, &pool.staking_pool, custom_error(StakingError::UnsupportedAccountVersion));
+    }
+}
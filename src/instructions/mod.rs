@@ -1,6 +1,8 @@
 use pinocchio::program_error::ProgramError;
 use shank::ShankInstruction;
 
+use crate::error::StakingError;
+
 pub mod init_global_config;
 pub mod update_global_config;
 pub mod create_staking_pool;
@@ -19,6 +21,17 @@ pub mod claim_rewards;
 pub mod enable_auto_compound;
 pub mod disable_auto_compound;
 pub mod execute_auto_compound;
+pub mod slashing;
+pub mod penalty;
+pub mod migrate_account;
+pub mod native_stake;
+pub mod validator_list;
+pub mod liquidity;
+pub mod reward_streams;
+pub mod lock_tiers;
+pub mod swap;
+pub mod roles;
+pub mod multisig;
 
 #[repr(u8)]
 #[derive(ShankInstruction)]
@@ -29,14 +42,14 @@ pub enum StakingInstructions {
     #[account(3, writable, name = "treasury_account", desc = "treasury")]
     #[account(4, name = "system_program", desc = "System program")]
     #[account(5, name = "token_program", desc = "Token Program")]
+    #[account(6, optional, signer, name = "config_id", desc = "Seeds the config PDA, the authority when omitted")]
     InitConfigAccount = 0,
 
-    #[account(0, writable, signer, name = "authority", desc = "Account that pays for account creation")]
-    #[account(1, writable, name = "global_config_account", desc = "GLobal Config Account")]
-    UpdateAuthority = 1,
-
-    #[account(0, writable, signer, name = "authority", desc = "Account that pays for account creation")]
+    // 1 was UpdateAuthority, which handed the config over in one step. It stays retired rather than
+    // reused, so clients still sending it get RetiredInstruction instead of a different call
+    #[account(0, signer, name = "fee_manager", desc = "Holder of the FeeManager role")]
     #[account(1, writable, name = "global_config_account", desc = "GLobal Config Account")]
+    #[account(2, name = "roles_account", desc = "Roles PDA of the global config")]
     UpdateProtocolFee = 2,
 
     #[account(0, writable, name = "authority", desc = "Account that pays for account creation")]
@@ -51,33 +64,42 @@ pub enum StakingInstructions {
     #[account(9, name = "price_feed_account", desc = "Price feed account")]
     #[account(10, name = "system_program", desc = "System program")]
     #[account(11, name = "token_program", desc = "Token program")]
+    #[account(12, name = "roles_account", desc = "Roles PDA, the creator needs the PoolCreator role")]
     CreateStakingPool = 3,
 
-    #[account(0, writable, name = "authority", desc = "Account that pays for account creation")]
-    #[account(1, writable, name = "staking_pool_account", desc = "Account that pays for account creation")]
-    #[account(2, name = "price_feed_account", desc = "Account that pays for account creation")]
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, only pauses apply here, other changes go through QueuePoolConfigChange")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Staking pool to update")]
+    #[account(2, name = "price_feed_account", desc = "New price feed for a PriceFeedAccount change, otherwise ignored")]
     UpdatePoolConfig = 4,
 
     #[account(0, writable, signer, name = "oracle_authority", desc = "Authority that can update oracle config")]
     #[account(1, writable, name = "oracle_config_account", desc = "Oracle configuration account to be created")]
     #[account(2, name = "price_feed_account", desc = "Price feed account for oracle data")]
     #[account(3, name = "system_program", desc = "System program for account creation")]
+    #[account(4, name = "global_config_account", desc = "Global config the oracle belongs to")]
+    #[account(5, name = "roles_account", desc = "Roles PDA, the signer needs the OracleManager role")]
     InitOracleConfig = 5,
 
-    #[account(0, writable, signer, name = "oracle_authority", desc = "Authority that can update oracle config")]
-    #[account(1, writable, name = "oracle_config_account", desc = "Oracle configuration account to be created")]
+    #[account(0, writable, signer, name = "oracle_manager", desc = "Holder of the OracleManager role")]
+    #[account(1, writable, name = "oracle_config_account", desc = "Oracle configuration account to update")]
+    #[account(2, name = "global_config_account", desc = "Global config the oracle belongs to")]
+    #[account(3, name = "roles_account", desc = "Roles PDA of the global config")]
     UpdateOraclePrice = 6,
 
     #[account(0, name = "oracle_authority", desc = "Authority used for PDA derivation")]
     #[account(1, name = "oracle_config_account", desc = "Oracle configuration account to read from")]
     GetOraclePrice = 7,
 
-    #[account(0, name = "authority", desc = "Authority used for PDA derivation")]
-    #[account(1, writable, name = "staking_pool_account", desc = "Account that pays for account creation")]
+    #[account(0, signer, name = "guardian", desc = "Holder of the Guardian role")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(3, name = "roles_account", desc = "Roles PDA of the global config")]
     PausePool = 8,
 
-    #[account(0, name = "authority", desc = "Authority used for PDA derivation")]
-    #[account(1, writable, name = "staking_pool_account", desc = "Account that pays for account creation")]
+    #[account(0, signer, name = "guardian", desc = "Holder of the Guardian role")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(3, name = "roles_account", desc = "Roles PDA of the global config")]
     ResumePool = 9,
 
     #[account(0, name = "authority", desc = "Authority used for PDA derivation")]
@@ -96,8 +118,10 @@ pub enum StakingInstructions {
     #[account(1, writable, name = "authority_token_account", desc = "Authority used for PDA derivation")]
     #[account(2, name = "reward_token_mint", desc = "Authority used for PDA derivation")]
     #[account(3, writable, name = "reward_token_vault", desc = "Authority used for PDA derivation")]
-    #[account(4, name = "staking_pool_account", desc = "Authority used for PDA derivation")]
+    #[account(4, writable, name = "staking_pool_account", desc = "Pool whose campaign is funded")]
     #[account(5, name = "token_program", desc = "Token program")]
+    #[account(6, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(7, name = "roles_account", desc = "Roles PDA, the signer needs the RewardFunder role")]
     FundRewardVault = 12,
 
     #[account(0, writable, signer, name = "user", desc = "Staker")]
@@ -109,14 +133,31 @@ pub enum StakingInstructions {
     #[account(6, writable, name = "global_config_account", desc = "Global config PDA")]
     #[account(7, writable, name = "staking_pool_account", desc = "Pool account")]
     #[account(8, writable, name = "user_token_account", desc = "User's source token account")]
-    #[account(9, writable, name = "user_stake_account", desc = "User's stake position account")]
-    #[account(10, writable, name = "user_lst_token_account", desc = "User's LST token account")]
-    #[account(11, name = "token_program", desc = "Token program")]
+    #[account(9, writable, name = "user_stake_account", desc = "User's aggregate stake account")]
+    #[account(10, writable, name = "stake_position_account", desc = "New position PDA at the user's next position index")]
+    #[account(11, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(12, name = "system_program", desc = "System program")]
+    #[account(13, name = "token_program", desc = "Token program")]
     StakeTokens = 13,
 
     IncreaseStake = 14,
 
-    UnstakeTokens = 15,
+    #[account(0, writable, signer, name = "user", desc = "Staker")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, writable, name = "stake_token_vault", desc = "Vault holding staked tokens")]
+    #[account(4, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(5, name = "global_config_account", desc = "Global config PDA")]
+    #[account(6, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(7, writable, name = "user_stake_account", desc = "User's aggregate stake account")]
+    #[account(8, writable, name = "stake_position_account", desc = "Position being unstaked")]
+    #[account(9, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(10, writable, name = "withdrawal_ticket", desc = "Withdrawal ticket PDA")]
+    #[account(11, writable, name = "treasury_account", desc = "Treasury for penalties routed to the treasury")]
+    #[account(12, writable, name = "reward_token_vault", desc = "Reward vault for penalties routed to the reward vault")]
+    #[account(13, name = "system_program", desc = "System program")]
+    #[account(14, name = "token_program", desc = "Token program")]
+    RequestUnstake = 15,
 
     WithdrawFunds = 16,
 
@@ -124,11 +165,433 @@ pub enum StakingInstructions {
 
     ClaimAllRewards = 18,
 
+    #[account(0, signer, name = "user", desc = "Position owner")]
+    #[account(1, writable, name = "user_stake_account", desc = "Owner's aggregate stake account")]
+    #[account(2, writable, name = "stake_position_account", desc = "Position to compound")]
+    #[account(3, name = "staking_pool_account", desc = "Pool whose compound bounds apply")]
     EnableAutoCompound = 19,
 
     DisableAutoCompound = 20,
 
+    #[account(0, signer, name = "executor", desc = "Anyone cranking the compound, receives the tip")]
+    #[account(1, name = "position_owner", desc = "Owner of the position")]
+    #[account(2, name = "authority", desc = "Global config authority")]
+    #[account(3, name = "reward_token_mint", desc = "Reward token mint")]
+    #[account(4, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(5, writable, name = "reward_token_vault", desc = "Pool's reward vault")]
+    #[account(6, writable, name = "stake_token_vault", desc = "Vault holding staked tokens")]
+    #[account(7, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(8, name = "global_config_account", desc = "Global config PDA")]
+    #[account(9, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(10, writable, name = "user_stake_account", desc = "Owner's aggregate stake account")]
+    #[account(11, writable, name = "stake_position_account", desc = "Position being compounded")]
+    #[account(12, writable, name = "user_lst_token_account", desc = "Owner's LST token account")]
+    #[account(13, writable, name = "treasury_account", desc = "Treasury for protocol fees")]
+    #[account(14, writable, name = "executor_token_account", desc = "Executor's reward token account, receives the tip")]
+    #[account(15, name = "token_program", desc = "Token program")]
+    #[account(16, optional, name = "swap_accounts", desc = "Swap adapter accounts when the reward and stake mints differ")]
     ExecuteAutoCompound = 21,
+
+    #[account(0, writable, signer, name = "user", desc = "Ticket owner")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, writable, name = "stake_token_vault", desc = "Vault holding staked tokens")]
+    #[account(4, name = "global_config_account", desc = "Global config PDA")]
+    #[account(5, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(6, writable, name = "user_token_account", desc = "User's token account to receive tokens")]
+    #[account(7, writable, name = "withdrawal_ticket", desc = "Withdrawal ticket PDA")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    ClaimUnstake = 22,
+
+    #[account(0, writable, signer, name = "user", desc = "Ticket owner")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(3, name = "global_config_account", desc = "Global config PDA")]
+    #[account(4, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(5, writable, name = "user_stake_account", desc = "User's aggregate stake account")]
+    #[account(6, writable, name = "stake_position_account", desc = "Position the ticket was opened from")]
+    #[account(7, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(8, writable, name = "withdrawal_ticket", desc = "Withdrawal ticket PDA")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    CancelUnstake = 23,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the reporter account")]
+    #[account(1, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, name = "reporter", desc = "Reporter being registered")]
+    #[account(3, writable, name = "slash_reporter_account", desc = "Slash reporter PDA")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(6, name = "roles_account", desc = "Roles PDA, the signer needs the Slasher role")]
+    RegisterSlashReporter = 24,
+
+    #[account(0, writable, signer, name = "authority", desc = "Holder of the Slasher role, receives the rent")]
+    #[account(1, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, writable, name = "slash_reporter_account", desc = "Slash reporter PDA")]
+    #[account(3, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(4, name = "roles_account", desc = "Roles PDA of the global config")]
+    RemoveSlashReporter = 25,
+
+    #[account(0, writable, signer, name = "reporter", desc = "Registered slash reporter")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, writable, name = "slash_reporter_account", desc = "Slash reporter PDA")]
+    #[account(3, writable, name = "slash_evidence_account", desc = "Evidence PDA for the current slash round")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, optional, writable, name = "validator_list_account", desc = "Validator list, required for NativeSol pools")]
+    SubmitSlashEvidence = 26,
+
+    #[account(0, signer, name = "executor", desc = "Anyone once enough evidence exists")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, writable, name = "stake_token_vault", desc = "Vault holding staked tokens")]
+    #[account(4, name = "global_config_account", desc = "Global config PDA")]
+    #[account(5, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(6, writable, name = "treasury_account", desc = "Treasury receiving the slashed stake")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    ExecuteSlash = 27,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, writable, name = "user_stake_account", desc = "Penalized user's stake account")]
+    #[account(3, name = "stake_position_account", desc = "Position the penalty is charged against")]
+    #[account(4, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(5, name = "roles_account", desc = "Roles PDA, the signer needs the Slasher role")]
+    OpenPenalty = 28,
+
+    #[account(0, signer, name = "user", desc = "Penalized user")]
+    #[account(1, writable, name = "user_stake_account", desc = "User's stake account")]
+    DisputePenalty = 29,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, writable, name = "user_stake_account", desc = "Penalized user's stake account")]
+    #[account(3, writable, name = "stake_position_account", desc = "Position the penalty is charged against")]
+    #[account(4, name = "global_config_account", desc = "Global config the pool is registered with")]
+    #[account(5, name = "roles_account", desc = "Roles PDA, the signer needs the Slasher role")]
+    ResolvePenalty = 30,
+
+    #[account(0, writable, signer, name = "user", desc = "Penalized user")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, writable, name = "stake_token_vault", desc = "Vault holding the penalty reserve")]
+    #[account(4, name = "global_config_account", desc = "Global config PDA")]
+    #[account(5, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(6, writable, name = "user_stake_account", desc = "User's stake account")]
+    #[account(7, writable, name = "stake_position_account", desc = "Position the penalty was charged against")]
+    #[account(8, writable, name = "user_token_account", desc = "User's token account receiving the recovery")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    RecoverPenalty = 31,
+
+    #[account(0, writable, signer, name = "payer", desc = "Pays for any extra rent")]
+    #[account(1, writable, name = "account", desc = "Program account to upgrade to the current layout")]
+    #[account(2, name = "system_program", desc = "System program, then a (staking_pool, stake_position) pair per active inline position of a version 1 user stake account")]
+    MigrateAccount = 32,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the new accounts")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Empty pool to switch to native SOL")]
+    #[account(2, writable, name = "validator_list_account", desc = "Validator list PDA, created here")]
+    #[account(3, name = "stake_authority", desc = "Stake authority PDA, staker and withdrawer of the pool's stake")]
+    #[account(4, writable, name = "reserve_stake", desc = "Reserve stake PDA, created here")]
+    #[account(5, name = "rent_sysvar", desc = "Rent sysvar")]
+    #[account(6, name = "system_program", desc = "System program")]
+    #[account(7, name = "stake_program", desc = "Stake program")]
+    InitializeNativePool = 33,
+
+    #[account(0, writable, signer, name = "user", desc = "Depositor")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "global_config_account", desc = "Global config PDA, LST mint authority")]
+    #[account(3, writable, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(4, writable, name = "reserve_stake", desc = "Reserve stake PDA receiving the SOL, undelegated until RebalanceValidators moves it")]
+    #[account(5, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(6, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(7, name = "system_program", desc = "System program")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    DepositSol = 34,
+
+    #[account(0, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(1, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(2, name = "validator_vote_account", desc = "Listed validator to move stake to or from")]
+    #[account(3, name = "stake_authority", desc = "Stake authority PDA")]
+    #[account(4, writable, name = "reserve_stake", desc = "Reserve stake PDA")]
+    #[account(5, writable, name = "validator_stake", desc = "Validator stake PDA")]
+    #[account(6, writable, name = "transient_stake", desc = "Transient stake PDA")]
+    #[account(7, name = "clock_sysvar", desc = "Clock sysvar")]
+    #[account(8, name = "stake_history_sysvar", desc = "Stake history sysvar")]
+    #[account(9, name = "stake_config", desc = "Stake config account")]
+    #[account(10, name = "system_program", desc = "System program")]
+    #[account(11, name = "stake_program", desc = "Stake program")]
+    RebalanceValidators = 35,
+
+    #[account(0, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(1, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(2, name = "validator_vote_account", desc = "Listed validator")]
+    #[account(3, name = "stake_authority", desc = "Stake authority PDA")]
+    #[account(4, writable, name = "reserve_stake", desc = "Reserve stake PDA")]
+    #[account(5, writable, name = "validator_stake", desc = "Validator stake PDA")]
+    #[account(6, writable, name = "transient_stake", desc = "Transient stake PDA")]
+    #[account(7, name = "clock_sysvar", desc = "Clock sysvar")]
+    #[account(8, name = "stake_history_sysvar", desc = "Stake history sysvar")]
+    #[account(9, name = "stake_program", desc = "Stake program")]
+    MergeTransientStake = 36,
+
+    #[account(0, writable, signer, name = "user", desc = "LST holder, pays rent for the new stake account")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(3, name = "validator_vote_account", desc = "Validator whose stake is split, ignored for the reserve")]
+    #[account(4, name = "stake_authority", desc = "Stake authority PDA")]
+    #[account(5, writable, name = "source_stake", desc = "Reserve or validator stake PDA to split from")]
+    #[account(6, writable, signer, name = "destination_stake", desc = "New stake account, authorized to the user")]
+    #[account(7, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(8, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(9, name = "clock_sysvar", desc = "Clock sysvar")]
+    #[account(10, name = "system_program", desc = "System program")]
+    #[account(11, name = "token_program", desc = "Token program")]
+    #[account(12, name = "stake_program", desc = "Stake program")]
+    WithdrawStake = 37,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(3, name = "validator_vote_account", desc = "Vote account to add")]
+    AddValidator = 38,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(3, name = "validator_vote_account", desc = "Vote account to remove")]
+    #[account(4, name = "validator_stake", desc = "Validator stake PDA, must be gone before the entry is dropped")]
+    RemoveValidator = 39,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    SetValidatorWeight = 40,
+
+    #[account(0, signer, name = "executor", desc = "Anyone once enough evidence exists")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    ExecuteValidatorSlash = 41,
+
+    #[account(0, name = "global_config_account", desc = "Global config PDA, LST mint authority")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Native SOL pool")]
+    #[account(2, writable, name = "validator_list_account", desc = "Pool's validator list")]
+    #[account(3, name = "reserve_stake", desc = "Reserve stake PDA")]
+    #[account(4, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(5, writable, name = "treasury_lst_account", desc = "Treasury's LST token account, receives the protocol fee")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    UpdatePoolBalance = 42,
+
+    #[account(0, writable, signer, name = "authority", desc = "Staking pool authority, pays for the new accounts")]
+    #[account(1, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(2, name = "global_config_account", desc = "Global config PDA, owns the vault and mints LP shares")]
+    #[account(3, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(4, writable, name = "liquidity_pool_account", desc = "Liquidity pool PDA")]
+    #[account(5, writable, name = "lp_mint", desc = "LP mint PDA")]
+    #[account(6, writable, name = "liquidity_vault", desc = "Liquidity vault PDA")]
+    #[account(7, name = "system_program", desc = "System program")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    InitializeLiquidityPool = 43,
+
+    #[account(0, signer, name = "provider", desc = "Liquidity provider")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, name = "global_config_account", desc = "Global config PDA")]
+    #[account(4, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(5, writable, name = "liquidity_pool_account", desc = "Liquidity pool PDA")]
+    #[account(6, writable, name = "lp_mint", desc = "LP mint")]
+    #[account(7, writable, name = "liquidity_vault", desc = "Liquidity vault")]
+    #[account(8, writable, name = "provider_token_account", desc = "Provider's stake token account")]
+    #[account(9, writable, name = "provider_lp_account", desc = "Provider's LP token account")]
+    #[account(10, name = "token_program", desc = "Token program")]
+    AddLiquidity = 44,
+
+    #[account(0, signer, name = "provider", desc = "Liquidity provider")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, name = "global_config_account", desc = "Global config PDA")]
+    #[account(4, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(5, writable, name = "liquidity_pool_account", desc = "Liquidity pool PDA")]
+    #[account(6, writable, name = "lp_mint", desc = "LP mint")]
+    #[account(7, writable, name = "liquidity_vault", desc = "Liquidity vault")]
+    #[account(8, writable, name = "provider_token_account", desc = "Provider's stake token account")]
+    #[account(9, writable, name = "provider_lp_account", desc = "Provider's LP token account")]
+    #[account(10, name = "token_program", desc = "Token program")]
+    RemoveLiquidity = 45,
+
+    #[account(0, signer, name = "user", desc = "Staker")]
+    #[account(1, name = "authority", desc = "Global config authority")]
+    #[account(2, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(3, writable, name = "stake_token_vault", desc = "Vault holding staked tokens")]
+    #[account(4, writable, name = "liquid_stake_mint", desc = "LST mint")]
+    #[account(5, name = "global_config_account", desc = "Global config PDA")]
+    #[account(6, writable, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(7, writable, name = "liquidity_pool_account", desc = "Liquidity pool PDA")]
+    #[account(8, name = "lp_mint", desc = "LP mint")]
+    #[account(9, writable, name = "liquidity_vault", desc = "Liquidity vault paying the user")]
+    #[account(10, writable, name = "user_stake_account", desc = "User's stake account")]
+    #[account(11, writable, name = "stake_position_account", desc = "Position the LST comes from")]
+    #[account(12, writable, name = "user_lst_token_account", desc = "User's LST token account")]
+    #[account(13, writable, name = "user_token_account", desc = "User's stake token account")]
+    #[account(14, writable, name = "treasury_account", desc = "Treasury for collected penalty debt routed to the treasury")]
+    #[account(15, writable, name = "reward_token_vault", desc = "Reward vault for collected penalty debt routed to the reward vault")]
+    #[account(16, name = "token_program", desc = "Token program")]
+    InstantUnstake = 46,
+
+    #[account(0, name = "authority", desc = "Global config authority")]
+    #[account(1, name = "stake_token_mint", desc = "Token being staked")]
+    #[account(2, writable, name = "stake_token_vault", desc = "Vault holding the pending backing")]
+    #[account(3, name = "global_config_account", desc = "Global config PDA")]
+    #[account(4, writable, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(5, writable, name = "liquidity_pool_account", desc = "Liquidity pool PDA")]
+    #[account(6, name = "lp_mint", desc = "LP mint")]
+    #[account(7, writable, name = "liquidity_vault", desc = "Liquidity vault")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    ReplenishLiquidity = 47,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the vault")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA, owns the vault")]
+    #[account(2, writable, name = "staking_pool_account", desc = "SPL token pool")]
+    #[account(3, name = "reward_mint", desc = "Token the stream pays out")]
+    #[account(4, writable, name = "reward_vault", desc = "Stream vault PDA, created unless the mint ran a finished stream before")]
+    #[account(5, name = "system_program", desc = "System program")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    AddRewardStream = 48,
+
+    #[account(0, signer, name = "funder", desc = "Anyone co-incentivizing the pool")]
+    #[account(1, writable, name = "funder_token_account", desc = "Funder's token account for the stream's mint")]
+    #[account(2, name = "reward_mint", desc = "Stream's reward mint")]
+    #[account(3, writable, name = "reward_vault", desc = "Stream's vault")]
+    #[account(4, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    FundRewardStream = 49,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, writable, name = "staking_pool_account", desc = "SPL token pool")]
+    SetLockTiers = 50,
+
+    #[account(0, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(1, writable, name = "stake_position_account", desc = "Position whose lock has expired")]
+    RefreshLockBoost = 51,
+
+    #[account(0, signer, name = "user", desc = "Position owner")]
+    #[account(1, writable, name = "user_stake_account", desc = "Owner's aggregate stake account")]
+    #[account(2, writable, name = "stake_position_account", desc = "Position with auto-compound enabled")]
+    #[account(3, name = "staking_pool_account", desc = "Pool whose compound bounds apply")]
+    UpdateAutoCompound = 52,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the swap input account")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Pool account")]
+    #[account(2, name = "adapter_account", desc = "Adapter program or SwapPool, ignored when clearing the adapter")]
+    #[account(3, name = "reward_token_mint", desc = "Pool's reward token mint")]
+    #[account(4, name = "swap_authority", desc = "Swap authority PDA, owns the swap input account")]
+    #[account(5, writable, name = "swap_input", desc = "Swap input PDA, created for a Program adapter")]
+    #[account(6, name = "system_program", desc = "System program")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    SetSwapAdapter = 53,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the new accounts")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA, owns the reserves")]
+    #[account(2, name = "staking_pool_account", desc = "Pool account")]
+    #[account(3, name = "reward_token_mint", desc = "Pool's reward token mint")]
+    #[account(4, name = "stake_token_mint", desc = "Pool's stake token mint")]
+    #[account(5, writable, name = "swap_pool_account", desc = "Swap pool PDA, created here")]
+    #[account(6, writable, name = "reward_reserve", desc = "Reward reserve PDA, created here")]
+    #[account(7, writable, name = "stake_reserve", desc = "Stake reserve PDA, created here")]
+    #[account(8, name = "system_program", desc = "System program")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    InitializeSwapPool = 54,
+
+    #[account(0, signer, name = "authority", desc = "Pool authority")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA, owns the reserves")]
+    #[account(2, name = "staking_pool_account", desc = "Pool account")]
+    #[account(3, name = "swap_pool_account", desc = "Pool's swap pool")]
+    #[account(4, name = "mint", desc = "Mint of the reserve being withdrawn")]
+    #[account(5, writable, name = "reserve", desc = "Reward or stake reserve")]
+    #[account(6, writable, name = "destination_token_account", desc = "Receives the withdrawn tokens")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    WithdrawSwapReserve = 55,
+
+    #[account(0, signer, name = "pending_authority", desc = "Proposed authority, takes over on accepting")]
+    #[account(1, writable, name = "global_config_account", desc = "GLobal Config Account")]
+    AcceptAuthority = 56,
+
+    #[account(0, signer, name = "authority", desc = "Current global config authority")]
+    #[account(1, writable, name = "global_config_account", desc = "GLobal Config Account")]
+    CancelAuthorityTransfer = 57,
+
+    #[account(0, signer, name = "authority", desc = "Global config authority")]
+    #[account(1, writable, signer, name = "payer", desc = "Pays for the roles account on the first grant")]
+    #[account(2, name = "global_config_account", desc = "Global config PDA")]
+    #[account(3, writable, name = "roles_account", desc = "Roles PDA, created on the first grant")]
+    #[account(4, name = "system_program", desc = "System program")]
+    GrantRole = 58,
+
+    #[account(0, signer, name = "authority", desc = "Global config authority")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA")]
+    #[account(2, writable, name = "roles_account", desc = "Roles PDA")]
+    RevokeRole = 59,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority, pays for the change account")]
+    #[account(1, name = "staking_pool_account", desc = "Staking pool the change applies to")]
+    #[account(2, name = "price_feed_account", desc = "New price feed for a PriceFeedAccount change, otherwise ignored")]
+    #[account(3, writable, name = "config_change_account", desc = "PoolConfigChange PDA for this update type")]
+    #[account(4, name = "system_program", desc = "System program")]
+    QueuePoolConfigChange = 60,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority that queued the change, receives its rent")]
+    #[account(1, writable, name = "staking_pool_account", desc = "Staking pool the change applies to")]
+    #[account(2, writable, name = "config_change_account", desc = "Queued PoolConfigChange, closed once applied")]
+    ExecutePoolConfigChange = 61,
+
+    #[account(0, writable, signer, name = "authority", desc = "Pool authority that queued the change, receives its rent")]
+    #[account(1, name = "staking_pool_account", desc = "Staking pool the change applies to")]
+    #[account(2, writable, name = "config_change_account", desc = "Queued PoolConfigChange to close")]
+    CancelPoolConfigChange = 62,
+
+    #[account(0, writable, signer, name = "authority", desc = "Global config authority, pays for the multisig account")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA")]
+    #[account(2, writable, name = "multisig_account", desc = "Multisig PDA")]
+    #[account(3, name = "system_program", desc = "System program")]
+    CreateMultisig = 63,
+
+    #[account(0, writable, signer, name = "proposer", desc = "Multisig member, pays for the proposal account")]
+    #[account(1, writable, name = "multisig_account", desc = "Multisig PDA")]
+    #[account(2, writable, name = "proposal_account", desc = "Proposal PDA at the multisig's next proposal index")]
+    #[account(3, name = "system_program", desc = "System program")]
+    CreateMultisigProposal = 64,
+
+    #[account(0, signer, name = "member", desc = "Multisig member approving")]
+    #[account(1, name = "multisig_account", desc = "Multisig PDA")]
+    #[account(2, writable, name = "proposal_account", desc = "Proposal to approve")]
+    ApproveMultisigProposal = 65,
+
+    #[account(0, signer, name = "executor", desc = "Anyone, once the threshold is met")]
+    #[account(1, writable, name = "multisig_account", desc = "Multisig PDA, signs the proposed call")]
+    #[account(2, writable, name = "proposal_account", desc = "Approved proposal")]
+    #[account(3, name = "staking_program", desc = "This program, target of the proposed call")]
+    ExecuteMultisigProposal = 66,
+
+    #[account(0, writable, signer, name = "multisig_account", desc = "Multisig PDA, only signs through ExecuteMultisigProposal")]
+    UpdateMultisig = 67,
+
+    #[account(0, writable, signer, name = "payer", desc = "Pays for the treasury")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA")]
+    #[account(2, name = "mint", desc = "Mint the treasury holds")]
+    #[account(3, writable, name = "treasury_account", desc = "Treasury PDA, created here")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    InitTreasuryAccount = 68,
+
+    #[account(0, signer, name = "treasury_manager", desc = "Holder of the TreasuryManager role")]
+    #[account(1, name = "global_config_account", desc = "Global config PDA, owns the treasury")]
+    #[account(2, name = "roles_account", desc = "Roles PDA of the global config")]
+    #[account(3, writable, name = "treasury_account", desc = "Treasury to withdraw from")]
+    #[account(4, writable, name = "destination_account", desc = "Token account receiving the withdrawal")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    WithdrawTreasury = 69,
+
+    #[account(0, signer, name = "authority", desc = "Current global config authority")]
+    #[account(1, writable, name = "global_config_account", desc = "GLobal Config Account")]
+    ProposeAuthority = 70,
 }
 
 impl TryFrom<&u8> for StakingInstructions {
@@ -137,7 +600,7 @@ impl TryFrom<&u8> for StakingInstructions {
     fn try_from(value: &u8) -> Result<Self, Self::Error> {
         match value {
             0 => Ok(StakingInstructions::InitConfigAccount),
-            1 => Ok(StakingInstructions::UpdateAuthority),
+            1 => Err(StakingError::RetiredInstruction.into()),
             2 => Ok(StakingInstructions::UpdateProtocolFee),
             3 => Ok(StakingInstructions::CreateStakingPool),
             4 => Ok(StakingInstructions::UpdatePoolConfig),
@@ -151,13 +614,62 @@ impl TryFrom<&u8> for StakingInstructions {
             12 => Ok(StakingInstructions::FundRewardVault),
             13 => Ok(StakingInstructions::StakeTokens),
             14 => Ok(StakingInstructions::IncreaseStake),
-            15 => Ok(StakingInstructions::UnstakeTokens),
+            15 => Ok(StakingInstructions::RequestUnstake),
             16 => Ok(StakingInstructions::WithdrawFunds),
             17 => Ok(StakingInstructions::ClaimRewards),
             18 => Ok(StakingInstructions::ClaimAllRewards),
             19 => Ok(StakingInstructions::EnableAutoCompound),
             20 => Ok(StakingInstructions::DisableAutoCompound),
-            21 => Ok(StakingInstructions::EnableAutoCompound),
+            21 => Ok(StakingInstructions::ExecuteAutoCompound),
+            22 => Ok(StakingInstructions::ClaimUnstake),
+            23 => Ok(StakingInstructions::CancelUnstake),
+            24 => Ok(StakingInstructions::RegisterSlashReporter),
+            25 => Ok(StakingInstructions::RemoveSlashReporter),
+            26 => Ok(StakingInstructions::SubmitSlashEvidence),
+            27 => Ok(StakingInstructions::ExecuteSlash),
+            28 => Ok(StakingInstructions::OpenPenalty),
+            29 => Ok(StakingInstructions::DisputePenalty),
+            30 => Ok(StakingInstructions::ResolvePenalty),
+            31 => Ok(StakingInstructions::RecoverPenalty),
+            32 => Ok(StakingInstructions::MigrateAccount),
+            33 => Ok(StakingInstructions::InitializeNativePool),
+            34 => Ok(StakingInstructions::DepositSol),
+            35 => Ok(StakingInstructions::RebalanceValidators),
+            36 => Ok(StakingInstructions::MergeTransientStake),
+            37 => Ok(StakingInstructions::WithdrawStake),
+            38 => Ok(StakingInstructions::AddValidator),
+            39 => Ok(StakingInstructions::RemoveValidator),
+            40 => Ok(StakingInstructions::SetValidatorWeight),
+            41 => Ok(StakingInstructions::ExecuteValidatorSlash),
+            42 => Ok(StakingInstructions::UpdatePoolBalance),
+            43 => Ok(StakingInstructions::InitializeLiquidityPool),
+            44 => Ok(StakingInstructions::AddLiquidity),
+            45 => Ok(StakingInstructions::RemoveLiquidity),
+            46 => Ok(StakingInstructions::InstantUnstake),
+            47 => Ok(StakingInstructions::ReplenishLiquidity),
+            48 => Ok(StakingInstructions::AddRewardStream),
+            49 => Ok(StakingInstructions::FundRewardStream),
+            50 => Ok(StakingInstructions::SetLockTiers),
+            51 => Ok(StakingInstructions::RefreshLockBoost),
+            52 => Ok(StakingInstructions::UpdateAutoCompound),
+            53 => Ok(StakingInstructions::SetSwapAdapter),
+            54 => Ok(StakingInstructions::InitializeSwapPool),
+            55 => Ok(StakingInstructions::WithdrawSwapReserve),
+            56 => Ok(StakingInstructions::AcceptAuthority),
+            57 => Ok(StakingInstructions::CancelAuthorityTransfer),
+            58 => Ok(StakingInstructions::GrantRole),
+            59 => Ok(StakingInstructions::RevokeRole),
+            60 => Ok(StakingInstructions::QueuePoolConfigChange),
+            61 => Ok(StakingInstructions::ExecutePoolConfigChange),
+            62 => Ok(StakingInstructions::CancelPoolConfigChange),
+            63 => Ok(StakingInstructions::CreateMultisig),
+            64 => Ok(StakingInstructions::CreateMultisigProposal),
+            65 => Ok(StakingInstructions::ApproveMultisigProposal),
+            66 => Ok(StakingInstructions::ExecuteMultisigProposal),
+            67 => Ok(StakingInstructions::UpdateMultisig),
+            68 => Ok(StakingInstructions::InitTreasuryAccount),
+            69 => Ok(StakingInstructions::WithdrawTreasury),
+            70 => Ok(StakingInstructions::ProposeAuthority),
             _ => Err(ProgramError::InvalidInstructionData)
         }
     }
@@ -0,0 +1,713 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::slice_invoke_signed,
+    instruction::{AccountMeta, Instruction, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    *
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::error::StakingError;
+use crate::events::{Event, MultisigProposalEvent, MultisigUpdatedEvent};
+use crate::instructions::StakingInstructions;
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    multisig::{Multisig, MultisigProposal, ProposalAccount, MAX_MULTISIG_MEMBERS, MAX_PROPOSAL_ACCOUNTS, MAX_PROPOSAL_DATA}
+};
+
+pub fn process_create_multisig(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,             // Global config authority, pays for the multisig account
+        global_config_account, // Global config PDA
+        multisig_account,      // Multisig PDA
+        _system_program,       // System program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    if GlobalConfig::from_account_info(global_config_account)?.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    let (threshold, members, member_count) = parse_members(instruction_data)?;
+
+    let (multisig_pda, multisig_bump) = pubkey::find_program_address(
+        &[b"multisig", global_config_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *multisig_account.key() != multisig_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let bump_ref = &[multisig_bump];
+    let seeds = seeds!(
+        b"multisig",
+        global_config_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: multisig_account,
+        lamports: Rent::get()?.minimum_balance(Multisig::SIZE),
+        space: Multisig::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut multisig = Multisig::initialize(multisig_account)?;
+    multisig.global_config = *global_config_account.key();
+    multisig.set_members(threshold, &members[..member_count])?;
+    multisig.bump = multisig_bump;
+
+    emit_multisig_updated(multisig_account, &multisig)
+}
+
+/// Only reachable through an executed proposal, the multisig PDA has to sign.
+pub fn process_update_multisig(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [multisig_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !multisig_account.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (threshold, members, member_count) = parse_members(instruction_data)?;
+
+    let mut multisig = Multisig::from_account_info_mut(multisig_account)?;
+    multisig.set_members(threshold, &members[..member_count])?;
+
+    emit_multisig_updated(multisig_account, &multisig)
+}
+
+pub fn process_create_multisig_proposal(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        proposer,          // Multisig member, pays for the proposal account
+        multisig_account,  // Multisig PDA
+        proposal_account,  // Proposal PDA at the multisig's next proposal index
+        _system_program,   // System program
+        proposed_accounts @ .. // Accounts the proposed call takes, in order
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !proposer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.is_empty() || instruction_data.len() > MAX_PROPOSAL_DATA {
+        return Err(StakingError::InvalidProposalInstruction.into());
+    }
+
+    if proposed_accounts.len() > MAX_PROPOSAL_ACCOUNTS {
+        return Err(StakingError::InvalidProposalInstruction.into());
+    }
+
+    // Proposals can change the member set but not drive the proposal flow itself
+    match StakingInstructions::try_from(&instruction_data[0])? {
+        StakingInstructions::CreateMultisig |
+        StakingInstructions::CreateMultisigProposal |
+        StakingInstructions::ApproveMultisigProposal |
+        StakingInstructions::ExecuteMultisigProposal => {
+            return Err(StakingError::InvalidProposalInstruction.into());
+        },
+        _ => {}
+    }
+
+    let (proposal_index, member_index, member_set_seq, threshold) = {
+        let mut multisig = Multisig::from_account_info_mut(multisig_account)?;
+        let member_index = multisig.member_index(proposer.key())?;
+        let proposal_index = multisig.proposal_count;
+        multisig.proposal_count = proposal_index
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        (proposal_index, member_index, multisig.member_set_seq, multisig.threshold)
+    };
+
+    let proposal_index_bytes = proposal_index.to_le_bytes();
+    let (proposal_pda, proposal_bump) = pubkey::find_program_address(
+        &[b"multisig_proposal", multisig_account.key().as_ref(), &proposal_index_bytes],
+        &crate::ID
+    );
+
+    if *proposal_account.key() != proposal_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let bump_ref = &[proposal_bump];
+    let seeds = seeds!(
+        b"multisig_proposal",
+        multisig_account.key().as_ref(),
+        &proposal_index_bytes,
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: proposer,
+        to: proposal_account,
+        lamports: Rent::get()?.minimum_balance(MultisigProposal::SIZE),
+        space: MultisigProposal::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    let mut proposal = MultisigProposal::initialize(proposal_account)?;
+    proposal.multisig = *multisig_account.key();
+    proposal.proposer = *proposer.key();
+    proposal.proposal_index = proposal_index;
+    proposal.member_set_seq = member_set_seq;
+    proposal.instruction_data[..instruction_data.len()].copy_from_slice(instruction_data);
+    proposal.instruction_data_len = instruction_data.len() as u16;
+    for (index, account) in proposed_accounts.iter().enumerate() {
+        proposal.accounts[index] = ProposalAccount {
+            pubkey: *account.key(),
+            is_writable: account.is_writable(),
+        };
+    }
+    proposal.account_count = proposed_accounts.len() as u8;
+    proposal.created_timestamp = current_timestamp;
+    proposal.bump = proposal_bump;
+
+    // Proposing counts as the proposer's approval
+    proposal.approve(member_index)?;
+
+    MultisigProposalEvent {
+        multisig: *multisig_account.key(),
+        proposal: *proposal_account.key(),
+        proposal_index,
+        member: *proposer.key(),
+        action: 0,
+        instruction: instruction_data[0],
+        approvals: proposal.approval_count(),
+        threshold,
+        timestamp: current_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_approve_multisig_proposal(accounts: &[AccountInfo]) -> ProgramResult {
+    let [member, multisig_account, proposal_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !member.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let multisig = Multisig::from_account_info(multisig_account)?;
+    let member_index = multisig.member_index(member.key())?;
+
+    let mut proposal = load_open_proposal(multisig_account, &multisig, proposal_account)?;
+    proposal.approve(member_index)?;
+
+    MultisigProposalEvent {
+        multisig: *multisig_account.key(),
+        proposal: *proposal_account.key(),
+        proposal_index: proposal.proposal_index,
+        member: *member.key(),
+        action: 1,
+        instruction: proposal.instruction_data[0],
+        approvals: proposal.approval_count(),
+        threshold: multisig.threshold,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_execute_multisig_proposal(accounts: &[AccountInfo]) -> ProgramResult {
+    let [
+        executor,          // Anyone, once the threshold is met
+        multisig_account,  // Multisig PDA, signs the proposed call
+        proposal_account,  // Approved proposal
+        staking_program,   // This program, target of the proposed call
+        proposed_accounts @ .. // Accounts the proposal was created with, in order
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !executor.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *staking_program.key() != crate::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (instruction_data, instruction_data_len, account_writable, global_config, multisig_bump) = {
+        let multisig = Multisig::from_account_info(multisig_account)?;
+        let mut proposal = load_open_proposal(multisig_account, &multisig, proposal_account)?;
+
+        if proposal.approval_count() < multisig.threshold {
+            return Err(StakingError::ThresholdNotMet.into());
+        }
+
+        let approved_accounts = proposal.proposal_accounts();
+
+        if approved_accounts.len() != proposed_accounts.len()
+            || approved_accounts.iter().zip(proposed_accounts).any(|(approved, account)| approved.pubkey != *account.key()) {
+            return Err(StakingError::ProposalAccountsMismatch.into());
+        }
+
+        let account_writable: [bool; MAX_PROPOSAL_ACCOUNTS] = core::array::from_fn(|index| {
+            approved_accounts.get(index).is_some_and(|account| account.is_writable)
+        });
+
+        // Marked before the call so the proposal can't run twice, even from within itself
+        let current_timestamp = Clock::get()?.unix_timestamp;
+        proposal.executed = true;
+        proposal.executed_timestamp = current_timestamp;
+
+        MultisigProposalEvent {
+            multisig: *multisig_account.key(),
+            proposal: *proposal_account.key(),
+            proposal_index: proposal.proposal_index,
+            member: *executor.key(),
+            action: 2,
+            instruction: proposal.instruction_data[0],
+            approvals: proposal.approval_count(),
+            threshold: multisig.threshold,
+            timestamp: current_timestamp,
+        }.emit();
+
+        (
+            proposal.instruction_data,
+            proposal.instruction_data_len as usize,
+            account_writable,
+            multisig.global_config,
+            multisig.bump
+        )
+    };
+
+    // Unused tail slots repeat the multisig and are sliced off below
+    let account_infos: [&AccountInfo; MAX_PROPOSAL_ACCOUNTS] = core::array::from_fn(|index| {
+        proposed_accounts.get(index).unwrap_or(multisig_account)
+    });
+
+    // The multisig signs as itself, other signers carry over from this transaction
+    let account_metas: [AccountMeta; MAX_PROPOSAL_ACCOUNTS] = core::array::from_fn(|index| {
+        let account = account_infos[index];
+        AccountMeta::new(
+            account.key(),
+            account_writable[index],
+            account.key() == multisig_account.key() || account.is_signer()
+        )
+    });
+
+    let instruction = Instruction {
+        program_id: &crate::ID,
+        accounts: &account_metas[..proposed_accounts.len()],
+        data: &instruction_data[..instruction_data_len],
+    };
+
+    let bump_ref = &[multisig_bump];
+    let seeds = seeds!(
+        b"multisig",
+        global_config.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    slice_invoke_signed(&instruction, &account_infos[..proposed_accounts.len()], &[signer_seeds])
+}
+
+// A proposal of this multisig that hasn't run and still matches its member set
+fn load_open_proposal<'a>(
+    multisig_account: &AccountInfo,
+    multisig: &Multisig,
+    proposal_account: &'a AccountInfo
+) -> Result<account_info::RefMut<'a, MultisigProposal>, ProgramError> {
+    let proposal = MultisigProposal::from_account_info_mut(proposal_account)?;
+
+    if proposal.multisig != *multisig_account.key() {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if proposal.executed {
+        return Err(StakingError::ProposalAlreadyExecuted.into());
+    }
+
+    if proposal.member_set_seq != multisig.member_set_seq {
+        return Err(StakingError::StaleProposal.into());
+    }
+
+    Ok(proposal)
+}
+
+// threshold (1), member_count (1), members (32 each)
+fn parse_members(instruction_data: &[u8]) -> Result<(u8, [Pubkey; MAX_MULTISIG_MEMBERS], usize), ProgramError> {
+    if instruction_data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let threshold = instruction_data[0];
+    let member_count = instruction_data[1] as usize;
+
+    if member_count == 0 || member_count > MAX_MULTISIG_MEMBERS {
+        return Err(StakingError::InvalidMultisigMembers.into());
+    }
+
+    if instruction_data.len() < 2 + member_count * 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut members = [Pubkey::default(); MAX_MULTISIG_MEMBERS];
+    for (index, member) in members[..member_count].iter_mut().enumerate() {
+        *member = Pubkey::try_from(&instruction_data[2 + index * 32..2 + (index + 1) * 32])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+    }
+
+    Ok((threshold, members, member_count))
+}
+
+fn emit_multisig_updated(multisig_account: &AccountInfo, multisig: &Multisig) -> ProgramResult {
+    MultisigUpdatedEvent {
+        multisig: *multisig_account.key(),
+        global_config: multisig.global_config,
+        threshold: multisig.threshold,
+        member_count: multisig.member_count,
+        member_set_seq: multisig.member_set_seq,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::{global_config::GlobalConfig, multisig::Multisig, roles::{RoleEnum, Roles}};
+    use crate::test_utils::*;
+
+    fn multisig_pda(global_config: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"multisig", global_config.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn proposal_pda(multisig: &Pubkey, proposal_index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"multisig_proposal", multisig.as_ref(), &proposal_index.to_le_bytes()],
+            &PROGRAM_ID
+        ).0
+    }
+
+    fn members_data(threshold: u8, members: &[Pubkey]) -> Vec<u8> {
+        let mut data = vec![threshold, members.len() as u8];
+        for member in members {
+            data.extend_from_slice(member.as_ref());
+        }
+        data
+    }
+
+    fn propose(context: &Context, proposer: &Pubkey, multisig: &Pubkey, proposal: &Pubkey, data: &[u8], accounts: &[AccountMeta], check: Check) {
+        let mut metas = vec![
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ];
+        metas.extend_from_slice(accounts);
+
+        // The proposed call follows our own discriminator, its own discriminator first
+        context.process_and_validate_instruction(
+            &instruction(StakingInstructions::CreateMultisigProposal, metas, data),
+            &[check],
+        );
+    }
+
+    fn approve(context: &Context, member: &Pubkey, multisig: &Pubkey, proposal: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ApproveMultisigProposal,
+                vec![
+                    AccountMeta::new_readonly(*member, true),
+                    AccountMeta::new_readonly(*multisig, false),
+                    AccountMeta::new(*proposal, false),
+                ],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    fn execute(context: &Context, executor: &Pubkey, multisig: &Pubkey, proposal: &Pubkey, accounts: &[AccountMeta], check: Check) {
+        let mut metas = vec![
+            AccountMeta::new_readonly(*executor, true),
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+        ];
+        metas.extend_from_slice(accounts);
+
+        context.process_and_validate_instruction(
+            &instruction(StakingInstructions::ExecuteMultisigProposal, metas, &[]),
+            &[check],
+        );
+    }
+
+    // Config with a 2-of-3 multisig that has been proposed as its next authority
+    fn setup_multisig() -> (Context, Pubkey, Pubkey, [Pubkey; 3]) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        let members = [funded_wallet(&context), funded_wallet(&context), funded_wallet(&context)];
+        let multisig = multisig_pda(&global_config);
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::CreateMultisig,
+                vec![
+                    AccountMeta::new(authority, true),
+                    AccountMeta::new_readonly(global_config, false),
+                    AccountMeta::new(multisig, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                &members_data(2, &members),
+            ),
+            &[Check::success()],
+        );
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ProposeAuthority,
+                vec![AccountMeta::new_readonly(authority, true), AccountMeta::new(global_config, false)],
+                multisig.as_ref(),
+            ),
+            &[Check::success()],
+        );
+
+        (context, global_config, multisig, members)
+    }
+
+    fn accept_authority_accounts(multisig: &Pubkey, global_config: &Pubkey) -> Vec<AccountMeta> {
+        vec![AccountMeta::new_readonly(*multisig, false), AccountMeta::new(*global_config, false)]
+    }
+
+    // Proposed by the first member, approved by the second and run by the third
+    fn run_proposal(context: &Context, multisig: &Pubkey, members: &[Pubkey; 3], proposal_index: u64, data: &[u8], accounts: &[AccountMeta]) {
+        let proposal = proposal_pda(multisig, proposal_index);
+
+        propose(context, &members[0], multisig, &proposal, data, accounts, Check::success());
+        approve(context, &members[1], multisig, &proposal, Check::success());
+        execute(context, &members[2], multisig, &proposal, accounts, Check::success());
+    }
+
+    // The multisig as config authority, through proposal 0
+    fn setup_multisig_authority() -> (Context, Pubkey, Pubkey, [Pubkey; 3]) {
+        let (context, global_config, multisig, members) = setup_multisig();
+        let accounts = accept_authority_accounts(&multisig, &global_config);
+
+        run_proposal(&context, &multisig, &members, 0, &[StakingInstructions::AcceptAuthority as u8], &accounts);
+
+        (context, global_config, multisig, members)
+    }
+
+    #[test]
+    fn test_proposal_executes_once_threshold_is_met() {
+        let (context, global_config, multisig, members) = setup_multisig();
+        let proposal = proposal_pda(&multisig, 0);
+        let accounts = accept_authority_accounts(&multisig, &global_config);
+
+        propose(&context, &members[0], &multisig, &proposal, &[StakingInstructions::AcceptAuthority as u8], &accounts, Check::success());
+
+        // Proposing only counts as one of the two approvals
+        execute(&context, &members[2], &multisig, &proposal, &accounts, custom_error(StakingError::ThresholdNotMet));
+
+        approve(&context, &members[1], &multisig, &proposal, Check::success());
+        execute(&context, &members[2], &multisig, &proposal, &accounts, Check::success());
+
+        let config = read::<GlobalConfig>(&context, &global_config);
+        assert_eq!(config.authority, multisig.to_bytes());
+        assert_eq!(config.pending_authority, [0u8; 32]);
+
+        execute(&context, &members[2], &multisig, &proposal, &accounts, custom_error(StakingError::ProposalAlreadyExecuted));
+    }
+
+    #[test]
+    fn test_execute_rejects_accounts_that_were_not_approved() {
+        let (context, global_config, multisig, members) = setup_multisig();
+        let proposal = proposal_pda(&multisig, 0);
+        let accounts = accept_authority_accounts(&multisig, &global_config);
+
+        propose(&context, &members[0], &multisig, &proposal, &[StakingInstructions::AcceptAuthority as u8], &accounts, Check::success());
+        approve(&context, &members[1], &multisig, &proposal, Check::success());
+
+        let swapped = vec![AccountMeta::new_readonly(members[0], false), AccountMeta::new(global_config, false)];
+        execute(&context, &members[2], &multisig, &proposal, &swapped, custom_error(StakingError::ProposalAccountsMismatch));
+    }
+
+    #[test]
+    fn test_non_member_cannot_propose_or_approve() {
+        let (context, global_config, multisig, members) = setup_multisig();
+        let outsider = funded_wallet(&context);
+        let proposal = proposal_pda(&multisig, 0);
+        let accounts = accept_authority_accounts(&multisig, &global_config);
+
+        propose(&context, &outsider, &multisig, &proposal, &[StakingInstructions::AcceptAuthority as u8], &accounts, custom_error(StakingError::NotMultisigMember));
+
+        propose(&context, &members[0], &multisig, &proposal, &[StakingInstructions::AcceptAuthority as u8], &accounts, Check::success());
+        approve(&context, &outsider, &multisig, &proposal, custom_error(StakingError::NotMultisigMember));
+    }
+
+    #[test]
+    fn test_proposal_cannot_drive_the_proposal_flow() {
+        let (context, _global_config, multisig, members) = setup_multisig();
+        let proposal = proposal_pda(&multisig, 0);
+
+        propose(
+            &context,
+            &members[0],
+            &multisig,
+            &proposal,
+            &[StakingInstructions::ExecuteMultisigProposal as u8],
+            &[],
+            custom_error(StakingError::InvalidProposalInstruction)
+        );
+    }
+
+    #[test]
+    fn test_update_multisig_requires_the_multisig_signature() {
+        let (context, _global_config, multisig, members) = setup_multisig();
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdateMultisig,
+                vec![AccountMeta::new(multisig, false)],
+                &members_data(1, &members[..1]),
+            ),
+            &[Check::err(ProgramError::MissingRequiredSignature)],
+        );
+    }
+
+    #[test]
+    fn test_member_change_makes_open_proposals_stale() {
+        let (context, global_config, multisig, members) = setup_multisig();
+        let update_proposal = proposal_pda(&multisig, 0);
+        let accept_proposal = proposal_pda(&multisig, 1);
+        let multisig_accounts = vec![AccountMeta::new(multisig, false)];
+        let accept_accounts = accept_authority_accounts(&multisig, &global_config);
+
+        let mut update_data = vec![StakingInstructions::UpdateMultisig as u8];
+        update_data.extend_from_slice(&members_data(1, &members[..2]));
+
+        propose(&context, &members[0], &multisig, &update_proposal, &update_data, &multisig_accounts, Check::success());
+        propose(&context, &members[0], &multisig, &accept_proposal, &[StakingInstructions::AcceptAuthority as u8], &accept_accounts, Check::success());
+
+        approve(&context, &members[1], &multisig, &update_proposal, Check::success());
+        execute(&context, &members[1], &multisig, &update_proposal, &multisig_accounts, Check::success());
+
+        let updated = read::<Multisig>(&context, &multisig);
+        assert_eq!(updated.threshold, 1);
+        assert_eq!(updated.member_count, 2);
+        assert_eq!(updated.active_members(), &[members[0].to_bytes(), members[1].to_bytes()]);
+
+        // Approved under the old member set, it can't be approved or run under the new one
+        approve(&context, &members[1], &multisig, &accept_proposal, custom_error(StakingError::StaleProposal));
+        execute(&context, &members[1], &multisig, &accept_proposal, &accept_accounts, custom_error(StakingError::StaleProposal));
+
+        // Removed members are out
+        let late_proposal = proposal_pda(&multisig, 2);
+        propose(&context, &members[2], &multisig, &late_proposal, &[StakingInstructions::AcceptAuthority as u8], &accept_accounts, custom_error(StakingError::NotMultisigMember));
+    }
+
+    #[test]
+    fn test_multisig_updates_the_protocol_fee() {
+        let (context, global_config, multisig, members) = setup_multisig_authority();
+
+        let mut data = vec![StakingInstructions::UpdateProtocolFee as u8];
+        data.extend_from_slice(&250u16.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new_readonly(multisig, false),
+            AccountMeta::new(global_config, false),
+            AccountMeta::new_readonly(roles_pda(&global_config), false),
+        ];
+
+        run_proposal(&context, &multisig, &members, 1, &data, &accounts);
+        assert_eq!(read::<GlobalConfig>(&context, &global_config).protocol_fee_rate, 250);
+    }
+
+    #[test]
+    fn test_multisig_grants_roles_with_a_separate_payer() {
+        let (context, global_config, multisig, members) = setup_multisig_authority();
+        let payer = funded_wallet(&context);
+        let fee_manager = Pubkey::new_unique();
+
+        let mut data = vec![StakingInstructions::GrantRole as u8, RoleEnum::FeeManager as u8];
+        data.extend_from_slice(fee_manager.as_ref());
+
+        // The multisig carries data and can't fund the roles account, the payer signs the execute transaction
+        let accounts = vec![
+            AccountMeta::new_readonly(multisig, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(global_config, false),
+            AccountMeta::new(roles_pda(&global_config), false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ];
+
+        run_proposal(&context, &multisig, &members, 1, &data, &accounts);
+
+        let roles = read::<Roles>(&context, &roles_pda(&global_config));
+        assert_eq!(roles.global_config, global_config.to_bytes());
+        assert!(roles.has_role(&fee_manager.to_bytes(), RoleEnum::FeeManager));
+    }
+
+    #[test]
+    fn test_multisig_creates_and_withdraws_from_a_treasury() {
+        let (context, global_config, multisig, members) = setup_multisig_authority();
+        let payer = funded_wallet(&context);
+        let mint = create_mint(&context, &payer, 6);
+        let config_id = Pubkey::new_from_array(read::<GlobalConfig>(&context, &global_config).config_id);
+        let treasury = treasury_pda(&mint, &config_id);
+
+        let init_accounts = vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(global_config, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+
+        run_proposal(&context, &multisig, &members, 1, &[StakingInstructions::InitTreasuryAccount as u8], &init_accounts);
+
+        // Fees collected into the treasury come back out through the multisig
+        set_token_account(&context, treasury, &mint, &global_config, 1_000);
+        let destination = create_token_account(&context, &mint, &members[0], 0);
+
+        let mut withdraw_data = vec![StakingInstructions::WithdrawTreasury as u8];
+        withdraw_data.extend_from_slice(&600u64.to_le_bytes());
+
+        let withdraw_accounts = vec![
+            AccountMeta::new_readonly(multisig, false),
+            AccountMeta::new_readonly(global_config, false),
+            AccountMeta::new_readonly(roles_pda(&global_config), false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+
+        run_proposal(&context, &multisig, &members, 2, &withdraw_data, &withdraw_accounts);
+
+        assert_eq!(token_amount(&context, &treasury), 400);
+        assert_eq!(token_amount(&context, &destination), 600);
+    }
+}
@@ -0,0 +1,1622 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer};
+use pinocchio_token::{instructions::{BurnChecked, MintToChecked}, state::{Mint, TokenAccount}};
+
+use crate::error::StakingError;
+use crate::events::{Event, PoolBalanceUpdatedEvent, SolDepositEvent, StakeWithdrawEvent};
+use crate::rewards::calculate_protocol_fee;
+use crate::stake_program::{
+    self,
+    AuthorizeStake,
+    DeactivateStake,
+    DelegateStake,
+    InitializeStake,
+    MergeStake,
+    SplitStake,
+    StakeAuthorize,
+    WithdrawStake
+};
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    staking_pool_account::{PoolTypeEnum, StakingPool},
+    validator_list::{TransientStateEnum, ValidatorList, ValidatorStatusEnum}
+};
+
+// Native pools keep their SOL in stake accounts owned by the Stake program, all under the pool's
+// `stake_authority` PDA as staker and withdrawer:
+//   - reserve_stake: initialized but undelegated, receives deposits and pays rent for new stake accounts
+//   - validator_stake: one per listed validator, delegated to its vote account
+//   - transient_stake: one per listed validator, stake activating or deactivating until it's merged
+// Lamports moving between them don't change total_underlying.
+
+pub fn process_initialize_native_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,              // Pool authority, pays for the new accounts
+        staking_pool_account,   // Pool account
+        validator_list_account, // Validator list PDA, created here
+        stake_authority,        // Stake authority PDA
+        reserve_stake,          // Reserve stake PDA, created here
+        rent_sysvar,            // Rent sysvar
+        _system_program,        // System program
+        _stake_program,         // Stake program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    // Only a pool nobody has staked in yet can switch to native SOL
+    if staking_pool.liquid_stake_supply != 0 || staking_pool.total_underlying != 0 {
+        return Err(StakingError::PoolNotEmpty.into());
+    }
+
+    let (stake_authority_pda, _stake_authority_bump) = pubkey::find_program_address(
+        &[b"stake_authority", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *stake_authority.key() != stake_authority_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (validator_list_pda, validator_list_bump) = pubkey::find_program_address(
+        &[b"validator_list", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *validator_list_account.key() != validator_list_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (reserve_stake_pda, reserve_stake_bump) = pubkey::find_program_address(
+        &[b"reserve_stake", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *reserve_stake.key() != reserve_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !validator_list_account.data_is_empty() || !reserve_stake.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = Rent::get()?;
+
+    let bump_ref = &[validator_list_bump];
+    let seeds = seeds!(
+        b"validator_list",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: validator_list_account,
+        lamports: rent.minimum_balance(ValidatorList::SIZE),
+        space: ValidatorList::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut validator_list = ValidatorList::initialize(validator_list_account)?;
+    validator_list.staking_pool = *staking_pool_account.key();
+    validator_list.bump = validator_list_bump;
+
+    let bump_ref = &[reserve_stake_bump];
+    let seeds = seeds!(
+        b"reserve_stake",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: reserve_stake,
+        lamports: rent.minimum_balance(stake_program::STAKE_ACCOUNT_SIZE as usize),
+        space: stake_program::STAKE_ACCOUNT_SIZE,
+        owner: &stake_program::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeStake {
+        stake: reserve_stake,
+        rent_sysvar,
+        staker: &stake_authority_pda,
+        withdrawer: &stake_authority_pda,
+    }.invoke()?;
+
+    staking_pool.pool_type = PoolTypeEnum::NativeSol as u8;
+    staking_pool.validator_list = validator_list_pda;
+    staking_pool.last_balance_update_epoch = Clock::get()?.epoch;
+
+    Ok(())
+}
+
+/// Deposits land in the undelegated reserve and earn nothing until a RebalanceValidators crank moves
+/// them into a validator's transient stake and MergeTransientStake folds it into the validator stake.
+/// They are still minted LST at the pool's exchange rate, so the pool's yield per LST dips by the
+/// reserve's share until the crank runs. Keepers should rebalance after deposits land each epoch.
+pub fn process_deposit_sol(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,                   // Depositor (signer)
+        _authority,              // Global config authority
+        global_config_account,  // Global config PDA, LST mint authority
+        staking_pool_account,   // Pool account
+        reserve_stake,          // Reserve stake PDA receiving the SOL
+        liquid_stake_mint,      // LST mint
+        user_lst_token_account, // User's LST token account
+        _system_program,        // System program
+        _token_program,         // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let lamports = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if lamports == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let (reserve_stake_pda, _reserve_stake_bump) = pubkey::find_program_address(
+        &[b"reserve_stake", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *reserve_stake.key() != reserve_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let global_config = GlobalConfig::from_account_info(global_config_account)?;
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::NativeSol)?;
+    check_balance_updated(&staking_pool)?;
+
+    if staking_pool.pool_status != 0 { // Not active
+        return Err(StakingError::PoolNotActive.into());
+    }
+
+    if staking_pool.emergency_pause_flag {
+        return Err(StakingError::PoolEmergencyPause.into());
+    }
+
+    if global_config.emergency_pause {
+        return Err(StakingError::GlobalEmergencyPause.into());
+    }
+
+    if lamports < staking_pool.minimum_stake_amount || lamports < global_config.min_stake_amount {
+        return Err(StakingError::BelowMinimumStake.into());
+    }
+
+    if staking_pool.maximum_stake_limit > 0 &&
+       staking_pool.total_underlying.saturating_add(lamports) > staking_pool.maximum_stake_limit {
+        return Err(StakingError::StakeLimitExceeded.into());
+    }
+
+    if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    {
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let lst_tokens = staking_pool.underlying_to_lst(lamports)?;
+
+    if lst_tokens == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    Transfer {
+        from: user,
+        to: reserve_stake,
+        lamports,
+    }.invoke()?;
+
+    let global_config_bump = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    MintToChecked {
+        mint: liquid_stake_mint,
+        account: user_lst_token_account,
+        mint_authority: global_config_account,
+        amount: lst_tokens,
+        decimals: Mint::from_account_info(liquid_stake_mint)?.decimals(),
+    }.invoke_signed(&[signer_seeds])?;
+
+    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_add(lamports);
+
+    SolDepositEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        lamports,
+        lst_minted: lst_tokens,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_rebalance_validators(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        staking_pool_account,   // Pool account
+        validator_list_account, // Pool's validator list
+        validator_vote_account, // Listed validator to rebalance
+        stake_authority,        // Stake authority PDA
+        reserve_stake,          // Reserve stake PDA
+        validator_stake,        // Validator stake PDA
+        transient_stake,        // Transient stake PDA
+        clock_sysvar,           // Clock sysvar
+        stake_history_sysvar,   // Stake history sysvar
+        stake_config,           // Stake config account
+        _system_program,        // System program
+        _stake_program,         // Stake program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let total_underlying = {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+        check_native_pool(&staking_pool, pool_id, validator_list_account)?;
+        staking_pool.total_underlying
+    };
+
+    let (stake_authority_bump, validator_stake_bump, transient_stake_bump) = check_stake_pdas(
+        staking_pool_account,
+        validator_vote_account,
+        stake_authority,
+        reserve_stake,
+        validator_stake,
+        transient_stake
+    )?;
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+
+    let target_lamports = {
+        let entry = *validator_list.find_mut(validator_vote_account.key())?;
+        validator_list.target_lamports(&entry, total_underlying)
+    };
+
+    let entry = validator_list.find_mut(validator_vote_account.key())?;
+
+    // A validator moves at most once per epoch, stake in flight has to be merged first
+    if !matches!(TransientStateEnum::try_from(&entry.transient_state)?, TransientStateEnum::None) {
+        return Err(StakingError::TransientStakeInUse.into());
+    }
+
+    let rent_lamports = Rent::get()?.minimum_balance(stake_program::STAKE_ACCOUNT_SIZE as usize);
+
+    // The reserve stays rent exempt and pays rent for the stake account it splits into
+    let reserve_available = reserve_stake.lamports().saturating_sub(rent_lamports.saturating_mul(2));
+
+    let stake_authority_bump = &[stake_authority_bump];
+    let stake_authority_seeds = seeds!(
+        b"stake_authority",
+        staking_pool_account.key().as_ref(),
+        stake_authority_bump
+    );
+
+    if target_lamports == 0 && !validator_stake.data_is_empty() {
+        // Nothing should stay with this validator, so the whole account cools down
+        DeactivateStake {
+            stake: validator_stake,
+            clock_sysvar,
+            stake_authority,
+        }.invoke_signed(&[Signer::from(&stake_authority_seeds)])?;
+
+        entry.transient_stake_lamports = entry.active_stake_lamports;
+        entry.active_stake_lamports = 0;
+        entry.transient_state = TransientStateEnum::Draining as u8;
+    } else if target_lamports > entry.active_stake_lamports {
+        let increase = (target_lamports - entry.active_stake_lamports).min(reserve_available);
+
+        if increase > 0 {
+            // The first delegation creates the validator stake, later ones activate in the transient account
+            let creates_validator_stake = validator_stake.data_is_empty();
+
+            let (new_stake, new_stake_seed, new_stake_bump): (&AccountInfo, &[u8], u8) = if creates_validator_stake {
+                (validator_stake, b"validator_stake", validator_stake_bump)
+            } else {
+                (transient_stake, b"transient_stake", transient_stake_bump)
+            };
+
+            let bump_ref = &[new_stake_bump];
+            let new_stake_seeds = seeds!(
+                new_stake_seed,
+                staking_pool_account.key().as_ref(),
+                validator_vote_account.key().as_ref(),
+                bump_ref
+            );
+
+            create_pool_stake_account(
+                reserve_stake,
+                new_stake,
+                stake_authority,
+                clock_sysvar,
+                stake_history_sysvar,
+                rent_lamports,
+                &[Signer::from(&new_stake_seeds)],
+                &[Signer::from(&stake_authority_seeds)]
+            )?;
+
+            SplitStake {
+                stake: reserve_stake,
+                split_stake: new_stake,
+                stake_authority,
+                lamports: increase,
+            }.invoke_signed(&[Signer::from(&stake_authority_seeds)])?;
+
+            DelegateStake {
+                stake: new_stake,
+                vote: validator_vote_account,
+                clock_sysvar,
+                stake_history_sysvar,
+                stake_config,
+                stake_authority,
+            }.invoke_signed(&[Signer::from(&stake_authority_seeds)])?;
+
+            if creates_validator_stake {
+                entry.active_stake_lamports = entry.active_stake_lamports.saturating_add(increase);
+            } else {
+                entry.transient_stake_lamports = increase;
+                entry.transient_state = TransientStateEnum::Activating as u8;
+            }
+        }
+    } else if target_lamports < entry.active_stake_lamports {
+        let decrease = entry.active_stake_lamports - target_lamports;
+
+        if reserve_stake.lamports().saturating_sub(rent_lamports) < rent_lamports {
+            return Err(StakingError::InsufficientReserve.into());
+        }
+
+        let bump_ref = &[transient_stake_bump];
+        let transient_stake_seeds = seeds!(
+            b"transient_stake",
+            staking_pool_account.key().as_ref(),
+            validator_vote_account.key().as_ref(),
+            bump_ref
+        );
+
+        create_pool_stake_account(
+            reserve_stake,
+            transient_stake,
+            stake_authority,
+            clock_sysvar,
+            stake_history_sysvar,
+            rent_lamports,
+            &[Signer::from(&transient_stake_seeds)],
+            &[Signer::from(&stake_authority_seeds)]
+        )?;
+
+        SplitStake {
+            stake: validator_stake,
+            split_stake: transient_stake,
+            stake_authority,
+            lamports: decrease,
+        }.invoke_signed(&[Signer::from(&stake_authority_seeds)])?;
+
+        DeactivateStake {
+            stake: transient_stake,
+            clock_sysvar,
+            stake_authority,
+        }.invoke_signed(&[Signer::from(&stake_authority_seeds)])?;
+
+        entry.active_stake_lamports -= decrease;
+        entry.transient_stake_lamports = decrease;
+        entry.transient_state = TransientStateEnum::Deactivating as u8;
+    }
+
+    entry.last_rebalance_epoch = Clock::get()?.epoch;
+
+    Ok(())
+}
+
+pub fn process_merge_transient_stake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        staking_pool_account,   // Pool account
+        validator_list_account, // Pool's validator list
+        validator_vote_account, // Listed validator
+        stake_authority,        // Stake authority PDA
+        reserve_stake,          // Reserve stake PDA
+        validator_stake,        // Validator stake PDA
+        transient_stake,        // Transient stake PDA
+        clock_sysvar,           // Clock sysvar
+        stake_history_sysvar,   // Stake history sysvar
+        _stake_program,         // Stake program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+        check_native_pool(&staking_pool, pool_id, validator_list_account)?;
+    }
+
+    let (stake_authority_bump, _validator_stake_bump, _transient_stake_bump) = check_stake_pdas(
+        staking_pool_account,
+        validator_vote_account,
+        stake_authority,
+        reserve_stake,
+        validator_stake,
+        transient_stake
+    )?;
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+    let entry = validator_list.find_mut(validator_vote_account.key())?;
+
+    let transient_state = TransientStateEnum::try_from(&entry.transient_state)?;
+
+    // The Stake program rejects the merge until the stake has fully (de)activated
+    let (destination, source) = match transient_state {
+        TransientStateEnum::None => return Err(StakingError::NoTransientStake.into()),
+        TransientStateEnum::Activating => (validator_stake, transient_stake),
+        TransientStateEnum::Deactivating => (reserve_stake, transient_stake),
+        TransientStateEnum::Draining => (reserve_stake, validator_stake),
+    };
+
+    let stake_authority_bump = &[stake_authority_bump];
+    let seeds = seeds!(
+        b"stake_authority",
+        staking_pool_account.key().as_ref(),
+        stake_authority_bump
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    MergeStake {
+        destination,
+        source,
+        clock_sysvar,
+        stake_history_sysvar,
+        stake_authority,
+    }.invoke_signed(&[signer_seeds])?;
+
+    if matches!(transient_state, TransientStateEnum::Activating) {
+        entry.active_stake_lamports = entry.active_stake_lamports.saturating_add(entry.transient_stake_lamports);
+    }
+
+    entry.transient_stake_lamports = 0;
+    entry.transient_state = TransientStateEnum::None as u8;
+
+    // A drained validator that's being removed has nothing left with the pool
+    let drained_for_removal = matches!(transient_state, TransientStateEnum::Draining)
+        && matches!(ValidatorStatusEnum::try_from(&entry.status)?, ValidatorStatusEnum::Removing);
+
+    if drained_for_removal {
+        validator_list.remove(validator_vote_account.key())?;
+    }
+
+    Ok(())
+}
+
+/// Brings total_underlying up to the lamports the pool's stake accounts actually hold, which is
+/// how native staking rewards (and losses) show up. The protocol fee on the growth is minted to the
+/// treasury as LST. SPL pools keep accruing through reward_rate_per_second instead. Anyone can crank
+/// it once per epoch, the balance it records is read from the stake accounts themselves.
+pub fn process_update_pool_balance(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        global_config_account,  // Global config PDA, LST mint authority
+        staking_pool_account,   // Pool account
+        validator_list_account, // Pool's validator list
+        reserve_stake,          // Reserve stake PDA
+        liquid_stake_mint,      // LST mint
+        treasury_lst_account,   // Treasury's LST token account, receives the fee
+        _token_program,         // Token program
+        stake_accounts @ ..,    // validator_stake and transient_stake of every listed validator, in list order
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let global_config = GlobalConfig::from_account_info(global_config_account)?;
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    check_native_pool(&staking_pool, pool_id, validator_list_account)?;
+
+    let clock = Clock::get()?;
+
+    if clock.epoch <= staking_pool.last_balance_update_epoch {
+        return Err(StakingError::BalanceAlreadyUpdated.into());
+    }
+
+    let (reserve_stake_pda, _reserve_stake_bump) = pubkey::find_program_address(
+        &[b"reserve_stake", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *reserve_stake.key() != reserve_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    global_config.check_treasury(treasury_lst_account, &staking_pool.liquid_stake_mint)?;
+
+    let rent_lamports = Rent::get()?.minimum_balance(stake_program::STAKE_ACCOUNT_SIZE as usize);
+
+    // The reserve's own rent was paid by the pool authority, everything else belongs to LST holders
+    let mut total_lamports = reserve_stake.lamports().saturating_sub(rent_lamports);
+
+    {
+        let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+        let count = validator_list.validator_count as usize;
+
+        if stake_accounts.len() != count * 2 {
+            return Err(StakingError::StakeAccountsMismatch.into());
+        }
+
+        for (entry, pair) in validator_list.validators[..count].iter_mut().zip(stake_accounts.chunks_exact(2)) {
+            let [validator_stake, transient_stake] = pair else {
+                return Err(StakingError::StakeAccountsMismatch.into());
+            };
+
+            let (validator_stake_pda, _validator_stake_bump) = pubkey::find_program_address(
+                &[
+                    b"validator_stake",
+                    staking_pool_account.key().as_ref(),
+                    entry.vote_account.as_ref(),
+                ],
+                &crate::ID
+            );
+
+            let (transient_stake_pda, _transient_stake_bump) = pubkey::find_program_address(
+                &[
+                    b"transient_stake",
+                    staking_pool_account.key().as_ref(),
+                    entry.vote_account.as_ref(),
+                ],
+                &crate::ID
+            );
+
+            if *validator_stake.key() != validator_stake_pda || *transient_stake.key() != transient_stake_pda {
+                return Err(StakingError::StakeAccountsMismatch.into());
+            }
+
+            total_lamports = total_lamports
+                .saturating_add(validator_stake.lamports())
+                .saturating_add(transient_stake.lamports());
+
+            // Rewards compound into the tracked stake so rebalancing sees current balances
+            let validator_lamports = validator_stake.lamports().saturating_sub(rent_lamports);
+            let transient_lamports = transient_stake.lamports().saturating_sub(rent_lamports);
+
+            match TransientStateEnum::try_from(&entry.transient_state)? {
+                TransientStateEnum::None => entry.active_stake_lamports = validator_lamports,
+                TransientStateEnum::Activating | TransientStateEnum::Deactivating => {
+                    entry.active_stake_lamports = validator_lamports;
+                    entry.transient_stake_lamports = transient_lamports;
+                }
+                TransientStateEnum::Draining => entry.transient_stake_lamports = validator_lamports,
+            }
+        }
+    }
+
+    let previous_underlying = staking_pool.total_underlying;
+    let growth = total_lamports.saturating_sub(previous_underlying);
+    let fee_lamports = calculate_protocol_fee(growth, global_config.protocol_fee_rate)?;
+
+    // The fee is priced after the rest of the growth lands, so minting it doesn't dilute that growth
+    staking_pool.total_underlying = total_lamports.saturating_sub(fee_lamports);
+    let fee_lst = staking_pool.underlying_to_lst(fee_lamports)?;
+    staking_pool.total_underlying = total_lamports;
+
+    if fee_lst > 0 {
+        let global_config_bump = &[global_config_bump];
+        let seeds = seeds!(
+            b"global_config_account",
+            config_id.as_ref(),
+            global_config_bump
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        MintToChecked {
+            mint: liquid_stake_mint,
+            account: treasury_lst_account,
+            mint_authority: global_config_account,
+            amount: fee_lst,
+            decimals: Mint::from_account_info(liquid_stake_mint)?.decimals(),
+        }.invoke_signed(&[signer_seeds])?;
+
+        staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(fee_lst);
+    }
+
+    staking_pool.last_balance_update_epoch = clock.epoch;
+
+    PoolBalanceUpdatedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        epoch: clock.epoch,
+        previous_underlying,
+        total_underlying: total_lamports,
+        fee_lamports,
+        fee_lst_minted: fee_lst,
+        timestamp: clock.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_withdraw_stake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,                   // LST holder (signer), pays rent for the new stake account
+        staking_pool_account,   // Pool account
+        validator_list_account, // Pool's validator list
+        validator_vote_account, // Validator whose stake is split, ignored when splitting from the reserve
+        stake_authority,        // Stake authority PDA
+        source_stake,           // Reserve or validator stake PDA to split from
+        destination_stake,      // New stake account keypair (signer), handed to the user
+        liquid_stake_mint,      // LST mint
+        user_lst_token_account, // User's LST token account
+        clock_sysvar,           // Clock sysvar
+        _system_program,        // System program
+        _token_program,         // Token program
+        _stake_program,         // Stake program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() || !destination_stake.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let lst_amount = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if lst_amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    check_native_pool(&staking_pool, pool_id, validator_list_account)?;
+    check_balance_updated(&staking_pool)?;
+
+    let (stake_authority_pda, stake_authority_bump) = pubkey::find_program_address(
+        &[b"stake_authority", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *stake_authority.key() != stake_authority_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (reserve_stake_pda, _reserve_stake_bump) = pubkey::find_program_address(
+        &[b"reserve_stake", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    let from_reserve = *source_stake.key() == reserve_stake_pda;
+
+    if !from_reserve {
+        let (validator_stake_pda, _validator_stake_bump) = pubkey::find_program_address(
+            &[
+                b"validator_stake",
+                staking_pool_account.key().as_ref(),
+                validator_vote_account.key().as_ref(),
+            ],
+            &crate::ID
+        );
+
+        if *source_stake.key() != validator_stake_pda {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+        let entry = validator_list.find_mut(validator_vote_account.key())?;
+
+        // A draining validator stake is already cooling down as a whole
+        if matches!(TransientStateEnum::try_from(&entry.transient_state)?, TransientStateEnum::Draining) {
+            return Err(StakingError::TransientStakeInUse.into());
+        }
+    }
+
+    if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    {
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        if user_lst_token_info.amount() < lst_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+    }
+
+    let lamports = staking_pool.lst_to_underlying(lst_amount)?;
+
+    if lamports == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    BurnChecked {
+        mint: liquid_stake_mint,
+        account: user_lst_token_account,
+        authority: user,
+        amount: lst_amount,
+        decimals: Mint::from_account_info(liquid_stake_mint)?.decimals(),
+    }.invoke()?;
+
+    // Splitting active stake needs a rent exempt destination up front
+    CreateAccount {
+        from: user,
+        to: destination_stake,
+        lamports: Rent::get()?.minimum_balance(stake_program::STAKE_ACCOUNT_SIZE as usize),
+        space: stake_program::STAKE_ACCOUNT_SIZE,
+        owner: &stake_program::ID
+    }.invoke()?;
+
+    let stake_authority_bump = &[stake_authority_bump];
+    let seeds = seeds!(
+        b"stake_authority",
+        staking_pool_account.key().as_ref(),
+        stake_authority_bump
+    );
+
+    SplitStake {
+        stake: source_stake,
+        split_stake: destination_stake,
+        stake_authority,
+        lamports,
+    }.invoke_signed(&[Signer::from(&seeds)])?;
+
+    AuthorizeStake {
+        stake: destination_stake,
+        clock_sysvar,
+        authority: stake_authority,
+        new_authority: user.key(),
+        stake_authorize: StakeAuthorize::Staker,
+    }.invoke_signed(&[Signer::from(&seeds)])?;
+
+    AuthorizeStake {
+        stake: destination_stake,
+        clock_sysvar,
+        authority: stake_authority,
+        new_authority: user.key(),
+        stake_authorize: StakeAuthorize::Withdrawer,
+    }.invoke_signed(&[Signer::from(&seeds)])?;
+
+    if !from_reserve {
+        let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+        let entry = validator_list.find_mut(validator_vote_account.key())?;
+        entry.active_stake_lamports = entry.active_stake_lamports.saturating_sub(lamports);
+    }
+
+    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_sub(lst_amount);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_sub(lamports);
+
+    StakeWithdrawEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_account: *destination_stake.key(),
+        pool_id,
+        lst_burned: lst_amount,
+        lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+fn check_native_pool(staking_pool: &StakingPool, pool_id: u64, validator_list_account: &AccountInfo) -> ProgramResult {
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::NativeSol)?;
+
+    if staking_pool.validator_list != *validator_list_account.key() {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    Ok(())
+}
+
+// Deposits and withdrawals are priced at the pool's exchange rate, which only includes an epoch's
+// rewards once UpdatePoolBalance has run for it
+fn check_balance_updated(staking_pool: &StakingPool) -> ProgramResult {
+    if staking_pool.last_balance_update_epoch != Clock::get()?.epoch {
+        return Err(StakingError::BalanceNotUpdated.into());
+    }
+
+    Ok(())
+}
+
+// Returns the bumps of the stake authority, validator stake and transient stake PDAs
+fn check_stake_pdas(
+    staking_pool_account: &AccountInfo,
+    validator_vote_account: &AccountInfo,
+    stake_authority: &AccountInfo,
+    reserve_stake: &AccountInfo,
+    validator_stake: &AccountInfo,
+    transient_stake: &AccountInfo,
+) -> Result<(u8, u8, u8), ProgramError> {
+    let (stake_authority_pda, stake_authority_bump) = pubkey::find_program_address(
+        &[b"stake_authority", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *stake_authority.key() != stake_authority_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (reserve_stake_pda, _reserve_stake_bump) = pubkey::find_program_address(
+        &[b"reserve_stake", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *reserve_stake.key() != reserve_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (validator_stake_pda, validator_stake_bump) = pubkey::find_program_address(
+        &[
+            b"validator_stake",
+            staking_pool_account.key().as_ref(),
+            validator_vote_account.key().as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *validator_stake.key() != validator_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (transient_stake_pda, transient_stake_bump) = pubkey::find_program_address(
+        &[
+            b"transient_stake",
+            staking_pool_account.key().as_ref(),
+            validator_vote_account.key().as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *transient_stake.key() != transient_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    Ok((stake_authority_bump, validator_stake_bump, transient_stake_bump))
+}
+
+// Funds a new stake PDA's rent from the reserve and hands it to the Stake program, ready to be split into
+#[allow(clippy::too_many_arguments)]
+fn create_pool_stake_account(
+    reserve_stake: &AccountInfo,
+    new_stake: &AccountInfo,
+    stake_authority: &AccountInfo,
+    clock_sysvar: &AccountInfo,
+    stake_history_sysvar: &AccountInfo,
+    rent_lamports: u64,
+    new_stake_signers: &[Signer],
+    stake_authority_signers: &[Signer],
+) -> ProgramResult {
+    if !new_stake.data_is_empty() {
+        return Err(StakingError::TransientStakeInUse.into());
+    }
+
+    WithdrawStake {
+        stake: reserve_stake,
+        recipient: new_stake,
+        clock_sysvar,
+        stake_history_sysvar,
+        withdraw_authority: stake_authority,
+        lamports: rent_lamports,
+    }.invoke_signed(stake_authority_signers)?;
+
+    Allocate {
+        account: new_stake,
+        space: stake_program::STAKE_ACCOUNT_SIZE,
+    }.invoke_signed(new_stake_signers)?;
+
+    Assign {
+        account: new_stake,
+        owner: &stake_program::ID,
+    }.invoke_signed(new_stake_signers)?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{account::Account, clock::Clock, instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+    use solana_vote_interface::state::{VoteInit, VoteState, VoteStateVersions};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::stake_program;
+    use crate::states::validator_list::{TransientStateEnum, ValidatorList, ValidatorStakeInfo, ValidatorStatusEnum};
+    use crate::test_utils::*;
+
+    const STAKE_PROGRAM_ID: Pubkey = Pubkey::new_from_array(stake_program::ID);
+    const STAKE_CONFIG_ID: Pubkey = Pubkey::new_from_array(stake_program::STAKE_CONFIG_ID);
+    const STAKE_HISTORY_ID: Pubkey = Pubkey::new_from_array(stake_program::STAKE_HISTORY_ID);
+    const VOTE_PROGRAM_ID: Pubkey = Pubkey::new_from_array(stake_program::VOTE_PROGRAM_ID);
+
+    const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+    // StakeStateV2 tags
+    const STAKE_STATE_INITIALIZED: u32 = 1;
+    const STAKE_STATE_DELEGATED: u32 = 2;
+
+    // StakeError::MergeMismatch
+    const STAKE_ERROR_MERGE_MISMATCH: u32 = 6;
+
+    struct NativePool {
+        pool: TestPool,
+        validator_list: Pubkey,
+        stake_authority: Pubkey,
+        reserve_stake: Pubkey,
+        vote_account: Pubkey,
+        validator_stake: Pubkey,
+        transient_stake: Pubkey,
+        treasury_lst: Pubkey,
+    }
+
+    struct Depositor {
+        user: Pubkey,
+        lst_account: Pubkey,
+    }
+
+    fn stake_rent(context: &Context) -> u64 {
+        context.mollusk.sysvars.rent.minimum_balance(stake_program::STAKE_ACCOUNT_SIZE as usize)
+    }
+
+    // The Stake program reads the sysvars from the accounts it's handed, keep them current
+    fn store_sysvars(context: &Context) {
+        let sysvars = &context.mollusk.sysvars;
+
+        for (key, sysvar_account) in [
+            sysvars.keyed_account_for_clock_sysvar(),
+            sysvars.keyed_account_for_rent_sysvar(),
+            sysvars.keyed_account_for_stake_history_sysvar(),
+        ] {
+            set_account(context, key, sysvar_account);
+        }
+    }
+
+    // With no stake history recorded, stake delegated in an earlier epoch counts as fully active
+    fn advance_epoch(context: &mut Context) {
+        context.mollusk.sysvars.clock.epoch += 1;
+        store_sysvars(context);
+    }
+
+    fn create_vote_account(context: &Context) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let node = Pubkey::new_unique();
+
+        let vote_state = VoteState::new(
+            &VoteInit { node_pubkey: node, authorized_voter: node, authorized_withdrawer: node, commission: 0 },
+            &Clock::default(),
+        );
+        let mut data = vec![0u8; VoteState::size_of()];
+        VoteState::serialize(&VoteStateVersions::new_current(vote_state), &mut data).unwrap();
+
+        set_account(context, key, Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: VOTE_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        });
+        key
+    }
+
+    // An SPL pool switched to native SOL, with one validator taking all of it
+    fn setup_native_pool() -> (Context, NativePool) {
+        let context = setup_with_programs(&[(STAKE_PROGRAM_ID, "solana_stake_program")]);
+        store_sysvars(&context);
+
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let treasury_lst = init_treasury(&context, &authority, &global_config, &pool.liquid_stake_mint);
+        let vote_account = create_vote_account(&context);
+
+        let pool_seed = pool.staking_pool;
+        let native = NativePool {
+            validator_list: Pubkey::find_program_address(&[b"validator_list", pool_seed.as_ref()], &PROGRAM_ID).0,
+            stake_authority: Pubkey::find_program_address(&[b"stake_authority", pool_seed.as_ref()], &PROGRAM_ID).0,
+            reserve_stake: Pubkey::find_program_address(&[b"reserve_stake", pool_seed.as_ref()], &PROGRAM_ID).0,
+            validator_stake: Pubkey::find_program_address(&[b"validator_stake", pool_seed.as_ref(), vote_account.as_ref()], &PROGRAM_ID).0,
+            transient_stake: Pubkey::find_program_address(&[b"transient_stake", pool_seed.as_ref(), vote_account.as_ref()], &PROGRAM_ID).0,
+            vote_account,
+            treasury_lst,
+            pool,
+        };
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::InitializeNativePool,
+                vec![
+                    AccountMeta::new(authority, true),
+                    AccountMeta::new(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.stake_authority, false),
+                    AccountMeta::new(native.reserve_stake, false),
+                    AccountMeta::new_readonly(solana_sdk::sysvar::rent::ID, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+                ],
+                &native.pool.pool_id.to_le_bytes(),
+            ),
+            &[Check::success()],
+        );
+
+        let mut data = native.pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(&100u16.to_le_bytes()); // target_weight
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::AddValidator,
+                vec![
+                    AccountMeta::new_readonly(authority, true),
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.vote_account, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+
+        (context, native)
+    }
+
+    fn create_depositor(context: &Context, native: &NativePool) -> Depositor {
+        let user = funded_wallet(context);
+
+        Depositor {
+            user,
+            lst_account: create_token_account(context, &native.pool.liquid_stake_mint, &user, 0),
+        }
+    }
+
+    fn deposit(context: &Context, native: &NativePool, depositor: &Depositor, lamports: u64, check: Check) {
+        let mut data = native.pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(&lamports.to_le_bytes());
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::DepositSol,
+                vec![
+                    AccountMeta::new(depositor.user, true),
+                    AccountMeta::new_readonly(native.pool.authority, false),
+                    AccountMeta::new_readonly(native.pool.global_config, false),
+                    AccountMeta::new(native.pool.staking_pool, false),
+                    AccountMeta::new(native.reserve_stake, false),
+                    AccountMeta::new(native.pool.liquid_stake_mint, false),
+                    AccountMeta::new(depositor.lst_account, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn rebalance(context: &Context, native: &NativePool, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RebalanceValidators,
+                vec![
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.vote_account, false),
+                    AccountMeta::new_readonly(native.stake_authority, false),
+                    AccountMeta::new(native.reserve_stake, false),
+                    AccountMeta::new(native.validator_stake, false),
+                    AccountMeta::new(native.transient_stake, false),
+                    AccountMeta::new_readonly(solana_sdk::sysvar::clock::ID, false),
+                    AccountMeta::new_readonly(STAKE_HISTORY_ID, false),
+                    AccountMeta::new_readonly(STAKE_CONFIG_ID, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+                ],
+                &native.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn merge(context: &Context, native: &NativePool, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::MergeTransientStake,
+                vec![
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.vote_account, false),
+                    AccountMeta::new_readonly(native.stake_authority, false),
+                    AccountMeta::new(native.reserve_stake, false),
+                    AccountMeta::new(native.validator_stake, false),
+                    AccountMeta::new(native.transient_stake, false),
+                    AccountMeta::new_readonly(solana_sdk::sysvar::clock::ID, false),
+                    AccountMeta::new_readonly(STAKE_HISTORY_ID, false),
+                    AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+                ],
+                &native.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn update_pool_balance(context: &Context, native: &NativePool, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdatePoolBalance,
+                vec![
+                    AccountMeta::new_readonly(native.pool.global_config, false),
+                    AccountMeta::new(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.reserve_stake, false),
+                    AccountMeta::new(native.pool.liquid_stake_mint, false),
+                    AccountMeta::new(native.treasury_lst, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(native.validator_stake, false),
+                    AccountMeta::new_readonly(native.transient_stake, false),
+                ],
+                &native.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    // Splits `lst_amount` worth of stake out of `source_stake` into a new account handed to the depositor
+    fn withdraw(context: &Context, native: &NativePool, depositor: &Depositor, source_stake: &Pubkey, lst_amount: u64, check: Check) -> Pubkey {
+        let destination_stake = Pubkey::new_unique();
+
+        let mut data = native.pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(&lst_amount.to_le_bytes());
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::WithdrawStake,
+                vec![
+                    AccountMeta::new(depositor.user, true),
+                    AccountMeta::new(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(native.vote_account, false),
+                    AccountMeta::new_readonly(native.stake_authority, false),
+                    AccountMeta::new(*source_stake, false),
+                    AccountMeta::new(destination_stake, true),
+                    AccountMeta::new(native.pool.liquid_stake_mint, false),
+                    AccountMeta::new(depositor.lst_account, false),
+                    AccountMeta::new_readonly(solana_sdk::sysvar::clock::ID, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+
+        destination_stake
+    }
+
+    fn add_validator(context: &Context, native: &NativePool, signer: &Pubkey, vote_account: &Pubkey, target_weight: u16, check: Check) {
+        let mut data = native.pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(&target_weight.to_le_bytes());
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::AddValidator,
+                vec![
+                    AccountMeta::new_readonly(*signer, true),
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(*vote_account, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn remove_validator(context: &Context, native: &NativePool, vote_account: &Pubkey, check: Check) {
+        let validator_stake = Pubkey::find_program_address(
+            &[b"validator_stake", native.pool.staking_pool.as_ref(), vote_account.as_ref()],
+            &PROGRAM_ID
+        ).0;
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RemoveValidator,
+                vec![
+                    AccountMeta::new_readonly(native.pool.authority, true),
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                    AccountMeta::new_readonly(*vote_account, false),
+                    AccountMeta::new_readonly(validator_stake, false),
+                ],
+                &native.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn set_validator_weight(context: &Context, native: &NativePool, vote_account: &Pubkey, target_weight: u16, check: Check) {
+        let mut data = native.pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(vote_account.as_ref());
+        data.extend_from_slice(&target_weight.to_le_bytes());
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::SetValidatorWeight,
+                vec![
+                    AccountMeta::new_readonly(native.pool.authority, true),
+                    AccountMeta::new_readonly(native.pool.staking_pool, false),
+                    AccountMeta::new(native.validator_list, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn validator_entry(context: &Context, native: &NativePool) -> ValidatorStakeInfo {
+        let validator_list: ValidatorList = read(context, &native.validator_list);
+        assert_eq!(validator_list.validator_count, 1);
+
+        validator_list.validators[0]
+    }
+
+    // StakeStateV2 tag, then Meta's rent_exempt_reserve, staker and withdrawer
+    fn stake_state_tag(context: &Context, stake: &Pubkey) -> u32 {
+        u32::from_le_bytes(account(context, stake).data[0..4].try_into().unwrap())
+    }
+
+    fn stake_authorities(context: &Context, stake: &Pubkey) -> (Pubkey, Pubkey) {
+        let data = account(context, stake).data;
+
+        (
+            Pubkey::try_from(&data[12..44]).unwrap(),
+            Pubkey::try_from(&data[44..76]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_deposit_mints_lst_against_the_reserve() {
+        let (context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+        let rent = stake_rent(&context);
+
+        assert_eq!(stake_state_tag(&context, &native.reserve_stake), STAKE_STATE_INITIALIZED);
+        assert_eq!(stake_authorities(&context, &native.reserve_stake), (native.stake_authority, native.stake_authority));
+
+        deposit(&context, &native, &depositor, 0, custom_error(StakingError::ZeroAmount));
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+
+        // The first deposit sets the exchange rate at one LST per lamport
+        assert_eq!(token_amount(&context, &depositor.lst_account), 10 * LAMPORTS_PER_SOL);
+        assert_eq!(account(&context, &native.reserve_stake).lamports, rent + 10 * LAMPORTS_PER_SOL);
+
+        let second = create_depositor(&context, &native);
+        deposit(&context, &native, &second, 5 * LAMPORTS_PER_SOL, Check::success());
+        assert_eq!(token_amount(&context, &second.lst_account), 5 * LAMPORTS_PER_SOL);
+    }
+
+    #[test]
+    fn test_deposits_wait_for_the_epoch_balance_update() {
+        let (mut context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+
+        advance_epoch(&mut context);
+        deposit(&context, &native, &depositor, LAMPORTS_PER_SOL, custom_error(StakingError::BalanceNotUpdated));
+
+        update_pool_balance(&context, &native, Check::success());
+        deposit(&context, &native, &depositor, LAMPORTS_PER_SOL, Check::success());
+    }
+
+    #[test]
+    fn test_rebalance_delegates_the_reserve() {
+        let (context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+        let rent = stake_rent(&context);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+
+        // The first delegation goes straight into the validator stake, the reserve keeps its rent
+        assert_eq!(stake_state_tag(&context, &native.validator_stake), STAKE_STATE_DELEGATED);
+        assert_eq!(stake_authorities(&context, &native.validator_stake), (native.stake_authority, native.stake_authority));
+        assert_eq!(account(&context, &native.reserve_stake).lamports, rent);
+        assert_eq!(account(&context, &native.validator_stake).lamports, 10 * LAMPORTS_PER_SOL);
+
+        let entry = validator_entry(&context, &native);
+        assert_eq!(entry.active_stake_lamports, 10 * LAMPORTS_PER_SOL - rent);
+        assert_eq!(entry.transient_state, TransientStateEnum::None as u8);
+
+        // Nothing left in the reserve to move
+        let before = account(&context, &native.validator_stake).lamports;
+        rebalance(&context, &native, Check::success());
+        assert_eq!(account(&context, &native.validator_stake).lamports, before);
+        assert!(account(&context, &native.transient_stake).data.is_empty());
+    }
+
+    #[test]
+    fn test_transient_stake_merges_once_active() {
+        let (mut context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+        let rent = stake_rent(&context);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+        merge(&context, &native, custom_error(StakingError::NoTransientStake));
+
+        advance_epoch(&mut context);
+        update_pool_balance(&context, &native, Check::success());
+
+        // Later deposits activate in the transient stake next to the active validator stake
+        deposit(&context, &native, &depositor, 5 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+
+        let entry = validator_entry(&context, &native);
+        assert_eq!(entry.transient_state, TransientStateEnum::Activating as u8);
+        assert_eq!(entry.transient_stake_lamports, 5 * LAMPORTS_PER_SOL - rent);
+        assert_eq!(stake_state_tag(&context, &native.transient_stake), STAKE_STATE_DELEGATED);
+
+        // One move per validator until it's merged
+        rebalance(&context, &native, custom_error(StakingError::TransientStakeInUse));
+
+        // The Stake program won't merge activating stake into active stake
+        merge(&context, &native, Check::err(ProgramError::Custom(STAKE_ERROR_MERGE_MISMATCH)));
+
+        advance_epoch(&mut context);
+        merge(&context, &native, Check::success());
+
+        let entry = validator_entry(&context, &native);
+        assert_eq!(entry.transient_state, TransientStateEnum::None as u8);
+        assert_eq!(entry.transient_stake_lamports, 0);
+        assert_eq!(account(&context, &native.validator_stake).lamports, 15 * LAMPORTS_PER_SOL);
+        assert_eq!(account(&context, &native.transient_stake).lamports, 0);
+
+        // The transient account's rent came along, the balance update counts it as stake
+        update_pool_balance(&context, &native, Check::success());
+        assert_eq!(validator_entry(&context, &native).active_stake_lamports, 15 * LAMPORTS_PER_SOL - rent);
+    }
+
+    #[test]
+    fn test_withdraw_splits_stake_to_the_holder() {
+        let (mut context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+
+        advance_epoch(&mut context);
+        update_pool_balance(&context, &native, Check::success());
+
+        withdraw(&context, &native, &depositor, &native.validator_stake, 11 * LAMPORTS_PER_SOL, custom_error(StakingError::InsufficientTokenBalance));
+
+        let validator_lamports = account(&context, &native.validator_stake).lamports;
+        let active_before = validator_entry(&context, &native).active_stake_lamports;
+
+        let stake_account = withdraw(&context, &native, &depositor, &native.validator_stake, 2 * LAMPORTS_PER_SOL, Check::success());
+
+        // The split stake stays delegated and belongs to the holder
+        assert_eq!(stake_state_tag(&context, &stake_account), STAKE_STATE_DELEGATED);
+        assert_eq!(stake_authorities(&context, &stake_account), (depositor.user, depositor.user));
+        assert_eq!(account(&context, &stake_account).lamports, stake_rent(&context) + 2 * LAMPORTS_PER_SOL);
+
+        assert_eq!(token_amount(&context, &depositor.lst_account), 8 * LAMPORTS_PER_SOL);
+        assert_eq!(account(&context, &native.validator_stake).lamports, validator_lamports - 2 * LAMPORTS_PER_SOL);
+        assert_eq!(validator_entry(&context, &native).active_stake_lamports, active_before - 2 * LAMPORTS_PER_SOL);
+    }
+
+    #[test]
+    fn test_withdraw_only_splits_pool_stake() {
+        let (context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+
+        let foreign_stake = Pubkey::new_unique();
+        withdraw(&context, &native, &depositor, &foreign_stake, LAMPORTS_PER_SOL, custom_error(StakingError::InvalidPda));
+        assert_eq!(token_amount(&context, &depositor.lst_account), 10 * LAMPORTS_PER_SOL);
+
+        // Undelegated SOL still in the reserve comes out as an inactive stake account
+        let stake_account = withdraw(&context, &native, &depositor, &native.reserve_stake, LAMPORTS_PER_SOL, Check::success());
+        assert_eq!(stake_state_tag(&context, &stake_account), STAKE_STATE_INITIALIZED);
+        assert_eq!(stake_authorities(&context, &stake_account), (depositor.user, depositor.user));
+        assert_eq!(token_amount(&context, &depositor.lst_account), 9 * LAMPORTS_PER_SOL);
+    }
+
+    #[test]
+    fn test_validators_are_added_weighted_and_removed() {
+        let (context, native) = setup_native_pool();
+        let authority = native.pool.authority;
+        let second = create_vote_account(&context);
+
+        add_validator(&context, &native, &authority, &second, 300, Check::success());
+        add_validator(&context, &native, &authority, &second, 300, custom_error(StakingError::ValidatorAlreadyListed));
+        add_validator(&context, &native, &authority, &funded_wallet(&context), 300, custom_error(StakingError::InvalidVoteAccount));
+        add_validator(&context, &native, &funded_wallet(&context), &create_vote_account(&context), 300, custom_error(StakingError::Unauthorized));
+
+        let validator_list: ValidatorList = read(&context, &native.validator_list);
+        assert_eq!(validator_list.validator_count, 2);
+        assert_eq!(validator_list.total_target_weight, 400);
+        assert_eq!(validator_list.validators[1].vote_account, second.to_bytes());
+
+        set_validator_weight(&context, &native, &second, 50, Check::success());
+
+        let validator_list: ValidatorList = read(&context, &native.validator_list);
+        assert_eq!(validator_list.validators[1].target_weight, 50);
+        assert_eq!(validator_list.total_target_weight, 150);
+
+        // Never delegated to, so it leaves the list straight away
+        remove_validator(&context, &native, &second, Check::success());
+
+        let validator_list: ValidatorList = read(&context, &native.validator_list);
+        assert_eq!(validator_list.validator_count, 1);
+        assert_eq!(validator_list.total_target_weight, 100);
+        assert_eq!(validator_list.validators[0].vote_account, native.vote_account.to_bytes());
+
+        set_validator_weight(&context, &native, &second, 50, custom_error(StakingError::ValidatorNotFound));
+    }
+
+    #[test]
+    fn test_removing_a_staked_validator_waits_for_its_stake() {
+        let (context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+
+        remove_validator(&context, &native, &native.vote_account, Check::success());
+
+        // Kept with no weight so the next rebalance pulls its stake back into the reserve
+        let entry = validator_entry(&context, &native);
+        assert_eq!(entry.status, ValidatorStatusEnum::Removing as u8);
+        assert_eq!(entry.target_weight, 0);
+
+        let validator_list: ValidatorList = read(&context, &native.validator_list);
+        assert_eq!(validator_list.total_target_weight, 0);
+
+        set_validator_weight(&context, &native, &native.vote_account, 100, custom_error(StakingError::ValidatorRemoving));
+    }
+
+    #[test]
+    fn test_balance_update_runs_once_per_epoch() {
+        let (mut context, native) = setup_native_pool();
+
+        update_pool_balance(&context, &native, custom_error(StakingError::BalanceAlreadyUpdated));
+
+        // No signer, any keeper can crank it once the epoch turns
+        advance_epoch(&mut context);
+        update_pool_balance(&context, &native, Check::success());
+        update_pool_balance(&context, &native, custom_error(StakingError::BalanceAlreadyUpdated));
+
+        advance_epoch(&mut context);
+        update_pool_balance(&context, &native, Check::success());
+    }
+
+    #[test]
+    fn test_balance_update_compounds_stake_rewards_and_takes_the_fee() {
+        let (mut context, native) = setup_native_pool();
+        let depositor = create_depositor(&context, &native);
+        let rent = stake_rent(&context);
+
+        deposit(&context, &native, &depositor, 10 * LAMPORTS_PER_SOL, Check::success());
+        rebalance(&context, &native, Check::success());
+
+        // An epoch of inflation rewards paid into the validator stake
+        let mut validator_stake = account(&context, &native.validator_stake);
+        validator_stake.lamports += LAMPORTS_PER_SOL;
+        set_account(&context, native.validator_stake, validator_stake);
+
+        advance_epoch(&mut context);
+        update_pool_balance(&context, &native, Check::success());
+
+        assert_eq!(validator_entry(&context, &native).active_stake_lamports, 11 * LAMPORTS_PER_SOL - rent);
+
+        // 1% of the 1 SOL growth, minted at the rate the other 0.99 SOL already set: 0.01 * 10 / 10.99
+        assert_eq!(token_amount(&context, &native.treasury_lst), 9_099_181);
+        assert_eq!(token_amount(&context, &depositor.lst_account), 10 * LAMPORTS_PER_SOL);
+    }
+}
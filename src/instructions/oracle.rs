@@ -1,17 +1,20 @@
-use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
 use pinocchio_system::instructions::CreateAccount;
 use pinocchio_log::log;
-use crate::states::{oracle_config::OracleConfigInfo, helper::AccountData};
+use crate::error::StakingError;
+use crate::states::{oracle_config::OracleConfigInfo, helper::AccountData, roles::{RoleEnum, Roles}};
 
 pub fn process_init_oracle_config(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
-    let [oracle_authority, oracle_config_account, price_feed_account, system_program] = accounts else {
+    let [oracle_authority, oracle_config_account, price_feed_account, _system_program, global_config_account, roles_account] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    if !oracle_authority.is_signer() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    Roles::check_role(global_config_account, roles_account, oracle_authority, RoleEnum::OracleManager)?;
+
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     let update_frequency_seconds = i64::from_le_bytes(
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
@@ -27,7 +30,7 @@ pub fn process_init_oracle_config(accounts: &[AccountInfo], instruction_data: &[
     );
 
     if *oracle_config_account.key() != oracle_config_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     if oracle_config_account.data_is_empty() {
@@ -51,13 +54,14 @@ pub fn process_init_oracle_config(accounts: &[AccountInfo], instruction_data: &[
             owner: &crate::ID
         }.invoke_signed(&[signer_seeds])?;
 
-        let mut oracle_config_account_info_mut = OracleConfigInfo::from_account_info_mut(oracle_config_account)?;
+        let mut oracle_config_account_info_mut = OracleConfigInfo::initialize(oracle_config_account)?;
         oracle_config_account_info_mut.price_feed_account = *price_feed_account.key();
         oracle_config_account_info_mut.update_frequency_seconds = update_frequency_seconds;
         oracle_config_account_info_mut.oracle_authority = *oracle_authority.key();
         oracle_config_account_info_mut.last_update_timestamp = Clock::get()?.unix_timestamp;
         oracle_config_account_info_mut.current_price = current_price;
         oracle_config_account_info_mut.oracle_account_bump = bump;
+        oracle_config_account_info_mut.global_config = *global_config_account.key();
     } else {
         return Err(ProgramError::AccountAlreadyInitialized);
     };
@@ -67,32 +71,39 @@ pub fn process_init_oracle_config(accounts: &[AccountInfo], instruction_data: &[
 
 pub fn process_update_price(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
-    let [oracle_authority, oracle_config_account] = accounts else {
+    let [oracle_manager, oracle_config_account, global_config_account, roles_account] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    if !oracle_authority.is_signer() {
+    if !oracle_manager.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     };
 
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
     let new_price = u64::from_le_bytes(
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
+    let mut oracle_account_info = OracleConfigInfo::from_account_info_mut(oracle_config_account)?;
+
     let (oracle_config_pda, _bump) = pubkey::find_program_address(
-        &[b"oracle_config_account", oracle_authority.key().as_ref()],
+        &[b"oracle_config_account", oracle_account_info.oracle_authority.as_ref()],
         &crate::ID
     );
 
     if *oracle_config_account.key() != oracle_config_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
-    let mut oracle_account_info = OracleConfigInfo::from_account_info_mut(oracle_config_account)?;
-
-    if oracle_account_info.oracle_authority != *oracle_authority.key() {
-        return Err(ProgramError::InvalidAccountOwner);
+    if oracle_account_info.global_config != *global_config_account.key() {
+        return Err(StakingError::InvalidPda.into());
     };
+
+    Roles::check_role(global_config_account, roles_account, oracle_manager, RoleEnum::OracleManager)?;
+
     let current_timestamp = Clock::get()?.unix_timestamp;
     
     oracle_account_info.current_price = new_price;
@@ -113,7 +124,7 @@ pub fn get_oracle_price(accounts: &[AccountInfo]) -> ProgramResult {
     );
 
     if *oracle_config_account.key() != oracle_config_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     };
 
     let oracle_account_info = OracleConfigInfo::from_account_info(oracle_config_account)?;
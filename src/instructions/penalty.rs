@@ -0,0 +1,650 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, *};
+use pinocchio_token::{state::{TokenAccount, Mint}, instructions::TransferChecked};
+use pinocchio_log::log;
+
+use crate::error::StakingError;
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    roles::{RoleEnum, Roles},
+    staking_pool_account::StakingPool,
+    user_stake_account::{PenaltyEvent, PenaltyType, StakePosition, UserStakeAccount}
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub fn process_open_penalty(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, user_stake_account, stake_position_account, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 53 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let penalty_type = instruction_data[8];
+
+    let amount = u64::from_le_bytes(
+        instruction_data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let recovery_period = u32::from_le_bytes(
+        instruction_data[17..21].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let validator: Pubkey = instruction_data[21..53]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    // Early-unstake penalties are charged by RequestUnstake itself
+    if matches!(PenaltyType::try_from(&penalty_type)?, PenaltyType::EarlyUnstake) {
+        return Err(StakingError::InvalidPenaltyType.into());
+    }
+
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+
+    Roles::check_pool_role(global_config_account, roles_account, authority, staking_pool_account.key(), RoleEnum::Slasher)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    let position = StakePosition::from_account_info(stake_position_account)?;
+
+    position.check_owner(&user_stake.owner, staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
+    }
+
+    // A penalty can't exceed what the position is still worth, counting penalties still open against it
+    let position_value = staking_pool.lst_to_underlying(position.lst_tokens)?;
+    let headroom = position_value
+        .saturating_sub(position.penalty_debt)
+        .saturating_sub(user_stake.open_penalty_amount(stake_position_account.key()));
+
+    if amount > headroom {
+        return Err(StakingError::PenaltyExceedsPosition.into());
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    let user = user_stake.owner;
+
+    let penalty_id = user_stake.record_penalty(PenaltyEvent {
+        penalty_type,
+        amount,
+        timestamp: current_timestamp,
+        grace_period_end: current_timestamp.saturating_add(staking_pool.penalty_grace_period),
+        pool_id,
+        user,
+        stake_position: *stake_position_account.key(),
+        validator,
+        original_stake_amount: position_value,
+        recovery_period,
+        recovery_bps: staking_pool.penalty_recovery_bps,
+        ..PenaltyEvent::default()
+    })?;
+
+    user_stake.active_penalties = user_stake.active_penalties.saturating_add(1);
+
+    log!("Penalty Opened: {}", penalty_id);
+
+    Ok(())
+}
+
+pub fn process_dispute_penalty(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [user, user_stake_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let penalty_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    let penalty = user_stake.find_penalty_mut(penalty_id)?;
+
+    if penalty.is_resolved {
+        return Err(StakingError::PenaltyAlreadyResolved.into());
+    }
+
+    if penalty.is_disputed {
+        return Err(StakingError::PenaltyAlreadyDisputed.into());
+    }
+
+    if current_timestamp > penalty.grace_period_end {
+        return Err(StakingError::GracePeriodOver.into());
+    }
+
+    penalty.is_disputed = true;
+
+    Ok(())
+}
+
+pub fn process_resolve_penalty(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, user_stake_account, stake_position_account, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let penalty_id = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let uphold = match instruction_data[16] {
+        0 => false,  // 0 = overturn
+        1 => true,   // 1 = uphold
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+
+    Roles::check_pool_role(global_config_account, roles_account, authority, staking_pool_account.key(), RoleEnum::Slasher)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    position.check_owner(&user_stake.owner, staking_pool_account.key())?;
+
+    let penalty = user_stake.find_penalty_mut(penalty_id)?;
+
+    if penalty.is_resolved {
+        return Err(StakingError::PenaltyAlreadyResolved.into());
+    }
+
+    if penalty.pool_id != pool_id || penalty.stake_position != *stake_position_account.key() {
+        return Err(StakingError::PenaltyPositionMismatch.into());
+    }
+
+    // Undisputed penalties can only be settled once the user has had the full grace period
+    if !penalty.is_disputed && current_timestamp <= penalty.grace_period_end {
+        return Err(StakingError::GracePeriodActive.into());
+    }
+
+    penalty.is_resolved = true;
+    penalty.is_overturned = !uphold;
+    penalty.resolution_timestamp = current_timestamp;
+    let amount = penalty.amount;
+
+    if uphold {
+        // Charged against the position and collected from its next unstake
+        position.penalty_debt = position.penalty_debt.saturating_add(amount);
+        user_stake.total_penalties = user_stake.total_penalties.saturating_add(amount);
+    }
+
+    user_stake.active_penalties = user_stake.active_penalties.saturating_sub(1);
+
+    Ok(())
+}
+
+pub fn process_recover_penalty(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,
+        _authority,
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        staking_pool_account,
+        user_stake_account,
+        stake_position_account,
+        user_token_account,
+        _token_program,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let penalty_id = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+    let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
+    }
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if *stake_token_vault.key() != staking_pool.stake_token_vault {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    if *stake_token_mint.key() != staking_pool.stake_token_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    {
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_token_info.mint() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    let penalty = user_stake.find_penalty_mut(penalty_id)?;
+
+    if !penalty.is_resolved || penalty.is_overturned || penalty.is_recovered {
+        return Err(StakingError::PenaltyNotRecoverable.into());
+    }
+
+    if penalty.pool_id != pool_id || penalty.stake_position != *stake_position_account.key() {
+        return Err(StakingError::PenaltyPositionMismatch.into());
+    }
+
+    if penalty.recovery_bps == 0 {
+        return Err(StakingError::PenaltyNotRecoverable.into());
+    }
+
+    let recovery_timestamp = penalty.resolution_timestamp
+        .saturating_add((penalty.recovery_period as i64).saturating_mul(SECONDS_PER_DAY));
+
+    if current_timestamp < recovery_timestamp {
+        return Err(StakingError::RecoveryPeriodActive.into());
+    }
+
+    let recovered_amount = penalty.recoverable_amount();
+
+    penalty.is_recovered = true;
+
+    // Debt that hasn't been collected yet is forgiven first, the rest is paid back from what
+    // was held for this penalty when it was collected
+    let forgiven_amount = penalty.amount
+        .saturating_sub(penalty.debt_settled)
+        .min(recovered_amount)
+        .min(position.penalty_debt);
+    position.penalty_debt = position.penalty_debt.saturating_sub(forgiven_amount);
+    penalty.debt_settled = penalty.debt_settled.saturating_add(forgiven_amount);
+
+    let refund_amount = penalty.recovery_reserve.min(staking_pool.penalty_reserve);
+    penalty.recovery_reserve = 0;
+
+    if refund_amount > 0 {
+        let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
+
+        let global_config_bump_arr = &[global_config_bump];
+        let seeds = seeds!(
+            b"global_config_account",
+            config_id.as_ref(),
+            global_config_bump_arr
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        TransferChecked {
+            from: stake_token_vault,
+            to: user_token_account,
+            mint: stake_token_mint,
+            authority: global_config_account,
+            amount: refund_amount,
+            decimals: stake_mint_decimals,
+        }.invoke_signed(&[signer_seeds])?;
+    }
+
+    staking_pool.penalty_reserve = staking_pool.penalty_reserve.saturating_sub(refund_amount);
+    user_stake.total_penalties = user_stake.total_penalties
+        .saturating_sub(forgiven_amount.saturating_add(refund_amount));
+    user_stake.last_update_timestamp = current_timestamp;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::user_stake_account::{PenaltyType, UserStakeAccount, MAX_HISTORY};
+    use crate::test_utils::*;
+
+    use super::SECONDS_PER_DAY;
+
+    const STAKE_AMOUNT: u64 = 1000;
+    const GRACE_PERIOD: i64 = 3 * SECONDS_PER_DAY;
+    const PENALTY_GRACE_PERIOD_UPDATE: u8 = 16;
+    const PENALTY_RECOVERY_BPS_UPDATE: u8 = 17;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+    }
+
+    // One staked position in a pool with a three day grace period and `recovery_bps` recovery
+    fn setup_position(recovery_bps: u16) -> Setup {
+        let mut context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.stake_mint);
+        apply_config_change(&mut context, &pool, PENALTY_GRACE_PERIOD_UPDATE, &GRACE_PERIOD.to_le_bytes());
+        apply_config_change(&mut context, &pool, PENALTY_RECOVERY_BPS_UPDATE, &recovery_bps.to_le_bytes());
+
+        let staker = create_staker(&context, &pool, STAKE_AMOUNT);
+        let position = stake(&context, &pool, &staker, STAKE_AMOUNT, 0);
+
+        Setup { context, pool, staker, position }
+    }
+
+    fn open(setup: &Setup, penalty_type: PenaltyType, amount: u64, recovery_days: u32, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.push(penalty_type as u8);
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&recovery_days.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::OpenPenalty,
+                vec![
+                    AccountMeta::new_readonly(setup.pool.authority, true),
+                    AccountMeta::new_readonly(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.user_stake, false),
+                    AccountMeta::new_readonly(setup.position, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new_readonly(roles_pda(&setup.pool.global_config), false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn dispute(setup: &Setup, penalty_id: u64, check: Check) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::DisputePenalty,
+                vec![AccountMeta::new_readonly(setup.staker.user, true), AccountMeta::new(setup.staker.user_stake, false)],
+                &penalty_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn resolve(setup: &Setup, penalty_id: u64, uphold: bool, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&penalty_id.to_le_bytes());
+        data.push(uphold as u8);
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ResolvePenalty,
+                vec![
+                    AccountMeta::new_readonly(setup.pool.authority, true),
+                    AccountMeta::new_readonly(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.user_stake, false),
+                    AccountMeta::new(setup.position, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new_readonly(roles_pda(&setup.pool.global_config), false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn recover(setup: &Setup, penalty_id: u64, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&penalty_id.to_le_bytes());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RecoverPenalty,
+                vec![
+                    AccountMeta::new_readonly(setup.staker.user, true),
+                    AccountMeta::new_readonly(setup.pool.authority, false),
+                    AccountMeta::new_readonly(setup.pool.stake_mint, false),
+                    AccountMeta::new(setup.pool.stake_vault, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.user_stake, false),
+                    AccountMeta::new(setup.position, false),
+                    AccountMeta::new(setup.staker.token_account, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn emergency_withdraw(setup: &Setup, check: Check) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::WithdrawFunds,
+                vec![
+                    AccountMeta::new(setup.staker.user, true),
+                    AccountMeta::new_readonly(setup.pool.authority, false),
+                    AccountMeta::new_readonly(setup.pool.stake_mint, false),
+                    AccountMeta::new(setup.pool.stake_vault, false),
+                    AccountMeta::new(setup.pool.liquid_stake_mint, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.token_account, false),
+                    AccountMeta::new(setup.staker.user_stake, false),
+                    AccountMeta::new(setup.position, false),
+                    AccountMeta::new(setup.staker.lst_account, false),
+                    AccountMeta::new(treasury(setup), false),
+                    AccountMeta::new(setup.pool.reward_vault, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &setup.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    fn treasury(setup: &Setup) -> Pubkey {
+        treasury_pda(&setup.pool.stake_mint, &setup.pool.authority)
+    }
+
+    fn user_stake(setup: &Setup) -> UserStakeAccount {
+        read::<UserStakeAccount>(&setup.context, &setup.staker.user_stake)
+    }
+
+    #[test]
+    fn test_disputed_penalty_can_be_overturned() {
+        let setup = setup_position(0);
+
+        open(&setup, PenaltyType::Slashing, 300, 0, Check::success());
+        assert_eq!(user_stake(&setup).active_penalties, 1);
+
+        dispute(&setup, 0, Check::success());
+        dispute(&setup, 0, custom_error(StakingError::PenaltyAlreadyDisputed));
+
+        // A dispute lets the penalty be settled before the grace period ends
+        resolve(&setup, 0, false, Check::success());
+        resolve(&setup, 0, false, custom_error(StakingError::PenaltyAlreadyResolved));
+        recover(&setup, 0, custom_error(StakingError::PenaltyNotRecoverable));
+
+        let user_stake = user_stake(&setup);
+        assert_eq!(user_stake.active_penalties, 0);
+        assert_eq!(user_stake.total_penalties, 0);
+
+        pause_pool(&setup.context, &setup.pool);
+        emergency_withdraw(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT);
+    }
+
+    #[test]
+    fn test_undisputed_penalty_waits_out_the_grace_period() {
+        let mut setup = setup_position(0);
+
+        open(&setup, PenaltyType::ValidatorMisbehavior, 300, 0, Check::success());
+        resolve(&setup, 0, true, custom_error(StakingError::GracePeriodActive));
+
+        advance_time(&mut setup.context, GRACE_PERIOD + 1);
+        dispute(&setup, 0, custom_error(StakingError::GracePeriodOver));
+        resolve(&setup, 0, true, Check::success());
+
+        let user_stake = user_stake(&setup);
+        assert_eq!(user_stake.active_penalties, 0);
+        assert_eq!(user_stake.total_penalties, 300);
+
+        // The upheld penalty is collected from the exit
+        pause_pool(&setup.context, &setup.pool);
+        emergency_withdraw(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT - 300);
+    }
+
+    #[test]
+    fn test_open_penalty_blocks_emergency_exit() {
+        let mut setup = setup_position(0);
+
+        open(&setup, PenaltyType::Inactivity, 300, 0, Check::success());
+        pause_pool(&setup.context, &setup.pool);
+        emergency_withdraw(&setup, custom_error(StakingError::PenaltyPending));
+
+        advance_time(&mut setup.context, GRACE_PERIOD + 1);
+        resolve(&setup, 0, true, Check::success());
+        emergency_withdraw(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT - 300);
+    }
+
+    #[test]
+    fn test_open_penalties_are_capped_by_the_position() {
+        let setup = setup_position(0);
+
+        open(&setup, PenaltyType::EarlyUnstake, 100, 0, custom_error(StakingError::InvalidPenaltyType));
+        open(&setup, PenaltyType::Slashing, 0, 0, custom_error(StakingError::ZeroAmount));
+        open(&setup, PenaltyType::Slashing, STAKE_AMOUNT + 1, 0, custom_error(StakingError::PenaltyExceedsPosition));
+
+        // Penalties still open count against what is left
+        open(&setup, PenaltyType::Slashing, 600, 0, Check::success());
+        open(&setup, PenaltyType::Slashing, 401, 0, custom_error(StakingError::PenaltyExceedsPosition));
+        open(&setup, PenaltyType::Slashing, 400, 0, Check::success());
+
+        assert_eq!(user_stake(&setup).active_penalties, 2);
+    }
+
+    #[test]
+    fn test_upheld_penalty_is_partly_recovered() {
+        let mut setup = setup_position(5000);
+
+        open(&setup, PenaltyType::Slashing, 400, 2, Check::success());
+        advance_time(&mut setup.context, GRACE_PERIOD + 1);
+        resolve(&setup, 0, true, Check::success());
+
+        recover(&setup, 0, custom_error(StakingError::RecoveryPeriodActive));
+
+        advance_time(&mut setup.context, 2 * SECONDS_PER_DAY);
+        recover(&setup, 0, Check::success());
+        recover(&setup, 0, custom_error(StakingError::PenaltyNotRecoverable));
+
+        // Half of the penalty is forgiven before it was ever collected
+        assert_eq!(user_stake(&setup).total_penalties, 200);
+
+        pause_pool(&setup.context, &setup.pool);
+        emergency_withdraw(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT - 200);
+        assert_eq!(token_amount(&setup.context, &treasury(&setup)), 200);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 0);
+    }
+
+    #[test]
+    fn test_collected_penalty_is_held_for_its_own_recovery() {
+        let mut setup = setup_position(5000);
+
+        open(&setup, PenaltyType::Slashing, 400, 2, Check::success());
+        advance_time(&mut setup.context, GRACE_PERIOD + 1);
+        resolve(&setup, 0, true, Check::success());
+
+        // The exit collects the whole penalty, the half that can't be recovered goes to the treasury
+        pause_pool(&setup.context, &setup.pool);
+        emergency_withdraw(&setup, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT - 400);
+        assert_eq!(token_amount(&setup.context, &treasury(&setup)), 200);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 200);
+
+        let penalty = user_stake(&setup).penalty_history[MAX_HISTORY - 1];
+        assert_eq!(penalty.debt_settled, 400);
+        assert_eq!(penalty.recovery_reserve, 200);
+
+        // The recovery pays back exactly what was held for this penalty
+        advance_time(&mut setup.context, 2 * SECONDS_PER_DAY);
+        recover(&setup, 0, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), STAKE_AMOUNT - 200);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 0);
+
+        let user_stake = user_stake(&setup);
+        assert_eq!(user_stake.penalty_history[MAX_HISTORY - 1].recovery_reserve, 0);
+        assert_eq!(user_stake.total_penalties, 200);
+    }
+}
@@ -1,39 +1,30 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey, *};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
 
-use crate::states::{helper::AccountData, staking_pool_account::{StakingPool, PoolStatusEnum}};
+use crate::error::StakingError;
+use crate::events::{Event, PoolStatusChangedEvent};
+use crate::states::{helper::AccountData, roles::{RoleEnum, Roles}, staking_pool_account::{StakingPool, PoolStatusEnum}};
 
 pub fn process_resume_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
-    let [authority, staking_pool_account] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+    let [guardian, staking_pool_account, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    if !authority.is_signer() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     let pool_id = u64::from_le_bytes(
         instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
-    let (staking_pool_pda, _staking_pool_bump) = pubkey::find_program_address(
-        &[
-            b"staking_pool",
-            authority.key().as_ref(),
-            pool_id.to_le_bytes().as_ref(),
-        ],
-        &crate::ID, 
-    );
-
-    if *staking_pool_account.key() != staking_pool_pda {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    Roles::check_pool_role(global_config_account, roles_account, guardian, staking_pool_account.key(), RoleEnum::Guardian)?;
 
     let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
 
-    if staking_pool_account_info.authority != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    if staking_pool_account_info.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
 
     let current_status = PoolStatusEnum::try_from(&staking_pool_account_info.pool_status)?;
     match current_status {
@@ -44,9 +35,17 @@ pub fn process_resume_pool(accounts: &[AccountInfo], instruction_data: &[u8]) ->
             return Ok(());
         },
         _ => {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(StakingError::InvalidPoolStatusTransition.into());
         }
     }
 
+    PoolStatusChangedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        old_status: current_status as u8,
+        new_status: staking_pool_account_info.pool_status,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
     Ok(())
 }
\ No newline at end of file
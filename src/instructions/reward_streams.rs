@@ -0,0 +1,470 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{instructions::{InitializeAccount3, TransferChecked}, state::{Mint, TokenAccount}};
+
+use crate::error::StakingError;
+use crate::events::{Event, RewardStreamAddedEvent};
+use crate::rewards::update_pool_rewards;
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    staking_pool_account::{PoolTypeEnum, RewardStream, StakingPool, MAX_REWARD_STREAMS}
+};
+
+pub fn process_add_reward_stream(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,             // Pool authority, pays for the vault
+        global_config_account, // Global config PDA, owns the vault
+        staking_pool_account,  // Pool account
+        reward_mint,           // Token the stream pays out
+        reward_vault,          // Stream vault PDA, created here unless the mint ran a stream before
+        _system_program,       // System program
+        _token_program,        // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let reward_rate_per_second = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let start_timestamp = i64::from_le_bytes(
+        instruction_data[16..24].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let end_timestamp = i64::from_le_bytes(
+        instruction_data[24..32].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    if end_timestamp <= start_timestamp || end_timestamp <= current_timestamp {
+        return Err(StakingError::InvalidRewardSchedule.into());
+    }
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    // Close out emissions up to now so the new stream doesn't accrue for time before it existed,
+    // and so finished streams have their final emissions counted
+    update_pool_rewards(&mut staking_pool, current_timestamp)?;
+
+    // A stream that has ended and been claimed out gives up its slot, positions notice through the generation
+    let reused_index = (0..staking_pool.reward_stream_count as usize)
+        .find(|index| staking_pool.is_reward_stream_finished(*index, current_timestamp));
+
+    if staking_pool.active_reward_streams()
+        .iter()
+        .enumerate()
+        .any(|(index, stream)| Some(index) != reused_index && stream.reward_mint == *reward_mint.key())
+    {
+        return Err(StakingError::DuplicateRewardMint.into());
+    }
+
+    let stream_index = reused_index.unwrap_or(staking_pool.reward_stream_count as usize);
+
+    if stream_index >= MAX_REWARD_STREAMS {
+        return Err(StakingError::RewardStreamsFull.into());
+    }
+
+    let (reward_vault_pda, reward_vault_bump) = pubkey::find_program_address(
+        &[
+            b"reward_stream_vault",
+            staking_pool_account.key().as_ref(),
+            reward_mint.key().as_ref()
+        ],
+        &crate::ID
+    );
+
+    if *reward_vault.key() != reward_vault_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    // A mint coming back after its stream finished keeps its old vault
+    if !reward_vault.data_is_empty() {
+        let reward_vault_info = TokenAccount::from_account_info(reward_vault)?;
+
+        if *reward_vault_info.owner() != *global_config_account.key() || *reward_vault_info.mint() != *reward_mint.key() {
+            return Err(StakingError::InvalidVault.into());
+        }
+    } else {
+        create_reward_vault(authority, global_config_account, staking_pool_account, reward_mint, reward_vault, reward_vault_bump)?;
+    }
+
+    staking_pool.reward_streams[stream_index] = RewardStream {
+        reward_mint: *reward_mint.key(),
+        reward_vault: reward_vault_pda,
+        reward_rate_per_second,
+        start_timestamp,
+        end_timestamp,
+        ..RewardStream::default()
+    };
+    staking_pool.reward_stream_emitted[stream_index] = 0;
+
+    if reused_index.is_some() {
+        staking_pool.reward_stream_generations[stream_index] = staking_pool.reward_stream_generations[stream_index]
+            .wrapping_add(1);
+    } else {
+        staking_pool.reward_stream_count = staking_pool.reward_stream_count.saturating_add(1);
+    }
+
+    RewardStreamAddedEvent {
+        staking_pool: *staking_pool_account.key(),
+        reward_mint: *reward_mint.key(),
+        stream_index: stream_index as u8,
+        reward_rate_per_second,
+        start_timestamp,
+        end_timestamp,
+        timestamp: current_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+fn create_reward_vault(
+    authority: &AccountInfo,
+    global_config_account: &AccountInfo,
+    staking_pool_account: &AccountInfo,
+    reward_mint: &AccountInfo,
+    reward_vault: &AccountInfo,
+    reward_vault_bump: u8
+) -> ProgramResult {
+    let bump_ref = &[reward_vault_bump];
+    let seeds = seeds!(
+        b"reward_stream_vault",
+        staking_pool_account.key().as_ref(),
+        reward_mint.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: reward_vault,
+        lamports: Rent::get()?.minimum_balance(TokenAccount::LEN),
+        space: TokenAccount::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeAccount3 {
+        account: reward_vault,
+        mint: reward_mint,
+        owner: global_config_account.key()
+    }.invoke()
+}
+
+pub fn process_fund_reward_stream(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        funder,               // Anyone co-incentivizing the pool
+        funder_token_account, // Funder's token account for the stream's mint
+        reward_mint,          // Stream's reward mint
+        reward_vault,         // Stream's vault
+        staking_pool_account, // Pool account
+        _token_program,       // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !funder.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let stream_index = instruction_data[8] as usize;
+
+    let amount = u64::from_le_bytes(
+        instruction_data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if staking_pool.emergency_pause_flag {
+        return Err(StakingError::PoolEmergencyPause.into());
+    }
+
+    let stream = staking_pool.active_reward_streams()
+        .get(stream_index)
+        .ok_or(StakingError::RewardStreamNotFound)?;
+
+    if stream.reward_mint != *reward_mint.key() {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if stream.reward_vault != *reward_vault.key() {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    {
+        let funder_token_info = TokenAccount::from_account_info(funder_token_account)?;
+
+        if *funder_token_info.owner() != *funder.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *funder_token_info.mint() != *reward_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        if funder_token_info.amount() < amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+    }
+
+    TransferChecked {
+        from: funder_token_account,
+        to: reward_vault,
+        mint: reward_mint,
+        authority: funder,
+        amount,
+        decimals: Mint::from_account_info(reward_mint)?.decimals(),
+    }.invoke()?;
+
+    let stream = &mut staking_pool.reward_streams[stream_index];
+    stream.total_funded = stream.total_funded.saturating_add(amount);
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::test_utils::*;
+
+    const STREAM_RATE: u64 = 10;
+    const STREAM_DURATION: i64 = 1000;
+    const STREAM_FUNDING: u64 = 10_000;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        reward_account: Pubkey,
+        stream_mint: Pubkey,
+        stream_vault: Pubkey,
+        stream_account: Pubkey,
+        stream_treasury: Pubkey,
+    }
+
+    // Pool with a funded reward vault, a second mint with its own treasury, and a staker holding accounts for both
+    fn setup_pool() -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+        set_token_account(&context, pool.reward_vault, &pool.reward_mint, &global_config, 1_000_000);
+
+        let stream_mint = create_mint(&context, &authority, 6);
+        let stream_treasury = init_treasury(&context, &authority, &global_config, &stream_mint);
+        let stream_vault = Pubkey::find_program_address(
+            &[b"reward_stream_vault", pool.staking_pool.as_ref(), stream_mint.as_ref()],
+            &PROGRAM_ID
+        ).0;
+
+        let staker = create_staker(&context, &pool, 1000);
+        let reward_account = create_token_account(&context, &pool.reward_mint, &staker.user, 0);
+        let stream_account = create_token_account(&context, &stream_mint, &staker.user, 0);
+
+        Setup { context, pool, staker, reward_account, stream_mint, stream_vault, stream_account, stream_treasury }
+    }
+
+    fn add_stream(setup: &Setup, reward_mint: &Pubkey, start_timestamp: i64, end_timestamp: i64, check: Check) {
+        let reward_vault = Pubkey::find_program_address(
+            &[b"reward_stream_vault", setup.pool.staking_pool.as_ref(), reward_mint.as_ref()],
+            &PROGRAM_ID
+        ).0;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&STREAM_RATE.to_le_bytes());
+        data.extend_from_slice(&start_timestamp.to_le_bytes());
+        data.extend_from_slice(&end_timestamp.to_le_bytes());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::AddRewardStream,
+                vec![
+                    AccountMeta::new(setup.pool.authority, true),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new_readonly(*reward_mint, false),
+                    AccountMeta::new(reward_vault, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    // Anyone can top up a stream, here a fresh wallet holding exactly `amount`
+    fn fund_stream(setup: &Setup, amount: u64) {
+        let funder = funded_wallet(&setup.context);
+        let funder_token_account = create_token_account(&setup.context, &setup.stream_mint, &funder, amount);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::FundRewardStream,
+                vec![
+                    AccountMeta::new(funder, true),
+                    AccountMeta::new(funder_token_account, false),
+                    AccountMeta::new_readonly(setup.stream_mint, false),
+                    AccountMeta::new(setup.stream_vault, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+    }
+
+    fn stream_accounts(setup: &Setup) -> [Pubkey; 4] {
+        [setup.stream_mint, setup.stream_vault, setup.stream_account, setup.stream_treasury]
+    }
+
+    #[test]
+    fn test_stream_pays_out_in_its_own_mint() {
+        let mut setup = setup_pool();
+        add_stream(&setup, &setup.stream_mint, START_TIMESTAMP, START_TIMESTAMP + STREAM_DURATION, Check::success());
+        fund_stream(&setup, STREAM_FUNDING);
+        assert_eq!(token_amount(&setup.context, &setup.stream_vault), STREAM_FUNDING);
+
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+        advance_time(&mut setup.context, 100);
+
+        // Every stream has to be passed along with the claim
+        claim_rewards(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &position,
+            &setup.reward_account,
+            &[],
+            custom_error(StakingError::RewardStreamAccountsMismatch),
+        );
+
+        claim_rewards(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &position,
+            &setup.reward_account,
+            &stream_accounts(&setup),
+            Check::success(),
+        );
+
+        // 10 a second for 100 seconds next to the pool's own 100 a second, 1% of both kept as the fee
+        assert_eq!(token_amount(&setup.context, &setup.stream_account), 990);
+        assert_eq!(token_amount(&setup.context, &setup.stream_treasury), 10);
+        assert_eq!(token_amount(&setup.context, &setup.stream_vault), STREAM_FUNDING - 1000);
+        assert_eq!(token_amount(&setup.context, &setup.reward_account), 9_900);
+    }
+
+    #[test]
+    fn test_stream_stops_emitting_at_its_end() {
+        let mut setup = setup_pool();
+        add_stream(&setup, &setup.stream_mint, START_TIMESTAMP, START_TIMESTAMP + STREAM_DURATION, Check::success());
+        fund_stream(&setup, STREAM_FUNDING);
+
+        let position = stake(&setup.context, &setup.pool, &setup.staker, 1000, 0);
+        advance_time(&mut setup.context, 2 * STREAM_DURATION);
+
+        claim_rewards(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &position,
+            &setup.reward_account,
+            &stream_accounts(&setup),
+            Check::success(),
+        );
+
+        // Only the 1000 seconds the stream ran for
+        assert_eq!(token_amount(&setup.context, &setup.stream_account), 9_900);
+        assert_eq!(token_amount(&setup.context, &setup.stream_treasury), 100);
+        assert_eq!(token_amount(&setup.context, &setup.stream_vault), 0);
+    }
+
+    #[test]
+    fn test_add_stream_rejects_bad_schedules_and_duplicate_mints() {
+        let setup = setup_pool();
+
+        // Ends before it starts
+        add_stream(
+            &setup,
+            &setup.stream_mint,
+            START_TIMESTAMP + STREAM_DURATION,
+            START_TIMESTAMP,
+            custom_error(StakingError::InvalidRewardSchedule),
+        );
+
+        // Already over
+        add_stream(
+            &setup,
+            &setup.stream_mint,
+            START_TIMESTAMP - 2 * STREAM_DURATION,
+            START_TIMESTAMP - STREAM_DURATION,
+            custom_error(StakingError::InvalidRewardSchedule),
+        );
+
+        add_stream(&setup, &setup.stream_mint, START_TIMESTAMP, START_TIMESTAMP + STREAM_DURATION, Check::success());
+        add_stream(
+            &setup,
+            &setup.stream_mint,
+            START_TIMESTAMP,
+            START_TIMESTAMP + STREAM_DURATION,
+            custom_error(StakingError::DuplicateRewardMint),
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::error::StakingError;
+use crate::events::{Event, RoleUpdatedEvent};
+use crate::states::{global_config::GlobalConfig, helper::AccountData, roles::{RoleEnum, Roles}};
+
+/// Grants `role` to a member, creating the roles account on the first grant. Rent comes from a
+/// separate payer, the authority can be a multisig PDA that carries data and can't fund accounts.
+pub fn process_grant_role(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,             // Global config authority
+        payer,                 // Pays for the roles account
+        global_config_account, // Global config PDA
+        roles_account,         // Roles PDA, created on the first grant
+        _system_program,       // System program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let (role, member) = parse_role_update(authority, global_config_account, instruction_data)?;
+
+    let (roles_pda, roles_bump) = pubkey::find_program_address(
+        &[b"roles", global_config_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *roles_account.key() != roles_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if roles_account.data_is_empty() {
+        if !payer.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let bump_ref = &[roles_bump];
+        let seeds = seeds!(
+            b"roles",
+            global_config_account.key().as_ref(),
+            bump_ref
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        CreateAccount {
+            from: payer,
+            to: roles_account,
+            lamports: Rent::get()?.minimum_balance(Roles::SIZE),
+            space: Roles::SIZE as u64,
+            owner: &crate::ID
+        }.invoke_signed(&[signer_seeds])?;
+
+        let mut roles = Roles::initialize(roles_account)?;
+        roles.global_config = *global_config_account.key();
+        roles.bump = roles_bump;
+    }
+
+    Roles::from_account_info_mut(roles_account)?.grant(&member, role)?;
+
+    RoleUpdatedEvent {
+        global_config: *global_config_account.key(),
+        member,
+        role: role as u8,
+        granted: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_revoke_role(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let (role, member) = parse_role_update(authority, global_config_account, instruction_data)?;
+
+    let mut roles = Roles::from_account_info_mut(roles_account)?;
+
+    if roles.global_config != *global_config_account.key() {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    roles.revoke(&member, role)?;
+
+    RoleUpdatedEvent {
+        global_config: *global_config_account.key(),
+        member,
+        role: role as u8,
+        granted: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+// role (1), member (32). Only the config authority hands out or takes back roles
+fn parse_role_update(
+    authority: &AccountInfo,
+    global_config_account: &AccountInfo,
+    instruction_data: &[u8]
+) -> Result<(RoleEnum, Pubkey), ProgramError> {
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 33 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let role = RoleEnum::try_from(&instruction_data[0])?;
+
+    let member = Pubkey::try_from(&instruction_data[1..33])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    if GlobalConfig::from_account_info(global_config_account)?.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    Ok((role, member))
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::{global_config::GlobalConfig, roles::{RoleEnum, Roles}};
+    use crate::test_utils::*;
+
+    fn role_data(role: RoleEnum, member: &Pubkey) -> Vec<u8> {
+        let mut data = vec![role as u8];
+        data.extend_from_slice(member.as_ref());
+        data
+    }
+
+    fn grant(context: &Context, authority: &Pubkey, global_config: &Pubkey, role: RoleEnum, member: &Pubkey, check: Check) {
+        let payer = funded_wallet(context);
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::GrantRole,
+                vec![
+                    AccountMeta::new_readonly(*authority, true),
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new_readonly(*global_config, false),
+                    AccountMeta::new(roles_pda(global_config), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                &role_data(role, member),
+            ),
+            &[check],
+        );
+    }
+
+    fn revoke(context: &Context, authority: &Pubkey, global_config: &Pubkey, role: RoleEnum, member: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RevokeRole,
+                vec![
+                    AccountMeta::new_readonly(*authority, true),
+                    AccountMeta::new_readonly(*global_config, false),
+                    AccountMeta::new(roles_pda(global_config), false),
+                ],
+                &role_data(role, member),
+            ),
+            &[check],
+        );
+    }
+
+    fn update_fee(context: &Context, fee_manager: &Pubkey, global_config: &Pubkey, fee: u16, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdateProtocolFee,
+                vec![
+                    AccountMeta::new_readonly(*fee_manager, true),
+                    AccountMeta::new(*global_config, false),
+                    AccountMeta::new_readonly(roles_pda(global_config), false),
+                ],
+                &fee.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_granted_role_gates_the_fee_update() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let fee_manager = funded_wallet(&context);
+        let outsider = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        // The config authority holds every role without an entry
+        update_fee(&context, &authority, &global_config, 200, Check::success());
+
+        grant(&context, &authority, &global_config, RoleEnum::FeeManager, &fee_manager, Check::success());
+        update_fee(&context, &fee_manager, &global_config, 300, Check::success());
+        assert_eq!(read::<GlobalConfig>(&context, &global_config).protocol_fee_rate, 300);
+
+        update_fee(&context, &outsider, &global_config, 400, custom_error(StakingError::MissingRole));
+
+        revoke(&context, &authority, &global_config, RoleEnum::FeeManager, &fee_manager, Check::success());
+        update_fee(&context, &fee_manager, &global_config, 400, custom_error(StakingError::MissingRole));
+        assert_eq!(read::<GlobalConfig>(&context, &global_config).protocol_fee_rate, 300);
+    }
+
+    #[test]
+    fn test_only_the_config_authority_grants_and_revokes() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let member = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        grant(&context, &member, &global_config, RoleEnum::FeeManager, &member, custom_error(StakingError::Unauthorized));
+
+        grant(&context, &authority, &global_config, RoleEnum::FeeManager, &member, Check::success());
+        revoke(&context, &member, &global_config, RoleEnum::FeeManager, &member, custom_error(StakingError::Unauthorized));
+    }
+
+    #[test]
+    fn test_revoke_keeps_the_members_other_roles() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let member = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        grant(&context, &authority, &global_config, RoleEnum::FeeManager, &member, Check::success());
+        grant(&context, &authority, &global_config, RoleEnum::Guardian, &member, Check::success());
+
+        let roles = read::<Roles>(&context, &roles_pda(&global_config));
+        assert_eq!(roles.member_count, 1);
+
+        revoke(&context, &authority, &global_config, RoleEnum::FeeManager, &member, Check::success());
+        revoke(&context, &authority, &global_config, RoleEnum::FeeManager, &member, custom_error(StakingError::RoleNotHeld));
+
+        let roles = read::<Roles>(&context, &roles_pda(&global_config));
+        assert!(roles.has_role(&member.to_bytes(), RoleEnum::Guardian));
+        assert!(!roles.has_role(&member.to_bytes(), RoleEnum::FeeManager));
+
+        // Losing the last role frees the slot
+        revoke(&context, &authority, &global_config, RoleEnum::Guardian, &member, Check::success());
+        assert_eq!(read::<Roles>(&context, &roles_pda(&global_config)).member_count, 0);
+    }
+}
@@ -0,0 +1,613 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{state::Mint, instructions::TransferChecked};
+
+use crate::error::StakingError;
+use crate::events::{Event, SlashEvent, ValidatorSlashEvent};
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    roles::{RoleEnum, Roles},
+    slashing::{SlashEvidence, SlashReporter},
+    staking_pool_account::{PoolTypeEnum, SlashTypeEnum, StakingPool},
+    user_stake_account::PenaltyType,
+    validator_list::ValidatorList
+};
+
+pub fn process_register_slash_reporter(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, reporter, slash_reporter_account, _system_program, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        Roles::check_pool_role(global_config_account, roles_account, authority, staking_pool_account.key(), RoleEnum::Slasher)?;
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+    }
+
+    let (slash_reporter_pda, bump) = pubkey::find_program_address(
+        &[b"slash_reporter", staking_pool_account.key().as_ref(), reporter.key().as_ref()],
+        &crate::ID
+    );
+
+    if *slash_reporter_account.key() != slash_reporter_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !slash_reporter_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let lamports = Rent::get()?.minimum_balance(SlashReporter::SIZE);
+
+    let bump_ref = &[bump];
+    let seeds = seeds!(
+        b"slash_reporter",
+        staking_pool_account.key().as_ref(),
+        reporter.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: slash_reporter_account,
+        lamports,
+        space: SlashReporter::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut slash_reporter_info = SlashReporter::initialize(slash_reporter_account)?;
+    slash_reporter_info.staking_pool = *staking_pool_account.key();
+    slash_reporter_info.reporter = *reporter.key();
+    slash_reporter_info.registered_timestamp = Clock::get()?.unix_timestamp;
+    slash_reporter_info.reports_filed = 0;
+    slash_reporter_info.bump = bump;
+
+    Ok(())
+}
+
+pub fn process_remove_slash_reporter(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, slash_reporter_account, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        Roles::check_pool_role(global_config_account, roles_account, authority, staking_pool_account.key(), RoleEnum::Slasher)?;
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        let slash_reporter_info = SlashReporter::from_account_info(slash_reporter_account)?;
+
+        if slash_reporter_info.staking_pool != *staking_pool_account.key() {
+            return Err(StakingError::PoolMismatch.into());
+        }
+    }
+
+    // Return rent to the authority that paid for the registration
+    let lamports = slash_reporter_account.lamports();
+    *authority.try_borrow_mut_lamports()? = authority.lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    *slash_reporter_account.try_borrow_mut_lamports()? = 0;
+
+    slash_reporter_account.close()?;
+
+    Ok(())
+}
+
+pub fn process_submit_slash_evidence(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [reporter, staking_pool_account, slash_reporter_account, slash_evidence_account, _system_program, remaining @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !reporter.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 41 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let condition_type = instruction_data[8];
+    SlashTypeEnum::try_from(&condition_type)?;
+
+    let evidence_hash: [u8; 32] = instruction_data[9..41]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let (slash_reporter_pda, _) = pubkey::find_program_address(
+        &[b"slash_reporter", staking_pool_account.key().as_ref(), reporter.key().as_ref()],
+        &crate::ID
+    );
+
+    if *slash_reporter_account.key() != slash_reporter_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if slash_reporter_account.data_is_empty() {
+        return Err(StakingError::ReporterNotRegistered.into());
+    }
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if !staking_pool.slashing_enabled {
+        return Err(StakingError::SlashingDisabled.into());
+    }
+
+    // Only reports for the pool's configured condition count towards a slash
+    if staking_pool.slashing_condition_type != condition_type {
+        return Err(StakingError::SlashConditionMismatch.into());
+    }
+
+    // Native pools only answer for their validators, so reports (DownTime, DoubleSign, ...) name a
+    // listed vote account in [41..73] and pass the validator list after the fixed accounts
+    let mut validator_list = None;
+    let mut validator = Pubkey::default();
+
+    if staking_pool.pool_type == PoolTypeEnum::NativeSol as u8 {
+        let [validator_list_account, ..] = remaining else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if *validator_list_account.key() != staking_pool.validator_list {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        validator = instruction_data
+            .get(41..73)
+            .ok_or(ProgramError::InvalidInstructionData)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let list = ValidatorList::from_account_info_mut(validator_list_account)?;
+        list.active_entries()
+            .iter()
+            .find(|entry| entry.vote_account == validator)
+            .ok_or(StakingError::ValidatorNotFound)?;
+
+        validator_list = Some(list);
+    }
+
+    let slash_round = staking_pool.slash_round;
+
+    // One evidence record per reporter per round keeps the reports distinct
+    let slash_round_ref = slash_round.to_le_bytes();
+    let (slash_evidence_pda, bump) = pubkey::find_program_address(
+        &[
+            b"slash_evidence",
+            staking_pool_account.key().as_ref(),
+            reporter.key().as_ref(),
+            slash_round_ref.as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *slash_evidence_account.key() != slash_evidence_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !slash_evidence_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let lamports = Rent::get()?.minimum_balance(SlashEvidence::SIZE);
+
+    let bump_ref = &[bump];
+    let seeds = seeds!(
+        b"slash_evidence",
+        staking_pool_account.key().as_ref(),
+        reporter.key().as_ref(),
+        slash_round_ref.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: reporter,
+        to: slash_evidence_account,
+        lamports,
+        space: SlashEvidence::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    let mut slash_evidence_info = SlashEvidence::initialize(slash_evidence_account)?;
+    slash_evidence_info.staking_pool = *staking_pool_account.key();
+    slash_evidence_info.reporter = *reporter.key();
+    slash_evidence_info.slash_round = slash_round;
+    slash_evidence_info.condition_type = condition_type;
+    slash_evidence_info.evidence_hash = evidence_hash;
+    slash_evidence_info.timestamp = current_timestamp;
+    slash_evidence_info.bump = bump;
+    slash_evidence_info.validator = validator;
+
+    let mut slash_reporter_info = SlashReporter::from_account_info_mut(slash_reporter_account)?;
+    slash_reporter_info.reports_filed = slash_reporter_info.reports_filed.saturating_add(1);
+
+    match validator_list.as_mut() {
+        Some(list) => {
+            let entry = list.find_mut(&validator)?;
+            entry.evidence_count = entry.evidence_count.saturating_add(1);
+        }
+        None => staking_pool.evidence_count = staking_pool.evidence_count.saturating_add(1),
+    }
+
+    Ok(())
+}
+
+pub fn process_execute_slash(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        executor,
+        _authority,
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        staking_pool_account,
+        treasury_account,       // Receives the slashed stake
+        _token_program,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !executor.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let global_config = GlobalConfig::from_account_info(global_config_account)?;
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    if !staking_pool.slashing_enabled {
+        return Err(StakingError::SlashingDisabled.into());
+    }
+
+    if *stake_token_vault.key() != staking_pool.stake_token_vault {
+        return Err(StakingError::InvalidVault.into());
+    }
+
+    if *stake_token_mint.key() != staking_pool.stake_token_mint {
+        return Err(StakingError::InvalidMint.into());
+    }
+
+    if staking_pool.min_evidence_required == 0 || staking_pool.evidence_count < staking_pool.min_evidence_required {
+        return Err(StakingError::NotEnoughEvidence.into());
+    }
+
+    global_config.check_treasury(treasury_account, &staking_pool.stake_token_mint)?;
+
+    // Taken from the LST backing only, so every holder loses the same share through the exchange rate.
+    // Tokens already owed to withdrawal tickets are left alone.
+    let slash_amount = ((staking_pool.total_underlying as u128)
+        .saturating_mul(staking_pool.slash_percentage as u128)
+        / 10000u128) as u64;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    if slash_amount > 0 {
+        let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
+
+        let global_config_bump_arr = &[global_config_bump];
+        let seeds = seeds!(
+            b"global_config_account",
+            config_id.as_ref(),
+            global_config_bump_arr
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        TransferChecked {
+            from: stake_token_vault,
+            to: treasury_account,
+            mint: stake_token_mint,
+            authority: global_config_account,
+            amount: slash_amount,
+            decimals: stake_mint_decimals,
+        }.invoke_signed(&[signer_seeds])?;
+    }
+
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_sub(slash_amount);
+    staking_pool.total_slashed = staking_pool.total_slashed.saturating_add(slash_amount);
+    staking_pool.last_slash_timestamp = current_timestamp;
+
+    SlashEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        penalty_type: PenaltyType::Slashing as u8,
+        condition_type: staking_pool.slashing_condition_type,
+        slash_round: staking_pool.slash_round,
+        slash_amount,
+        total_underlying: staking_pool.total_underlying,
+        timestamp: current_timestamp,
+    }.emit();
+
+    // Evidence from this round is spent, the next slash needs fresh reports
+    staking_pool.slash_round = staking_pool.slash_round.saturating_add(1);
+    staking_pool.evidence_count = 0;
+
+    Ok(())
+}
+
+/// Slashes one validator of a native pool. Solana has no protocol slashing to pass on, so the pool
+/// stops delegating to it instead: its target weight drops to zero and RebalanceValidators drains its stake.
+pub fn process_execute_validator_slash(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [executor, staking_pool_account, validator_list_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !executor.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 40 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let validator: Pubkey = instruction_data[8..40]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::NativeSol)?;
+
+    if !staking_pool.slashing_enabled {
+        return Err(StakingError::SlashingDisabled.into());
+    }
+
+    if *validator_list_account.key() != staking_pool.validator_list {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+    let entry = validator_list.find_mut(&validator)?;
+
+    if staking_pool.min_evidence_required == 0 || entry.evidence_count < staking_pool.min_evidence_required {
+        return Err(StakingError::NotEnoughEvidence.into());
+    }
+
+    entry.slash_count = entry.slash_count.saturating_add(1);
+    let stake_lamports = entry.active_stake_lamports.saturating_add(entry.transient_stake_lamports);
+
+    validator_list.set_weight(&validator, 0)?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    staking_pool.last_slash_timestamp = current_timestamp;
+
+    ValidatorSlashEvent {
+        staking_pool: *staking_pool_account.key(),
+        validator,
+        pool_id,
+        condition_type: staking_pool.slashing_condition_type,
+        slash_round: staking_pool.slash_round,
+        stake_lamports,
+        timestamp: current_timestamp,
+    }.emit();
+
+    // Evidence from this round is spent for every validator
+    staking_pool.slash_round = staking_pool.slash_round.saturating_add(1);
+    staking_pool.evidence_count = 0;
+    validator_list.reset_evidence();
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::{slashing::SlashEvidence, staking_pool_account::SlashTypeEnum, withdrawal_ticket::WithdrawalTicket};
+    use crate::test_utils::*;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+        treasury: Pubkey,
+    }
+
+    // 1000 staked in a pool that slashes 10% on one DownTime report
+    fn setup_position() -> Setup {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        let treasury = init_treasury(&context, &authority, &global_config, &pool.stake_mint);
+
+        let staker = create_staker(&context, &pool, 1000);
+        let position = stake(&context, &pool, &staker, 1000, 0);
+
+        Setup { context, pool, staker, position, treasury }
+    }
+
+    fn slash_reporter_pda(setup: &Setup, reporter: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"slash_reporter", setup.pool.staking_pool.as_ref(), reporter.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn slash_evidence_pda(setup: &Setup, reporter: &Pubkey, slash_round: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"slash_evidence", setup.pool.staking_pool.as_ref(), reporter.as_ref(), &slash_round.to_le_bytes()],
+            &PROGRAM_ID
+        ).0
+    }
+
+    fn register(setup: &Setup, reporter: &Pubkey) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::RegisterSlashReporter,
+                vec![
+                    AccountMeta::new(setup.pool.authority, true),
+                    AccountMeta::new_readonly(setup.pool.staking_pool, false),
+                    AccountMeta::new_readonly(*reporter, false),
+                    AccountMeta::new(slash_reporter_pda(setup, reporter), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new_readonly(roles_pda(&setup.pool.global_config), false),
+                ],
+                &setup.pool.pool_id.to_le_bytes(),
+            ),
+            &[Check::success()],
+        );
+    }
+
+    fn submit(setup: &Setup, reporter: &Pubkey, slash_round: u64, condition_type: SlashTypeEnum, check: Check) -> Pubkey {
+        let slash_evidence = slash_evidence_pda(setup, reporter, slash_round);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.push(condition_type as u8);
+        data.extend_from_slice(&[7u8; 32]);
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::SubmitSlashEvidence,
+                vec![
+                    AccountMeta::new(*reporter, true),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(slash_reporter_pda(setup, reporter), false),
+                    AccountMeta::new(slash_evidence, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+
+        slash_evidence
+    }
+
+    fn execute(setup: &Setup, check: Check) {
+        let executor = funded_wallet(&setup.context);
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ExecuteSlash,
+                vec![
+                    AccountMeta::new_readonly(executor, true),
+                    AccountMeta::new_readonly(setup.pool.authority, false),
+                    AccountMeta::new_readonly(setup.pool.stake_mint, false),
+                    AccountMeta::new(setup.pool.stake_vault, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.treasury, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &setup.pool.pool_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_evidence_needs_a_registered_reporter_and_the_pool_condition() {
+        let setup = setup_position();
+        let reporter = funded_wallet(&setup.context);
+
+        submit(&setup, &reporter, 0, SlashTypeEnum::DownTime, custom_error(StakingError::ReporterNotRegistered));
+
+        register(&setup, &reporter);
+        submit(&setup, &reporter, 0, SlashTypeEnum::DoubleSign, custom_error(StakingError::SlashConditionMismatch));
+        let evidence = submit(&setup, &reporter, 0, SlashTypeEnum::DownTime, Check::success());
+
+        let evidence: SlashEvidence = read(&setup.context, &evidence);
+        assert_eq!(evidence.reporter, reporter.to_bytes());
+        assert_eq!(evidence.slash_round, 0);
+        assert_eq!(evidence.condition_type, SlashTypeEnum::DownTime as u8);
+
+        // One record per reporter per round
+        submit(&setup, &reporter, 0, SlashTypeEnum::DownTime, Check::err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_slash_cuts_the_exchange_rate_and_spends_the_evidence() {
+        let setup = setup_position();
+        let reporter = funded_wallet(&setup.context);
+        register(&setup, &reporter);
+
+        execute(&setup, custom_error(StakingError::NotEnoughEvidence));
+
+        submit(&setup, &reporter, 0, SlashTypeEnum::DownTime, Check::success());
+        execute(&setup, Check::success());
+
+        assert_eq!(token_amount(&setup.context, &setup.treasury), 100);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 900);
+
+        // The round's evidence is used up, a new report opens the next round
+        execute(&setup, custom_error(StakingError::NotEnoughEvidence));
+        submit(&setup, &reporter, 1, SlashTypeEnum::DownTime, Check::success());
+
+        // Holders carry the loss through the rate, 1000 LST now redeem 900
+        let ticket = request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 0, Check::success());
+        let ticket: WithdrawalTicket = read(&setup.context, &ticket);
+        assert_eq!(ticket.underlying_amount, 900);
+    }
+}
@@ -1,17 +1,21 @@
-use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, Sysvar}, *};
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
 use pinocchio_token::{instructions::{TransferChecked, MintToChecked}, state::{Mint, TokenAccount}};
 
+use crate::error::StakingError;
+use crate::events::{Event, StakeEvent};
+use crate::rewards::{reset_reward_debt, update_pool_rewards};
 use crate::states::{
     global_config::GlobalConfig, 
     helper::AccountData, 
-    staking_pool_account::StakingPool, 
+    staking_pool_account::{PoolTypeEnum, StakingPool, NO_LOCK_TIER}, 
     user_stake_account::{StakePosition, UserStakeAccount}
 };
 
 pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,                    // Staker (signer)
-        authority,              // Global config authority  
+        _authority,              // Global config authority  
         creator,                // Pool creator
         stake_token_mint,       // Token being staked
         stake_token_vault,      // Where staked tokens go
@@ -19,11 +23,13 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
         global_config_account,  // Global config PDA
         staking_pool_account,   // Pool account
         user_token_account,     // User's source token account
-        user_stake_account,     // User's stake position account
+        user_stake_account,     // User's aggregate stake account
+        stake_position_account, // New position PDA at user_stake.next_position_index
         user_lst_token_account, // User's LST token account
-        token_program,          // Token program
+        _system_program,        // System program
+        _token_program,         // Token program
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -43,17 +49,10 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
     );
 
     if stake_amount == 0 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::ZeroAmount.into());
     }
 
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let (staking_pool_pda, _staking_pool_bump) = pubkey::find_program_address(
         &[
@@ -65,7 +64,7 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
     );
 
     if *staking_pool_account.key() != staking_pool_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     }
 
     let (stake_token_vault_pda, _stake_token_vault_bump) = pubkey::find_program_address(
@@ -78,7 +77,7 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
     );
 
     if *stake_token_vault.key() != stake_token_vault_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     }
 
     let (liquid_stake_mint_pda, _liquid_stake_mint_bump) = pubkey::find_program_address(
@@ -87,7 +86,7 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
     );
 
     if *liquid_stake_mint.key() != liquid_stake_mint_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     }
 
     // Verify user stake account PDA
@@ -101,90 +100,123 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
     );
 
     if *user_stake_account.key() != user_stake_pda {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::InvalidPda.into());
     }
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
 
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
     if staking_pool.pool_status != 0 { // Not active
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::PoolNotActive.into());
     }
 
     if staking_pool.emergency_pause_flag {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::PoolEmergencyPause.into());
     }
 
     if global_config.emergency_pause {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::GlobalEmergencyPause.into());
     }
 
+    // Optional byte after the amount picks one of the pool's lock tiers, NO_LOCK_TIER stakes unlocked.
+    // Without it the pool-wide lock_period_duration and reward_multiplier apply.
+    let (lock_enabled, lock_duration, lock_multiplier) = match instruction_data.get(16) {
+        Some(&NO_LOCK_TIER) => (false, 0, 0),
+        Some(&tier_index) => {
+            let tier = staking_pool.active_lock_tiers()
+                .get(tier_index as usize)
+                .ok_or(StakingError::InvalidLockTier)?;
+
+            (true, tier.duration, tier.multiplier)
+        },
+        None => (staking_pool.lock_period_enabled, staking_pool.lock_period_duration, 0),
+    };
+
     if stake_amount < staking_pool.minimum_stake_amount {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::BelowMinimumStake.into());
     }
 
     if stake_amount < global_config.min_stake_amount {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::BelowMinimumStake.into());
     }
 
     if staking_pool.maximum_stake_limit > 0 && 
        staking_pool.total_staked.saturating_add(stake_amount) > staking_pool.maximum_stake_limit {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::StakeLimitExceeded.into());
     }
 
-    let user_token_info = TokenAccount::from_account_info(user_token_account)?;
-    let stake_vault_info = TokenAccount::from_account_info(stake_token_vault)?;
-    let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+    {
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+        let stake_vault_info = TokenAccount::from_account_info(stake_token_vault)?;
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
 
-    if *user_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_token_info.mint() != *stake_token_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_token_info.mint() != *stake_token_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    if user_token_info.amount() < stake_amount {
-        return Err(ProgramError::InsufficientFunds);
-    }
+        if user_token_info.amount() < stake_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
 
-    if *stake_vault_info.owner() != *global_config_account.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *stake_vault_info.owner() != *global_config_account.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *stake_vault_info.mint() != staking_pool.stake_token_mint {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *stake_vault_info.mint() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
 
-    if *user_lst_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
-        return Err(ProgramError::InvalidAccountData);
+        if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+            return Err(StakingError::InvalidMint.into());
+        }
     }
 
     if user_stake.owner != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if !position.is_active {
-            position_index = Some(i);
-            break;
-        }
+    let position_index = user_stake.next_position_index;
+    let position_index_ref = position_index.to_le_bytes();
+
+    let (stake_position_pda, stake_position_bump) = pubkey::find_program_address(
+        &[
+            b"stake_position",
+            user.key().as_ref(),
+            staking_pool_account.key().as_ref(),
+            position_index_ref.as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *stake_position_account.key() != stake_position_pda {
+        return Err(StakingError::InvalidPda.into());
     }
 
-    let position_idx = position_index.ok_or(ProgramError::AccountDataTooSmall)?;
+    if !stake_position_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
     update_pool_rewards(&mut staking_pool, current_timestamp)?;
 
-    let lst_tokens = stake_amount;
+    let lst_tokens = staking_pool.underlying_to_lst(stake_amount)?;
 
-    let stake_mint_info = Mint::from_account_info(stake_token_mint)?;
+    if lst_tokens == 0 {
+        return Err(StakingError::AmountTooSmall.into());
+    }
+
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
 
     TransferChecked {
         from: user_token_account,
@@ -192,28 +224,52 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
         mint: stake_token_mint,
         authority: user,
         amount: stake_amount,
-        decimals: stake_mint_info.decimals(),
+        decimals: stake_mint_decimals,
     }.invoke()?;
 
     let global_config_bump = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let mint_account_info = Mint::from_account_info(liquid_stake_mint)?;
+    let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
 
     MintToChecked {
        mint: liquid_stake_mint,
        account: user_lst_token_account,
        mint_authority: global_config_account,
        amount: lst_tokens,
-       decimals: mint_account_info.decimals()
+       decimals: lst_mint_decimals
     }.invoke_signed(&[signer_seeds])?;
 
-    user_stake.positions[position_idx] = StakePosition {
+    let lamports = Rent::get()?.minimum_balance(StakePosition::SIZE);
+
+    let bump_ref = &[stake_position_bump];
+    let seeds = seeds!(
+        b"stake_position",
+        user.key().as_ref(),
+        staking_pool_account.key().as_ref(),
+        position_index_ref.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: user,
+        to: stake_position_account,
+        lamports,
+        space: StakePosition::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut position = StakePosition::initialize(stake_position_account)?;
+
+    *position = StakePosition {
+        owner: *user.key(),
+        position_index,
         pool_id,
         staking_pool: *staking_pool_account.key(),
         lst_token_account: *user_lst_token_account.key(),
@@ -221,10 +277,12 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
         lst_tokens,
         last_reward_update: current_timestamp,
         pending_rewards: 0,
+        reward_debt: 0,
+        penalty_debt: 0,
         stake_timestamp: current_timestamp,
-        lock_exipry_enable: staking_pool.lock_period_enabled,
-        lock_expiry: if staking_pool.lock_period_enabled {
-            current_timestamp.saturating_add(staking_pool.lock_period_duration)
+        lock_exipry_enable: lock_enabled,
+        lock_expiry: if lock_enabled {
+            current_timestamp.saturating_add(lock_duration)
         } else {
             0
         },
@@ -234,8 +292,13 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
         compound_frequency_hours: 0,
         last_compound_timestamp: 0,
         compound_count: 0,
-        bump: 0,
+        bump: stake_position_bump,
+        lock_multiplier,
+        ..*position
     };
+    reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+
+    user_stake.next_position_index = position_index.saturating_add(1);
 
     user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_add(stake_amount);
     user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_add(lst_tokens);
@@ -244,30 +307,186 @@ pub fn process_stake_tokens(accounts: &[AccountInfo], instruction_data: &[u8]) -
 
     staking_pool.total_staked = staking_pool.total_staked.saturating_add(stake_amount);
     staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_add(stake_amount);
+
+    StakeEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        pool_id,
+        amount: stake_amount,
+        lst_minted: lst_tokens,
+        timestamp: current_timestamp,
+    }.emit();
 
     Ok(())
 }
 
-fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
-    if pool.total_staked == 0 {
-        return Ok(());
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+
+    use crate::error::StakingError;
+    use crate::states::{user_stake_account::{StakePosition, UserStakeAccount}, withdrawal_ticket::WithdrawalTicket};
+    use crate::test_utils::*;
+
+    const LOCK_ENABLED_UPDATE: u8 = 9;
+    const LOCK_DURATION_UPDATE: u8 = 1;
+    const EARLY_PENALTY_UPDATE: u8 = 3;
+    const PENALTY_DESTINATION_UPDATE: u8 = 15;
+    const MAXIMUM_STAKE_UPDATE: u8 = 7;
+    const MINIMUM_STAKE_UPDATE: u8 = 8;
+    const LOCK_DURATION: i64 = 30 * 86_400;
+
+    // Config and pool open for staking, with the stake mint's treasury in place
+    fn setup_pool() -> (Context, TestPool) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.stake_mint);
+
+        (context, pool)
     }
 
-    let time_elapsed = current_timestamp.saturating_sub(pool.creation_timestamp).max(0) as u64;
-    if time_elapsed == 0 {
-        return Ok(());
+    #[test]
+    fn test_first_stake_mints_lst_one_to_one() {
+        let (context, pool) = setup_pool();
+        let staker = create_staker(&context, &pool, 1000);
+
+        let position = stake(&context, &pool, &staker, 1000, 0);
+
+        assert_eq!(token_amount(&context, &staker.lst_account), 1000);
+        assert_eq!(token_amount(&context, &staker.token_account), 0);
+        assert_eq!(token_amount(&context, &pool.stake_vault), 1000);
+
+        let lst_tokens: u64 = read_at(&context, &position, core::mem::offset_of!(StakePosition, lst_tokens));
+        assert_eq!(lst_tokens, 1000);
     }
 
-    let rewards_earned = (pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128);
-    
-    let scaling_factor = 1_000_000_000_000u128; 
-    let reward_per_share = rewards_earned
-        .saturating_mul(scaling_factor)
-        .saturating_div(pool.total_staked as u128);
+    #[test]
+    fn test_penalty_left_with_stakers_raises_the_exchange_rate() {
+        let (mut context, pool) = setup_pool();
+        apply_config_change(&mut context, &pool, LOCK_ENABLED_UPDATE, &[1]);
+        apply_config_change(&mut context, &pool, LOCK_DURATION_UPDATE, &LOCK_DURATION.to_le_bytes());
+        apply_config_change(&mut context, &pool, EARLY_PENALTY_UPDATE, &1000u64.to_le_bytes());
+        apply_config_change(&mut context, &pool, PENALTY_DESTINATION_UPDATE, &[1]);
+
+        let leaver = create_staker(&context, &pool, 1000);
+        let holder = create_staker(&context, &pool, 1000);
+        let late_staker = create_staker(&context, &pool, 1000);
+        let leaver_position = stake(&context, &pool, &leaver, 1000, 0);
+        let holder_position = stake(&context, &pool, &holder, 1000, 0);
+
+        // 10% of the leaver's 1000 stays in the vault, 1100 now backs the holder's 1000 LST
+        let ticket = request_unstake(&context, &pool, &leaver, &leaver_position, 1000, 0, Check::success());
+        let ticket: WithdrawalTicket = read(&context, &ticket);
+        assert_eq!(ticket.underlying_amount, 900);
+
+        // 1000 * 1000 / 1100, rounded down
+        stake(&context, &pool, &late_staker, 1000, 0);
+        assert_eq!(token_amount(&context, &late_staker.lst_account), 909);
+
+        // 1000 * 2100 / 1909, rounded down
+        advance_time(&mut context, LOCK_DURATION);
+        let ticket = request_unstake(&context, &pool, &holder, &holder_position, 1000, 0, Check::success());
+        let ticket: WithdrawalTicket = read(&context, &ticket);
+        assert_eq!(ticket.underlying_amount, 1100);
+        assert_eq!(token_amount(&context, &holder.lst_account), 0);
+    }
 
-    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
-        .saturating_add(reward_per_share);
+    #[test]
+    fn test_each_stake_opens_the_next_position_pda() {
+        let (context, pool) = setup_pool();
+        let staker = create_staker(&context, &pool, 3000);
 
-    Ok(())
-}
\ No newline at end of file
+        let first = stake(&context, &pool, &staker, 1000, 0);
+        let second = stake(&context, &pool, &staker, 2000, 1);
+
+        for (position, index, amount) in [(first, 0u64, 1000u64), (second, 1, 2000)] {
+            let owner: [u8; 32] = read_at(&context, &position, core::mem::offset_of!(StakePosition, owner));
+            let position_index: u64 = read_at(&context, &position, core::mem::offset_of!(StakePosition, position_index));
+            let staked_amount: u64 = read_at(&context, &position, core::mem::offset_of!(StakePosition, staked_amount));
+
+            assert_eq!(owner, staker.user.to_bytes());
+            assert_eq!(position_index, index);
+            assert_eq!(staked_amount, amount);
+        }
+
+        let user_stake: UserStakeAccount = read(&context, &staker.user_stake);
+        assert_eq!(user_stake.next_position_index, 2);
+        assert_eq!(user_stake.active_positions, 2);
+        assert_eq!(user_stake.total_staked_amount, 3000);
+    }
+
+    #[test]
+    fn test_stake_only_opens_the_next_index() {
+        let (context, pool) = setup_pool();
+        let staker = create_staker(&context, &pool, 3000);
+        stake(&context, &pool, &staker, 1000, 0);
+
+        // Index 0 is taken and 2 skips ahead, only 1 is open
+        for position_index in [0, 2] {
+            context.process_and_validate_instruction(
+                &stake_instruction(&pool, &staker, 1000, position_index, &[]),
+                &[custom_error(StakingError::InvalidPda)],
+            );
+        }
+
+        stake(&context, &pool, &staker, 1000, 1);
+    }
+
+    #[test]
+    fn test_positions_belong_to_their_staker() {
+        let (context, pool) = setup_pool();
+        let owner = create_staker(&context, &pool, 1000);
+        let other = create_staker(&context, &pool, 1000);
+        let position = stake(&context, &pool, &owner, 1000, 0);
+        stake(&context, &pool, &other, 1000, 0);
+
+        request_unstake(&context, &pool, &other, &position, 1000, 0, custom_error(StakingError::OwnerMismatch));
+    }
+
+    #[test]
+    fn test_stake_rejections_report_their_cause() {
+        let (mut context, pool) = setup_pool();
+        apply_config_change(&mut context, &pool, MINIMUM_STAKE_UPDATE, &500u64.to_le_bytes());
+        apply_config_change(&mut context, &pool, MAXIMUM_STAKE_UPDATE, &1500u64.to_le_bytes());
+        let staker = create_staker(&context, &pool, 1000);
+
+        let rejections = [
+            (0, StakingError::ZeroAmount),
+            (100, StakingError::BelowMinimumStake),
+            (2000, StakingError::StakeLimitExceeded),
+            (1200, StakingError::InsufficientTokenBalance),
+        ];
+
+        for (amount, error) in rejections {
+            context.process_and_validate_instruction(&stake_instruction(&pool, &staker, amount, 0, &[]), &[custom_error(error)]);
+        }
+
+        set_config_pause(&context, &pool.global_config, true);
+        context.process_and_validate_instruction(
+            &stake_instruction(&pool, &staker, 1000, 0, &[]),
+            &[custom_error(StakingError::GlobalEmergencyPause)],
+        );
+        set_config_pause(&context, &pool.global_config, false);
+
+        pause_pool(&context, &pool);
+        context.process_and_validate_instruction(
+            &stake_instruction(&pool, &staker, 1000, 0, &[]),
+            &[custom_error(StakingError::PoolEmergencyPause)],
+        );
+    }
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        use pinocchio::program_error::ProgramError;
+
+        // Clients match on these numbers
+        assert_eq!(ProgramError::from(StakingError::PositionInactive), ProgramError::Custom(1002));
+        assert_eq!(ProgramError::from(StakingError::NoRewardsToClaim), ProgramError::Custom(3001));
+    }
+}
@@ -0,0 +1,399 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, sysvars::{rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{instructions::{InitializeAccount3, TransferChecked}, state::{Mint, TokenAccount}};
+
+use crate::error::StakingError;
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    staking_pool_account::{PoolTypeEnum, StakingPool, SwapAdapterEnum, MAX_SWAP_SLIPPAGE_BPS},
+    swap_pool::SwapPool
+};
+
+pub fn process_set_swap_adapter(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,            // Pool authority, pays for the swap input account
+        staking_pool_account, // Pool account
+        adapter_account,      // Adapter program or SwapPool, ignored when clearing the adapter
+        reward_token_mint,    // Pool's reward token mint
+        swap_authority,       // Swap authority PDA, owns the swap input account
+        swap_input,           // Swap input PDA, created here for a Program adapter
+        _system_program,      // System program
+        _token_program,       // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 11 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let adapter_type = instruction_data[8];
+
+    let max_slippage_bps = u16::from_le_bytes(
+        instruction_data[9..11].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if max_slippage_bps > MAX_SWAP_SLIPPAGE_BPS {
+        return Err(StakingError::InvalidSlippage.into());
+    }
+
+    // Adapters and swap pool reserves can be moved by anyone, so compounds are floored against the pool's own rate
+    let swap_reference_rate = match instruction_data.get(11..19) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?),
+        None => 0,
+    };
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if staking_pool.authority != *authority.key() {
+            return Err(StakingError::Unauthorized.into());
+        }
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+        if *reward_token_mint.key() != staking_pool.reward_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let adapter = SwapAdapterEnum::try_from(&adapter_type).map_err(|_| StakingError::InvalidSwapAdapter)?;
+
+    if !matches!(adapter, SwapAdapterEnum::None) && swap_reference_rate == 0 {
+        return Err(StakingError::MissingSwapReferenceRate.into());
+    }
+
+    let swap_adapter = match adapter {
+        SwapAdapterEnum::None => pubkey::Pubkey::default(),
+        SwapAdapterEnum::Program => {
+            if !adapter_account.executable() || *adapter_account.key() == crate::ID {
+                return Err(StakingError::InvalidSwapAdapter.into());
+            }
+
+            create_swap_input(authority, staking_pool_account, reward_token_mint, swap_authority, swap_input)?;
+
+            *adapter_account.key()
+        },
+        SwapAdapterEnum::ConstantProduct => {
+            let swap_pool = SwapPool::from_account_info(adapter_account)?;
+
+            if swap_pool.staking_pool != *staking_pool_account.key() {
+                return Err(StakingError::InvalidSwapAdapter.into());
+            }
+
+            *adapter_account.key()
+        },
+    };
+
+    let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+    staking_pool.swap_adapter_type = adapter_type;
+    staking_pool.swap_adapter = swap_adapter;
+    staking_pool.swap_max_slippage_bps = max_slippage_bps;
+    staking_pool.swap_reference_rate = swap_reference_rate;
+
+    Ok(())
+}
+
+// Token account the reward vault pays into before a Program adapter swaps it, reused across adapters
+fn create_swap_input(
+    authority: &AccountInfo,
+    staking_pool_account: &AccountInfo,
+    reward_token_mint: &AccountInfo,
+    swap_authority: &AccountInfo,
+    swap_input: &AccountInfo
+) -> ProgramResult {
+    let (swap_authority_pda, _swap_authority_bump) = pubkey::find_program_address(
+        &[b"swap_authority", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *swap_authority.key() != swap_authority_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let (swap_input_pda, swap_input_bump) = pubkey::find_program_address(
+        &[
+            b"swap_input",
+            staking_pool_account.key().as_ref(),
+            reward_token_mint.key().as_ref()
+        ],
+        &crate::ID
+    );
+
+    if *swap_input.key() != swap_input_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !swap_input.data_is_empty() {
+        return Ok(());
+    }
+
+    let bump_ref = &[swap_input_bump];
+    let seeds = seeds!(
+        b"swap_input",
+        staking_pool_account.key().as_ref(),
+        reward_token_mint.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: swap_input,
+        lamports: Rent::get()?.minimum_balance(TokenAccount::LEN),
+        space: TokenAccount::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeAccount3 {
+        account: swap_input,
+        mint: reward_token_mint,
+        owner: swap_authority.key()
+    }.invoke()
+}
+
+pub fn process_initialize_swap_pool(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,             // Pool authority, pays for the new accounts
+        global_config_account, // Global config PDA, owns the reserves
+        staking_pool_account,  // Pool account
+        reward_token_mint,     // Pool's reward token mint
+        stake_token_mint,      // Pool's stake token mint
+        swap_pool_account,     // Swap pool PDA, created here
+        reward_reserve,        // Reward reserve PDA, created here
+        stake_reserve,         // Stake reserve PDA, created here
+        _system_program,       // System program
+        _token_program,        // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let fee_bps = u16::from_le_bytes(
+        instruction_data[8..10].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if fee_bps > MAX_SWAP_SLIPPAGE_BPS {
+        return Err(StakingError::InvalidSlippage.into());
+    }
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if staking_pool.authority != *authority.key() {
+            return Err(StakingError::Unauthorized.into());
+        }
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+        if *reward_token_mint.key() != staking_pool.reward_token_mint
+            || *stake_token_mint.key() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        // Same-mint pools compound without a swap
+        if staking_pool.reward_token_mint == staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+    }
+
+    let (swap_pool_pda, swap_pool_bump) = pubkey::find_program_address(
+        &[b"swap_pool", staking_pool_account.key().as_ref()],
+        &crate::ID
+    );
+
+    if *swap_pool_account.key() != swap_pool_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !swap_pool_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = Rent::get()?;
+
+    let reward_reserve_pda = create_swap_reserve(authority, global_config_account, swap_pool_account, reward_token_mint, reward_reserve, &rent)?;
+    let stake_reserve_pda = create_swap_reserve(authority, global_config_account, swap_pool_account, stake_token_mint, stake_reserve, &rent)?;
+
+    let bump_ref = &[swap_pool_bump];
+    let seeds = seeds!(
+        b"swap_pool",
+        staking_pool_account.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: swap_pool_account,
+        lamports: rent.minimum_balance(SwapPool::SIZE),
+        space: SwapPool::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut swap_pool = SwapPool::initialize(swap_pool_account)?;
+    swap_pool.staking_pool = *staking_pool_account.key();
+    swap_pool.reward_reserve = reward_reserve_pda;
+    swap_pool.stake_reserve = stake_reserve_pda;
+    swap_pool.fee_bps = fee_bps;
+    swap_pool.bump = swap_pool_bump;
+
+    Ok(())
+}
+
+fn create_swap_reserve(
+    authority: &AccountInfo,
+    global_config_account: &AccountInfo,
+    swap_pool_account: &AccountInfo,
+    mint: &AccountInfo,
+    reserve: &AccountInfo,
+    rent: &Rent
+) -> Result<pubkey::Pubkey, ProgramError> {
+    let (reserve_pda, reserve_bump) = pubkey::find_program_address(
+        &[b"swap_reserve", swap_pool_account.key().as_ref(), mint.key().as_ref()],
+        &crate::ID
+    );
+
+    if *reserve.key() != reserve_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let bump_ref = &[reserve_bump];
+    let seeds = seeds!(
+        b"swap_reserve",
+        swap_pool_account.key().as_ref(),
+        mint.key().as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: reserve,
+        lamports: rent.minimum_balance(TokenAccount::LEN),
+        space: TokenAccount::LEN as u64,
+        owner: &pinocchio_token::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    InitializeAccount3 {
+        account: reserve,
+        mint,
+        owner: global_config_account.key()
+    }.invoke()?;
+
+    Ok(reserve_pda)
+}
+
+pub fn process_withdraw_swap_reserve(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,                 // Pool authority
+        global_config_account,     // Global config PDA, owns the reserves
+        staking_pool_account,      // Pool account
+        swap_pool_account,         // Pool's swap pool
+        mint,                      // Mint of the reserve being withdrawn
+        reserve,                   // Reward or stake reserve
+        destination_token_account, // Receives the withdrawn tokens
+        _token_program,            // Token program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let amount = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if staking_pool.authority != *authority.key() {
+            return Err(StakingError::Unauthorized.into());
+        }
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        let swap_pool = SwapPool::from_account_info(swap_pool_account)?;
+
+        if swap_pool.staking_pool != *staking_pool_account.key() {
+            return Err(StakingError::InvalidSwapAdapter.into());
+        }
+
+        if *reserve.key() != swap_pool.reward_reserve && *reserve.key() != swap_pool.stake_reserve {
+            return Err(StakingError::InvalidVault.into());
+        }
+
+        let reserve_info = TokenAccount::from_account_info(reserve)?;
+
+        if reserve_info.amount() < amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
+    }
+
+    let global_config_bump_arr = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump_arr
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    TransferChecked {
+        from: reserve,
+        to: destination_token_account,
+        mint,
+        authority: global_config_account,
+        amount,
+        decimals: Mint::from_account_info(mint)?.decimals(),
+    }.invoke_signed(&[signer_seeds])?;
+
+    Ok(())
+}
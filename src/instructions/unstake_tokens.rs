@@ -1,30 +1,45 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, sysvars::{clock::Clock, Sysvar}, *};
-use pinocchio_token::{state::{TokenAccount, Mint}, instructions::{TransferChecked, BurnChecked}};
-
-use crate::states::{helper::AccountData, staking_pool_account::StakingPool, user_stake_account::UserStakeAccount, global_config::GlobalConfig};
-
-pub fn process_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{state::{TokenAccount, Mint}, instructions::{TransferChecked, BurnChecked, MintToChecked}};
+
+use crate::error::StakingError;
+use crate::events::{Event, UnstakeCancelledEvent, UnstakeCompletedEvent, UnstakeRequestedEvent};
+use crate::penalty_payout::PenaltyPayout;
+use crate::rewards::{reset_reward_debt, settle_position_rewards, update_pool_rewards};
+use crate::states::{
+    global_config::GlobalConfig,
+    helper::AccountData,
+    staking_pool_account::{PoolTypeEnum, StakingPool},
+    user_stake_account::{PenaltyEvent, PenaltyType, StakePosition, UserStakeAccount},
+    withdrawal_ticket::WithdrawalTicket
+};
+
+pub fn process_request_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
-        user,                    
-        authority,              
-        stake_token_mint,       
-        stake_token_vault,      
-        liquid_stake_mint,      
-        global_config_account,  
-        staking_pool_account,   
-        user_token_account,     
-        user_stake_account,     
-        user_lst_token_account, 
-        token_program,          
+        user,
+        _authority,
+        stake_token_mint,
+        stake_token_vault,
+        liquid_stake_mint,
+        global_config_account,
+        staking_pool_account,
+        user_stake_account,
+        stake_position_account,
+        user_lst_token_account,
+        withdrawal_ticket,      // Ticket PDA created for this request
+        treasury_account,       // Receives early-unstake penalties routed to the treasury
+        reward_token_vault,     // Receives early-unstake penalties routed to the reward vault
+        _system_program,
+        _token_program,
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if instruction_data.len() < 16 {
+    if instruction_data.len() < 24 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
@@ -36,98 +51,328 @@ pub fn process_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> Pro
         instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
     );
 
+    let ticket_id = u64::from_le_bytes(
+        instruction_data[16..24].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
     if lst_amount == 0 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::ZeroAmount.into());
     }
 
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let (withdrawal_ticket_pda, withdrawal_ticket_bump) = pubkey::find_program_address(
+        &[
+            b"withdrawal_ticket",
+            user.key().as_ref(),
+            staking_pool_account.key().as_ref(),
+            ticket_id.to_le_bytes().as_ref(),
+        ],
         &crate::ID
     );
 
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
+    if *withdrawal_ticket.key() != withdrawal_ticket_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    if !withdrawal_ticket.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
     }
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
 
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if position.is_active && position.pool_id == pool_id {
-            position_index = Some(i);
-            break;
-        }
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
+
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    let position_idx = position_index.ok_or(ProgramError::Custom(1002))?; 
-    let position = &user_stake.positions[position_idx];
+    position.check_owner(user.key(), staking_pool_account.key())?;
 
-    if position.lst_tokens < lst_amount {
-        return Err(ProgramError::InsufficientFunds);
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
     }
 
-    let current_timestamp = Clock::get()?.unix_timestamp;
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
 
-    if position.lock_exipry_enable && current_timestamp < position.lock_expiry {
-        let penalty_amount = calculate_early_withdrawal_penalty(
-            lst_amount, 
-            staking_pool.early_withdraw_penalty
-        )?;
+    if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+        return Err(StakingError::InvalidMint.into());
     }
 
-    // Calculate how many underlying tokens to return (1:1 ratio in basic case)
-    let underlying_tokens = lst_amount;
+    if *stake_token_vault.key() != staking_pool.stake_token_vault {
+        return Err(StakingError::InvalidVault.into());
+    }
 
-    // Validate token accounts
-    let user_token_info = TokenAccount::from_account_info(user_token_account)?;
-    let stake_vault_info = TokenAccount::from_account_info(stake_token_vault)?;
-    let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+    // Exiting while a penalty is open would leave nothing to charge if it is upheld
+    if user_stake.has_open_penalty(stake_position_account.key()) {
+        return Err(StakingError::PenaltyPending.into());
+    }
 
-    if *user_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+    if position.lst_tokens < lst_amount {
+        return Err(StakingError::InsufficientPositionBalance.into());
     }
 
-    if *user_lst_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    // Underlying redeemed at the pool's current exchange rate
+    let underlying_tokens = staking_pool.lst_to_underlying(lst_amount)?;
+
+    if underlying_tokens == 0 {
+        return Err(StakingError::AmountTooSmall.into());
     }
 
-    if user_lst_token_info.amount() < lst_amount {
-        return Err(ProgramError::InsufficientFunds);
+    let penalty_amount = if position.lock_exipry_enable && current_timestamp < position.lock_expiry {
+        calculate_early_withdrawal_penalty(
+            underlying_tokens,
+            staking_pool.early_withdraw_penalty
+        )?
+    } else {
+        0
+    };
+
+    // Upheld penalties still owed on the position come out of the payout first
+    let penalty_debt_collected = position.penalty_debt.min(underlying_tokens.saturating_sub(penalty_amount));
+
+    // What the ticket pays out once the cooldown has passed
+    let payout_amount = underlying_tokens
+        .saturating_sub(penalty_amount)
+        .saturating_sub(penalty_debt_collected);
+
+    // Principal leaves the position in proportion to the LST burned
+    let principal_amount = ((position.staked_amount as u128)
+        .saturating_mul(lst_amount as u128)
+        / position.lst_tokens as u128) as u64;
+
+    {
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if user_lst_token_info.amount() < lst_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
     }
 
     // Update pool rewards before processing unstake
     update_pool_rewards(&mut staking_pool, current_timestamp)?;
 
-    // Calculate and distribute any pending rewards
-    calculate_and_distribute_rewards(
-        &mut user_stake.positions[position_idx],
-        &staking_pool,
-        current_timestamp
-    )?;
+    // Settle any pending rewards before the stake shrinks
+    settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
 
     // Burn LST tokens from user
-    let lst_mint_info = Mint::from_account_info(liquid_stake_mint)?;
-    
+    let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
+
     BurnChecked {
         mint: liquid_stake_mint,
         account: user_lst_token_account,
         authority: user,
         amount: lst_amount,
-        decimals: lst_mint_info.decimals(),
+        decimals: lst_mint_decimals,
     }.invoke()?;
 
-    // Transfer underlying tokens back to user from vault
+    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_sub(lst_amount);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_sub(underlying_tokens);
+
+    // Debt held for a later recovery stays in the vault, the rest is final and leaves with the penalty
+    let reserved_debt = user_stake.collect_penalty_debt(stake_position_account.key(), penalty_debt_collected);
+    staking_pool.penalty_reserve = staking_pool.penalty_reserve.saturating_add(reserved_debt);
+    position.penalty_debt = position.penalty_debt.saturating_sub(penalty_debt_collected);
+
     let global_config_bump_arr = &[global_config_bump];
+    let config_seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
+        global_config_bump_arr
+    );
+
+    PenaltyPayout {
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        treasury_account,
+        reward_token_vault,
+        amount: penalty_amount.saturating_add(penalty_debt_collected.saturating_sub(reserved_debt)),
+    }.invoke_signed(&mut staking_pool, &global_config, &[Signer::from(&config_seeds)])?;
+
+    if penalty_amount > 0 {
+        user_stake.record_penalty(PenaltyEvent {
+            penalty_type: PenaltyType::EarlyUnstake as u8,
+            amount: penalty_amount,
+            timestamp: current_timestamp,
+            grace_period_end: current_timestamp,
+            is_resolved: true,
+            resolution_timestamp: current_timestamp,
+            pool_id,
+            user: *user.key(),
+            stake_position: *stake_position_account.key(),
+            original_stake_amount: underlying_tokens,
+            ..PenaltyEvent::default()
+        })?;
+        user_stake.total_penalties = user_stake.total_penalties.saturating_add(penalty_amount);
+    }
+
+    // Underlying stays in the vault until the ticket is claimed after the cooldown
+    let lamports = Rent::get()?.minimum_balance(WithdrawalTicket::SIZE);
+
+    let bump_ref = &[withdrawal_ticket_bump];
+    let ticket_id_ref = ticket_id.to_le_bytes();
     let seeds = seeds!(
-        b"global_config_account", 
-        authority.key().as_ref(),
+        b"withdrawal_ticket",
+        user.key().as_ref(),
+        staking_pool_account.key().as_ref(),
+        ticket_id_ref.as_ref(),
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: user,
+        to: withdrawal_ticket,
+        lamports,
+        space: WithdrawalTicket::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let mut withdrawal_ticket_info = WithdrawalTicket::initialize(withdrawal_ticket)?;
+    withdrawal_ticket_info.owner = *user.key();
+    withdrawal_ticket_info.staking_pool = *staking_pool_account.key();
+    withdrawal_ticket_info.stake_position = *stake_position_account.key();
+    withdrawal_ticket_info.pool_id = pool_id;
+    withdrawal_ticket_info.ticket_id = ticket_id;
+    withdrawal_ticket_info.lst_amount = lst_amount;
+    withdrawal_ticket_info.underlying_amount = payout_amount;
+    withdrawal_ticket_info.principal_amount = principal_amount;
+    withdrawal_ticket_info.request_timestamp = current_timestamp;
+    withdrawal_ticket_info.unlock_timestamp = current_timestamp.saturating_add(staking_pool.cooldown_period);
+    withdrawal_ticket_info.bump = withdrawal_ticket_bump;
+
+    // Update user's position
+    position.staked_amount = position.staked_amount.saturating_sub(principal_amount);
+    position.lst_tokens = position.lst_tokens.saturating_sub(lst_amount);
+    reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+
+    if position.lst_tokens == 0 {
+        position.is_active = false;
+        user_stake.active_positions = user_stake.active_positions.saturating_sub(1);
+    }
+
+    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_sub(principal_amount);
+    user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_sub(lst_amount);
+    user_stake.last_update_timestamp = current_timestamp;
+
+    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(principal_amount);
+    staking_pool.total_pending_unstake = staking_pool.total_pending_unstake.saturating_add(payout_amount);
+
+    UnstakeRequestedEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        withdrawal_ticket: *withdrawal_ticket.key(),
+        lst_burned: lst_amount,
+        underlying_amount: payout_amount,
+        penalty_amount: penalty_amount.saturating_add(penalty_debt_collected),
+        unlock_timestamp: withdrawal_ticket_info.unlock_timestamp,
+        timestamp: current_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_claim_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,
+        _authority,
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        staking_pool_account,
+        user_token_account,
+        withdrawal_ticket,
+        _token_program,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let ticket_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let (withdrawal_ticket_pda, _withdrawal_ticket_bump) = pubkey::find_program_address(
+        &[
+            b"withdrawal_ticket",
+            user.key().as_ref(),
+            staking_pool_account.key().as_ref(),
+            ticket_id.to_le_bytes().as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *withdrawal_ticket.key() != withdrawal_ticket_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let underlying_tokens = {
+        let withdrawal_ticket_info = WithdrawalTicket::from_account_info(withdrawal_ticket)?;
+        let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+        if withdrawal_ticket_info.owner != *user.key() {
+            return Err(StakingError::OwnerMismatch.into());
+        }
+
+        if withdrawal_ticket_info.staking_pool != *staking_pool_account.key() {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        if Clock::get()?.unix_timestamp < withdrawal_ticket_info.unlock_timestamp {
+            return Err(StakingError::CooldownActive.into());
+        }
+
+        if *stake_token_vault.key() != staking_pool.stake_token_vault {
+            return Err(StakingError::InvalidVault.into());
+        }
+
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
+
+        if *user_token_info.mint() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        staking_pool.total_pending_unstake = staking_pool.total_pending_unstake
+            .saturating_sub(withdrawal_ticket_info.underlying_amount);
+
+        withdrawal_ticket_info.underlying_amount
+    };
+
+    let global_config_bump_arr = &[global_config_bump];
+    let seeds = seeds!(
+        b"global_config_account",
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let stake_mint_info = Mint::from_account_info(stake_token_mint)?;
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
 
     TransferChecked {
         from: stake_token_vault,
@@ -135,90 +380,399 @@ pub fn process_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> Pro
         mint: stake_token_mint,
         authority: global_config_account, // Vault is owned by global config
         amount: underlying_tokens,
-        decimals: stake_mint_info.decimals(),
+        decimals: stake_mint_decimals,
     }.invoke_signed(&[signer_seeds])?;
 
-    // Update user's position
-    user_stake.positions[position_idx].staked_amount = 
-        user_stake.positions[position_idx].staked_amount.saturating_sub(underlying_tokens);
-    user_stake.positions[position_idx].lst_tokens = 
-        user_stake.positions[position_idx].lst_tokens.saturating_sub(lst_amount);
-    user_stake.positions[position_idx].last_reward_update = current_timestamp;
-
-    if user_stake.positions[position_idx].lst_tokens == 0 {
-        user_stake.positions[position_idx].is_active = false;
-        user_stake.active_positions = user_stake.active_positions.saturating_sub(1);
+    UnstakeCompletedEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        withdrawal_ticket: *withdrawal_ticket.key(),
+        amount: underlying_tokens,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    close_withdrawal_ticket(withdrawal_ticket, user)
+}
+
+pub fn process_cancel_unstake(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        user,
+        _authority,
+        liquid_stake_mint,
+        global_config_account,
+        staking_pool_account,
+        user_stake_account,
+        stake_position_account, // Position the ticket was opened from
+        user_lst_token_account,
+        withdrawal_ticket,
+        _token_program,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !user.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_sub(underlying_tokens);
-    user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_sub(lst_amount);
-    user_stake.last_update_timestamp = current_timestamp;
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
-    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(underlying_tokens);
-    staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_sub(lst_amount);
+    let ticket_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
 
-    Ok(())
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
+
+    let (withdrawal_ticket_pda, _withdrawal_ticket_bump) = pubkey::find_program_address(
+        &[
+            b"withdrawal_ticket",
+            user.key().as_ref(),
+            staking_pool_account.key().as_ref(),
+            ticket_id.to_le_bytes().as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *withdrawal_ticket.key() != withdrawal_ticket_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    {
+        let withdrawal_ticket_info = WithdrawalTicket::from_account_info(withdrawal_ticket)?;
+        let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
+        let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+        let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+        if withdrawal_ticket_info.owner != *user.key() {
+            return Err(StakingError::OwnerMismatch.into());
+        }
+
+        if withdrawal_ticket_info.staking_pool != *staking_pool_account.key() {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        if user_stake.owner != *user.key() {
+            return Err(StakingError::OwnerMismatch.into());
+        }
+
+        if withdrawal_ticket_info.stake_position != *stake_position_account.key() {
+            return Err(StakingError::TicketPositionMismatch.into());
+        }
+
+        if *liquid_stake_mint.key() != staking_pool.liquid_stake_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        {
+            let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
+
+            if *user_lst_token_info.owner() != *user.key() {
+                return Err(StakingError::InvalidTokenAccountOwner.into());
+            }
+
+            if *user_lst_token_info.mint() != *liquid_stake_mint.key() {
+                return Err(StakingError::InvalidMint.into());
+            }
+        }
+
+        let current_timestamp = Clock::get()?.unix_timestamp;
+
+        // A position emptied by the request is reopened, anything earned before stays pending
+        if !position.is_active {
+            position.is_active = true;
+            position.last_reward_update = current_timestamp;
+            user_stake.active_positions = user_stake.active_positions.saturating_add(1);
+        }
+
+        update_pool_rewards(&mut staking_pool, current_timestamp)?;
+        settle_position_rewards(&mut position, &mut staking_pool, current_timestamp)?;
+
+        // Re-mint at the current rate, any appreciation since the request stays with the pool
+        let lst_tokens = staking_pool.underlying_to_lst(withdrawal_ticket_info.underlying_amount)?;
+
+        let global_config_bump_arr = &[global_config_bump];
+        let seeds = seeds!(
+            b"global_config_account",
+            config_id.as_ref(),
+            global_config_bump_arr
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
+
+        MintToChecked {
+            mint: liquid_stake_mint,
+            account: user_lst_token_account,
+            mint_authority: global_config_account,
+            amount: lst_tokens,
+            decimals: lst_mint_decimals
+        }.invoke_signed(&[signer_seeds])?;
+
+        let principal_amount = withdrawal_ticket_info.principal_amount;
+
+        position.staked_amount = position.staked_amount.saturating_add(principal_amount);
+        position.lst_tokens = position.lst_tokens.saturating_add(lst_tokens);
+        reset_reward_debt(&mut position, &mut staking_pool, current_timestamp);
+
+        user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_add(principal_amount);
+        user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_add(lst_tokens);
+        user_stake.last_update_timestamp = current_timestamp;
+
+        staking_pool.total_staked = staking_pool.total_staked.saturating_add(principal_amount);
+        staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_add(lst_tokens);
+        staking_pool.total_underlying = staking_pool.total_underlying
+            .saturating_add(withdrawal_ticket_info.underlying_amount);
+        staking_pool.total_pending_unstake = staking_pool.total_pending_unstake
+            .saturating_sub(withdrawal_ticket_info.underlying_amount);
+
+        UnstakeCancelledEvent {
+            user: *user.key(),
+            staking_pool: *staking_pool_account.key(),
+            stake_position: *stake_position_account.key(),
+            withdrawal_ticket: *withdrawal_ticket.key(),
+            underlying_amount: withdrawal_ticket_info.underlying_amount,
+            lst_minted: lst_tokens,
+            timestamp: current_timestamp,
+        }.emit();
+    }
+
+    close_withdrawal_ticket(withdrawal_ticket, user)
+}
+
+fn close_withdrawal_ticket(withdrawal_ticket: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let ticket_lamports = withdrawal_ticket.lamports();
+
+    *destination.try_borrow_mut_lamports()? = destination.lamports()
+        .checked_add(ticket_lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    *withdrawal_ticket.try_borrow_mut_lamports()? = 0;
+
+    withdrawal_ticket.close()
 }
 
 fn calculate_early_withdrawal_penalty(amount: u64, penalty_rate: u64) -> Result<u64, ProgramError> {
     let penalty = (amount as u128)
-        .saturating_mul(penalty_rate as u128)
-        .saturating_div(10000u128) as u64; 
-    
+        .saturating_mul(penalty_rate.min(10000) as u128)
+        .saturating_div(10000u128) as u64;
+
     Ok(penalty)
 }
 
-fn calculate_and_distribute_rewards(
-    position: &mut crate::states::user_stake_account::StakePosition,
-    pool: &StakingPool,
-    current_timestamp: i64
-) -> ProgramResult {
-    let time_staked = current_timestamp.saturating_sub(position.last_reward_update);
-    if time_staked > 0 {
-        let rewards = calculate_position_rewards(position, pool, time_staked)?;
-        position.pending_rewards = position.pending_rewards.saturating_add(rewards);
-    }
-    
-    Ok(())
-}
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::{
+        global_config::GlobalConfig,
+        staking_pool_account::PenaltyDestinationEnum,
+        user_stake_account::{PenaltyType, UserStakeAccount, MAX_HISTORY},
+        withdrawal_ticket::WithdrawalTicket
+    };
+    use crate::test_utils::*;
+
+    const COOLDOWN_PERIOD: i64 = 86_400;
+    const LOCK_DURATION: i64 = 30 * 86_400;
+    const LOCK_ENABLED_UPDATE: u8 = 9;
+    const LOCK_DURATION_UPDATE: u8 = 1;
+    const EARLY_PENALTY_UPDATE: u8 = 3;
+    const PENALTY_DESTINATION_UPDATE: u8 = 15;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+    }
 
-fn calculate_position_rewards(
-    position: &crate::states::user_stake_account::StakePosition,
-    pool: &StakingPool,
-    time_elapsed: i64
-) -> Result<u64, ProgramError> {
-    if position.staked_amount == 0 || time_elapsed <= 0 {
-        return Ok(0);
+    // Pool with the default one day cooldown and a treasury for the stake mint
+    fn setup_pool() -> (Context, TestPool) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        init_treasury(&context, &authority, &global_config, &pool.stake_mint);
+
+        (context, pool)
     }
 
-    let rewards = (position.staked_amount as u128)
-        .saturating_mul(pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128)
-        .saturating_div(1_000_000_000_000u128) as u64; 
+    fn setup_position(stake_amount: u64) -> Setup {
+        let (context, pool) = setup_pool();
+        let staker = create_staker(&context, &pool, stake_amount);
+        let position = stake(&context, &pool, &staker, stake_amount, 0);
 
-    Ok(rewards)
-}
+        Setup { context, pool, staker, position }
+    }
+
+    // Position locked for LOCK_DURATION with a 10% early-unstake penalty paid to `penalty_destination`
+    fn setup_locked_position(stake_amount: u64, penalty_destination: PenaltyDestinationEnum) -> Setup {
+        let (mut context, pool) = setup_pool();
+        apply_config_change(&mut context, &pool, LOCK_ENABLED_UPDATE, &[1]);
+        apply_config_change(&mut context, &pool, LOCK_DURATION_UPDATE, &LOCK_DURATION.to_le_bytes());
+        apply_config_change(&mut context, &pool, EARLY_PENALTY_UPDATE, &1000u64.to_le_bytes());
+        apply_config_change(&mut context, &pool, PENALTY_DESTINATION_UPDATE, &[penalty_destination as u8]);
+
+        let staker = create_staker(&context, &pool, stake_amount);
+        let position = stake(&context, &pool, &staker, stake_amount, 0);
 
-fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
-    if pool.total_staked == 0 {
-        return Ok(());
+        Setup { context, pool, staker, position }
     }
 
-    let time_elapsed = current_timestamp.saturating_sub(pool.creation_timestamp).max(0) as u64;
-    if time_elapsed == 0 {
-        return Ok(());
+    fn claim(setup: &Setup, ticket_id: u64, check: Check) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ClaimUnstake,
+                vec![
+                    AccountMeta::new(setup.staker.user, true),
+                    AccountMeta::new_readonly(setup.pool.authority, false),
+                    AccountMeta::new_readonly(setup.pool.stake_mint, false),
+                    AccountMeta::new(setup.pool.stake_vault, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.token_account, false),
+                    AccountMeta::new(ticket_pda(&setup.staker.user, &setup.pool.staking_pool, ticket_id), false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &ticket_id.to_le_bytes(),
+            ),
+            &[check],
+        );
     }
 
-    let rewards_earned = (pool.reward_rate_per_second as u128)
-        .saturating_mul(time_elapsed as u128);
-    
-    let scaling_factor = 1_000_000_000_000u128; 
-    let reward_per_share = rewards_earned
-        .saturating_mul(scaling_factor)
-        .saturating_div(pool.total_staked as u128);
+    fn cancel(setup: &Setup, ticket_id: u64, check: Check) {
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::CancelUnstake,
+                vec![
+                    AccountMeta::new(setup.staker.user, true),
+                    AccountMeta::new_readonly(setup.pool.authority, false),
+                    AccountMeta::new(setup.pool.liquid_stake_mint, false),
+                    AccountMeta::new_readonly(setup.pool.global_config, false),
+                    AccountMeta::new(setup.pool.staking_pool, false),
+                    AccountMeta::new(setup.staker.user_stake, false),
+                    AccountMeta::new(setup.position, false),
+                    AccountMeta::new(setup.staker.lst_account, false),
+                    AccountMeta::new(ticket_pda(&setup.staker.user, &setup.pool.staking_pool, ticket_id), false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &ticket_id.to_le_bytes(),
+            ),
+            &[check],
+        );
+    }
 
-    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
-        .saturating_add(reward_per_share);
+    #[test]
+    fn test_claim_unstake_waits_for_the_cooldown() {
+        let mut setup = setup_position(1000);
 
-    Ok(())
-}
\ No newline at end of file
+        let ticket = request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 400, 0, Check::success());
+        assert_eq!(token_amount(&setup.context, &setup.staker.lst_account), 600);
+
+        let ticket_info: WithdrawalTicket = read(&setup.context, &ticket);
+        assert_eq!(ticket_info.lst_amount, 400);
+        assert_eq!(ticket_info.underlying_amount, 400);
+        assert_eq!(ticket_info.unlock_timestamp, START_TIMESTAMP + COOLDOWN_PERIOD);
+
+        claim(&setup, 0, custom_error(StakingError::CooldownActive));
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), 0);
+
+        advance_time(&mut setup.context, COOLDOWN_PERIOD);
+        claim(&setup, 0, Check::success());
+
+        assert_eq!(token_amount(&setup.context, &setup.staker.token_account), 400);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 600);
+        assert_eq!(account(&setup.context, &ticket).lamports, 0);
+    }
+
+    #[test]
+    fn test_request_unstake_is_capped_at_the_position() {
+        let setup = setup_position(1000);
+
+        request_unstake(
+            &setup.context,
+            &setup.pool,
+            &setup.staker,
+            &setup.position,
+            1001,
+            0,
+            custom_error(StakingError::InsufficientPositionBalance),
+        );
+        request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 0, 0, custom_error(StakingError::ZeroAmount));
+    }
+
+    #[test]
+    fn test_cancel_unstake_restores_the_position() {
+        let mut setup = setup_position(1000);
+
+        let ticket = request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 0, Check::success());
+
+        let user_stake: UserStakeAccount = read(&setup.context, &setup.staker.user_stake);
+        assert_eq!(user_stake.active_positions, 0);
+        assert_eq!(user_stake.total_staked_amount, 0);
+
+        advance_time(&mut setup.context, 100);
+        cancel(&setup, 0, Check::success());
+
+        assert_eq!(token_amount(&setup.context, &setup.staker.lst_account), 1000);
+        assert_eq!(account(&setup.context, &ticket).lamports, 0);
+
+        let user_stake: UserStakeAccount = read(&setup.context, &setup.staker.user_stake);
+        assert_eq!(user_stake.active_positions, 1);
+        assert_eq!(user_stake.total_staked_amount, 1000);
+        assert_eq!(user_stake.total_lst_balance, 1000);
+
+        // Back in the pool, so the position can be unstaked again
+        request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 1, Check::success());
+    }
+
+    #[test]
+    fn test_early_unstake_penalty_goes_to_the_treasury() {
+        let setup = setup_locked_position(1000, PenaltyDestinationEnum::Treasury);
+        let config: GlobalConfig = read(&setup.context, &setup.pool.global_config);
+        let treasury = treasury_pda(&setup.pool.stake_mint, &Pubkey::new_from_array(config.config_id));
+
+        let ticket = request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 0, Check::success());
+
+        let ticket: WithdrawalTicket = read(&setup.context, &ticket);
+        assert_eq!(ticket.underlying_amount, 900);
+        assert_eq!(token_amount(&setup.context, &treasury), 100);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), 900);
+
+        let user_stake: UserStakeAccount = read(&setup.context, &setup.staker.user_stake);
+        let penalty = user_stake.penalty_history[MAX_HISTORY - 1];
+        assert_eq!(penalty.penalty_type, PenaltyType::EarlyUnstake as u8);
+        assert_eq!(penalty.amount, 100);
+        assert_eq!(penalty.original_stake_amount, 1000);
+        assert_eq!(penalty.stake_position, setup.position.to_bytes());
+        assert!(penalty.is_resolved);
+        assert_eq!(user_stake.total_penalties, 100);
+    }
+
+    #[test]
+    fn test_no_early_unstake_penalty_once_the_lock_expires() {
+        let mut setup = setup_locked_position(1000, PenaltyDestinationEnum::Treasury);
+
+        advance_time(&mut setup.context, LOCK_DURATION);
+        let ticket = request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 0, Check::success());
+
+        let ticket: WithdrawalTicket = read(&setup.context, &ticket);
+        assert_eq!(ticket.underlying_amount, 1000);
+
+        let user_stake: UserStakeAccount = read(&setup.context, &setup.staker.user_stake);
+        assert_eq!(user_stake.total_penalties, 0);
+        assert_eq!(user_stake.penalty_history[MAX_HISTORY - 1].timestamp, 0);
+    }
+
+    #[test]
+    fn test_reward_vault_destination_needs_a_stake_mint_reward_vault() {
+        let setup = setup_locked_position(1000, PenaltyDestinationEnum::RewardVault);
+
+        // The penalty is paid in the stake mint, this pool's reward vault holds another one
+        request_unstake(&setup.context, &setup.pool, &setup.staker, &setup.position, 1000, 0, custom_error(StakingError::InvalidMint));
+    }
+}
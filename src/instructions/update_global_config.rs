@@ -1,13 +1,17 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, *};
-use crate::states::{global_config::GlobalConfig, helper::AccountData};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, Sysvar}, *};
+use crate::error::StakingError;
+use crate::events::{Event, GlobalConfigUpdatedEvent};
+use crate::states::{global_config::GlobalConfig, helper::AccountData, roles::{RoleEnum, Roles}};
 
-pub fn process_update_authority(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+/// First half of an authority transfer. Nothing moves until the proposed key accepts, so a
+/// mistyped key can be cancelled or proposed over.
+pub fn process_propose_authority(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [authority, global_config_account] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::MissingRequiredSignature);
     };
 
     if instruction_data.len() < 32 {
@@ -17,32 +21,101 @@ pub fn process_update_authority(accounts: &[AccountInfo], instruction_data: &[u8
     let new_authority = Pubkey::try_from(&instruction_data[0..32])
         .map_err(|_| ProgramError::InvalidInstructionData)?;
 
-    let (global_config_pda, _bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    GlobalConfig::check_pda(global_config_account)?;
 
     let mut global_config_info = GlobalConfig::from_account_info_mut(global_config_account)?;
     
     if global_config_info.authority != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(StakingError::Unauthorized.into());
+    };
+
+    if new_authority == Pubkey::default() || new_authority == global_config_info.authority {
+        return Err(StakingError::InvalidPendingAuthority.into());
     };
 
-    global_config_info.authority = new_authority;
+    global_config_info.pending_authority = new_authority;
+
+    GlobalConfigUpdatedEvent {
+        global_config: *global_config_account.key(),
+        update_type: 2,
+        value: new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
     Ok(())
 }
 
-pub fn process_update_protocol_fee(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+pub fn process_accept_authority(accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    let [pending_authority, global_config_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !pending_authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    let mut global_config_info = GlobalConfig::from_account_info_mut(global_config_account)?;
+
+    if global_config_info.pending_authority == Pubkey::default() {
+        return Err(StakingError::NoPendingAuthority.into());
+    };
+
+    if global_config_info.pending_authority != *pending_authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    };
+
+    global_config_info.authority = *pending_authority.key();
+    global_config_info.pending_authority = Pubkey::default();
+
+    GlobalConfigUpdatedEvent {
+        global_config: *global_config_account.key(),
+        update_type: 0,
+        value: *pending_authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_cancel_authority_transfer(accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
     let [authority, global_config_account] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+
+    GlobalConfig::check_pda(global_config_account)?;
+
+    let mut global_config_info = GlobalConfig::from_account_info_mut(global_config_account)?;
+
+    if global_config_info.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    };
+
+    if global_config_info.pending_authority == Pubkey::default() {
+        return Err(StakingError::NoPendingAuthority.into());
+    };
+
+    let cancelled_authority = global_config_info.pending_authority;
+    global_config_info.pending_authority = Pubkey::default();
+
+    GlobalConfigUpdatedEvent {
+        global_config: *global_config_account.key(),
+        update_type: 3,
+        value: cancelled_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_update_protocol_fee(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [fee_manager, global_config_account, roles_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if instruction_data.len() < 2 {
@@ -54,25 +127,147 @@ pub fn process_update_protocol_fee(accounts: &[AccountInfo], instruction_data: &
     );
 
     if new_protocol_fee_rate > 10000 {
-        return Err(ProgramError::InvalidArgument);
+        return Err(StakingError::InvalidFeeRate.into());
     };
 
-    let (global_config_pda, _bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    };
+    Roles::check_role(global_config_account, roles_account, fee_manager, RoleEnum::FeeManager)?;
 
     let mut global_config_info = GlobalConfig::from_account_info_mut(global_config_account)?;
-    
-    if global_config_info.authority != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
-    };
 
     global_config_info.protocol_fee_rate = new_protocol_fee_rate;
 
+    let mut value = [0u8; 32];
+    value[..2].copy_from_slice(&new_protocol_fee_rate.to_le_bytes());
+
+    GlobalConfigUpdatedEvent {
+        global_config: *global_config_account.key(),
+        update_type: 1,
+        value,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
     Ok(())
 }
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::{AccountMeta, Instruction}, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::global_config::GlobalConfig;
+    use crate::test_utils::*;
+
+    fn propose(context: &Context, authority: &Pubkey, global_config: &Pubkey, new_authority: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::ProposeAuthority,
+                vec![AccountMeta::new_readonly(*authority, true), AccountMeta::new(*global_config, false)],
+                new_authority.as_ref(),
+            ),
+            &[check],
+        );
+    }
+
+    fn call(context: &Context, instruction_type: StakingInstructions, signer: &Pubkey, global_config: &Pubkey, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                instruction_type,
+                vec![AccountMeta::new_readonly(*signer, true), AccountMeta::new(*global_config, false)],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_authority_moves_once_accepted() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let new_authority = funded_wallet(&context);
+        let outsider = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        propose(&context, &outsider, &global_config, &outsider, custom_error(StakingError::Unauthorized));
+        propose(&context, &authority, &global_config, &new_authority, Check::success());
+
+        // Nothing moves on the proposal alone
+        let config = read::<GlobalConfig>(&context, &global_config);
+        assert_eq!(config.authority, authority.to_bytes());
+        assert_eq!(config.pending_authority, new_authority.to_bytes());
+
+        call(&context, StakingInstructions::AcceptAuthority, &outsider, &global_config, custom_error(StakingError::Unauthorized));
+        call(&context, StakingInstructions::AcceptAuthority, &new_authority, &global_config, Check::success());
+
+        let config = read::<GlobalConfig>(&context, &global_config);
+        assert_eq!(config.authority, new_authority.to_bytes());
+        assert_eq!(config.pending_authority, [0u8; 32]);
+
+        // The config PDA stays put, it is seeded by config_id rather than the authority
+        assert_eq!(config.config_id, authority.to_bytes());
+        assert_eq!(global_config, config_pda(&authority));
+
+        propose(&context, &authority, &global_config, &outsider, custom_error(StakingError::Unauthorized));
+        propose(&context, &new_authority, &global_config, &outsider, Check::success());
+    }
+
+    #[test]
+    fn test_cancelled_transfer_cannot_be_accepted() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let new_authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        call(&context, StakingInstructions::CancelAuthorityTransfer, &authority, &global_config, custom_error(StakingError::NoPendingAuthority));
+        propose(&context, &authority, &global_config, &authority, custom_error(StakingError::InvalidPendingAuthority));
+        propose(&context, &authority, &global_config, &Pubkey::default(), custom_error(StakingError::InvalidPendingAuthority));
+
+        propose(&context, &authority, &global_config, &new_authority, Check::success());
+        call(&context, StakingInstructions::CancelAuthorityTransfer, &new_authority, &global_config, custom_error(StakingError::Unauthorized));
+        call(&context, StakingInstructions::CancelAuthorityTransfer, &authority, &global_config, Check::success());
+
+        call(&context, StakingInstructions::AcceptAuthority, &new_authority, &global_config, custom_error(StakingError::NoPendingAuthority));
+        assert_eq!(read::<GlobalConfig>(&context, &global_config).authority, authority.to_bytes());
+    }
+
+    #[test]
+    fn test_new_proposal_replaces_the_pending_one() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let mistyped = Pubkey::new_unique();
+        let new_authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        propose(&context, &authority, &global_config, &mistyped, Check::success());
+        propose(&context, &authority, &global_config, &new_authority, Check::success());
+
+        call(&context, StakingInstructions::AcceptAuthority, &mistyped, &global_config, custom_error(StakingError::Unauthorized));
+        call(&context, StakingInstructions::AcceptAuthority, &new_authority, &global_config, Check::success());
+    }
+
+    #[test]
+    fn test_retired_update_authority_is_rejected() {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let new_authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+
+        // Discriminator 1 used to be UpdateAuthority, old clients must not land on another call
+        let mut data = vec![1u8];
+        data.extend_from_slice(new_authority.as_ref());
+
+        context.process_and_validate_instruction(
+            &Instruction::new_with_bytes(
+                PROGRAM_ID,
+                &data,
+                vec![AccountMeta::new_readonly(authority, true), AccountMeta::new(global_config, false)],
+            ),
+            &[custom_error(StakingError::RetiredInstruction)],
+        );
+
+        let config = read::<GlobalConfig>(&context, &global_config);
+        assert_eq!(config.authority, authority.to_bytes());
+        assert_eq!(config.pending_authority, [0u8; 32]);
+    }
+}
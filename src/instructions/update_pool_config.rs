@@ -1,5 +1,13 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, *};
-use crate::states::{helper::AccountData, staking_pool_account::{PoolStatusEnum, SlashTypeEnum, StakingPool}};
+use pinocchio::{account_info::{AccountInfo, Ref}, instruction::Signer, program_error::ProgramError, pubkey::Pubkey, sysvars::{clock::Clock, rent::Rent, Sysvar}, *};
+use pinocchio_system::instructions::CreateAccount;
+use crate::error::StakingError;
+use crate::events::{Event, PoolConfigChangeCancelledEvent, PoolConfigChangeQueuedEvent, PoolConfigUpdatedEvent, PoolStatusChangedEvent};
+use crate::rewards::update_pool_rewards;
+use crate::states::{
+    helper::AccountData,
+    pool_config_change::{PoolConfigChange, MAX_CONFIG_CHANGE_DATA},
+    staking_pool_account::{PenaltyDestinationEnum, PoolStatusEnum, SlashTypeEnum, StakingPool, MAX_COMPOUND_TIP_BPS, MIN_CONFIG_CHANGE_DELAY}
+};
 
 #[derive(Debug)]
 pub enum PoolUpdateType {
@@ -18,17 +26,281 @@ pub enum PoolUpdateType {
     PriceFeedAccount(Pubkey),
     PoolStatus(u8),
     EmergencyPause(bool),
+    PenaltyDestination(u8),
+    PenaltyGracePeriod(i64),
+    PenaltyRecoveryBps(u16),
+    CompoundTipBps(u16),
+    CompoundMinFrequencyHours(u32),
+    CompoundMinAmount(u64),
+    ConfigChangeDelay(i64),
+}
+
+impl PoolUpdateType {
+    // Pausing only ever protects stakers, everything else waits out the pool's config change delay
+    fn is_immediate(&self) -> bool {
+        match self {
+            PoolUpdateType::EmergencyPause(_) => true,
+            PoolUpdateType::PoolStatus(value) => *value == PoolStatusEnum::Paused as u8,
+            _ => false
+        }
+    }
 }
 
 pub fn process_update_pool_config(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
 
     let [authority, staking_pool_account, price_feed_account] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+
+    let (update_type_discriminator, pool_id, update_type) = parse_pool_update(instruction_data, price_feed_account.key())?;
+
+    let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool_account_info.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    if staking_pool_account_info.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    // Anything that can hurt stakers already in the pool goes through QueuePoolConfigChange
+    if !update_type.is_immediate() {
+        return Err(StakingError::ConfigChangeTimelocked.into());
+    }
+
+    apply_pool_update(
+        staking_pool_account,
+        &mut staking_pool_account_info,
+        update_type_discriminator,
+        update_type,
+        instruction_data
+    )
+}
+
+pub fn process_queue_pool_config_change(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [
+        authority,             // Pool authority, pays for the change account
+        staking_pool_account,  // Staking pool the change applies to
+        price_feed_account,    // New price feed for a PriceFeedAccount change, otherwise ignored
+        config_change_account, // PoolConfigChange PDA for this update type
+        _system_program,       // System program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Same payload as UpdatePoolConfig
+    if instruction_data.len() > MAX_CONFIG_CHANGE_DATA + 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (update_type_discriminator, pool_id, _update_type) = parse_pool_update(instruction_data, price_feed_account.key())?;
+
+    let config_change_delay = {
+        let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+        if staking_pool.authority != *authority.key() {
+            return Err(StakingError::Unauthorized.into());
+        }
+
+        if staking_pool.pool_id != pool_id {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        staking_pool.config_change_delay()
+    };
+
+    let update_type_ref = &[update_type_discriminator];
+    let (config_change_pda, config_change_bump) = pubkey::find_program_address(
+        &[b"pool_config_change", staking_pool_account.key().as_ref(), update_type_ref],
+        &crate::ID
+    );
+
+    if *config_change_account.key() != config_change_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    // A queued change has to execute or be cancelled before the next one for the same parameter
+    if !config_change_account.data_is_empty() {
+        return Err(StakingError::ConfigChangeAlreadyQueued.into());
+    }
+
+    let bump_ref = &[config_change_bump];
+    let seeds = seeds!(
+        b"pool_config_change",
+        staking_pool_account.key().as_ref(),
+        update_type_ref,
+        bump_ref
+    );
+    let signer_seeds = Signer::from(&seeds);
+
+    CreateAccount {
+        from: authority,
+        to: config_change_account,
+        lamports: Rent::get()?.minimum_balance(PoolConfigChange::SIZE),
+        space: PoolConfigChange::SIZE as u64,
+        owner: &crate::ID
+    }.invoke_signed(&[signer_seeds])?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    let execute_after = current_timestamp
+        .checked_add(config_change_delay)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let update_data = &instruction_data[1..];
+
+    let mut config_change = PoolConfigChange::initialize(config_change_account)?;
+    config_change.staking_pool = *staking_pool_account.key();
+    config_change.authority = *authority.key();
+    config_change.update_type = update_type_discriminator;
+    config_change.update_data[..update_data.len()].copy_from_slice(update_data);
+    config_change.update_data_len = update_data.len() as u8;
+    config_change.price_feed_account = *price_feed_account.key();
+    config_change.queued_timestamp = current_timestamp;
+    config_change.execute_after = execute_after;
+    config_change.bump = config_change_bump;
+
+    let mut value = [0u8; 32];
+    let raw_value = &instruction_data[9..];
+    value[..raw_value.len()].copy_from_slice(raw_value);
+
+    PoolConfigChangeQueuedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id,
+        update_type: update_type_discriminator,
+        value,
+        execute_after,
+        timestamp: current_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+pub fn process_execute_pool_config_change(accounts: &[AccountInfo]) -> ProgramResult {
+    let [authority, staking_pool_account, config_change_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !authority.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
+
+    if staking_pool_account_info.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    // Rebuild the UpdatePoolConfig payload that was queued
+    let (instruction_data, instruction_data_len, price_feed_account) = {
+        let config_change = load_config_change(authority, staking_pool_account, config_change_account)?;
+
+        if Clock::get()?.unix_timestamp < config_change.execute_after {
+            return Err(StakingError::ConfigChangeNotReady.into());
+        }
+
+        let update_data_len = config_change.update_data_len as usize;
+        let mut instruction_data = [0u8; MAX_CONFIG_CHANGE_DATA + 1];
+        instruction_data[0] = config_change.update_type;
+        instruction_data[1..update_data_len + 1].copy_from_slice(&config_change.update_data[..update_data_len]);
+
+        (instruction_data, update_data_len + 1, config_change.price_feed_account)
     };
+    let instruction_data = &instruction_data[..instruction_data_len];
+
+    // Values are checked again, the pool may have moved on while the change waited
+    let (update_type_discriminator, pool_id, update_type) = parse_pool_update(instruction_data, &price_feed_account)?;
+
+    if staking_pool_account_info.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    apply_pool_update(
+        staking_pool_account,
+        &mut staking_pool_account_info,
+        update_type_discriminator,
+        update_type,
+        instruction_data
+    )?;
+
+    close_config_change(authority, config_change_account)
+}
+
+pub fn process_cancel_pool_config_change(accounts: &[AccountInfo]) -> ProgramResult {
+    let [authority, staking_pool_account, config_change_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+    if staking_pool.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    let update_type = load_config_change(authority, staking_pool_account, config_change_account)?.update_type;
+
+    close_config_change(authority, config_change_account)?;
+
+    PoolConfigChangeCancelledEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id: staking_pool.pool_id,
+        update_type,
+        timestamp: Clock::get()?.unix_timestamp,
+    }.emit();
+
+    Ok(())
+}
+
+// A change queued for this pool by `authority`, which gets the rent back when it closes
+fn load_config_change<'a>(
+    authority: &AccountInfo,
+    staking_pool_account: &AccountInfo,
+    config_change_account: &'a AccountInfo
+) -> Result<Ref<'a, PoolConfigChange>, ProgramError> {
+    let config_change = PoolConfigChange::from_account_info(config_change_account)?;
+
+    if config_change.staking_pool != *staking_pool_account.key() {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    if config_change.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    Ok(config_change)
+}
+
+// Return rent to the authority that queued the change
+fn close_config_change(authority: &AccountInfo, config_change_account: &AccountInfo) -> ProgramResult {
+    let lamports = config_change_account.lamports();
+    *authority.try_borrow_mut_lamports()? = authority.lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    *config_change_account.try_borrow_mut_lamports()? = 0;
+
+    config_change_account.close()
+}
+
+// update_type (1), pool_id (8), value. PriceFeedAccount takes its value from `price_feed_account`
+fn parse_pool_update(
+    instruction_data: &[u8],
+    price_feed_account: &Pubkey
+) -> Result<(u8, u64, PoolUpdateType), ProgramError> {
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     let update_type_discriminator = instruction_data[0];
     let pool_id = u64::from_le_bytes(
@@ -136,7 +408,7 @@ pub fn process_update_pool_config(accounts: &[AccountInfo], instruction_data: &[
             PoolUpdateType::SlashingConditionType(value)
         },
         12 => {
-            let value = *price_feed_account.key();
+            let value = *price_feed_account;
             PoolUpdateType::PriceFeedAccount(value)
         },
         13 => {
@@ -153,33 +425,100 @@ pub fn process_update_pool_config(accounts: &[AccountInfo], instruction_data: &[
             };
             PoolUpdateType::EmergencyPause(value_type)
         },
+        15 => {
+            let value = instruction_data[9];
+            PenaltyDestinationEnum::try_from(&value)?;
+            PoolUpdateType::PenaltyDestination(value)
+        },
+        16 => {
+            let value = i64::from_le_bytes(
+                instruction_data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            // 0 lets penalties be resolved as soon as they are opened
+            if value < 0 {
+                return Err(ProgramError::InvalidInstructionData);
+            };
+            PoolUpdateType::PenaltyGracePeriod(value)
+        },
+        17 => {
+            let value = u16::from_le_bytes(
+                instruction_data[9..11].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            if value > 10000 {
+                return Err(ProgramError::InvalidInstructionData);
+            };
+            PoolUpdateType::PenaltyRecoveryBps(value)
+        },
+        18 => {
+            let value = u16::from_le_bytes(
+                instruction_data[9..11].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            if value > MAX_COMPOUND_TIP_BPS {
+                return Err(StakingError::InvalidCompoundTip.into());
+            };
+            PoolUpdateType::CompoundTipBps(value)
+        },
+        19 => {
+            let value = u32::from_le_bytes(
+                instruction_data[9..13].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            PoolUpdateType::CompoundMinFrequencyHours(value)
+        },
+        20 => {
+            let value = u64::from_le_bytes(
+                instruction_data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            PoolUpdateType::CompoundMinAmount(value)
+        },
+        21 => {
+            let value = i64::from_le_bytes(
+                instruction_data[9..17].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+            );
+            if value < MIN_CONFIG_CHANGE_DELAY {
+                return Err(StakingError::InvalidConfigChangeDelay.into());
+            };
+            PoolUpdateType::ConfigChangeDelay(value)
+        },
         _ => {
             return Err(ProgramError::InvalidInstructionData);
         }
     };
 
-    let mut staking_pool_account_info = StakingPool::from_account_info_mut(staking_pool_account)?;
-
-    if staking_pool_account_info.authority != *authority.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-
-    if staking_pool_account_info.pool_id != pool_id {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    Ok((update_type_discriminator, pool_id, update_type))
+}
 
+// Applies a parsed change and emits its events, for immediate updates and executed queued changes alike
+fn apply_pool_update(
+    staking_pool_account: &AccountInfo,
+    staking_pool_account_info: &mut StakingPool,
+    update_type_discriminator: u8,
+    update_type: PoolUpdateType,
+    instruction_data: &[u8]
+) -> ProgramResult {
     if staking_pool_account_info.emergency_pause_flag {
         match update_type {
             PoolUpdateType::EmergencyPause(_) | 
             PoolUpdateType::PoolStatus(_) => {},
             _ => {
-                return Err(ProgramError::InvalidAccountData);
+                return Err(StakingError::UpdateBlockedByEmergencyPause.into());
             }
         }
     }
 
+    let old_status = staking_pool_account_info.pool_status;
+
     match update_type {
         PoolUpdateType::RewardRatePerSecond(value) => {
+            let current_timestamp = Clock::get()?.unix_timestamp;
+
+            // FundRewardVault sized the rate to the vault, overriding it could promise more than is there
+            if current_timestamp < staking_pool_account_info.reward_end_timestamp {
+                return Err(StakingError::RewardCampaignActive.into());
+            }
+
+            // Accrue at the old rate up to now so the change only affects future emissions
+            update_pool_rewards(staking_pool_account_info, current_timestamp)?;
+            staking_pool_account_info.reward_end_timestamp = 0;
             staking_pool_account_info.reward_rate_per_second = value;
         },
         PoolUpdateType::LockPeriodDuration(value) => {
@@ -225,6 +564,11 @@ pub fn process_update_pool_config(accounts: &[AccountInfo], instruction_data: &[
             staking_pool_account_info.slashing_enabled = value;
         },
         PoolUpdateType::SlashingConditionType(value) => {
+            // Evidence filed for the old condition no longer counts
+            if staking_pool_account_info.slashing_condition_type != value {
+                staking_pool_account_info.slash_round = staking_pool_account_info.slash_round.saturating_add(1);
+                staking_pool_account_info.evidence_count = 0;
+            }
             staking_pool_account_info.slashing_condition_type = value;
         },
         PoolUpdateType::PriceFeedAccount(value) => {
@@ -239,7 +583,221 @@ pub fn process_update_pool_config(accounts: &[AccountInfo], instruction_data: &[
                 staking_pool_account_info.pool_status = 3; 
             }
         },
+        PoolUpdateType::PenaltyDestination(value) => {
+            staking_pool_account_info.penalty_destination = value;
+        },
+        PoolUpdateType::PenaltyGracePeriod(value) => {
+            staking_pool_account_info.penalty_grace_period = value;
+        },
+        PoolUpdateType::PenaltyRecoveryBps(value) => {
+            staking_pool_account_info.penalty_recovery_bps = value;
+        },
+        PoolUpdateType::CompoundTipBps(value) => {
+            staking_pool_account_info.compound_tip_bps = value;
+        },
+        // Positions already enabled keep their settings until they next update them
+        PoolUpdateType::CompoundMinFrequencyHours(value) => {
+            staking_pool_account_info.compound_min_frequency_hours = value;
+        },
+        PoolUpdateType::CompoundMinAmount(value) => {
+            staking_pool_account_info.compound_min_amount = value;
+        },
+        PoolUpdateType::ConfigChangeDelay(value) => {
+            staking_pool_account_info.config_change_delay = value;
+        },
+    }
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+
+    let mut value = [0u8; 32];
+    let raw_value = &instruction_data[9..instruction_data.len().min(41)];
+    value[..raw_value.len()].copy_from_slice(raw_value);
+
+    PoolConfigUpdatedEvent {
+        staking_pool: *staking_pool_account.key(),
+        pool_id: staking_pool_account_info.pool_id,
+        update_type: update_type_discriminator,
+        value,
+        timestamp: current_timestamp,
+    }.emit();
+
+    if staking_pool_account_info.pool_status != old_status {
+        PoolStatusChangedEvent {
+            staking_pool: *staking_pool_account.key(),
+            pool_id: staking_pool_account_info.pool_id,
+            old_status,
+            new_status: staking_pool_account_info.pool_status,
+            timestamp: current_timestamp,
+        }.emit();
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::staking_pool_account::{StakingPool, MIN_CONFIG_CHANGE_DELAY};
+    use crate::test_utils::*;
+
+    const REWARD_RATE_UPDATE: u8 = 0;
+    const EMERGENCY_PAUSE_UPDATE: u8 = 14;
+
+    fn update_data(update_type: u8, pool: &TestPool, value: &[u8]) -> Vec<u8> {
+        let mut data = vec![update_type];
+        data.extend_from_slice(&pool.pool_id.to_le_bytes());
+        data.extend_from_slice(value);
+        data
+    }
+
+    fn config_change_pda(pool: &TestPool, update_type: u8) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"pool_config_change", pool.staking_pool.as_ref(), &[update_type]],
+            &PROGRAM_ID
+        ).0
+    }
+
+    fn queue(context: &Context, authority: &Pubkey, pool: &TestPool, data: &[u8], check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::QueuePoolConfigChange,
+                vec![
+                    AccountMeta::new(*authority, true),
+                    AccountMeta::new_readonly(pool.staking_pool, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                    AccountMeta::new(config_change_pda(pool, data[0]), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+                data,
+            ),
+            &[check],
+        );
+    }
+
+    fn settle(context: &Context, instruction_type: StakingInstructions, authority: &Pubkey, pool: &TestPool, update_type: u8, check: Check) {
+        context.process_and_validate_instruction(
+            &instruction(
+                instruction_type,
+                vec![
+                    AccountMeta::new(*authority, true),
+                    AccountMeta::new(pool.staking_pool, false),
+                    AccountMeta::new(config_change_pda(pool, update_type), false),
+                ],
+                &[],
+            ),
+            &[check],
+        );
+    }
+
+    fn reward_rate(context: &Context, pool: &TestPool) -> u64 {
+        read_at(context, &pool.staking_pool, core::mem::offset_of!(StakingPool, reward_rate_per_second))
+    }
+
+    fn setup_pool() -> (Context, TestPool) {
+        let context = setup();
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        let pool = create_pool(&context, &authority, &global_config, None);
+
+        (context, pool)
+    }
+
+    #[test]
+    fn test_risky_update_has_to_be_queued() {
+        let (context, pool) = setup_pool();
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdatePoolConfig,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new(pool.staking_pool, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                ],
+                &update_data(REWARD_RATE_UPDATE, &pool, &500u64.to_le_bytes()),
+            ),
+            &[custom_error(StakingError::ConfigChangeTimelocked)],
+        );
+
+        // Pausing protects stakers and still goes through right away
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdatePoolConfig,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new(pool.staking_pool, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                ],
+                &update_data(EMERGENCY_PAUSE_UPDATE, &pool, &[1]),
+            ),
+            &[Check::success()],
+        );
+    }
+
+    #[test]
+    fn test_queued_change_executes_after_the_delay() {
+        let (mut context, pool) = setup_pool();
+        let data = update_data(REWARD_RATE_UPDATE, &pool, &500u64.to_le_bytes());
+
+        queue(&context, &pool.authority, &pool, &data, Check::success());
+        queue(&context, &pool.authority, &pool, &data, custom_error(StakingError::ConfigChangeAlreadyQueued));
+
+        settle(&context, StakingInstructions::ExecutePoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, custom_error(StakingError::ConfigChangeNotReady));
+
+        set_time(&mut context, START_TIMESTAMP + MIN_CONFIG_CHANGE_DELAY - 1);
+        settle(&context, StakingInstructions::ExecutePoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, custom_error(StakingError::ConfigChangeNotReady));
+        assert_eq!(reward_rate(&context, &pool), 100);
+
+        set_time(&mut context, START_TIMESTAMP + MIN_CONFIG_CHANGE_DELAY);
+        settle(&context, StakingInstructions::ExecutePoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, Check::success());
+        assert_eq!(reward_rate(&context, &pool), 500);
+
+        // Closed on execute, so the same parameter can be queued again
+        assert_eq!(account(&context, &config_change_pda(&pool, REWARD_RATE_UPDATE)).lamports, 0);
+        queue(&context, &pool.authority, &pool, &update_data(REWARD_RATE_UPDATE, &pool, &700u64.to_le_bytes()), Check::success());
+    }
+
+    #[test]
+    fn test_only_the_pool_authority_queues_and_cancels() {
+        let (mut context, pool) = setup_pool();
+        let outsider = funded_wallet(&context);
+        let data = update_data(REWARD_RATE_UPDATE, &pool, &500u64.to_le_bytes());
+
+        queue(&context, &outsider, &pool, &data, custom_error(StakingError::Unauthorized));
+        queue(&context, &pool.authority, &pool, &data, Check::success());
+
+        settle(&context, StakingInstructions::CancelPoolConfigChange, &outsider, &pool, REWARD_RATE_UPDATE, custom_error(StakingError::Unauthorized));
+        settle(&context, StakingInstructions::CancelPoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, Check::success());
+
+        // A cancelled change is gone even once its delay has passed
+        set_time(&mut context, START_TIMESTAMP + MIN_CONFIG_CHANGE_DELAY);
+        settle(&context, StakingInstructions::ExecutePoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, Check::err(ProgramError::InvalidAccountOwner));
+        assert_eq!(reward_rate(&context, &pool), 100);
+    }
+
+    #[test]
+    fn test_emergency_pause_blocks_queued_changes() {
+        let (mut context, pool) = setup_pool();
+
+        queue(&context, &pool.authority, &pool, &update_data(REWARD_RATE_UPDATE, &pool, &500u64.to_le_bytes()), Check::success());
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::UpdatePoolConfig,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new(pool.staking_pool, false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                ],
+                &update_data(EMERGENCY_PAUSE_UPDATE, &pool, &[1]),
+            ),
+            &[Check::success()],
+        );
+
+        set_time(&mut context, START_TIMESTAMP + MIN_CONFIG_CHANGE_DELAY);
+        settle(&context, StakingInstructions::ExecutePoolConfigChange, &pool.authority, &pool, REWARD_RATE_UPDATE, custom_error(StakingError::UpdateBlockedByEmergencyPause));
+    }
+}
@@ -0,0 +1,154 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, *};
+
+use crate::error::StakingError;
+use crate::stake_program;
+use crate::states::{
+    helper::AccountData,
+    staking_pool_account::{PoolTypeEnum, StakingPool},
+    validator_list::{TransientStateEnum, ValidatorList, ValidatorStatusEnum}
+};
+
+pub fn process_add_validator(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, validator_list_account, validator_vote_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 10 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let target_weight = u16::from_le_bytes(
+        instruction_data[8..10].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if !validator_vote_account.is_owned_by(&stake_program::VOTE_PROGRAM_ID) {
+        return Err(StakingError::InvalidVoteAccount.into());
+    }
+
+    check_pool_authority(authority, staking_pool_account, validator_list_account, pool_id)?;
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+    validator_list.add(*validator_vote_account.key(), target_weight)?;
+
+    Ok(())
+}
+
+pub fn process_remove_validator(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, validator_list_account, validator_vote_account, validator_stake] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    check_pool_authority(authority, staking_pool_account, validator_list_account, pool_id)?;
+
+    let (validator_stake_pda, _validator_stake_bump) = pubkey::find_program_address(
+        &[
+            b"validator_stake",
+            staking_pool_account.key().as_ref(),
+            validator_vote_account.key().as_ref(),
+        ],
+        &crate::ID
+    );
+
+    if *validator_stake.key() != validator_stake_pda {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+    let entry = *validator_list.find_mut(validator_vote_account.key())?;
+
+    let holds_stake = !validator_stake.data_is_empty()
+        || !matches!(TransientStateEnum::try_from(&entry.transient_state)?, TransientStateEnum::None);
+
+    if !holds_stake {
+        return validator_list.remove(validator_vote_account.key());
+    }
+
+    // RebalanceValidators drains it back into the reserve, MergeTransientStake then drops the entry
+    validator_list.set_weight(validator_vote_account.key(), 0)?;
+    validator_list.find_mut(validator_vote_account.key())?.status = ValidatorStatusEnum::Removing as u8;
+
+    Ok(())
+}
+
+pub fn process_set_validator_weight(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let [authority, staking_pool_account, validator_list_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if instruction_data.len() < 42 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let pool_id = u64::from_le_bytes(
+        instruction_data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let vote_account: Pubkey = instruction_data[8..40]
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let target_weight = u16::from_le_bytes(
+        instruction_data[40..42].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    check_pool_authority(authority, staking_pool_account, validator_list_account, pool_id)?;
+
+    let mut validator_list = ValidatorList::from_account_info_mut(validator_list_account)?;
+
+    if matches!(ValidatorStatusEnum::try_from(&validator_list.find_mut(&vote_account)?.status)?, ValidatorStatusEnum::Removing) {
+        return Err(StakingError::ValidatorRemoving.into());
+    }
+
+    validator_list.set_weight(&vote_account, target_weight)?;
+
+    Ok(())
+}
+
+fn check_pool_authority(
+    authority: &AccountInfo,
+    staking_pool_account: &AccountInfo,
+    validator_list_account: &AccountInfo,
+    pool_id: u64,
+) -> ProgramResult {
+    let staking_pool = StakingPool::from_account_info(staking_pool_account)?;
+
+    if staking_pool.authority != *authority.key() {
+        return Err(StakingError::Unauthorized.into());
+    }
+
+    if staking_pool.pool_id != pool_id {
+        return Err(StakingError::PoolMismatch.into());
+    }
+
+    staking_pool.check_pool_type(PoolTypeEnum::NativeSol)?;
+
+    if staking_pool.validator_list != *validator_list_account.key() {
+        return Err(StakingError::InvalidPda.into());
+    }
+
+    Ok(())
+}
@@ -1,24 +1,30 @@
 use pinocchio::{account_info::AccountInfo, program_error::ProgramError, instruction::Signer, sysvars::{clock::Clock, Sysvar}, *};
 use pinocchio_token::{state::{TokenAccount, Mint}, instructions::{TransferChecked, BurnChecked}};
 
-use crate::states::{helper::AccountData, staking_pool_account::StakingPool, user_stake_account::UserStakeAccount, global_config::GlobalConfig};
+use crate::error::StakingError;
+use crate::events::{EmergencyWithdrawEvent, Event};
+use crate::penalty_payout::PenaltyPayout;
+use crate::rewards::update_pool_rewards;
+use crate::states::{helper::AccountData, staking_pool_account::{PoolTypeEnum, StakingPool, MAX_REWARD_STREAMS, SLASH_EMERGENCY_WINDOW}, user_stake_account::{StakePosition, UserStakeAccount}, global_config::GlobalConfig};
 
 pub fn process_emergency_withdraw(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     let [
         user,                    // User requesting emergency withdrawal
-        authority,              // Global config authority (for emergency validation)
+        _authority,              // Global config authority (for emergency validation)
         stake_token_mint,       // Original staked token mint
         stake_token_vault,      // Vault holding staked tokens
         liquid_stake_mint,      // LST mint (to burn tokens)
         global_config_account,  // Global config PDA
         staking_pool_account,   // Pool account
         user_token_account,     // User's token account to receive tokens
-        user_stake_account,     // User's stake position account
+        user_stake_account,     // User's aggregate stake account
+        stake_position_account, // Position being exited
         user_lst_token_account, // User's LST token account
         treasury_account,       // Treasury account for penalty collection
-        token_program,          // Token program
+        reward_token_vault,     // Receives collected penalty debt routed to the reward vault
+        _token_program,         // Token program
     ] = accounts else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !user.is_signer() {
@@ -34,18 +40,14 @@ pub fn process_emergency_withdraw(accounts: &[AccountInfo], instruction_data: &[
     );
 
     // Verify PDAs
-    let (global_config_pda, global_config_bump) = pubkey::find_program_address(
-        &[b"global_config_account", authority.key().as_ref()],
-        &crate::ID
-    );
-
-    if *global_config_account.key() != global_config_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let (config_id, global_config_bump) = GlobalConfig::check_pda(global_config_account)?;
 
     let global_config = GlobalConfig::from_account_info(global_config_account)?;
     let mut staking_pool = StakingPool::from_account_info_mut(staking_pool_account)?;
     let mut user_stake = UserStakeAccount::from_account_info_mut(user_stake_account)?;
+    let mut position = StakePosition::from_account_info_mut(stake_position_account)?;
+
+    staking_pool.check_pool_type(PoolTypeEnum::SplToken)?;
 
     // Emergency withdraw conditions - at least one must be true
     let emergency_conditions_met = check_emergency_conditions(
@@ -55,30 +57,38 @@ pub fn process_emergency_withdraw(accounts: &[AccountInfo], instruction_data: &[
     )?;
 
     if !emergency_conditions_met {
-        return Err(ProgramError::Custom(2001)); // No emergency conditions met
+        return Err(StakingError::NoEmergencyCondition.into());
     }
 
-    // Find user's position for this pool
-    let mut position_index = None;
-    for (i, position) in user_stake.positions.iter().enumerate() {
-        if position.is_active && position.pool_id == pool_id {
-            position_index = Some(i);
-            break;
-        }
+    if user_stake.owner != *user.key() {
+        return Err(StakingError::OwnerMismatch.into());
     }
 
-    let position_idx = position_index.ok_or(ProgramError::Custom(1002))?; // Position not found
-    let position = &user_stake.positions[position_idx];
+    position.check_owner(user.key(), staking_pool_account.key())?;
+
+    if !position.is_active {
+        return Err(StakingError::PositionInactive.into());
+    }
+
+    if position.pool_id != pool_id {
+        return Err(StakingError::PositionNotFound.into());
+    }
 
     if position.lst_tokens == 0 {
-        return Err(ProgramError::Custom(2002)); // No tokens to withdraw
+        return Err(StakingError::NothingToWithdraw.into());
+    }
+
+    // Only upheld penalties are collected below, an open one would be escaped by exiting now
+    if user_stake.has_open_penalty(stake_position_account.key()) {
+        return Err(StakingError::PenaltyPending.into());
     }
 
     let current_timestamp = Clock::get()?.unix_timestamp;
 
     // Calculate emergency withdrawal amounts
     let lst_amount = position.lst_tokens;
-    let underlying_tokens = position.staked_amount;
+    let principal_amount = position.staked_amount;
+    let underlying_tokens = staking_pool.lst_to_underlying(lst_amount)?;
 
     // Calculate emergency penalty (usually higher than normal early withdrawal)
     let emergency_penalty = calculate_emergency_penalty(
@@ -87,56 +97,65 @@ pub fn process_emergency_withdraw(accounts: &[AccountInfo], instruction_data: &[
         &global_config
     )?;
 
-    let tokens_after_penalty = underlying_tokens.saturating_sub(emergency_penalty);
+    // Upheld penalties still owed on the position are collected before the user is paid
+    let penalty_debt_collected = position.penalty_debt.min(underlying_tokens.saturating_sub(emergency_penalty));
+
+    let tokens_after_penalty = underlying_tokens
+        .saturating_sub(emergency_penalty)
+        .saturating_sub(penalty_debt_collected);
 
     // Validate accounts
-    let user_token_info = TokenAccount::from_account_info(user_token_account)?;
-    let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
-    let treasury_info = TokenAccount::from_account_info(treasury_account)?;
+    {
+        let user_token_info = TokenAccount::from_account_info(user_token_account)?;
+        let user_lst_token_info = TokenAccount::from_account_info(user_lst_token_account)?;
 
-    if *user_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if *user_lst_token_info.owner() != *user.key() {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *user_lst_token_info.owner() != *user.key() {
+            return Err(StakingError::InvalidTokenAccountOwner.into());
+        }
 
-    if user_lst_token_info.amount() < lst_amount {
-        return Err(ProgramError::InsufficientFunds);
+        if user_lst_token_info.amount() < lst_amount {
+            return Err(StakingError::InsufficientTokenBalance.into());
+        }
     }
 
     // Burn all LST tokens for this position
-    let lst_mint_info = Mint::from_account_info(liquid_stake_mint)?;
+    let lst_mint_decimals = Mint::from_account_info(liquid_stake_mint)?.decimals();
     
     BurnChecked {
         mint: liquid_stake_mint,
         account: user_lst_token_account,
         authority: user,
         amount: lst_amount,
-        decimals: lst_mint_info.decimals(),
+        decimals: lst_mint_decimals,
     }.invoke()?;
 
     // Transfer penalty to treasury if applicable
     let global_config_bump_arr = &[global_config_bump];
     let seeds = seeds!(
         b"global_config_account", 
-        authority.key().as_ref(),
+        config_id.as_ref(),
         global_config_bump_arr
     );
     let signer_seeds = Signer::from(&seeds);
 
-    let stake_mint_info = Mint::from_account_info(stake_token_mint)?;
+    let stake_mint_decimals = Mint::from_account_info(stake_token_mint)?.decimals();
 
+    let payout_seeds = signer_seeds.clone();
     let signer_seeds_clone = signer_seeds.clone();
     if emergency_penalty > 0 {
+        global_config.check_treasury(treasury_account, &staking_pool.stake_token_mint)?;
+
         TransferChecked {
             from: stake_token_vault,
             to: treasury_account,
             mint: stake_token_mint,
             authority: global_config_account,
             amount: emergency_penalty,
-            decimals: stake_mint_info.decimals(),
+            decimals: stake_mint_decimals,
         }.invoke_signed(&[signer_seeds_clone])?;
     }
 
@@ -148,28 +167,59 @@ pub fn process_emergency_withdraw(accounts: &[AccountInfo], instruction_data: &[
             mint: stake_token_mint,
             authority: global_config_account,
             amount: tokens_after_penalty,
-            decimals: stake_mint_info.decimals(),
+            decimals: stake_mint_decimals,
         }.invoke_signed(&[signer_seeds])?;
     }
 
+    // Bring the accumulator up to date before the pool's stake shrinks
+    update_pool_rewards(&mut staking_pool, current_timestamp)?;
+
     // Deactivate the position completely (emergency withdraw = full exit)
-    user_stake.positions[position_idx].staked_amount = 0;
-    user_stake.positions[position_idx].lst_tokens = 0;
-    user_stake.positions[position_idx].is_active = false;
-    user_stake.positions[position_idx].last_reward_update = current_timestamp;
+    position.staked_amount = 0;
+    position.lst_tokens = 0;
+    position.is_active = false;
+    position.reward_debt = 0;
+    position.stream_reward_debt = [0; MAX_REWARD_STREAMS];
+    staking_pool.total_boost_weight = staking_pool.total_boost_weight.saturating_sub(position.boost_weight);
+    position.boost_weight = 0;
+    position.penalty_debt = 0;
+    position.last_reward_update = current_timestamp;
 
     // Update user totals
-    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_sub(underlying_tokens);
+    user_stake.total_staked_amount = user_stake.total_staked_amount.saturating_sub(principal_amount);
     user_stake.total_lst_balance = user_stake.total_lst_balance.saturating_sub(lst_amount);
     user_stake.active_positions = user_stake.active_positions.saturating_sub(1);
     user_stake.last_update_timestamp = current_timestamp;
 
     // Update pool totals
-    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(underlying_tokens);
+    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(principal_amount);
     staking_pool.liquid_stake_supply = staking_pool.liquid_stake_supply.saturating_sub(lst_amount);
+    staking_pool.total_underlying = staking_pool.total_underlying.saturating_sub(underlying_tokens);
+
+    // Debt held for a later recovery stays in the vault, the rest goes where the pool routes penalties
+    let reserved_debt = user_stake.collect_penalty_debt(stake_position_account.key(), penalty_debt_collected);
+    staking_pool.penalty_reserve = staking_pool.penalty_reserve.saturating_add(reserved_debt);
+
+    PenaltyPayout {
+        stake_token_mint,
+        stake_token_vault,
+        global_config_account,
+        treasury_account,
+        reward_token_vault,
+        amount: penalty_debt_collected.saturating_sub(reserved_debt),
+    }.invoke_signed(&mut staking_pool, &global_config, &[payout_seeds])?;
+
+    EmergencyWithdrawEvent {
+        user: *user.key(),
+        staking_pool: *staking_pool_account.key(),
+        stake_position: *stake_position_account.key(),
+        lst_burned: lst_amount,
+        amount_returned: tokens_after_penalty,
+        penalty_amount: emergency_penalty,
+        penalty_debt_collected,
+        timestamp: current_timestamp,
+    }.emit();
 
-    // Log emergency withdrawal event (you might want to emit an event here)
-    
     Ok(())
 }
 
@@ -212,7 +262,7 @@ fn check_emergency_conditions(
 fn calculate_emergency_penalty(
     amount: u64,
     pool: &StakingPool,
-    global_config: &GlobalConfig
+    _global_config: &GlobalConfig
 ) -> Result<u64, ProgramError> {
     // Emergency penalty is typically higher than normal early withdrawal
     // You might have different penalty rates for different emergency types
@@ -235,14 +285,15 @@ fn calculate_emergency_penalty(
 
 // Check for slashing events
 fn has_slashing_event(pool: &StakingPool) -> Result<bool, ProgramError> {
-    // Implement your slashing detection logic here
-    // This might check various conditions like:
-    // - Validator misbehavior
-    // - Oracle price manipulation
-    // - Protocol violations
-    
-    // For now, return false - implement based on your slashing conditions
-    Ok(false)
+    // Set by ExecuteSlash once enough evidence has been filed against the pool
+    if pool.last_slash_timestamp == 0 {
+        return Ok(false);
+    }
+
+    // Only a recent slash opens the emergency exit, not every slash the pool ever had
+    let window_end = pool.last_slash_timestamp.saturating_add(SLASH_EMERGENCY_WINDOW);
+
+    Ok(Clock::get()?.unix_timestamp < window_end)
 }
 
 // Detect smart contract vulnerabilities
@@ -1,4 +1,4 @@
-use pinocchio::{account_info::AccountInfo, pubkey::Pubkey, ProgramResult, program_error::ProgramError};
+use pinocchio::{account_info::AccountInfo, pubkey::Pubkey, program_error::ProgramError};
 pub use pinocchio::*;
 pub use pinocchio_pubkey::declare_id;
 
@@ -8,11 +8,20 @@ declare_id!("AxTzuiQFJVbaRwFjshTSdAHxsCHu1iTYzfPZYG41qJd");
 
 entrypoint!(process_instruction);
 
+pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod penalty_payout;
+pub mod rewards;
+pub mod stake_program;
+pub mod swap_adapter;
 pub mod states;
 
+#[cfg(all(test, feature = "test-sbf"))]
+mod test_utils;
+
 pub fn process_instruction(
-    program_id: &Pubkey,
+    _program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8]
 ) -> ProgramResult {
@@ -21,7 +30,6 @@ pub fn process_instruction(
 
     match StakingInstructions::try_from(ix_disc)? {
         StakingInstructions::InitConfigAccount => instructions::init_global_config::process_initialize_global_config(accounts, instruction_data)?,
-        StakingInstructions::UpdateAuthority => instructions::update_global_config::process_update_authority(accounts, instruction_data)?,
         StakingInstructions::UpdateProtocolFee => instructions::update_global_config::process_update_protocol_fee(accounts, instruction_data)?,
         StakingInstructions::CreateStakingPool => instructions::create_staking_pool::process_create_staking_pool(accounts, instruction_data)?,
         StakingInstructions::UpdatePoolConfig => instructions::update_pool_config::process_update_pool_config(accounts, instruction_data)?,
@@ -35,13 +43,62 @@ pub fn process_instruction(
         StakingInstructions::FundRewardVault => instructions::fund_reward_vault::process_fund_reward_vault(accounts, instruction_data)?,
         StakingInstructions::StakeTokens => instructions::stake_tokens::process_stake_tokens(accounts, instruction_data)?,
         StakingInstructions::IncreaseStake => instructions::increase_stake::process_increase_stake(accounts, instruction_data)?,
-        StakingInstructions::UnstakeTokens => instructions::unstake_tokens::process_unstake(accounts, instruction_data)?,
+        StakingInstructions::RequestUnstake => instructions::unstake_tokens::process_request_unstake(accounts, instruction_data)?,
         StakingInstructions::WithdrawFunds => instructions::withdraw_funds::process_emergency_withdraw(accounts, instruction_data)?,
         StakingInstructions::ClaimRewards => instructions::claim_rewards::process_claim_rewards(accounts, instruction_data)?,
         StakingInstructions::ClaimAllRewards => instructions::claim_rewards::process_claim_all_rewards(accounts, instruction_data)?,
         StakingInstructions::EnableAutoCompound => instructions::enable_auto_compound::process_enable_auto_compound(accounts, instruction_data)?,
         StakingInstructions::DisableAutoCompound => instructions::disable_auto_compound::process_disable_auto_compound(accounts, instruction_data)?,
-        StakingInstructions::ExecuteAutoCompound => instructions::execute_auto_compound::process_execute_auto_compound(accounts, instruction_data)?
+        StakingInstructions::ExecuteAutoCompound => instructions::execute_auto_compound::process_execute_auto_compound(accounts, instruction_data)?,
+        StakingInstructions::ClaimUnstake => instructions::unstake_tokens::process_claim_unstake(accounts, instruction_data)?,
+        StakingInstructions::CancelUnstake => instructions::unstake_tokens::process_cancel_unstake(accounts, instruction_data)?,
+        StakingInstructions::RegisterSlashReporter => instructions::slashing::process_register_slash_reporter(accounts, instruction_data)?,
+        StakingInstructions::RemoveSlashReporter => instructions::slashing::process_remove_slash_reporter(accounts, instruction_data)?,
+        StakingInstructions::SubmitSlashEvidence => instructions::slashing::process_submit_slash_evidence(accounts, instruction_data)?,
+        StakingInstructions::ExecuteSlash => instructions::slashing::process_execute_slash(accounts, instruction_data)?,
+        StakingInstructions::OpenPenalty => instructions::penalty::process_open_penalty(accounts, instruction_data)?,
+        StakingInstructions::DisputePenalty => instructions::penalty::process_dispute_penalty(accounts, instruction_data)?,
+        StakingInstructions::ResolvePenalty => instructions::penalty::process_resolve_penalty(accounts, instruction_data)?,
+        StakingInstructions::RecoverPenalty => instructions::penalty::process_recover_penalty(accounts, instruction_data)?,
+        StakingInstructions::MigrateAccount => instructions::migrate_account::process_migrate_account(accounts)?,
+        StakingInstructions::InitializeNativePool => instructions::native_stake::process_initialize_native_pool(accounts, instruction_data)?,
+        StakingInstructions::DepositSol => instructions::native_stake::process_deposit_sol(accounts, instruction_data)?,
+        StakingInstructions::RebalanceValidators => instructions::native_stake::process_rebalance_validators(accounts, instruction_data)?,
+        StakingInstructions::MergeTransientStake => instructions::native_stake::process_merge_transient_stake(accounts, instruction_data)?,
+        StakingInstructions::WithdrawStake => instructions::native_stake::process_withdraw_stake(accounts, instruction_data)?,
+        StakingInstructions::AddValidator => instructions::validator_list::process_add_validator(accounts, instruction_data)?,
+        StakingInstructions::RemoveValidator => instructions::validator_list::process_remove_validator(accounts, instruction_data)?,
+        StakingInstructions::SetValidatorWeight => instructions::validator_list::process_set_validator_weight(accounts, instruction_data)?,
+        StakingInstructions::ExecuteValidatorSlash => instructions::slashing::process_execute_validator_slash(accounts, instruction_data)?,
+        StakingInstructions::UpdatePoolBalance => instructions::native_stake::process_update_pool_balance(accounts, instruction_data)?,
+        StakingInstructions::InitializeLiquidityPool => instructions::liquidity::process_initialize_liquidity_pool(accounts, instruction_data)?,
+        StakingInstructions::AddLiquidity => instructions::liquidity::process_add_liquidity(accounts, instruction_data)?,
+        StakingInstructions::RemoveLiquidity => instructions::liquidity::process_remove_liquidity(accounts, instruction_data)?,
+        StakingInstructions::InstantUnstake => instructions::liquidity::process_instant_unstake(accounts, instruction_data)?,
+        StakingInstructions::ReplenishLiquidity => instructions::liquidity::process_replenish_liquidity(accounts, instruction_data)?,
+        StakingInstructions::AddRewardStream => instructions::reward_streams::process_add_reward_stream(accounts, instruction_data)?,
+        StakingInstructions::FundRewardStream => instructions::reward_streams::process_fund_reward_stream(accounts, instruction_data)?,
+        StakingInstructions::SetLockTiers => instructions::lock_tiers::process_set_lock_tiers(accounts, instruction_data)?,
+        StakingInstructions::RefreshLockBoost => instructions::lock_tiers::process_refresh_lock_boost(accounts, instruction_data)?,
+        StakingInstructions::UpdateAutoCompound => instructions::enable_auto_compound::process_update_auto_compound(accounts, instruction_data)?,
+        StakingInstructions::SetSwapAdapter => instructions::swap::process_set_swap_adapter(accounts, instruction_data)?,
+        StakingInstructions::InitializeSwapPool => instructions::swap::process_initialize_swap_pool(accounts, instruction_data)?,
+        StakingInstructions::WithdrawSwapReserve => instructions::swap::process_withdraw_swap_reserve(accounts, instruction_data)?,
+        StakingInstructions::AcceptAuthority => instructions::update_global_config::process_accept_authority(accounts, instruction_data)?,
+        StakingInstructions::CancelAuthorityTransfer => instructions::update_global_config::process_cancel_authority_transfer(accounts, instruction_data)?,
+        StakingInstructions::GrantRole => instructions::roles::process_grant_role(accounts, instruction_data)?,
+        StakingInstructions::RevokeRole => instructions::roles::process_revoke_role(accounts, instruction_data)?,
+        StakingInstructions::QueuePoolConfigChange => instructions::update_pool_config::process_queue_pool_config_change(accounts, instruction_data)?,
+        StakingInstructions::ExecutePoolConfigChange => instructions::update_pool_config::process_execute_pool_config_change(accounts)?,
+        StakingInstructions::CancelPoolConfigChange => instructions::update_pool_config::process_cancel_pool_config_change(accounts)?,
+        StakingInstructions::CreateMultisig => instructions::multisig::process_create_multisig(accounts, instruction_data)?,
+        StakingInstructions::CreateMultisigProposal => instructions::multisig::process_create_multisig_proposal(accounts, instruction_data)?,
+        StakingInstructions::ApproveMultisigProposal => instructions::multisig::process_approve_multisig_proposal(accounts)?,
+        StakingInstructions::ExecuteMultisigProposal => instructions::multisig::process_execute_multisig_proposal(accounts)?,
+        StakingInstructions::UpdateMultisig => instructions::multisig::process_update_multisig(accounts, instruction_data)?,
+        StakingInstructions::InitTreasuryAccount => instructions::init_global_config::process_init_treasury_account(accounts)?,
+        StakingInstructions::WithdrawTreasury => instructions::init_global_config::process_withdraw_treasury(accounts, instruction_data)?,
+        StakingInstructions::ProposeAuthority => instructions::update_global_config::process_propose_authority(accounts, instruction_data)?
     };
 
     Ok(())
@@ -0,0 +1,77 @@
+use pinocchio::{account_info::AccountInfo, instruction::Signer, *};
+use pinocchio_token::{instructions::TransferChecked, state::Mint};
+
+use crate::error::StakingError;
+use crate::states::{global_config::GlobalConfig, staking_pool_account::{PenaltyDestinationEnum, StakingPool}};
+
+/// Pays a penalty held in the stake vault out to the pool's `penalty_destination`. Penalties routed to
+/// stakers stay in the vault as backing and go to the treasury instead once no LST is left.
+///
+/// ### Accounts:
+///   0. `[]` Stake token mint
+///   1. `[WRITE]` Stake token vault the penalty is held in
+///   2. `[SIGNER]` Global config PDA, owner of the vault
+///   3. `[WRITE]` Treasury for the stake mint, used when routed to the treasury
+///   4. `[WRITE]` Pool's reward vault, used when routed to the reward vault
+pub struct PenaltyPayout<'a> {
+    pub stake_token_mint: &'a AccountInfo,
+    pub stake_token_vault: &'a AccountInfo,
+    pub global_config_account: &'a AccountInfo,
+    pub treasury_account: &'a AccountInfo,
+    pub reward_token_vault: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl PenaltyPayout<'_> {
+    pub fn invoke_signed(
+        &self,
+        staking_pool: &mut StakingPool,
+        global_config: &GlobalConfig,
+        signers: &[Signer]
+    ) -> ProgramResult {
+        if self.amount == 0 {
+            return Ok(());
+        }
+
+        let mut destination = PenaltyDestinationEnum::try_from(&staking_pool.penalty_destination)?;
+
+        // With no holders left there is nobody for the exchange rate to reward
+        if matches!(destination, PenaltyDestinationEnum::Stakers) && staking_pool.liquid_stake_supply == 0 {
+            destination = PenaltyDestinationEnum::Treasury;
+        }
+
+        let to = match destination {
+            PenaltyDestinationEnum::Treasury => {
+                global_config.check_treasury(self.treasury_account, &staking_pool.stake_token_mint)?;
+                self.treasury_account
+            },
+            PenaltyDestinationEnum::Stakers => {
+                // Left in the vault as backing, raising the rate for remaining holders
+                staking_pool.total_underlying = staking_pool.total_underlying.saturating_add(self.amount);
+                return Ok(());
+            },
+            PenaltyDestinationEnum::RewardVault => {
+                if staking_pool.reward_token_mint != staking_pool.stake_token_mint {
+                    return Err(StakingError::InvalidMint.into());
+                }
+
+                if *self.reward_token_vault.key() != staking_pool.reward_token_vault {
+                    return Err(StakingError::InvalidVault.into());
+                }
+
+                self.reward_token_vault
+            },
+        };
+
+        let stake_mint_decimals = Mint::from_account_info(self.stake_token_mint)?.decimals();
+
+        TransferChecked {
+            from: self.stake_token_vault,
+            to,
+            mint: self.stake_token_mint,
+            authority: self.global_config_account,
+            amount: self.amount,
+            decimals: stake_mint_decimals,
+        }.invoke_signed(signers)
+    }
+}
@@ -0,0 +1,188 @@
+use pinocchio::{program_error::ProgramError, ProgramResult};
+
+use crate::states::{staking_pool_account::{RewardStream, StakingPool}, user_stake_account::StakePosition};
+
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Accrues emissions since `last_update_timestamp` into `accumulated_reward_per_share` and
+/// every reward stream's accumulator. Shares are units of boosted weight, see `total_reward_weight`.
+pub fn update_pool_rewards(pool: &mut StakingPool, current_timestamp: i64) -> ProgramResult {
+    if current_timestamp <= pool.last_update_timestamp {
+        return Ok(());
+    }
+
+    let total_weight = pool.total_reward_weight();
+
+    if total_weight == 0 {
+        pool.last_update_timestamp = current_timestamp;
+        return Ok(());
+    }
+
+    // A funded campaign emits nothing past its end
+    let emission_timestamp = if pool.reward_end_timestamp == 0 {
+        current_timestamp
+    } else {
+        current_timestamp.min(pool.reward_end_timestamp)
+    };
+
+    let time_elapsed = emission_timestamp.saturating_sub(pool.last_update_timestamp).max(0) as u128;
+
+    let reward_per_share = (pool.reward_rate_per_second as u128)
+        .saturating_mul(time_elapsed)
+        .saturating_mul(REWARD_PRECISION)
+        / total_weight as u128;
+
+    pool.accumulated_reward_per_share = pool.accumulated_reward_per_share
+        .saturating_add(reward_per_share);
+
+    let last_update_timestamp = pool.last_update_timestamp;
+    let stream_count = pool.reward_stream_count as usize;
+
+    for (index, stream) in pool.reward_streams[..stream_count].iter_mut().enumerate() {
+        let emitted = accrue_stream(stream, last_update_timestamp, current_timestamp, total_weight);
+        pool.reward_stream_emitted[index] = pool.reward_stream_emitted[index].saturating_add(emitted);
+    }
+
+    pool.last_update_timestamp = current_timestamp;
+
+    Ok(())
+}
+
+// Streams only emit inside their own [start_timestamp, end_timestamp) window, returns the amount emitted
+fn accrue_stream(stream: &mut RewardStream, from_timestamp: i64, to_timestamp: i64, total_weight: u64) -> u64 {
+    let from_timestamp = from_timestamp.max(stream.start_timestamp);
+    let to_timestamp = to_timestamp.min(stream.end_timestamp);
+
+    if to_timestamp <= from_timestamp {
+        return 0;
+    }
+
+    let emitted = (stream.reward_rate_per_second as u128)
+        .saturating_mul(to_timestamp.saturating_sub(from_timestamp) as u128);
+
+    let reward_per_share = emitted
+        .saturating_mul(REWARD_PRECISION)
+        / total_weight as u128;
+
+    stream.accumulated_reward_per_share = stream.accumulated_reward_per_share
+        .saturating_add(reward_per_share);
+
+    u64::try_from(emitted).unwrap_or(u64::MAX)
+}
+
+/// Rewards earned by `position` since its reward debt was last reset.
+pub fn pending_rewards(position: &StakePosition, pool: &StakingPool) -> Result<u64, ProgramError> {
+    if !position.is_active || position.staked_amount == 0 {
+        return Ok(0);
+    }
+
+    let accumulated = (position.reward_weight() as u128)
+        .saturating_mul(pool.accumulated_reward_per_share)
+        / REWARD_PRECISION;
+
+    u64::try_from(accumulated.saturating_sub(position.reward_debt))
+        .map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Rewards earned by `position` from reward stream `index` since its stream reward debt was last reset.
+pub fn pending_stream_rewards(position: &StakePosition, pool: &StakingPool, index: usize) -> Result<u64, ProgramError> {
+    if !position.is_active || position.staked_amount == 0 {
+        return Ok(0);
+    }
+
+    let accumulated = (position.reward_weight() as u128)
+        .saturating_mul(pool.reward_streams[index].accumulated_reward_per_share)
+        / REWARD_PRECISION;
+
+    // Debt taken against a stream that has since been replaced says nothing about the current one
+    let reward_debt = if position.stream_generations[index] == pool.reward_stream_generations[index] {
+        position.stream_reward_debt[index]
+    } else {
+        0
+    };
+
+    u64::try_from(accumulated.saturating_sub(reward_debt))
+        .map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Moves earned rewards into `position.pending_rewards` and `position.stream_pending_rewards`.
+/// Call after `update_pool_rewards` and before `staked_amount` changes. Returns the primary reward earned.
+pub fn settle_position_rewards(
+    position: &mut StakePosition,
+    pool: &mut StakingPool,
+    current_timestamp: i64
+) -> Result<u64, ProgramError> {
+    let earned = pending_rewards(position, pool)?;
+
+    position.pending_rewards = position.pending_rewards.saturating_add(earned);
+
+    for index in 0..pool.reward_stream_count as usize {
+        // Anything left over from a replaced stream is dust, it was paid in the old stream's mint
+        if position.stream_generations[index] != pool.reward_stream_generations[index] {
+            position.stream_pending_rewards[index] = 0;
+        }
+
+        let stream_earned = pending_stream_rewards(position, pool, index)?;
+        position.stream_pending_rewards[index] = position.stream_pending_rewards[index]
+            .saturating_add(stream_earned);
+    }
+
+    position.last_reward_update = current_timestamp;
+    reset_reward_debt(position, pool, current_timestamp);
+
+    Ok(earned)
+}
+
+/// Re-weighs the position and re-bases its reward debt once `staked_amount` or its lock has changed.
+/// The lock boost is dropped here once `lock_expiry` has passed.
+pub fn reset_reward_debt(position: &mut StakePosition, pool: &mut StakingPool, current_timestamp: i64) {
+    let boost_weight = if position.is_active && position.lock_exipry_enable && current_timestamp < position.lock_expiry {
+        // Positions staked before lock tiers existed carry the pool's single multiplier
+        let multiplier = if position.lock_multiplier == 0 {
+            pool.reward_multiplier
+        } else {
+            position.lock_multiplier
+        };
+
+        apply_reward_multiplier(position.staked_amount, multiplier).saturating_sub(position.staked_amount)
+    } else {
+        0
+    };
+
+    pool.total_boost_weight = pool.total_boost_weight
+        .saturating_sub(position.boost_weight)
+        .saturating_add(boost_weight);
+    position.boost_weight = boost_weight;
+
+    let weight = position.reward_weight() as u128;
+
+    position.reward_debt = weight
+        .saturating_mul(pool.accumulated_reward_per_share)
+        / REWARD_PRECISION;
+
+    for (index, stream) in pool.active_reward_streams().iter().enumerate() {
+        position.stream_reward_debt[index] = weight
+            .saturating_mul(stream.accumulated_reward_per_share)
+            / REWARD_PRECISION;
+        position.stream_generations[index] = pool.reward_stream_generations[index];
+    }
+}
+
+/// `amount` scaled by a multiplier in percent, 100 or below leaves it unchanged.
+pub fn apply_reward_multiplier(amount: u64, multiplier: u16) -> u64 {
+    if multiplier <= 100 {
+        return amount;
+    }
+
+    (amount as u128)
+        .saturating_mul(multiplier as u128)
+        .saturating_div(100u128) as u64
+}
+
+pub fn calculate_protocol_fee(amount: u64, fee_rate: u16) -> Result<u64, ProgramError> {
+    let fee = (amount as u128)
+        .saturating_mul(fee_rate as u128)
+        .saturating_div(10000u128) as u64;
+
+    Ok(fee)
+}
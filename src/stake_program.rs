@@ -0,0 +1,336 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction, Signer},
+    program::invoke_signed,
+    pubkey::Pubkey,
+    ProgramResult
+};
+use pinocchio_pubkey::pubkey;
+
+/// Native Stake program, called directly since pinocchio has no stake client.
+pub const ID: Pubkey = pubkey!("Stake11111111111111111111111111111111111111");
+pub const STAKE_CONFIG_ID: Pubkey = pubkey!("StakeConfig11111111111111111111111111111111");
+pub const STAKE_HISTORY_ID: Pubkey = pubkey!("SysvarStakeHistory1111111111111111111111111");
+pub const VOTE_PROGRAM_ID: Pubkey = pubkey!("Vote111111111111111111111111111111111111111");
+
+/// Size of a `StakeStateV2` account.
+pub const STAKE_ACCOUNT_SIZE: u64 = 200;
+
+// StakeInstruction discriminators, bincode encodes the enum tag as a u32
+const INITIALIZE: u32 = 0;
+const AUTHORIZE: u32 = 1;
+const DELEGATE_STAKE: u32 = 2;
+const SPLIT: u32 = 3;
+const WITHDRAW: u32 = 4;
+const DEACTIVATE: u32 = 5;
+const MERGE: u32 = 7;
+
+pub enum StakeAuthorize {
+    Staker,
+    Withdrawer,
+}
+
+/// Initializes a stake account with no lockup.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Stake account
+///   1. `[]` Rent sysvar
+pub struct InitializeStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub rent_sysvar: &'a AccountInfo,
+    pub staker: &'a Pubkey,
+    pub withdrawer: &'a Pubkey,
+}
+
+impl InitializeStake<'_> {
+    pub fn invoke(&self) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::readonly(self.rent_sysvar.key()),
+        ];
+
+        // -  [0..4  ]: discriminator
+        // -  [4..36 ]: staker
+        // -  [36..68]: withdrawer
+        // -  [68..116]: lockup (unix_timestamp, epoch, custodian), all zero
+        let mut instruction_data = [0u8; 116];
+        instruction_data[0..4].copy_from_slice(&INITIALIZE.to_le_bytes());
+        instruction_data[4..36].copy_from_slice(self.staker);
+        instruction_data[36..68].copy_from_slice(self.withdrawer);
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(&instruction, &[self.stake, self.rent_sysvar], &[])
+    }
+}
+
+/// Hands the staker or withdrawer role to `new_authority`.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Stake account
+///   1. `[]` Clock sysvar
+///   2. `[SIGNER]` Current authority
+pub struct AuthorizeStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub clock_sysvar: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+    pub new_authority: &'a Pubkey,
+    pub stake_authorize: StakeAuthorize,
+}
+
+impl AuthorizeStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::readonly(self.clock_sysvar.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        let stake_authorize = match self.stake_authorize {
+            StakeAuthorize::Staker => 0u32,
+            StakeAuthorize::Withdrawer => 1u32,
+        };
+
+        // -  [0..4  ]: discriminator
+        // -  [4..36 ]: new authority
+        // -  [36..40]: StakeAuthorize
+        let mut instruction_data = [0u8; 40];
+        instruction_data[0..4].copy_from_slice(&AUTHORIZE.to_le_bytes());
+        instruction_data[4..36].copy_from_slice(self.new_authority);
+        instruction_data[36..40].copy_from_slice(&stake_authorize.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(&instruction, &[self.stake, self.clock_sysvar, self.authority], signers)
+    }
+}
+
+/// Delegates the stake account to a vote account.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Stake account
+///   1. `[]` Vote account
+///   2. `[]` Clock sysvar
+///   3. `[]` Stake history sysvar
+///   4. `[]` Stake config account
+///   5. `[SIGNER]` Stake authority
+pub struct DelegateStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub vote: &'a AccountInfo,
+    pub clock_sysvar: &'a AccountInfo,
+    pub stake_history_sysvar: &'a AccountInfo,
+    pub stake_config: &'a AccountInfo,
+    pub stake_authority: &'a AccountInfo,
+}
+
+impl DelegateStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::readonly(self.vote.key()),
+            AccountMeta::readonly(self.clock_sysvar.key()),
+            AccountMeta::readonly(self.stake_history_sysvar.key()),
+            AccountMeta::readonly(self.stake_config.key()),
+            AccountMeta::readonly_signer(self.stake_authority.key()),
+        ];
+
+        let instruction_data = DELEGATE_STAKE.to_le_bytes();
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.stake,
+                self.vote,
+                self.clock_sysvar,
+                self.stake_history_sysvar,
+                self.stake_config,
+                self.stake_authority,
+            ],
+            signers
+        )
+    }
+}
+
+/// Moves `lamports` into an allocated, uninitialized stake account with the same delegation.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Source stake account
+///   1. `[WRITE]` Destination stake account
+///   2. `[SIGNER]` Stake authority
+pub struct SplitStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub split_stake: &'a AccountInfo,
+    pub stake_authority: &'a AccountInfo,
+    pub lamports: u64,
+}
+
+impl SplitStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::writable(self.split_stake.key()),
+            AccountMeta::readonly_signer(self.stake_authority.key()),
+        ];
+
+        // -  [0..4 ]: discriminator
+        // -  [4..12]: lamports
+        let mut instruction_data = [0u8; 12];
+        instruction_data[0..4].copy_from_slice(&SPLIT.to_le_bytes());
+        instruction_data[4..12].copy_from_slice(&self.lamports.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(&instruction, &[self.stake, self.split_stake, self.stake_authority], signers)
+    }
+}
+
+/// Withdraws unstaked lamports to any account, the source has to stay rent exempt.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Stake account
+///   1. `[WRITE]` Recipient
+///   2. `[]` Clock sysvar
+///   3. `[]` Stake history sysvar
+///   4. `[SIGNER]` Withdraw authority
+pub struct WithdrawStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub recipient: &'a AccountInfo,
+    pub clock_sysvar: &'a AccountInfo,
+    pub stake_history_sysvar: &'a AccountInfo,
+    pub withdraw_authority: &'a AccountInfo,
+    pub lamports: u64,
+}
+
+impl WithdrawStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::writable(self.recipient.key()),
+            AccountMeta::readonly(self.clock_sysvar.key()),
+            AccountMeta::readonly(self.stake_history_sysvar.key()),
+            AccountMeta::readonly_signer(self.withdraw_authority.key()),
+        ];
+
+        // -  [0..4 ]: discriminator
+        // -  [4..12]: lamports
+        let mut instruction_data = [0u8; 12];
+        instruction_data[0..4].copy_from_slice(&WITHDRAW.to_le_bytes());
+        instruction_data[4..12].copy_from_slice(&self.lamports.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.stake,
+                self.recipient,
+                self.clock_sysvar,
+                self.stake_history_sysvar,
+                self.withdraw_authority,
+            ],
+            signers
+        )
+    }
+}
+
+/// Starts cooling down a delegated stake account, it's inactive from the next epoch.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Stake account
+///   1. `[]` Clock sysvar
+///   2. `[SIGNER]` Stake authority
+pub struct DeactivateStake<'a> {
+    pub stake: &'a AccountInfo,
+    pub clock_sysvar: &'a AccountInfo,
+    pub stake_authority: &'a AccountInfo,
+}
+
+impl DeactivateStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.stake.key()),
+            AccountMeta::readonly(self.clock_sysvar.key()),
+            AccountMeta::readonly_signer(self.stake_authority.key()),
+        ];
+
+        let instruction_data = DEACTIVATE.to_le_bytes();
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(&instruction, &[self.stake, self.clock_sysvar, self.stake_authority], signers)
+    }
+}
+
+/// Merges `source` into `destination`, both need the same authorities and a compatible state.
+///
+/// ### Accounts:
+///   0. `[WRITE]` Destination stake account
+///   1. `[WRITE]` Source stake account, drained and closed
+///   2. `[]` Clock sysvar
+///   3. `[]` Stake history sysvar
+///   4. `[SIGNER]` Stake authority
+pub struct MergeStake<'a> {
+    pub destination: &'a AccountInfo,
+    pub source: &'a AccountInfo,
+    pub clock_sysvar: &'a AccountInfo,
+    pub stake_history_sysvar: &'a AccountInfo,
+    pub stake_authority: &'a AccountInfo,
+}
+
+impl MergeStake<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.destination.key()),
+            AccountMeta::writable(self.source.key()),
+            AccountMeta::readonly(self.clock_sysvar.key()),
+            AccountMeta::readonly(self.stake_history_sysvar.key()),
+            AccountMeta::readonly_signer(self.stake_authority.key()),
+        ];
+
+        let instruction_data = MERGE.to_le_bytes();
+
+        let instruction = Instruction {
+            program_id: &ID,
+            accounts: &account_metas,
+            data: &instruction_data,
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.destination,
+                self.source,
+                self.clock_sysvar,
+                self.stake_history_sysvar,
+                self.stake_authority,
+            ],
+            signers
+        )
+    }
+}
@@ -1,9 +1,16 @@
-use pinocchio::{account_info::{AccountInfo, Ref, RefMut}, program_error::ProgramError, pubkey::Pubkey, *};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, *};
 use shank::ShankAccount;
+use crate::error::StakingError;
 use crate::states::helper::AccountData;
 
+/// Capacity of the on-account pool registry, `max_pools` can't be configured above it.
+pub const MAX_POOLS: usize = 100;
+
+#[repr(C)]
 #[derive(Debug, Clone, ShankAccount)]
 pub struct GlobalConfig {
+    pub discriminator: [u8; 8],
+    pub version: u8,
     pub authority: Pubkey,
     pub treasury: Pubkey,
     pub protocol_fee_rate: u16,  
@@ -12,11 +19,63 @@ pub struct GlobalConfig {
     pub emergency_pause: bool, 
     pub total_pools_created: u64,
     pub active_pools: u64,
-    pub active_pool_keys: Vec<Pubkey>,
+    pub active_pool_keys: [Pubkey; 100], // MAX_POOLS, shank needs a literal length. First active_pools entries are in use
     pub bump: u8, 
     pub treasury_bump: u8,
+    pub config_id: Pubkey, // Seeds the config PDA, fixed for life so the authority can move
+    pub pending_authority: Pubkey, // Proposed authority until it accepts, default when none is pending
 }
 
 impl AccountData for GlobalConfig {
     const SIZE: usize = core::mem::size_of::<GlobalConfig>();
+    const DISCRIMINATOR: [u8; 8] = *b"GLBLCONF";
+    const VERSION: u8 = 1;
+}
+
+impl GlobalConfig {
+    /// Checks `account` is the config PDA of the id it stores, returning that id and the bump to sign with.
+    pub fn check_pda(account: &AccountInfo) -> Result<(Pubkey, u8), ProgramError> {
+        let global_config = Self::from_account_info(account)?;
+
+        let global_config_pda = pubkey::create_program_address(
+            &[b"global_config_account", global_config.config_id.as_ref(), &[global_config.bump]],
+            &crate::ID
+        ).map_err(|_| StakingError::InvalidPda)?;
+
+        if *account.key() != global_config_pda {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        Ok((global_config.config_id, global_config.bump))
+    }
+
+    /// Checks `account` is this config's treasury for `mint`, PDA of [b"treasury_account", mint, config_id].
+    /// The treasury created with the config is the one for its own mint, InitTreasuryAccount adds the rest.
+    pub fn check_treasury(&self, account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+        let (treasury_pda, _treasury_bump) = pubkey::find_program_address(
+            &[b"treasury_account", mint.as_ref(), self.config_id.as_ref()],
+            &crate::ID
+        );
+
+        if *account.key() != treasury_pda {
+            return Err(StakingError::InvalidTreasury.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn register_pool(&mut self, pool: Pubkey) -> ProgramResult {
+        let count = self.active_pools as usize;
+
+        if count >= self.max_pools as usize || count >= MAX_POOLS {
+            return Err(StakingError::PoolLimitReached.into());
+        }
+
+        self.active_pool_keys[count] = pool;
+        self.active_pools = self.active_pools
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
 }
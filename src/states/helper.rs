@@ -1,25 +1,89 @@
-use pinocchio::{account_info::{AccountInfo, Ref, RefMut}, program_error::ProgramError, pubkey::Pubkey, *};
+use pinocchio::{account_info::{AccountInfo, Ref, RefMut}, program_error::ProgramError, *};
+use crate::error::StakingError;
 
+/// Byte offset of the version that follows the 8-byte discriminator in every account.
+pub const VERSION_OFFSET: usize = 8;
+
+/// Every program account starts with `discriminator: [u8; 8]` followed by `version: u8`.
+///
+/// Layouts are append-only: a new version may only add fields at the end of the struct, so
+/// `MigrateAccount` can grow an old account in place and zero-fill the tail before `migrate`
+/// sets any non-zero defaults. Bump `VERSION` whenever `SIZE` changes.
 pub trait AccountData: Sized {
     const SIZE: usize;
-    
-    fn from_account_info(account: &AccountInfo) -> Result<Ref<Self>, ProgramError> {
-        if account.data_len() < Self::SIZE {
-            return Err(ProgramError::InvalidAccountData);
-        }
+    const DISCRIMINATOR: [u8; 8];
+    const VERSION: u8;
+
+    fn from_account_info(account: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        Self::check_account(account)?;
 
         Ok(Ref::map(account.try_borrow_data()?, |data| unsafe {
             &*(data.as_ptr() as *const Self)
         }))
     }
 
-    fn from_account_info_mut(account: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
+    fn from_account_info_mut(account: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        Self::check_account(account)?;
+
+        Ok(RefMut::map(account.try_borrow_mut_data()?, |data| unsafe {
+            &mut *(data.as_mut_ptr() as *mut Self)
+        }))
+    }
+
+    /// Writes the header into a freshly created account and hands back the zeroed struct.
+    fn initialize(account: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if !account.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
         if account.data_len() < Self::SIZE {
             return Err(ProgramError::InvalidAccountData);
         }
 
-        Ok(RefMut::map(account.try_borrow_mut_data()?, |data| unsafe {
+        let mut data = account.try_borrow_mut_data()?;
+
+        if data[..VERSION_OFFSET] != [0u8; 8] {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        data[..VERSION_OFFSET].copy_from_slice(&Self::DISCRIMINATOR);
+        data[VERSION_OFFSET] = Self::VERSION;
+
+        Ok(RefMut::map(data, |data| unsafe {
             &mut *(data.as_mut_ptr() as *mut Self)
         }))
     }
+
+    fn check_account(account: &AccountInfo) -> ProgramResult {
+        if !account.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let data = account.try_borrow_data()?;
+
+        if data.len() <= VERSION_OFFSET {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if data[..VERSION_OFFSET] != Self::DISCRIMINATOR {
+            return Err(StakingError::InvalidDiscriminator.into());
+        }
+
+        // Older layouts have to go through MigrateAccount first
+        if data[VERSION_OFFSET] != Self::VERSION {
+            return Err(StakingError::AccountNeedsMigration.into());
+        }
+
+        if data.len() < Self::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    /// Upgrades `data` (already resized to `SIZE`) from `from_version` to `VERSION`.
+    /// Appended fields are zero when this runs; override to set other defaults.
+    fn migrate(_data: &mut [u8], _from_version: u8) -> ProgramResult {
+        Ok(())
+    }
 }
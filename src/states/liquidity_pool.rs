@@ -0,0 +1,92 @@
+use pinocchio::pubkey::Pubkey;
+use shank::ShankAccount;
+use crate::states::helper::AccountData;
+
+/// Liquidity for instant unstakes from an SPL pool, PDA of [b"liquidity_pool", staking_pool].
+///
+/// LPs deposit stake tokens into `liquidity_vault` for LP shares. InstantUnstake pays users out of
+/// the vault right away, less a fee, and the burned LST's backing follows from stake_token_vault once
+/// the pool's cooldown has passed. LP shares are worth `liquidity` plus that pending backing, never the
+/// raw vault balance, so tokens sent straight to the vault can't move the share price.
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct LiquidityPool {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub lp_mint: Pubkey, // PDA of [b"lp_mint", staking_pool], minted by the global config
+    pub liquidity_vault: Pubkey, // PDA of [b"liquidity_vault", staking_pool], holds stake tokens
+    pub lp_supply: u64,
+    pub liquidity: u64, // Stake tokens in liquidity_vault that belong to the LPs
+    pub pending_underlying: u64, // Oldest batch owed from stake_token_vault for instantly unstaked LST
+    pub pending_unlock_timestamp: i64, // When pending_underlying can be moved into the vault, never pushed back
+    pub next_pending_underlying: u64, // Unstaked while the oldest batch waits, promoted once it's moved
+    pub next_pending_unlock_timestamp: i64, // Cooldown end of the latest unstake in the next batch
+    pub target_liquidity: u64, // Vault balance at or above which unstakes pay min_fee_bps
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16, // Charged when an unstake empties the vault
+    pub total_fees_earned: u64,
+    pub bump: u8,
+}
+
+impl AccountData for LiquidityPool {
+    const SIZE: usize = core::mem::size_of::<LiquidityPool>();
+    const DISCRIMINATOR: [u8; 8] = *b"LIQDPOOL";
+    const VERSION: u8 = 1;
+}
+
+impl LiquidityPool {
+    /// Fee for an unstake that leaves `liquidity_after` in the vault, falling linearly from
+    /// `max_fee_bps` at an empty vault to `min_fee_bps` at `target_liquidity`.
+    pub fn unstake_fee_bps(&self, liquidity_after: u64) -> u16 {
+        if liquidity_after >= self.target_liquidity {
+            return self.min_fee_bps;
+        }
+
+        let spread = self.max_fee_bps.saturating_sub(self.min_fee_bps) as u128;
+        let discount = spread
+            .saturating_mul(liquidity_after as u128)
+            / self.target_liquidity as u128;
+
+        self.max_fee_bps.saturating_sub(discount as u16)
+    }
+
+    /// Underlying the LP shares are backed by.
+    pub fn total_value(&self) -> u64 {
+        self.liquidity
+            .saturating_add(self.pending_underlying)
+            .saturating_add(self.next_pending_underlying)
+    }
+
+    /// Queues backing owed for an instant unstake. The oldest batch keeps its unlock time so a
+    /// steady stream of unstakes can't hold it back, later ones collect in the next batch.
+    pub fn queue_pending(&mut self, amount: u64, unlock_timestamp: i64) {
+        if self.pending_underlying == 0 {
+            self.pending_underlying = amount;
+            self.pending_unlock_timestamp = unlock_timestamp;
+        } else {
+            self.next_pending_underlying = self.next_pending_underlying.saturating_add(amount);
+            self.next_pending_unlock_timestamp = unlock_timestamp;
+        }
+    }
+
+    /// Whether the oldest batch has finished its cooldown.
+    pub fn has_unlocked(&self, current_timestamp: i64) -> bool {
+        self.pending_underlying > 0 && current_timestamp >= self.pending_unlock_timestamp
+    }
+
+    /// Takes every batch that has finished its cooldown, promoting the next batch as the oldest one goes.
+    pub fn take_unlocked(&mut self, current_timestamp: i64) -> u64 {
+        let mut amount = 0u64;
+
+        while self.has_unlocked(current_timestamp) {
+            amount = amount.saturating_add(self.pending_underlying);
+            self.pending_underlying = self.next_pending_underlying;
+            self.pending_unlock_timestamp = self.next_pending_unlock_timestamp;
+            self.next_pending_underlying = 0;
+            self.next_pending_unlock_timestamp = 0;
+        }
+
+        amount
+    }
+}
@@ -2,4 +2,12 @@ pub mod global_config;
 pub mod helper;
 pub mod staking_pool_account;
 pub mod oracle_config;
-pub mod user_stake_account;
\ No newline at end of file
+pub mod user_stake_account;
+pub mod withdrawal_ticket;
+pub mod slashing;
+pub mod validator_list;
+pub mod liquidity_pool;
+pub mod swap_pool;
+pub mod roles;
+pub mod pool_config_change;
+pub mod multisig;
\ No newline at end of file
@@ -0,0 +1,134 @@
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+use shank::{ShankAccount, ShankType};
+use crate::error::StakingError;
+use crate::states::helper::AccountData;
+
+/// Members a multisig can have.
+pub const MAX_MULTISIG_MEMBERS: usize = 10;
+
+/// Longest StakingInstructions call a proposal can carry, discriminator included.
+pub const MAX_PROPOSAL_DATA: usize = 256;
+
+/// Accounts a proposed call can take.
+pub const MAX_PROPOSAL_ACCOUNTS: usize = 16;
+
+/// M-of-N approver set for a global config, PDA of [b"multisig", global_config].
+///
+/// The multisig acts by becoming the config authority: propose this PDA with ProposeAuthority,
+/// then execute an AcceptAuthority proposal. Executed proposals reach the program's handlers
+/// through a CPI signed by this PDA.
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct Multisig {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub global_config: Pubkey,
+    pub threshold: u8, // Approvals a proposal needs before it can execute
+    pub member_count: u8, // First member_count entries are in use
+    pub members: [Pubkey; 10], // MAX_MULTISIG_MEMBERS, shank needs a literal length
+    pub member_set_seq: u64, // Bumped whenever members change, proposals from an older set can't pass
+    pub proposal_count: u64, // Index of the next proposal
+    pub bump: u8,
+}
+
+impl AccountData for Multisig {
+    const SIZE: usize = core::mem::size_of::<Multisig>();
+    const DISCRIMINATOR: [u8; 8] = *b"MULTISIG";
+    const VERSION: u8 = 1;
+}
+
+impl Multisig {
+    pub fn active_members(&self) -> &[Pubkey] {
+        &self.members[..self.member_count as usize]
+    }
+
+    pub fn member_index(&self, member: &Pubkey) -> Result<usize, ProgramError> {
+        self.active_members()
+            .iter()
+            .position(|key| key == member)
+            .ok_or(StakingError::NotMultisigMember.into())
+    }
+
+    /// Replaces the member set, rejecting duplicates and thresholds it can't meet.
+    pub fn set_members(&mut self, threshold: u8, members: &[Pubkey]) -> Result<(), ProgramError> {
+        if members.is_empty() || members.len() > MAX_MULTISIG_MEMBERS {
+            return Err(StakingError::InvalidMultisigMembers.into());
+        }
+
+        for (index, member) in members.iter().enumerate() {
+            if members[..index].contains(member) {
+                return Err(StakingError::InvalidMultisigMembers.into());
+            }
+        }
+
+        if threshold == 0 || threshold as usize > members.len() {
+            return Err(StakingError::InvalidMultisigThreshold.into());
+        }
+
+        self.members = [Pubkey::default(); MAX_MULTISIG_MEMBERS];
+        self.members[..members.len()].copy_from_slice(members);
+        self.member_count = members.len() as u8;
+        self.threshold = threshold;
+        self.member_set_seq = self.member_set_seq.saturating_add(1);
+
+        Ok(())
+    }
+}
+
+/// A StakingInstructions call waiting on multisig approvals,
+/// PDA of [b"multisig_proposal", multisig, proposal_index].
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct MultisigProposal {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub multisig: Pubkey,
+    pub proposer: Pubkey,
+    pub proposal_index: u64,
+    pub member_set_seq: u64, // Multisig member set the approvals belong to
+    pub approvals: u16, // Bit per index into the multisig's members
+    pub executed: bool,
+    pub instruction_data_len: u16, // First instruction_data_len bytes of instruction_data are in use
+    pub instruction_data: [u8; 256], // MAX_PROPOSAL_DATA, shank needs a literal length
+    pub account_count: u8, // First account_count entries are in use
+    pub accounts: [ProposalAccount; 16], // MAX_PROPOSAL_ACCOUNTS, shank needs a literal length
+    pub created_timestamp: i64,
+    pub executed_timestamp: i64,
+    pub bump: u8,
+}
+
+/// An account the proposed call takes, in order.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, ShankType)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+impl AccountData for MultisigProposal {
+    const SIZE: usize = core::mem::size_of::<MultisigProposal>();
+    const DISCRIMINATOR: [u8; 8] = *b"MSIGPROP";
+    const VERSION: u8 = 1;
+}
+
+impl MultisigProposal {
+    pub fn approval_count(&self) -> u8 {
+        self.approvals.count_ones() as u8
+    }
+
+    pub fn approve(&mut self, member_index: usize) -> Result<(), ProgramError> {
+        let mask = 1u16 << member_index;
+
+        if self.approvals & mask != 0 {
+            return Err(StakingError::AlreadyApproved.into());
+        }
+
+        self.approvals |= mask;
+
+        Ok(())
+    }
+
+    pub fn proposal_accounts(&self) -> &[ProposalAccount] {
+        &self.accounts[..self.account_count as usize]
+    }
+}
@@ -1,17 +1,23 @@
-use pinocchio::{account_info::{AccountInfo, Ref, RefMut}, program_error::ProgramError, pubkey::Pubkey, *};
+use pinocchio::pubkey::Pubkey;
 use shank::ShankAccount;
 use crate::states::helper::AccountData;
 
+#[repr(C)]
 #[derive(Debug, Clone, ShankAccount)]
 pub struct OracleConfigInfo {
+    pub discriminator: [u8; 8],
+    pub version: u8,
     pub price_feed_account: Pubkey,
     pub update_frequency_seconds: i64,
     pub oracle_authority: Pubkey,
     pub last_update_timestamp: i64,
     pub current_price: u64,
     pub oracle_account_bump: u8,
+    pub global_config: Pubkey, // Config whose oracle managers push prices
 }
 
 impl AccountData for OracleConfigInfo {
     const SIZE: usize = core::mem::size_of::<OracleConfigInfo>();
+    const DISCRIMINATOR: [u8; 8] = *b"ORACLCFG";
+    const VERSION: u8 = 1;
 }
\ No newline at end of file
@@ -0,0 +1,33 @@
+use pinocchio::pubkey::Pubkey;
+use shank::ShankAccount;
+use crate::states::helper::AccountData;
+
+/// Longest UpdatePoolConfig payload a queued change stores, pool_id included.
+pub const MAX_CONFIG_CHANGE_DATA: usize = 40;
+
+/// A risky UpdatePoolConfig change waiting out the pool's delay,
+/// PDA of [b"pool_config_change", staking_pool, update_type].
+///
+/// One change per parameter can be queued at a time. Executing or cancelling it closes the account
+/// and returns the rent to `authority`.
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct PoolConfigChange {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub authority: Pubkey, // Queued the change and paid its rent
+    pub update_type: u8, // UpdatePoolConfig discriminator
+    pub update_data: [u8; 40], // MAX_CONFIG_CHANGE_DATA, shank needs a literal length
+    pub update_data_len: u8, // First update_data_len bytes of update_data are in use
+    pub price_feed_account: Pubkey, // Value of a queued PriceFeedAccount change
+    pub queued_timestamp: i64,
+    pub execute_after: i64,
+    pub bump: u8,
+}
+
+impl AccountData for PoolConfigChange {
+    const SIZE: usize = core::mem::size_of::<PoolConfigChange>();
+    const DISCRIMINATOR: [u8; 8] = *b"CFGCHNGE";
+    const VERSION: u8 = 1;
+}
@@ -0,0 +1,168 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, *};
+use shank::{ShankAccount, ShankType};
+use crate::error::StakingError;
+use crate::states::{global_config::GlobalConfig, helper::AccountData};
+
+/// Keys a roles account can hold roles for.
+pub const MAX_ROLE_MEMBERS: usize = 16;
+
+/// Admin roles the global config authority hands out, PDA of [b"roles", global_config].
+///
+/// The config authority holds every role without an entry here, so a config with no roles
+/// account keeps working as before.
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct Roles {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub global_config: Pubkey,
+    pub member_count: u8, // First member_count entries are in use
+    pub members: [RoleMember; 16], // MAX_ROLE_MEMBERS, shank needs a literal length
+    pub bump: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, ShankType)]
+pub struct RoleMember {
+    pub member: Pubkey,
+    pub roles: u8, // Bit per RoleEnum
+}
+
+impl AccountData for Roles {
+    const SIZE: usize = core::mem::size_of::<Roles>();
+    const DISCRIMINATOR: [u8; 8] = *b"ROLESACC";
+    const VERSION: u8 = 1;
+}
+
+impl Roles {
+    pub fn active_members(&self) -> &[RoleMember] {
+        &self.members[..self.member_count as usize]
+    }
+
+    pub fn has_role(&self, member: &Pubkey, role: RoleEnum) -> bool {
+        self.active_members()
+            .iter()
+            .any(|entry| entry.member == *member && entry.roles & role.mask() != 0)
+    }
+
+    pub fn grant(&mut self, member: &Pubkey, role: RoleEnum) -> ProgramResult {
+        let count = self.member_count as usize;
+
+        if let Some(entry) = self.members[..count].iter_mut().find(|entry| entry.member == *member) {
+            entry.roles |= role.mask();
+            return Ok(());
+        }
+
+        if count >= MAX_ROLE_MEMBERS {
+            return Err(StakingError::RoleMembersFull.into());
+        }
+
+        self.members[count] = RoleMember { member: *member, roles: role.mask() };
+        self.member_count += 1;
+
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, member: &Pubkey, role: RoleEnum) -> ProgramResult {
+        let count = self.member_count as usize;
+
+        let index = self.members[..count]
+            .iter()
+            .position(|entry| entry.member == *member && entry.roles & role.mask() != 0)
+            .ok_or(StakingError::RoleNotHeld)?;
+
+        self.members[index].roles &= !role.mask();
+
+        // Members left without a role give their slot back
+        if self.members[index].roles == 0 {
+            self.members[index] = self.members[count - 1];
+            self.members[count - 1] = RoleMember::default();
+            self.member_count -= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Checks `member` signed and holds `role` under the config at `global_config_account`.
+    pub fn check_role(
+        global_config_account: &AccountInfo,
+        roles_account: &AccountInfo,
+        member: &AccountInfo,
+        role: RoleEnum
+    ) -> ProgramResult {
+        if !member.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        GlobalConfig::check_pda(global_config_account)?;
+
+        if GlobalConfig::from_account_info(global_config_account)?.authority == *member.key() {
+            return Ok(());
+        }
+
+        let roles = Roles::from_account_info(roles_account)?;
+
+        if roles.global_config != *global_config_account.key() {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        if !roles.has_role(member.key(), role) {
+            return Err(StakingError::MissingRole.into());
+        }
+
+        Ok(())
+    }
+
+    /// Like `check_role`, for a pool that has to be registered under that config.
+    pub fn check_pool_role(
+        global_config_account: &AccountInfo,
+        roles_account: &AccountInfo,
+        member: &AccountInfo,
+        staking_pool: &Pubkey,
+        role: RoleEnum
+    ) -> ProgramResult {
+        Self::check_role(global_config_account, roles_account, member, role)?;
+
+        let global_config = GlobalConfig::from_account_info(global_config_account)?;
+
+        if !global_config.active_pool_keys[..global_config.active_pools as usize].contains(staking_pool) {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum RoleEnum {
+    FeeManager,    // Sets the protocol fee
+    PoolCreator,   // Creates staking pools
+    Guardian,      // Pauses and resumes pools
+    OracleManager, // Creates oracle configs and pushes prices
+    RewardFunder,  // Funds reward campaigns
+    Slasher,       // Opens and resolves penalties, manages slash reporters
+    TreasuryManager // Withdraws collected fees from the treasuries
+}
+
+impl RoleEnum {
+    pub fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl TryFrom<&u8> for RoleEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RoleEnum::FeeManager),
+            1 => Ok(RoleEnum::PoolCreator),
+            2 => Ok(RoleEnum::Guardian),
+            3 => Ok(RoleEnum::OracleManager),
+            4 => Ok(RoleEnum::RewardFunder),
+            5 => Ok(RoleEnum::Slasher),
+            6 => Ok(RoleEnum::TreasuryManager),
+            _ => Err(StakingError::InvalidRole.into())
+        }
+    }
+}
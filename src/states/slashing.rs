@@ -0,0 +1,42 @@
+use pinocchio::pubkey::Pubkey;
+use shank::ShankAccount;
+use crate::states::helper::AccountData;
+
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct SlashReporter {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub reporter: Pubkey,
+    pub registered_timestamp: i64,
+    pub reports_filed: u64,
+    pub bump: u8,
+}
+
+impl AccountData for SlashReporter {
+    const SIZE: usize = core::mem::size_of::<SlashReporter>();
+    const DISCRIMINATOR: [u8; 8] = *b"SLSHRPTR";
+    const VERSION: u8 = 1;
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct SlashEvidence {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub reporter: Pubkey,
+    pub slash_round: u64, // StakingPool::slash_round the report counts towards
+    pub condition_type: u8, //SlashTypeEnum
+    pub evidence_hash: [u8; 32], // Hash of the off-chain proof
+    pub timestamp: i64,
+    pub bump: u8,
+    pub validator: Pubkey, // Vote account the report is against, default for SplToken pools
+}
+
+impl AccountData for SlashEvidence {
+    const SIZE: usize = core::mem::size_of::<SlashEvidence>();
+    const DISCRIMINATOR: [u8; 8] = *b"SLSHEVID";
+    const VERSION: u8 = 1;
+}
@@ -1,9 +1,41 @@
-use pinocchio::{account_info::{AccountInfo, Ref, RefMut}, program_error::ProgramError, pubkey::Pubkey, *};
-use shank::ShankAccount;
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey, *};
+use shank::{ShankAccount, ShankType};
+use crate::error::StakingError;
 use crate::states::helper::AccountData;
 
+/// Reward streams a pool can run next to its primary reward token.
+pub const MAX_REWARD_STREAMS: usize = 4;
+
+/// Unclaimed rewards a finished stream may still hold and have its slot reused, rounding left
+/// behind by per-position settlement.
+pub const REWARD_STREAM_DUST: u64 = 1000;
+
+/// Lock tiers a pool can offer stakers.
+pub const MAX_LOCK_TIERS: usize = 4;
+
+/// Lock tier index for an unlocked stake.
+pub const NO_LOCK_TIER: u8 = u8::MAX;
+
+/// Largest share of compounded rewards a pool can pay auto-compound executors.
+pub const MAX_COMPOUND_TIP_BPS: u16 = 1000;
+
+/// Loosest slippage bound a pool can set for compound swaps.
+pub const MAX_SWAP_SLIPPAGE_BPS: u16 = 1000;
+
+/// Fixed-point scale of `swap_reference_rate`.
+pub const SWAP_RATE_PRECISION: u128 = 1_000_000_000;
+
+/// How long after a slash holders can leave through EmergencyWithdraw, seven days.
+pub const SLASH_EMERGENCY_WINDOW: i64 = 604800;
+
+/// Shortest wait between queueing a risky config change and executing it, one day.
+pub const MIN_CONFIG_CHANGE_DELAY: i64 = 86400;
+
+#[repr(C)]
 #[derive(Debug, Clone, ShankAccount)]
 pub struct StakingPool {
+    pub discriminator: [u8; 8],
+    pub version: u8,
     pub authority: Pubkey,
     pub pool_id: u64,
     pub creation_timestamp: i64,
@@ -16,6 +48,7 @@ pub struct StakingPool {
     pub total_reward_distributed: u64,
     pub reward_rate_per_second: u64, //u
     pub accumulated_reward_per_share: u128, //u
+    pub last_update_timestamp: i64, // Last time accumulated_reward_per_share was brought up to date
     pub lock_period_enabled: bool, //u
     pub lock_period_duration: i64, //u
     pub reward_multiplier: u16, //u
@@ -24,14 +57,141 @@ pub struct StakingPool {
     pub slashing_condition_type: u8, //SlashTypeEnum //u
     pub slash_percentage: u16, //u
     pub min_evidence_required: u8, //u
+    pub slash_round: u64, // Bumped after every executed slash, evidence is filed per round
+    pub evidence_count: u8, // Distinct reports filed in the current slash_round
+    pub total_slashed: u64,
+    pub last_slash_timestamp: i64,
     pub cooldown_period: i64, //u
+    pub penalty_destination: u8, //PenaltyDestinationEnum //u
+    pub penalty_grace_period: i64, //u Seconds a user has to dispute an opened penalty
+    pub penalty_recovery_bps: u16, //u Share of an upheld penalty restored after its recovery period
     pub price_feed_account: Pubkey, //u
     pub maximum_stake_limit: u64, //u
     pub minimum_stake_amount: u64, //u
     pub liquid_stake_mint: Pubkey, 
     pub liquid_stake_supply: u64,
+    pub total_underlying: u64, // Underlying tokens backing liquid_stake_supply
+    pub total_pending_unstake: u64, // Underlying held in the vault for open withdrawal tickets
+    pub penalty_reserve: u64, // Collected penalty debt held in stake_token_vault until the penalties it was held for are recovered
     pub emergency_pause_flag: bool, 
     pub stake_pool_bump: u8,
+    pub pool_type: u8, // PoolTypeEnum
+    pub validator_list: Pubkey, // ValidatorList of a NativeSol pool
+    pub last_balance_update_epoch: u64, // Epoch UpdatePoolBalance last ran for a NativeSol pool
+    pub reward_stream_count: u8, // First reward_stream_count entries are in use
+    pub reward_streams: [RewardStream; 4], // MAX_REWARD_STREAMS, shank needs a literal length
+    pub reward_end_timestamp: i64, // Primary emission stops here, 0 while the rate is set by hand
+    pub lock_tier_count: u8, // First lock_tier_count entries are offered, by ascending duration
+    pub lock_tiers: [LockTier; 4], // MAX_LOCK_TIERS, shank needs a literal length
+    pub total_boost_weight: u64, // Sum of every position's boost_weight
+    pub compound_tip_bps: u16, // Share of compounded rewards paid to the ExecuteAutoCompound executor
+    pub compound_min_frequency_hours: u32, // Lowest compound_frequency_hours a position may pick
+    pub compound_min_amount: u64, // Lowest min_compound_amount a position may pick
+    pub swap_adapter_type: u8, // SwapAdapterEnum, how cross-mint rewards are swapped when compounding
+    pub swap_adapter: Pubkey, // Adapter program or SwapPool account, per swap_adapter_type
+    pub swap_max_slippage_bps: u16, // Largest shortfall from swap_reference_rate a compound swap accepts
+    pub config_change_delay: i64, // Wait before a queued config change can execute, 0 for MIN_CONFIG_CHANGE_DELAY
+    pub reward_stream_emitted: [u64; 4], // Emitted by each reward stream so far, MAX_REWARD_STREAMS
+    pub reward_stream_generations: [u32; 4], // Bumped when a finished stream's slot is reused, MAX_REWARD_STREAMS
+    pub swap_reference_rate: u64, // Stake base units per reward base unit, scaled by SWAP_RATE_PRECISION. Floors compound swaps
+}
+
+/// A lock a staker can pick, boosting their reward weight to `multiplier` percent until it expires.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, ShankType)]
+pub struct LockTier {
+    pub duration: i64,
+    pub multiplier: u16,
+}
+
+/// An extra reward token emitted to stakers between `start_timestamp` and `end_timestamp`,
+/// paid from a vault PDA of [b"reward_stream_vault", staking_pool, reward_mint].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, ShankType)]
+pub struct RewardStream {
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+    pub reward_rate_per_second: u64,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub accumulated_reward_per_share: u128,
+    pub total_funded: u64,
+    pub total_distributed: u64,
+}
+
+impl StakingPool {
+    /// Seconds a queued config change waits, never shorter than MIN_CONFIG_CHANGE_DELAY.
+    pub fn config_change_delay(&self) -> i64 {
+        self.config_change_delay.max(MIN_CONFIG_CHANGE_DELAY)
+    }
+
+    /// LST minted for `underlying_amount`, rounded down so the pool never over-mints.
+    pub fn underlying_to_lst(&self, underlying_amount: u64) -> Result<u64, ProgramError> {
+        if self.liquid_stake_supply == 0 {
+            return Ok(underlying_amount);
+        }
+
+        // Outstanding LST with nothing backing it, minting now would hand the deposit to existing holders
+        if self.total_underlying == 0 {
+            return Err(StakingError::EmptyPoolBacking.into());
+        }
+
+        let lst_amount = (underlying_amount as u128)
+            .checked_mul(self.liquid_stake_supply as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            / self.total_underlying as u128;
+
+        u64::try_from(lst_amount).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    /// Underlying released for `lst_amount`, rounded down so the pool never over-pays.
+    pub fn lst_to_underlying(&self, lst_amount: u64) -> Result<u64, ProgramError> {
+        if self.liquid_stake_supply == 0 {
+            return Ok(lst_amount);
+        }
+
+        let underlying_amount = (lst_amount as u128)
+            .checked_mul(self.total_underlying as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            / self.liquid_stake_supply as u128;
+
+        u64::try_from(underlying_amount).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    /// Weight rewards are split over, staked tokens plus the boost of locked positions.
+    pub fn total_reward_weight(&self) -> u64 {
+        self.total_staked.saturating_add(self.total_boost_weight)
+    }
+
+    pub fn active_lock_tiers(&self) -> &[LockTier] {
+        &self.lock_tiers[..self.lock_tier_count as usize]
+    }
+
+    pub fn active_reward_streams(&self) -> &[RewardStream] {
+        &self.reward_streams[..self.reward_stream_count as usize]
+    }
+
+    /// Whether reward stream `index` has ended and paid out everything it emitted, so its slot can take a new stream.
+    pub fn is_reward_stream_finished(&self, index: usize, current_timestamp: i64) -> bool {
+        let stream = &self.reward_streams[index];
+
+        if current_timestamp < stream.end_timestamp {
+            return false;
+        }
+
+        // Emissions past what was funded can never be paid out of the vault
+        let claimable = self.reward_stream_emitted[index].min(stream.total_funded);
+
+        claimable.saturating_sub(stream.total_distributed) <= REWARD_STREAM_DUST
+    }
+
+    pub fn check_pool_type(&self, pool_type: PoolTypeEnum) -> ProgramResult {
+        if self.pool_type != pool_type as u8 {
+            return Err(StakingError::WrongPoolType.into());
+        }
+
+        Ok(())
+    }
 }
 
 pub enum PoolStatusEnum {
@@ -78,6 +238,63 @@ impl TryFrom<&u8> for SlashTypeEnum {
     }
 }
 
+pub enum PenaltyDestinationEnum {
+    Treasury,
+    Stakers,
+    RewardVault
+}
+
+impl TryFrom<&u8> for PenaltyDestinationEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PenaltyDestinationEnum::Treasury),
+            1 => Ok(PenaltyDestinationEnum::Stakers),
+            2 => Ok(PenaltyDestinationEnum::RewardVault),
+            _ => Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+pub enum PoolTypeEnum {
+    SplToken,  // Stakes stake_token_mint into stake_token_vault
+    NativeSol  // Stakes SOL through the Stake program
+}
+
+impl TryFrom<&u8> for PoolTypeEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PoolTypeEnum::SplToken),
+            1 => Ok(PoolTypeEnum::NativeSol),
+            _ => Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+pub enum SwapAdapterEnum {
+    None,            // Cross-mint rewards can't be compounded
+    Program,         // CPI into swap_adapter, an adapter program in front of an external AMM
+    ConstantProduct  // Swap against the protocol's SwapPool at swap_adapter
+}
+
+impl TryFrom<&u8> for SwapAdapterEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SwapAdapterEnum::None),
+            1 => Ok(SwapAdapterEnum::Program),
+            2 => Ok(SwapAdapterEnum::ConstantProduct),
+            _ => Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
 impl AccountData for StakingPool {
     const SIZE: usize = core::mem::size_of::<StakingPool>();
+    const DISCRIMINATOR: [u8; 8] = *b"STKPOOL_";
+    const VERSION: u8 = 1;
 }
\ No newline at end of file
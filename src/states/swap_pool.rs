@@ -0,0 +1,45 @@
+use pinocchio::pubkey::Pubkey;
+use shank::ShankAccount;
+use crate::states::helper::AccountData;
+
+/// Protocol-owned constant-product pool a staking pool can compound rewards through,
+/// PDA of [b"swap_pool", staking_pool].
+///
+/// Reserves are token accounts owned by the global config at [b"swap_reserve", swap_pool, mint].
+/// Anyone can top them up with a plain transfer, the pool authority withdraws with WithdrawSwapReserve.
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct SwapPool {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub reward_reserve: Pubkey, // Holds the staking pool's reward token
+    pub stake_reserve: Pubkey, // Holds the staking pool's stake token
+    pub fee_bps: u16, // Kept in the reserves on every swap
+    pub total_swapped_in: u64,
+    pub total_swapped_out: u64,
+    pub bump: u8,
+}
+
+impl AccountData for SwapPool {
+    const SIZE: usize = core::mem::size_of::<SwapPool>();
+    const DISCRIMINATOR: [u8; 8] = *b"SWAPPOOL";
+    const VERSION: u8 = 1;
+}
+
+impl SwapPool {
+    /// Output for `amount_in` after the fee, x * y = k over the current reserves.
+    pub fn quote(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+        let amount_in_after_fee = (amount_in as u128)
+            .saturating_mul(10000u128.saturating_sub(self.fee_bps as u128))
+            / 10000u128;
+
+        let denominator = (reserve_in as u128).saturating_add(amount_in_after_fee);
+
+        if denominator == 0 {
+            return 0;
+        }
+
+        ((reserve_out as u128).saturating_mul(amount_in_after_fee) / denominator) as u64
+    }
+}
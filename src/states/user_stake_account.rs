@@ -1,14 +1,14 @@
-use std::default;
-
 use pinocchio::{program_error::ProgramError, pubkey::Pubkey, *};
+use crate::error::StakingError;
 use crate::states::helper::AccountData;
 
-pub const MAX_POSITIONS: usize = 10;
 pub const MAX_HISTORY: usize = 10;
 
 #[repr(C)]
 #[derive(Clone, Debug)]
 pub struct UserStakeAccount {
+    pub discriminator: [u8; 8],
+    pub version: u8,
     pub owner: Pubkey,
     pub global_config: Pubkey,
     pub user_token_account: Pubkey,
@@ -20,7 +20,7 @@ pub struct UserStakeAccount {
 
     pub active_positions: u8,
     pub is_paused: bool,
-    pub positions: [StakePosition; MAX_POSITIONS], //A user can have multiple stake account so we keep the track of them inside the positions
+    pub next_position_index: u64, // Seed of the next StakePosition PDA, positions are never reused
 
     pub total_earned: u64,
     pub total_claimed: u64,
@@ -31,6 +31,7 @@ pub struct UserStakeAccount {
 
     pub total_penalties: u64,
     pub active_penalties: u64,
+    pub next_penalty_id: u64,
     pub penalty_type_count: u8, // ( PenaltyType ) total penalty type count can be 4
     pub penalty_history: [PenaltyEvent; MAX_HISTORY],
 
@@ -39,11 +40,96 @@ pub struct UserStakeAccount {
 
 impl AccountData for UserStakeAccount {
     const SIZE: usize = core::mem::size_of::<UserStakeAccount>();
+    const DISCRIMINATOR: [u8; 8] = *b"USRSTAKE";
+    const VERSION: u8 = 2;
+}
+
+impl UserStakeAccount {
+    /// Assigns the next penalty id and appends the event, dropping the oldest entry that
+    /// no longer needs tracking. Fails if every entry is still open or awaiting recovery.
+    pub fn record_penalty(&mut self, mut event: PenaltyEvent) -> Result<u64, ProgramError> {
+        let evict_idx = self.penalty_history
+            .iter()
+            .position(|entry| entry.is_settled())
+            .ok_or(StakingError::PenaltyHistoryFull)?;
+
+        event.penalty_id = self.next_penalty_id;
+        self.next_penalty_id = self.next_penalty_id.saturating_add(1);
+
+        for i in evict_idx + 1..MAX_HISTORY {
+            self.penalty_history[i - 1] = self.penalty_history[i];
+        }
+        self.penalty_history[MAX_HISTORY - 1] = event;
+
+        Ok(event.penalty_id)
+    }
+
+    /// Penalties opened against `stake_position` that haven't been upheld or overturned yet.
+    pub fn open_penalties<'a>(&'a self, stake_position: &'a Pubkey) -> impl Iterator<Item = &'a PenaltyEvent> {
+        self.penalty_history
+            .iter()
+            .filter(move |entry| entry.timestamp != 0 && !entry.is_resolved && entry.stake_position == *stake_position)
+    }
+
+    pub fn has_open_penalty(&self, stake_position: &Pubkey) -> bool {
+        self.open_penalties(stake_position).next().is_some()
+    }
+
+    pub fn open_penalty_amount(&self, stake_position: &Pubkey) -> u64 {
+        self.open_penalties(stake_position)
+            .fold(0u64, |total, entry| total.saturating_add(entry.amount))
+    }
+
+    pub fn find_penalty_mut(&mut self, penalty_id: u64) -> Result<&mut PenaltyEvent, ProgramError> {
+        self.penalty_history
+            .iter_mut()
+            .find(|entry| entry.timestamp != 0 && entry.penalty_id == penalty_id)
+            .ok_or(StakingError::PenaltyNotFound.into())
+    }
+
+    /// Charges `amount` of debt collected from `stake_position` to its upheld penalties, oldest first.
+    /// The part of a penalty its recovery can't give back is settled first, anything past it is held
+    /// for the recovery. Returns how much of `amount` is held, the rest is final.
+    pub fn collect_penalty_debt(&mut self, stake_position: &Pubkey, amount: u64) -> u64 {
+        let mut remaining = amount;
+        let mut reserved = 0u64;
+
+        let upheld = self.penalty_history.iter_mut().filter(|entry| {
+            entry.timestamp != 0
+                && entry.is_resolved
+                && !entry.is_overturned
+                && entry.stake_position == *stake_position
+                && entry.penalty_type != PenaltyType::EarlyUnstake as u8
+        });
+
+        for penalty in upheld {
+            let collected = remaining.min(penalty.amount.saturating_sub(penalty.debt_settled));
+            let settled = penalty.debt_settled.saturating_add(collected);
+
+            if !penalty.is_recovered {
+                let final_amount = penalty.amount.saturating_sub(penalty.recoverable_amount());
+                let held = settled.saturating_sub(penalty.debt_settled.max(final_amount));
+
+                penalty.recovery_reserve = penalty.recovery_reserve.saturating_add(held);
+                reserved = reserved.saturating_add(held);
+            }
+
+            penalty.debt_settled = settled;
+            remaining = remaining.saturating_sub(collected);
+        }
+
+        reserved
+    }
 }
 
+/// One stake in one pool, PDA of [b"stake_position", owner, staking_pool, position_index].
 #[repr(C)]
 #[derive(Clone, Debug, Copy, Default)]
 pub struct StakePosition {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub position_index: u64,
     pub pool_id: u64,
     pub staking_pool: Pubkey,
     pub lst_token_account: Pubkey, //Need to create this account for the user when he stake his tokens
@@ -51,6 +137,8 @@ pub struct StakePosition {
     pub lst_tokens: u64,
     pub last_reward_update: i64,
     pub pending_rewards: u64,
+    pub reward_debt: u128, // staked_amount * accumulated_reward_per_share at the last settlement
+    pub penalty_debt: u64, // Upheld penalties not yet collected, taken out of the next unstake
     pub stake_timestamp: i64,
     pub lock_exipry_enable: bool,
     pub lock_expiry: i64,
@@ -61,10 +149,37 @@ pub struct StakePosition {
     pub last_compound_timestamp: i64,
     pub compound_count: u32,
     pub bump: u8,
+    pub stream_reward_debt: [u128; 4], // reward_debt for each of the pool's reward streams
+    pub stream_pending_rewards: [u64; 4], // pending_rewards for each of the pool's reward streams
+    pub lock_multiplier: u16, // Percent, from the chosen lock tier. 0 falls back to the pool's reward_multiplier
+    pub boost_weight: u64, // Reward weight on top of staked_amount while the lock runs
+    pub max_compound_tip_bps: u16, // Caps the pool's compound_tip_bps for this position
+    pub stream_generations: [u32; 4], // Pool's reward_stream_generations the stream debt was taken against
 }
 
 impl AccountData for StakePosition {
     const SIZE: usize = core::mem::size_of::<StakePosition>();
+    const DISCRIMINATOR: [u8; 8] = *b"STKPOSTN";
+    const VERSION: u8 = 1;
+}
+
+impl StakePosition {
+    /// Share of the pool's rewards this position earns, see `StakingPool::total_reward_weight`.
+    pub fn reward_weight(&self) -> u64 {
+        self.staked_amount.saturating_add(self.boost_weight)
+    }
+
+    pub fn check_owner(&self, owner: &Pubkey, staking_pool: &Pubkey) -> ProgramResult {
+        if self.owner != *owner {
+            return Err(StakingError::OwnerMismatch.into());
+        }
+
+        if self.staking_pool != *staking_pool {
+            return Err(StakingError::PoolMismatch.into());
+        }
+
+        Ok(())
+    }
 }
 
 #[repr(C)]
@@ -86,9 +201,56 @@ pub struct PenaltyEvent {
     pub resolution_timestamp: i64, // When penalty was resolved (0 if unresolved)
     pub pool_id: u64, // Which staking pool this penalty relates to
     pub user: Pubkey, // User who incurred the penalty
+    pub stake_position: Pubkey, // Position the penalty is charged against
     pub validator: Pubkey, // Validator that caused the penalty 
     pub original_stake_amount: u64, // Original stake amount before penalty
     pub recovery_period: u32,  // Days until partial recovery 
+    pub recovery_bps: u16, // Share of amount restored after recovery_period, snapshot of the pool setting
+    pub is_disputed: bool,
+    pub is_overturned: bool,
+    pub is_recovered: bool,
+    pub debt_settled: u64, // Collected from or forgiven on the position so far
+    pub recovery_reserve: u64, // Collected debt held in the stake vault for the recovery
+}
+
+impl PenaltyEvent {
+    fn from_v1(event: &PenaltyEventV1, stake_position: Pubkey) -> Self {
+        PenaltyEvent {
+            penalty_type: event.penalty_type,
+            penalty_id: event.penalty_id,
+            amount: event.amount,
+            timestamp: event.timestamp,
+            grace_period_end: event.grace_period_end,
+            is_resolved: event.is_resolved,
+            resolution_timestamp: event.resolution_timestamp,
+            pool_id: event.pool_id,
+            user: event.user,
+            stake_position,
+            validator: event.validator,
+            original_stake_amount: event.original_stake_amount,
+            recovery_period: event.recovery_period,
+            recovery_bps: event.recovery_bps,
+            is_disputed: event.is_disputed,
+            is_overturned: event.is_overturned,
+            is_recovered: event.is_recovered,
+            debt_settled: 0,
+            recovery_reserve: 0,
+        }
+    }
+
+    /// Share of the penalty handed back once its recovery period has passed.
+    pub fn recoverable_amount(&self) -> u64 {
+        ((self.amount as u128)
+            .saturating_mul(self.recovery_bps as u128)
+            / 10000u128) as u64
+    }
+
+
+    /// Empty slots and penalties with nothing left to do can be dropped from the history.
+    pub fn is_settled(&self) -> bool {
+        self.timestamp == 0
+            || (self.is_resolved && (self.is_overturned || self.is_recovered || self.recovery_bps == 0))
+    }
 }
 
 pub enum PenaltyType {
@@ -110,4 +272,133 @@ impl TryFrom<&u8> for PenaltyType {
             _ => Err(ProgramError::InvalidAccountData)
         }
     }
-}
\ No newline at end of file
+}
+/// Positions a version 1 user stake account held inline.
+pub const MAX_POSITIONS_V1: usize = 10;
+
+/// Version 1 layout of `UserStakeAccount`, positions were stored inline before they moved to
+/// their own PDAs. Only read by MigrateAccount.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct UserStakeAccountV1 {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub global_config: Pubkey,
+    pub user_token_account: Pubkey,
+
+    pub total_lst_balance: u64,
+    pub total_staked_amount: u64,
+    pub total_pending_rewards: u64,
+    pub creation_timestamp: i64,
+
+    pub active_positions: u8,
+    pub is_paused: bool,
+    pub positions: [StakePositionV1; MAX_POSITIONS_V1],
+
+    pub total_earned: u64,
+    pub total_claimed: u64,
+    pub pending_rewards: u64,
+    pub last_claim_timestamp: i64,
+    pub last_update_timestamp: i64,
+    pub claim_history: [ClaimEvent; MAX_HISTORY],
+
+    pub total_penalties: u64,
+    pub active_penalties: u64,
+    pub next_penalty_id: u64,
+    pub penalty_type_count: u8,
+    pub penalty_history: [PenaltyEventV1; MAX_HISTORY],
+
+    pub bump: u8,
+}
+
+impl UserStakeAccountV1 {
+    pub const SIZE: usize = core::mem::size_of::<UserStakeAccountV1>();
+
+    /// Rewrites the version 1 account as the current layout, its positions already moved out
+    /// to `position_keys` (default for positions that weren't migrated).
+    pub fn to_current(&self, account: &mut UserStakeAccount, position_keys: &[Pubkey; MAX_POSITIONS_V1]) {
+        account.discriminator = self.discriminator;
+        account.version = UserStakeAccount::VERSION;
+        account.owner = self.owner;
+        account.global_config = self.global_config;
+        account.user_token_account = self.user_token_account;
+        account.total_lst_balance = self.total_lst_balance;
+        account.total_staked_amount = self.total_staked_amount;
+        account.total_pending_rewards = self.total_pending_rewards;
+        account.creation_timestamp = self.creation_timestamp;
+        account.active_positions = self.active_positions;
+        account.is_paused = self.is_paused;
+        account.next_position_index = MAX_POSITIONS_V1 as u64;
+        account.total_earned = self.total_earned;
+        account.total_claimed = self.total_claimed;
+        account.pending_rewards = self.pending_rewards;
+        account.last_claim_timestamp = self.last_claim_timestamp;
+        account.last_update_timestamp = self.last_update_timestamp;
+        account.claim_history = self.claim_history;
+        account.total_penalties = self.total_penalties;
+        account.active_penalties = self.active_penalties;
+        account.next_penalty_id = self.next_penalty_id;
+        account.penalty_type_count = self.penalty_type_count;
+
+        for (index, event) in self.penalty_history.iter().enumerate() {
+            // Version 1 penalties only named the pool, charge them to the position staked there
+            let stake_position = self.positions
+                .iter()
+                .position(|position| position.is_active && position.pool_id == event.pool_id)
+                .map(|position_index| position_keys[position_index])
+                .unwrap_or_default();
+
+            account.penalty_history[index] = PenaltyEvent::from_v1(event, stake_position);
+        }
+
+        account.bump = self.bump;
+    }
+}
+
+/// A position as stored inline in a version 1 user stake account.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StakePositionV1 {
+    pub pool_id: u64,
+    pub staking_pool: Pubkey,
+    pub lst_token_account: Pubkey,
+    pub staked_amount: u64,
+    pub lst_tokens: u64,
+    pub last_reward_update: i64,
+    pub pending_rewards: u64,
+    pub reward_debt: u128,
+    pub penalty_debt: u64,
+    pub stake_timestamp: i64,
+    pub lock_exipry_enable: bool,
+    pub lock_expiry: i64,
+    pub is_active: bool,
+    pub auto_compound_enabled: bool,
+    pub min_compound_amount: u64,
+    pub compound_frequency_hours: u32,
+    pub last_compound_timestamp: i64,
+    pub compound_count: u32,
+    pub bump: u8,
+}
+
+/// A penalty as stored in a version 1 user stake account, before it named its position.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PenaltyEventV1 {
+    pub penalty_type: u8,
+    pub penalty_id: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub grace_period_end: i64,
+    pub is_resolved: bool,
+    pub resolution_timestamp: i64,
+    pub pool_id: u64,
+    pub user: Pubkey,
+    pub validator: Pubkey,
+    pub original_stake_amount: u64,
+    pub recovery_period: u32,
+    pub recovery_bps: u16,
+    pub is_disputed: bool,
+    pub is_overturned: bool,
+    pub is_recovered: bool,
+}
@@ -0,0 +1,166 @@
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey, *};
+use shank::{ShankAccount, ShankType};
+use crate::error::StakingError;
+use crate::states::helper::AccountData;
+
+/// Capacity of a pool's validator list.
+pub const MAX_VALIDATORS: usize = 16;
+
+/// Validators a NativeSol pool delegates to, PDA of [b"validator_list", staking_pool].
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct ValidatorList {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub staking_pool: Pubkey,
+    pub validator_count: u8, // First validator_count entries are in use
+    pub total_target_weight: u32, // Sum of every entry's target_weight
+    pub validators: [ValidatorStakeInfo; 16], // MAX_VALIDATORS, shank needs a literal length
+    pub bump: u8,
+}
+
+/// Stake the pool holds with one validator. Each validator has two stake PDAs:
+/// [b"validator_stake", staking_pool, vote_account] and [b"transient_stake", staking_pool, vote_account].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, ShankType)]
+pub struct ValidatorStakeInfo {
+    pub vote_account: Pubkey,
+    pub target_weight: u16, // Share of total_underlying relative to total_target_weight
+    pub status: u8, // ValidatorStatusEnum
+    pub transient_state: u8, // TransientStateEnum
+    pub active_stake_lamports: u64, // Delegated in validator_stake
+    pub transient_stake_lamports: u64, // Moving in or out, waiting for MergeTransientStake
+    pub last_rebalance_epoch: u64,
+    pub evidence_count: u8, // Slash reports against this validator in the pool's current slash_round
+    pub slash_count: u32,
+}
+
+impl AccountData for ValidatorList {
+    const SIZE: usize = core::mem::size_of::<ValidatorList>();
+    const DISCRIMINATOR: [u8; 8] = *b"VALDLIST";
+    const VERSION: u8 = 1;
+}
+
+impl ValidatorList {
+    pub fn active_entries(&self) -> &[ValidatorStakeInfo] {
+        &self.validators[..self.validator_count as usize]
+    }
+
+    pub fn find_mut(&mut self, vote_account: &Pubkey) -> Result<&mut ValidatorStakeInfo, ProgramError> {
+        let count = self.validator_count as usize;
+
+        self.validators[..count]
+            .iter_mut()
+            .find(|entry| entry.vote_account == *vote_account)
+            .ok_or(StakingError::ValidatorNotFound.into())
+    }
+
+    pub fn add(&mut self, vote_account: Pubkey, target_weight: u16) -> ProgramResult {
+        let count = self.validator_count as usize;
+
+        if self.active_entries().iter().any(|entry| entry.vote_account == vote_account) {
+            return Err(StakingError::ValidatorAlreadyListed.into());
+        }
+
+        if count >= MAX_VALIDATORS {
+            return Err(StakingError::ValidatorListFull.into());
+        }
+
+        self.validators[count] = ValidatorStakeInfo {
+            vote_account,
+            target_weight,
+            ..ValidatorStakeInfo::default()
+        };
+        self.validator_count += 1;
+        self.total_target_weight = self.total_target_weight.saturating_add(target_weight as u32);
+
+        Ok(())
+    }
+
+    /// Drops an entry that no longer holds any stake, keeping the used entries contiguous.
+    pub fn remove(&mut self, vote_account: &Pubkey) -> ProgramResult {
+        let count = self.validator_count as usize;
+
+        let idx = self.active_entries()
+            .iter()
+            .position(|entry| entry.vote_account == *vote_account)
+            .ok_or(StakingError::ValidatorNotFound)?;
+
+        self.total_target_weight = self.total_target_weight
+            .saturating_sub(self.validators[idx].target_weight as u32);
+
+        self.validators[idx] = self.validators[count - 1];
+        self.validators[count - 1] = ValidatorStakeInfo::default();
+        self.validator_count -= 1;
+
+        Ok(())
+    }
+
+    pub fn set_weight(&mut self, vote_account: &Pubkey, target_weight: u16) -> ProgramResult {
+        let entry = self.find_mut(vote_account)?;
+        let old_weight = entry.target_weight;
+        entry.target_weight = target_weight;
+
+        self.total_target_weight = self.total_target_weight
+            .saturating_sub(old_weight as u32)
+            .saturating_add(target_weight as u32);
+
+        Ok(())
+    }
+
+    /// Lamports `entry` should have delegated out of `total_lamports`, rounded down.
+    pub fn target_lamports(&self, entry: &ValidatorStakeInfo, total_lamports: u64) -> u64 {
+        if self.total_target_weight == 0 {
+            return 0;
+        }
+
+        ((total_lamports as u128)
+            .saturating_mul(entry.target_weight as u128)
+            / self.total_target_weight as u128) as u64
+    }
+
+    /// Slash evidence is counted per validator within a round, a new round starts them all over.
+    pub fn reset_evidence(&mut self) {
+        for entry in self.validators.iter_mut() {
+            entry.evidence_count = 0;
+        }
+    }
+}
+
+pub enum ValidatorStatusEnum {
+    Active,
+    Removing // Target weight is zero, dropped from the list once its stake is back in the reserve
+}
+
+impl TryFrom<&u8> for ValidatorStatusEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ValidatorStatusEnum::Active),
+            1 => Ok(ValidatorStatusEnum::Removing),
+            _ => Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
+
+pub enum TransientStateEnum {
+    None,
+    Activating,   // transient_stake is warming up, merges into validator_stake
+    Deactivating, // transient_stake is cooling down, merges into the reserve
+    Draining      // validator_stake itself is cooling down, merges into the reserve
+}
+
+impl TryFrom<&u8> for TransientStateEnum {
+    type Error = ProgramError;
+
+    fn try_from(value: &u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TransientStateEnum::None),
+            1 => Ok(TransientStateEnum::Activating),
+            2 => Ok(TransientStateEnum::Deactivating),
+            3 => Ok(TransientStateEnum::Draining),
+            _ => Err(ProgramError::InvalidAccountData)
+        }
+    }
+}
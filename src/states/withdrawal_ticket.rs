@@ -0,0 +1,27 @@
+use pinocchio::pubkey::Pubkey;
+use shank::ShankAccount;
+use crate::states::helper::AccountData;
+
+#[repr(C)]
+#[derive(Debug, Clone, ShankAccount)]
+pub struct WithdrawalTicket {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub staking_pool: Pubkey,
+    pub stake_position: Pubkey, // Position the principal came from, restored on cancel
+    pub pool_id: u64,
+    pub ticket_id: u64,
+    pub lst_amount: u64, // LST burned when the ticket was opened
+    pub underlying_amount: u64, // Tokens released from stake_token_vault on claim
+    pub principal_amount: u64, // Principal taken off the position, restored on cancel
+    pub request_timestamp: i64,
+    pub unlock_timestamp: i64, // request_timestamp + cooldown_period
+    pub bump: u8,
+}
+
+impl AccountData for WithdrawalTicket {
+    const SIZE: usize = core::mem::size_of::<WithdrawalTicket>();
+    const DISCRIMINATOR: [u8; 8] = *b"WDTICKET";
+    const VERSION: u8 = 1;
+}
@@ -0,0 +1,643 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::slice_invoke_signed,
+    instruction::{AccountMeta, Instruction, Signer},
+    program_error::ProgramError,
+    *
+};
+use pinocchio_token::{instructions::TransferChecked, state::{Mint, TokenAccount}};
+
+use crate::error::StakingError;
+use crate::states::{
+    helper::AccountData,
+    staking_pool_account::{StakingPool, SwapAdapterEnum, SWAP_RATE_PRECISION},
+    swap_pool::SwapPool
+};
+
+/// First 8 bytes of the instruction a swap adapter program has to accept.
+pub const SWAP_DISCRIMINATOR: [u8; 8] = *b"SWAPEXIN";
+
+/// Accounts a swap adapter CPI can carry, the six fixed ones included.
+pub const MAX_ADAPTER_ACCOUNTS: usize = 16;
+
+const FIXED_ADAPTER_ACCOUNTS: usize = 6;
+
+/// Swaps `amount_in` of `source` into `destination` through a pool's adapter program. Any AMM can be
+/// plugged in by deploying an adapter that takes this layout, swaps all of `amount_in` and pays at least
+/// `min_amount_out`. The pool also floors the output against its `swap_reference_rate`.
+///
+/// ### Accounts:
+///   0. `[SIGNER]` Swap authority PDA, owner of `source`
+///   1. `[WRITE]` Source token account
+///   2. `[WRITE]` Destination token account
+///   3. `[]` Source mint
+///   4. `[]` Destination mint
+///   5. `[]` Token program
+///   6. .. Adapter specific accounts, passed through as given
+pub struct AdapterSwap<'a> {
+    pub adapter_program: &'a AccountInfo,
+    pub swap_authority: &'a AccountInfo,
+    pub source: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+    pub source_mint: &'a AccountInfo,
+    pub destination_mint: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub remaining_accounts: &'a [AccountInfo],
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    pub max_slippage_bps: u16,
+}
+
+impl AdapterSwap<'_> {
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_count = FIXED_ADAPTER_ACCOUNTS + self.remaining_accounts.len();
+
+        if account_count > MAX_ADAPTER_ACCOUNTS {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let fixed_accounts = [
+            self.swap_authority,
+            self.source,
+            self.destination,
+            self.source_mint,
+            self.destination_mint,
+            self.token_program,
+        ];
+
+        // Unused tail slots repeat the authority and are sliced off below
+        let account_infos: [&AccountInfo; MAX_ADAPTER_ACCOUNTS] = core::array::from_fn(|index| {
+            if index < FIXED_ADAPTER_ACCOUNTS {
+                fixed_accounts[index]
+            } else {
+                self.remaining_accounts.get(index - FIXED_ADAPTER_ACCOUNTS).unwrap_or(self.swap_authority)
+            }
+        });
+
+        let account_metas: [AccountMeta; MAX_ADAPTER_ACCOUNTS] = core::array::from_fn(|index| match index {
+            0 => AccountMeta::readonly_signer(self.swap_authority.key()),
+            1 | 2 => AccountMeta::writable(account_infos[index].key()),
+            3..=5 => AccountMeta::readonly(account_infos[index].key()),
+            _ => AccountMeta::from(account_infos[index]),
+        });
+
+        // -  [0..8  ]: SWAP_DISCRIMINATOR
+        // -  [8..16 ]: amount_in
+        // -  [16..24]: min_amount_out
+        // -  [24..26]: max_slippage_bps, the pool's bound for adapters that price against their own feed
+        let mut instruction_data = [0u8; 26];
+        instruction_data[0..8].copy_from_slice(&SWAP_DISCRIMINATOR);
+        instruction_data[8..16].copy_from_slice(&self.amount_in.to_le_bytes());
+        instruction_data[16..24].copy_from_slice(&self.min_amount_out.to_le_bytes());
+        instruction_data[24..26].copy_from_slice(&self.max_slippage_bps.to_le_bytes());
+
+        let instruction = Instruction {
+            program_id: self.adapter_program.key(),
+            accounts: &account_metas[..account_count],
+            data: &instruction_data,
+        };
+
+        slice_invoke_signed(&instruction, &account_infos[..account_count], signers)
+    }
+}
+
+/// Swaps compounded rewards out of a pool's reward vault into its stake vault through the pool's
+/// swap adapter, returning the stake tokens received.
+///
+/// `swap_accounts` depend on the adapter:
+///   - `Program`: adapter program, swap authority PDA, swap input PDA, then the adapter's own accounts
+///   - `ConstantProduct`: swap pool, reward reserve, stake reserve
+pub struct CompoundSwap<'a> {
+    pub staking_pool_account: &'a AccountInfo,
+    pub global_config_account: &'a AccountInfo,
+    pub reward_token_mint: &'a AccountInfo,
+    pub stake_token_mint: &'a AccountInfo,
+    pub reward_token_vault: &'a AccountInfo,
+    pub stake_token_vault: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub swap_accounts: &'a [AccountInfo],
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+impl CompoundSwap<'_> {
+    /// `signers` have to sign for the global config, which owns both vaults.
+    pub fn invoke_signed(&self, staking_pool: &StakingPool, signers: &[Signer]) -> Result<u64, ProgramError> {
+        if *self.reward_token_mint.key() != staking_pool.reward_token_mint
+            || *self.stake_token_mint.key() != staking_pool.stake_token_mint {
+            return Err(StakingError::InvalidMint.into());
+        }
+
+        // Anyone can execute a compound, an unbounded swap would hand the rewards to whoever sandwiches it
+        if self.min_amount_out == 0 {
+            return Err(StakingError::InvalidSlippage.into());
+        }
+
+        match SwapAdapterEnum::try_from(&staking_pool.swap_adapter_type)? {
+            SwapAdapterEnum::None => Err(StakingError::CrossMintCompound.into()),
+            SwapAdapterEnum::Program => self.swap_through_program(staking_pool, signers),
+            SwapAdapterEnum::ConstantProduct => self.swap_through_pool(staking_pool, signers),
+        }
+    }
+
+    fn swap_through_program(&self, staking_pool: &StakingPool, signers: &[Signer]) -> Result<u64, ProgramError> {
+        let [adapter_program, swap_authority, swap_input, remaining_accounts @ ..] = self.swap_accounts else {
+            return Err(StakingError::SwapAccountsMismatch.into());
+        };
+
+        if *adapter_program.key() != staking_pool.swap_adapter {
+            return Err(StakingError::InvalidSwapAdapter.into());
+        }
+
+        let (swap_authority_pda, swap_authority_bump) = pubkey::find_program_address(
+            &[b"swap_authority", self.staking_pool_account.key().as_ref()],
+            &crate::ID
+        );
+
+        if *swap_authority.key() != swap_authority_pda {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        let (swap_input_pda, _swap_input_bump) = pubkey::find_program_address(
+            &[
+                b"swap_input",
+                self.staking_pool_account.key().as_ref(),
+                self.reward_token_mint.key().as_ref()
+            ],
+            &crate::ID
+        );
+
+        if *swap_input.key() != swap_input_pda {
+            return Err(StakingError::InvalidPda.into());
+        }
+
+        let slippage_floor = reference_floor(staking_pool, self.amount_in)?;
+
+        // Whatever sits in the swap input already isn't part of this swap and has to still be there after it
+        let input_balance_before = TokenAccount::from_account_info(swap_input)?.amount();
+
+        // The adapter only ever holds the swap authority's signature, never the global config's
+        TransferChecked {
+            from: self.reward_token_vault,
+            to: swap_input,
+            mint: self.reward_token_mint,
+            authority: self.global_config_account,
+            amount: self.amount_in,
+            decimals: Mint::from_account_info(self.reward_token_mint)?.decimals(),
+        }.invoke_signed(signers)?;
+
+        let balance_before = TokenAccount::from_account_info(self.stake_token_vault)?.amount();
+
+        let bump_ref = &[swap_authority_bump];
+        let seeds = seeds!(
+            b"swap_authority",
+            self.staking_pool_account.key().as_ref(),
+            bump_ref
+        );
+        let signer_seeds = Signer::from(&seeds);
+
+        AdapterSwap {
+            adapter_program,
+            swap_authority,
+            source: swap_input,
+            destination: self.stake_token_vault,
+            source_mint: self.reward_token_mint,
+            destination_mint: self.stake_token_mint,
+            token_program: self.token_program,
+            remaining_accounts,
+            amount_in: self.amount_in,
+            min_amount_out: self.min_amount_out,
+            max_slippage_bps: staking_pool.swap_max_slippage_bps,
+        }.invoke_signed(&[signer_seeds])?;
+
+        // Rewards the adapter didn't swap would be stranded in the swap input
+        if TokenAccount::from_account_info(swap_input)?.amount() != input_balance_before {
+            return Err(StakingError::SwapInputNotConsumed.into());
+        }
+
+        // Trust the vault balance, not the adapter
+        let balance_after = TokenAccount::from_account_info(self.stake_token_vault)?.amount();
+        let amount_out = balance_after.saturating_sub(balance_before);
+
+        // The pool's bound holds whatever minimum the executor asked for
+        if amount_out == 0 || amount_out < self.min_amount_out.max(slippage_floor) {
+            return Err(StakingError::SlippageExceeded.into());
+        }
+
+        Ok(amount_out)
+    }
+
+    fn swap_through_pool(&self, staking_pool: &StakingPool, signers: &[Signer]) -> Result<u64, ProgramError> {
+        let [swap_pool_account, reward_reserve, stake_reserve] = self.swap_accounts else {
+            return Err(StakingError::SwapAccountsMismatch.into());
+        };
+
+        if *swap_pool_account.key() != staking_pool.swap_adapter {
+            return Err(StakingError::InvalidSwapAdapter.into());
+        }
+
+        let slippage_floor = reference_floor(staking_pool, self.amount_in)?;
+
+        let mut swap_pool = SwapPool::from_account_info_mut(swap_pool_account)?;
+
+        if swap_pool.staking_pool != *self.staking_pool_account.key() {
+            return Err(StakingError::InvalidSwapAdapter.into());
+        }
+
+        if *reward_reserve.key() != swap_pool.reward_reserve || *stake_reserve.key() != swap_pool.stake_reserve {
+            return Err(StakingError::InvalidVault.into());
+        }
+
+        let (reserve_in, reserve_out) = {
+            let reward_reserve_info = TokenAccount::from_account_info(reward_reserve)?;
+            let stake_reserve_info = TokenAccount::from_account_info(stake_reserve)?;
+
+            (reward_reserve_info.amount(), stake_reserve_info.amount())
+        };
+
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(StakingError::EmptySwapPool.into());
+        }
+
+        let amount_out = swap_pool.quote(self.amount_in, reserve_in, reserve_out);
+
+        // Spot over the reserves is whatever the last trade left it at, so the floor comes from the pool's rate
+        if amount_out == 0 || amount_out < self.min_amount_out.max(slippage_floor) {
+            return Err(StakingError::SlippageExceeded.into());
+        }
+
+        TransferChecked {
+            from: self.reward_token_vault,
+            to: reward_reserve,
+            mint: self.reward_token_mint,
+            authority: self.global_config_account,
+            amount: self.amount_in,
+            decimals: Mint::from_account_info(self.reward_token_mint)?.decimals(),
+        }.invoke_signed(signers)?;
+
+        TransferChecked {
+            from: stake_reserve,
+            to: self.stake_token_vault,
+            mint: self.stake_token_mint,
+            authority: self.global_config_account,
+            amount: amount_out,
+            decimals: Mint::from_account_info(self.stake_token_mint)?.decimals(),
+        }.invoke_signed(signers)?;
+
+        swap_pool.total_swapped_in = swap_pool.total_swapped_in.saturating_add(self.amount_in);
+        swap_pool.total_swapped_out = swap_pool.total_swapped_out.saturating_add(amount_out);
+
+        Ok(amount_out)
+    }
+}
+
+// Least a swap of `amount_in` may pay out, `swap_max_slippage_bps` under the pool's reference rate.
+// Anyone can move an adapter's price or the swap pool's reserves, the reference rate only the pool authority
+fn reference_floor(staking_pool: &StakingPool, amount_in: u64) -> Result<u64, ProgramError> {
+    if staking_pool.swap_reference_rate == 0 {
+        return Err(StakingError::MissingSwapReferenceRate.into());
+    }
+
+    let reference_amount_out = (amount_in as u128)
+        .saturating_mul(staking_pool.swap_reference_rate as u128)
+        / SWAP_RATE_PRECISION;
+
+    Ok((reference_amount_out
+        .saturating_mul(10000u128.saturating_sub(staking_pool.swap_max_slippage_bps as u128))
+        / 10000u128) as u64)
+}
+
+#[cfg(all(test, feature = "test-sbf"))]
+mod testing {
+    use mollusk_svm::result::Check;
+    use solana_sdk::{account::Account, instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::error::StakingError;
+    use crate::instructions::StakingInstructions;
+    use crate::states::staking_pool_account::SwapAdapterEnum;
+    use crate::test_utils::*;
+
+    // Two deployments of tests/programs/mock-swap-adapter, only the first is set on the pool
+    const MOCK_ADAPTER_ID: Pubkey = Pubkey::new_from_array([0xad; 32]);
+    const OTHER_ADAPTER_ID: Pubkey = Pubkey::new_from_array([0xae; 32]);
+
+    const RATE_PRECISION: u64 = 1_000_000_000;
+    const MAX_SLIPPAGE_BPS: u16 = 100;
+    const STAKE_AMOUNT: u64 = 1000;
+    const RESERVE_BALANCE: u64 = 100_000_000;
+
+    struct Setup {
+        context: Context,
+        pool: TestPool,
+        staker: TestStaker,
+        position: Pubkey,
+        executor: Pubkey,
+        executor_token_account: Pubkey,
+        swap_authority: Pubkey,
+        swap_input: Pubkey,
+        mock_config: Pubkey,
+        reserve_authority: Pubkey,
+        reserve: Pubkey,
+        sink: Pubkey,
+    }
+
+    fn set_swap_adapter(context: &Context, pool: &TestPool, adapter: &Pubkey, reference_rate: Option<u64>, check: Check) {
+        set_adapter(context, pool, SwapAdapterEnum::Program, adapter, reference_rate, check);
+    }
+
+    fn set_adapter(context: &Context, pool: &TestPool, adapter_type: SwapAdapterEnum, adapter: &Pubkey, reference_rate: Option<u64>, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&pool.pool_id.to_le_bytes());
+        data.push(adapter_type as u8);
+        data.extend_from_slice(&MAX_SLIPPAGE_BPS.to_le_bytes());
+        if let Some(reference_rate) = reference_rate {
+            data.extend_from_slice(&reference_rate.to_le_bytes());
+        }
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::SetSwapAdapter,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new(pool.staking_pool, false),
+                    AccountMeta::new_readonly(*adapter, false),
+                    AccountMeta::new_readonly(pool.reward_mint, false),
+                    AccountMeta::new_readonly(swap_authority_pda(pool), false),
+                    AccountMeta::new(swap_input_pda(pool), false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[check],
+        );
+    }
+
+    fn swap_authority_pda(pool: &TestPool) -> Pubkey {
+        Pubkey::find_program_address(&[b"swap_authority", pool.staking_pool.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn swap_input_pda(pool: &TestPool) -> Pubkey {
+        Pubkey::find_program_address(&[b"swap_input", pool.staking_pool.as_ref(), pool.reward_mint.as_ref()], &PROGRAM_ID).0
+    }
+
+    // Price the mock pays at, and the share of the input it actually takes
+    fn set_mock(setup: &Setup, rate: u64, consume_bps: u16) {
+        let mut data = rate.to_le_bytes().to_vec();
+        data.extend_from_slice(&consume_bps.to_le_bytes());
+
+        set_account(&setup.context, setup.mock_config, Account { lamports: 1_000_000, data, owner: MOCK_ADAPTER_ID, executable: false, rent_epoch: 0 });
+    }
+
+    // A 1:1 reference rate pool with a cross-mint position that has an hour of rewards to compound
+    fn setup_compound() -> Setup {
+        let mut context = setup_with_programs(&[
+            (MOCK_ADAPTER_ID, "target/deploy/mock_swap_adapter"),
+            (OTHER_ADAPTER_ID, "target/deploy/mock_swap_adapter"),
+        ]);
+        let authority = funded_wallet(&context);
+        let (global_config, _mint) = init_config(&context, &authority);
+        set_config_pause(&context, &global_config, false);
+
+        let pool = create_pool(&context, &authority, &global_config, None);
+        set_token_account(&context, pool.reward_vault, &pool.reward_mint, &global_config, 100_000_000);
+
+        // Protocol fees on compounded rewards go to the reward mint's treasury
+        init_treasury(&context, &authority, &global_config, &pool.reward_mint);
+
+        set_swap_adapter(&context, &pool, &MOCK_ADAPTER_ID, Some(RATE_PRECISION), Check::success());
+
+        let staker = create_staker(&context, &pool, STAKE_AMOUNT);
+        let position = stake(&context, &pool, &staker, STAKE_AMOUNT, 0);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes()); // compound_frequency_hours
+        data.extend_from_slice(&0u64.to_le_bytes()); // min_compound_amount
+
+        context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::EnableAutoCompound,
+                vec![
+                    AccountMeta::new_readonly(staker.user, true),
+                    AccountMeta::new(staker.user_stake, false),
+                    AccountMeta::new(position, false),
+                    AccountMeta::new_readonly(pool.staking_pool, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+
+        advance_time(&mut context, 3600);
+
+        let reserve_authority = Pubkey::find_program_address(&[b"reserve_authority"], &MOCK_ADAPTER_ID).0;
+        let executor = funded_wallet(&context);
+
+        let setup = Setup {
+            executor_token_account: create_token_account(&context, &pool.reward_mint, &executor, 0),
+            reserve: create_token_account(&context, &pool.stake_mint, &reserve_authority, RESERVE_BALANCE),
+            sink: create_token_account(&context, &pool.reward_mint, &reserve_authority, 0),
+            swap_authority: swap_authority_pda(&pool),
+            swap_input: swap_input_pda(&pool),
+            mock_config: Pubkey::new_unique(),
+            reserve_authority,
+            executor,
+            context,
+            pool,
+            staker,
+            position,
+        };
+        set_mock(&setup, RATE_PRECISION, 10000);
+
+        setup
+    }
+
+    fn execute_compound(setup: &Setup, adapter: &Pubkey, min_amount_out: u64, check: Check) {
+        // Swap accounts, then the mock's own
+        let swap_accounts = vec![
+            AccountMeta::new_readonly(*adapter, false),
+            AccountMeta::new_readonly(setup.swap_authority, false),
+            AccountMeta::new(setup.swap_input, false),
+            AccountMeta::new_readonly(setup.mock_config, false),
+            AccountMeta::new_readonly(setup.reserve_authority, false),
+            AccountMeta::new(setup.reserve, false),
+            AccountMeta::new(setup.sink, false),
+        ];
+
+        execute_compound_with(setup, swap_accounts, min_amount_out, check);
+    }
+
+    fn execute_compound_with(setup: &Setup, swap_accounts: Vec<AccountMeta>, min_amount_out: u64, check: Check) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&setup.pool.pool_id.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(setup.executor, true),
+            AccountMeta::new_readonly(setup.staker.user, false),
+            AccountMeta::new_readonly(setup.pool.authority, false),
+            AccountMeta::new_readonly(setup.pool.reward_mint, false),
+            AccountMeta::new_readonly(setup.pool.stake_mint, false),
+            AccountMeta::new(setup.pool.reward_vault, false),
+            AccountMeta::new(setup.pool.stake_vault, false),
+            AccountMeta::new(setup.pool.liquid_stake_mint, false),
+            AccountMeta::new_readonly(setup.pool.global_config, false),
+            AccountMeta::new(setup.pool.staking_pool, false),
+            AccountMeta::new(setup.staker.user_stake, false),
+            AccountMeta::new(setup.position, false),
+            AccountMeta::new(setup.staker.lst_account, false),
+            AccountMeta::new(treasury_pda(&setup.pool.reward_mint, &setup.pool.authority), false),
+            AccountMeta::new(setup.executor_token_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ];
+        accounts.extend(swap_accounts);
+
+        setup.context.process_and_validate_instruction(
+            &instruction(StakingInstructions::ExecuteAutoCompound, accounts, &data),
+            &[check],
+        );
+    }
+
+    #[test]
+    fn test_program_adapter_needs_a_reference_rate() {
+        let setup = setup_compound();
+
+        set_swap_adapter(&setup.context, &setup.pool, &OTHER_ADAPTER_ID, None, custom_error(StakingError::MissingSwapReferenceRate));
+        set_swap_adapter(&setup.context, &setup.pool, &OTHER_ADAPTER_ID, Some(0), custom_error(StakingError::MissingSwapReferenceRate));
+
+        // Only executable programs other than this one can be adapters
+        set_swap_adapter(&setup.context, &setup.pool, &setup.mock_config, Some(RATE_PRECISION), custom_error(StakingError::InvalidSwapAdapter));
+        set_swap_adapter(&setup.context, &setup.pool, &PROGRAM_ID, Some(RATE_PRECISION), custom_error(StakingError::InvalidSwapAdapter));
+    }
+
+    #[test]
+    fn test_compound_swaps_rewards_through_the_adapter() {
+        let setup = setup_compound();
+
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 1, Check::success());
+
+        // The mock paid 1:1 for everything left after the protocol fee
+        let amount_in = token_amount(&setup.context, &setup.sink);
+        assert!(amount_in > 0);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT + amount_in);
+        assert_eq!(token_amount(&setup.context, &setup.reserve), RESERVE_BALANCE - amount_in);
+        assert_eq!(token_amount(&setup.context, &setup.swap_input), 0);
+        assert!(token_amount(&setup.context, &treasury_pda(&setup.pool.reward_mint, &setup.pool.authority)) > 0);
+        assert_eq!(token_amount(&setup.context, &setup.staker.lst_account), STAKE_AMOUNT + amount_in);
+    }
+
+    #[test]
+    fn test_compound_holds_the_pool_slippage_floor() {
+        let setup = setup_compound();
+
+        // An executor can't opt out of a bound
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 0, custom_error(StakingError::InvalidSlippage));
+
+        // 2% under the reference rate, past the pool's 1% even though the executor asked for little
+        set_mock(&setup, RATE_PRECISION * 98 / 100, 10000);
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 1, custom_error(StakingError::SlippageExceeded));
+
+        // Within the pool's bound but under the executor's own minimum
+        set_mock(&setup, RATE_PRECISION * 995 / 1000, 10000);
+        execute_compound(&setup, &MOCK_ADAPTER_ID, u64::MAX, custom_error(StakingError::SlippageExceeded));
+
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 1, Check::success());
+        assert!(token_amount(&setup.context, &setup.pool.stake_vault) > STAKE_AMOUNT);
+    }
+
+    #[test]
+    fn test_compound_only_calls_the_configured_adapter() {
+        let setup = setup_compound();
+
+        execute_compound(&setup, &OTHER_ADAPTER_ID, 1, custom_error(StakingError::InvalidSwapAdapter));
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT);
+    }
+
+    #[test]
+    fn test_adapter_has_to_swap_the_whole_input() {
+        let setup = setup_compound();
+
+        set_mock(&setup, RATE_PRECISION, 5000);
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 1, custom_error(StakingError::SwapInputNotConsumed));
+    }
+
+    #[test]
+    fn test_leftover_swap_input_is_not_swapped() {
+        let setup = setup_compound();
+
+        // Tokens sent to the swap input outside a compound stay there, they aren't this swap's to spend
+        set_token_account(&setup.context, setup.swap_input, &setup.pool.reward_mint, &setup.swap_authority, 500);
+
+        execute_compound(&setup, &MOCK_ADAPTER_ID, 1, Check::success());
+
+        let amount_in = token_amount(&setup.context, &setup.sink);
+        assert_eq!(token_amount(&setup.context, &setup.swap_input), 500);
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT + amount_in);
+    }
+
+    // Swap pool with `reward_balance` and `stake_balance` in its reserves, set as the pool's adapter at the 1:1 reference rate
+    fn setup_swap_pool(setup: &Setup, reward_balance: u64, stake_balance: u64) -> Vec<AccountMeta> {
+        let pool = &setup.pool;
+        let swap_pool = Pubkey::find_program_address(&[b"swap_pool", pool.staking_pool.as_ref()], &PROGRAM_ID).0;
+        let reserve = |mint: &Pubkey| Pubkey::find_program_address(&[b"swap_reserve", swap_pool.as_ref(), mint.as_ref()], &PROGRAM_ID).0;
+        let (reward_reserve, stake_reserve) = (reserve(&pool.reward_mint), reserve(&pool.stake_mint));
+
+        let mut data = pool.pool_id.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u16.to_le_bytes()); // fee_bps
+
+        setup.context.process_and_validate_instruction(
+            &instruction(
+                StakingInstructions::InitializeSwapPool,
+                vec![
+                    AccountMeta::new(pool.authority, true),
+                    AccountMeta::new_readonly(pool.global_config, false),
+                    AccountMeta::new_readonly(pool.staking_pool, false),
+                    AccountMeta::new_readonly(pool.reward_mint, false),
+                    AccountMeta::new_readonly(pool.stake_mint, false),
+                    AccountMeta::new(swap_pool, false),
+                    AccountMeta::new(reward_reserve, false),
+                    AccountMeta::new(stake_reserve, false),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                ],
+                &data,
+            ),
+            &[Check::success()],
+        );
+
+        set_token_account(&setup.context, reward_reserve, &pool.reward_mint, &pool.global_config, reward_balance);
+        set_token_account(&setup.context, stake_reserve, &pool.stake_mint, &pool.global_config, stake_balance);
+
+        set_adapter(&setup.context, pool, SwapAdapterEnum::ConstantProduct, &swap_pool, None, custom_error(StakingError::MissingSwapReferenceRate));
+        set_adapter(&setup.context, pool, SwapAdapterEnum::ConstantProduct, &swap_pool, Some(RATE_PRECISION), Check::success());
+
+        vec![
+            AccountMeta::new(swap_pool, false),
+            AccountMeta::new(reward_reserve, false),
+            AccountMeta::new(stake_reserve, false),
+        ]
+    }
+
+    #[test]
+    fn test_swap_pool_is_floored_by_the_reference_rate() {
+        let setup = setup_compound();
+
+        // Reserves pushed to half a stake token per reward, the spot price no longer bounds anything
+        let swap_accounts = setup_swap_pool(&setup, 2 * RESERVE_BALANCE, RESERVE_BALANCE);
+        execute_compound_with(&setup, swap_accounts, 1, custom_error(StakingError::SlippageExceeded));
+        assert_eq!(token_amount(&setup.context, &setup.pool.stake_vault), STAKE_AMOUNT);
+    }
+
+    #[test]
+    fn test_swap_pool_compounds_near_the_reference_rate() {
+        let setup = setup_compound();
+
+        let swap_accounts = setup_swap_pool(&setup, RESERVE_BALANCE, RESERVE_BALANCE);
+        execute_compound_with(&setup, swap_accounts, 1, Check::success());
+
+        // An hour of rewards is well inside the pool's 1% on reserves this deep
+        assert!(token_amount(&setup.context, &setup.pool.stake_vault) > STAKE_AMOUNT);
+        assert!(token_amount(&setup.context, &setup.staker.lst_account) > STAKE_AMOUNT);
+    }
+}
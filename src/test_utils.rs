@@ -0,0 +1,626 @@
+//! Shared Mollusk harness for the handler tests.
+//!
+//! Needs the workspace built with `cargo build-sbf` (target/deploy/staking_platform.so and the
+//! tests/programs mocks), plus the SPL token and Stake programs dumped to tests/fixtures/spl_token.so
+//! and tests/fixtures/solana_stake_program.so, then `cargo test --features test-sbf`.
+//! scripts/build-fixtures.sh fetches and builds all of them.
+
+#![allow(dead_code)]
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use mollusk_svm::{program::{keyed_account_for_system_program, loader_keys::LOADER_V3}, result::Check, Mollusk, MolluskContext};
+use solana_log_collector::LogCollector;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::StakingError;
+use crate::events::Event;
+use crate::instructions::StakingInstructions;
+use crate::states::{global_config::GlobalConfig, staking_pool_account::MIN_CONFIG_CHANGE_DELAY};
+
+pub const PROGRAM_ID: Pubkey = Pubkey::new_from_array(crate::ID);
+pub const TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array(pinocchio_token::ID);
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::new_from_array(pinocchio_system::ID);
+
+/// Clock the tests start from, timelocks and grace periods count from it.
+pub const START_TIMESTAMP: i64 = 1_700_000_000;
+
+pub type Context = MolluskContext<HashMap<Pubkey, Account>>;
+
+pub fn setup() -> Context {
+    setup_with_programs(&[])
+}
+
+/// Like `setup`, with more programs loaded by ELF name, e.g. the tests/programs crates from target/deploy.
+pub fn setup_with_programs(programs: &[(Pubkey, &str)]) -> Context {
+    let mut mollusk = Mollusk::new(&PROGRAM_ID, "target/deploy/staking_platform");
+    mollusk.add_program(&TOKEN_PROGRAM_ID, "spl_token", &LOADER_V3);
+    for (program_id, program_name) in programs {
+        mollusk.add_program(program_id, program_name, &LOADER_V3);
+    }
+    mollusk.sysvars.clock.unix_timestamp = START_TIMESTAMP;
+
+    let context = mollusk.with_context(HashMap::new());
+    let (system_program, system_account) = keyed_account_for_system_program();
+    set_account(&context, system_program, system_account);
+
+    context
+}
+
+pub fn set_time(context: &mut Context, unix_timestamp: i64) {
+    context.mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+}
+
+pub fn advance_time(context: &mut Context, seconds: i64) {
+    context.mollusk.sysvars.clock.unix_timestamp += seconds;
+}
+
+/// Starts collecting program logs, read the events out of them with `events`.
+pub fn capture_logs(context: &mut Context) -> Rc<RefCell<LogCollector>> {
+    let logs = LogCollector::new_ref();
+    context.mollusk.logger = Some(logs.clone());
+    logs
+}
+
+/// Every `E` logged since `capture_logs`, decoded from its `sol_log_data` payload.
+pub fn events<E: Event>(logs: &Rc<RefCell<LogCollector>>) -> Vec<E> {
+    logs.borrow()
+        .get_recorded_content()
+        .iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|fields| {
+            let mut fields = fields.split(' ').map(|field| STANDARD.decode(field).unwrap());
+
+            if fields.next()? != E::DISCRIMINATOR {
+                return None;
+            }
+
+            let payload = fields.next()?;
+            assert_eq!(payload.len(), core::mem::size_of::<E>(), "event payload has the wrong size");
+
+            Some(unsafe { core::ptr::read_unaligned(payload.as_ptr() as *const E) })
+        })
+        .collect()
+}
+
+pub fn set_account(context: &Context, key: Pubkey, account: Account) {
+    context.account_store.borrow_mut().insert(key, account);
+}
+
+pub fn account(context: &Context, key: &Pubkey) -> Account {
+    context.account_store.borrow().get(key).cloned().unwrap_or_default()
+}
+
+/// A fresh system-owned wallet with enough SOL to pay for any account the program creates.
+pub fn funded_wallet(context: &Context) -> Pubkey {
+    let key = Pubkey::new_unique();
+    set_account(context, key, Account::new(100_000_000_000, 0, &SYSTEM_PROGRAM_ID));
+    key
+}
+
+/// Reads a program account back as `T`. Only for layouts without u128, those pad differently on the host.
+pub fn read<T>(context: &Context, key: &Pubkey) -> T {
+    let data = account(context, key).data;
+    assert!(data.len() >= core::mem::size_of::<T>(), "account {key} is too small");
+
+    unsafe { core::ptr::read_unaligned(data.as_ptr() as *const T) }
+}
+
+/// Reads a `T` at `offset`, for fields laid out before an account's first u128.
+pub fn read_at<T>(context: &Context, key: &Pubkey, offset: usize) -> T {
+    let data = account(context, key).data;
+    assert!(data.len() >= offset + core::mem::size_of::<T>(), "account {key} is too small");
+
+    unsafe { core::ptr::read_unaligned(data[offset..].as_ptr() as *const T) }
+}
+
+pub fn instruction(instruction: StakingInstructions, accounts: Vec<AccountMeta>, data: &[u8]) -> Instruction {
+    let mut instruction_data = vec![instruction as u8];
+    instruction_data.extend_from_slice(data);
+
+    Instruction::new_with_bytes(PROGRAM_ID, &instruction_data, accounts)
+}
+
+pub fn custom_error(error: StakingError) -> Check<'static> {
+    Check::err(ProgramError::Custom(error as u32))
+}
+
+pub fn create_mint(context: &Context, mint_authority: &Pubkey, decimals: u8) -> Pubkey {
+    let key = Pubkey::new_unique();
+
+    // COption<Pubkey> mint_authority, supply, decimals, is_initialized, COption<Pubkey> freeze_authority
+    let mut data = vec![0u8; 82];
+    data[0..4].copy_from_slice(&1u32.to_le_bytes());
+    data[4..36].copy_from_slice(mint_authority.as_ref());
+    data[44] = decimals;
+    data[45] = 1;
+
+    set_account(context, key, Account { lamports: 1_461_600, data, owner: TOKEN_PROGRAM_ID, executable: false, rent_epoch: 0 });
+    key
+}
+
+pub fn create_token_account(context: &Context, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+    let key = Pubkey::new_unique();
+    set_token_account(context, key, mint, owner, amount);
+    key
+}
+
+/// Writes an initialized token account at `key`, used for PDAs the program expects to exist.
+pub fn set_token_account(context: &Context, key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+    // mint, owner, amount, COption<Pubkey> delegate, state, COption<u64> is_native, delegated_amount, COption<Pubkey> close_authority
+    let mut data = vec![0u8; 165];
+    data[0..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data[108] = 1;
+
+    set_account(context, key, Account { lamports: 2_039_280, data, owner: TOKEN_PROGRAM_ID, executable: false, rent_epoch: 0 });
+
+    // Keep the mint supply covering every balance minted outside the program
+    if let Some(mut mint_account) = context.account_store.borrow().get(mint).cloned() {
+        let supply = u64::from_le_bytes(mint_account.data[36..44].try_into().unwrap()) + amount;
+        mint_account.data[36..44].copy_from_slice(&supply.to_le_bytes());
+        context.account_store.borrow_mut().insert(*mint, mint_account);
+    }
+}
+
+pub fn token_amount(context: &Context, key: &Pubkey) -> u64 {
+    u64::from_le_bytes(account(context, key).data[64..72].try_into().unwrap())
+}
+
+pub fn config_pda(config_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"global_config_account", config_id.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn treasury_pda(mint: &Pubkey, config_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"treasury_account", mint.as_ref(), config_id.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn roles_pda(global_config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"roles", global_config.as_ref()], &PROGRAM_ID).0
+}
+
+/// Global config keyed by its authority, returns the config and the mint its treasury holds.
+pub fn init_config(context: &Context, authority: &Pubkey) -> (Pubkey, Pubkey) {
+    let mint = create_mint(context, authority, 9);
+    let global_config = config_pda(authority);
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&100u16.to_le_bytes()); // protocol_fee_rate
+    data.extend_from_slice(&1u64.to_le_bytes()); // min_stake_amount
+    data.extend_from_slice(&10u32.to_le_bytes()); // max_pools
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::InitConfigAccount,
+            vec![
+                AccountMeta::new(*authority, true),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(global_config, false),
+                AccountMeta::new(treasury_pda(&mint, authority), false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+            &data,
+        ),
+        &[Check::success()],
+    );
+
+    (global_config, mint)
+}
+
+/// Treasury token account for `mint` under the config, fees in that mint are paid into it.
+pub fn init_treasury(context: &Context, payer: &Pubkey, global_config: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let config: GlobalConfig = read(context, global_config);
+    let treasury = treasury_pda(mint, &Pubkey::new_from_array(config.config_id));
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::InitTreasuryAccount,
+            vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(*global_config, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(treasury, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+            &[],
+        ),
+        &[Check::success()],
+    );
+
+    treasury
+}
+
+pub struct TestPool {
+    pub authority: Pubkey,
+    pub global_config: Pubkey,
+    pub pool_id: u64,
+    pub staking_pool: Pubkey,
+    pub stake_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+    pub liquid_stake_mint: Pubkey,
+}
+
+/// SPL pool created by the config authority, no lock, slashing on, 100 tokens per second.
+pub fn create_pool(context: &Context, authority: &Pubkey, global_config: &Pubkey, reward_mint: Option<Pubkey>) -> TestPool {
+    let stake_mint = create_mint(context, authority, 9);
+    let reward_mint = reward_mint.unwrap_or_else(|| create_mint(context, authority, 9));
+
+    create_pool_with_mints(context, authority, global_config, stake_mint, reward_mint)
+}
+
+/// Like `create_pool`, `reward_mint` may be `stake_mint` for a pool that compounds without a swap.
+pub fn create_pool_with_mints(context: &Context, authority: &Pubkey, global_config: &Pubkey, stake_mint: Pubkey, reward_mint: Pubkey) -> TestPool {
+    let pool_id = 1u64;
+
+    let pool = TestPool {
+        authority: *authority,
+        global_config: *global_config,
+        pool_id,
+        staking_pool: Pubkey::find_program_address(&[b"staking_pool", authority.as_ref(), &pool_id.to_le_bytes()], &PROGRAM_ID).0,
+        stake_mint,
+        reward_mint,
+        stake_vault: Pubkey::find_program_address(&[b"stake_token_vault", stake_mint.as_ref(), global_config.as_ref()], &PROGRAM_ID).0,
+        reward_vault: Pubkey::find_program_address(&[b"reward_token_vault", reward_mint.as_ref(), global_config.as_ref()], &PROGRAM_ID).0,
+        liquid_stake_mint: Pubkey::find_program_address(&[b"liquid_stake_mint", authority.as_ref()], &PROGRAM_ID).0,
+    };
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&pool_id.to_le_bytes());
+    data.extend_from_slice(&100u64.to_le_bytes()); // reward_rate_per_second
+    data.push(0); // lock_period_enabled
+    data.extend_from_slice(&0i64.to_le_bytes()); // lock_period_duration
+    data.extend_from_slice(&100u16.to_le_bytes()); // reward_multiplier
+    data.extend_from_slice(&0u64.to_le_bytes()); // early_withdraw_penalty
+    data.push(1); // slashing_enabled
+    data.push(0); // slashing_condition_type
+    data.extend_from_slice(&1000u16.to_le_bytes()); // slash_percentage
+    data.push(1); // min_evidence_required
+    data.extend_from_slice(&86_400i64.to_le_bytes()); // cooldown_period
+    data.extend_from_slice(&0u64.to_le_bytes()); // maximum_stake_limit
+    data.extend_from_slice(&1u64.to_le_bytes()); // minimum_stake_amount
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::CreateStakingPool,
+            vec![
+                AccountMeta::new_readonly(*authority, false),
+                AccountMeta::new(*authority, true),
+                AccountMeta::new_readonly(pool.stake_mint, false),
+                AccountMeta::new_readonly(pool.reward_mint, false),
+                AccountMeta::new(pool.stake_vault, false),
+                AccountMeta::new(pool.reward_vault, false),
+                AccountMeta::new(pool.staking_pool, false),
+                AccountMeta::new(*global_config, false),
+                AccountMeta::new(pool.liquid_stake_mint, false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(roles_pda(global_config), false),
+            ],
+            &data,
+        ),
+        &[Check::success()],
+    );
+
+    pool
+}
+
+/// Configs start paused and nothing in the program lifts it yet, so tests that stake flip it here.
+pub fn set_config_pause(context: &Context, global_config: &Pubkey, paused: bool) {
+    let mut config_account = account(context, global_config);
+    config_account.data[core::mem::offset_of!(GlobalConfig, emergency_pause)] = paused as u8;
+    set_account(context, *global_config, config_account);
+}
+
+pub struct TestStaker {
+    pub user: Pubkey,
+    pub user_stake: Pubkey,
+    pub token_account: Pubkey,
+    pub lst_account: Pubkey,
+}
+
+pub fn position_pda(user: &Pubkey, staking_pool: &Pubkey, position_index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"stake_position", user.as_ref(), staking_pool.as_ref(), &position_index.to_le_bytes()],
+        &PROGRAM_ID
+    ).0
+}
+
+/// Wallet holding `balance` stake tokens, with its UserStakeAccount under the pool's config.
+pub fn create_staker(context: &Context, pool: &TestPool, balance: u64) -> TestStaker {
+    let user = funded_wallet(context);
+    let staker = TestStaker {
+        user,
+        user_stake: Pubkey::find_program_address(&[b"user_stake_account", user.as_ref(), pool.global_config.as_ref()], &PROGRAM_ID).0,
+        token_account: create_token_account(context, &pool.stake_mint, &user, balance),
+        lst_account: create_token_account(context, &pool.liquid_stake_mint, &user, 0),
+    };
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::InitUserStakeAccount,
+            vec![
+                AccountMeta::new(user, true),
+                AccountMeta::new_readonly(pool.authority, false),
+                AccountMeta::new_readonly(pool.global_config, false),
+                AccountMeta::new_readonly(staker.token_account, false),
+                AccountMeta::new(staker.user_stake, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            &[],
+        ),
+        &[Check::success()],
+    );
+
+    staker
+}
+
+/// StakeTokens into the position PDA at `position_index`, `extra` is appended after the amount (e.g. a lock tier).
+pub fn stake_instruction(pool: &TestPool, staker: &TestStaker, amount: u64, position_index: u64, extra: &[u8]) -> Instruction {
+    let mut data = Vec::new();
+    data.extend_from_slice(&pool.pool_id.to_le_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(extra);
+
+    instruction(
+        StakingInstructions::StakeTokens,
+        vec![
+            AccountMeta::new(staker.user, true),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new_readonly(pool.stake_mint, false),
+            AccountMeta::new(pool.stake_vault, false),
+            AccountMeta::new(pool.liquid_stake_mint, false),
+            AccountMeta::new_readonly(pool.global_config, false),
+            AccountMeta::new(pool.staking_pool, false),
+            AccountMeta::new(staker.token_account, false),
+            AccountMeta::new(staker.user_stake, false),
+            AccountMeta::new(position_pda(&staker.user, &pool.staking_pool, position_index), false),
+            AccountMeta::new(staker.lst_account, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        &data,
+    )
+}
+
+/// Stakes into a new position at `position_index`, which has to be the staker's next one.
+pub fn stake(context: &Context, pool: &TestPool, staker: &TestStaker, amount: u64, position_index: u64) -> Pubkey {
+    context.process_and_validate_instruction(
+        &stake_instruction(pool, staker, amount, position_index, &[]),
+        &[Check::success()],
+    );
+
+    position_pda(&staker.user, &pool.staking_pool, position_index)
+}
+
+/// Queues an UpdatePoolConfig change, waits out the timelock and executes it.
+pub fn apply_config_change(context: &mut Context, pool: &TestPool, update_type: u8, value: &[u8]) {
+    let config_change = Pubkey::find_program_address(
+        &[b"pool_config_change", pool.staking_pool.as_ref(), &[update_type]],
+        &PROGRAM_ID
+    ).0;
+
+    let mut data = vec![update_type];
+    data.extend_from_slice(&pool.pool_id.to_le_bytes());
+    data.extend_from_slice(value);
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::QueuePoolConfigChange,
+            vec![
+                AccountMeta::new(pool.authority, true),
+                AccountMeta::new_readonly(pool.staking_pool, false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                AccountMeta::new(config_change, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            &data,
+        ),
+        &[Check::success()],
+    );
+
+    advance_time(context, MIN_CONFIG_CHANGE_DELAY);
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::ExecutePoolConfigChange,
+            vec![
+                AccountMeta::new(pool.authority, true),
+                AccountMeta::new(pool.staking_pool, false),
+                AccountMeta::new(config_change, false),
+            ],
+            &[],
+        ),
+        &[Check::success()],
+    );
+}
+
+/// Immediate pool pause through UpdatePoolConfig, opens EmergencyWithdraw.
+pub fn pause_pool(context: &Context, pool: &TestPool) {
+    let mut data = vec![14];
+    data.extend_from_slice(&pool.pool_id.to_le_bytes());
+    data.push(1);
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::UpdatePoolConfig,
+            vec![
+                AccountMeta::new(pool.authority, true),
+                AccountMeta::new(pool.staking_pool, false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+            &data,
+        ),
+        &[Check::success()],
+    );
+}
+
+pub fn ticket_pda(user: &Pubkey, staking_pool: &Pubkey, ticket_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"withdrawal_ticket", user.as_ref(), staking_pool.as_ref(), &ticket_id.to_le_bytes()],
+        &PROGRAM_ID
+    ).0
+}
+
+/// Burns `lst_amount` from the position into withdrawal ticket `ticket_id`, penalties go to the stake mint's treasury.
+pub fn request_unstake(
+    context: &Context,
+    pool: &TestPool,
+    staker: &TestStaker,
+    stake_position: &Pubkey,
+    lst_amount: u64,
+    ticket_id: u64,
+    check: Check,
+) -> Pubkey {
+    let config: GlobalConfig = read(context, &pool.global_config);
+    let withdrawal_ticket = ticket_pda(&staker.user, &pool.staking_pool, ticket_id);
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&pool.pool_id.to_le_bytes());
+    data.extend_from_slice(&lst_amount.to_le_bytes());
+    data.extend_from_slice(&ticket_id.to_le_bytes());
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::RequestUnstake,
+            vec![
+                AccountMeta::new(staker.user, true),
+                AccountMeta::new_readonly(pool.authority, false),
+                AccountMeta::new_readonly(pool.stake_mint, false),
+                AccountMeta::new(pool.stake_vault, false),
+                AccountMeta::new(pool.liquid_stake_mint, false),
+                AccountMeta::new_readonly(pool.global_config, false),
+                AccountMeta::new(pool.staking_pool, false),
+                AccountMeta::new(staker.user_stake, false),
+                AccountMeta::new(*stake_position, false),
+                AccountMeta::new(staker.lst_account, false),
+                AccountMeta::new(withdrawal_ticket, false),
+                AccountMeta::new(treasury_pda(&pool.stake_mint, &Pubkey::new_from_array(config.config_id)), false),
+                AccountMeta::new(pool.reward_vault, false),
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+            &data,
+        ),
+        &[check],
+    );
+
+    withdrawal_ticket
+}
+
+/// ClaimRewards on one position into `reward_account`, fees go to the reward mint's treasury.
+/// `stream_accounts` carries the (mint, vault, user token account, treasury) of every reward stream.
+pub fn claim_rewards(
+    context: &Context,
+    pool: &TestPool,
+    staker: &TestStaker,
+    stake_position: &Pubkey,
+    reward_account: &Pubkey,
+    stream_accounts: &[Pubkey],
+    check: Check,
+) {
+    let config: GlobalConfig = read(context, &pool.global_config);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(staker.user, true),
+        AccountMeta::new_readonly(pool.authority, false),
+        AccountMeta::new_readonly(pool.reward_mint, false),
+        AccountMeta::new(pool.reward_vault, false),
+        AccountMeta::new_readonly(pool.global_config, false),
+        AccountMeta::new(pool.staking_pool, false),
+        AccountMeta::new(*reward_account, false),
+        AccountMeta::new(staker.user_stake, false),
+        AccountMeta::new(*stake_position, false),
+        AccountMeta::new(treasury_pda(&pool.reward_mint, &Pubkey::new_from_array(config.config_id)), false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+    ];
+    accounts.extend(stream_accounts.iter().map(|key| AccountMeta::new(*key, false)));
+
+    context.process_and_validate_instruction(
+        &instruction(StakingInstructions::ClaimRewards, accounts, &pool.pool_id.to_le_bytes()),
+        &[check],
+    );
+}
+
+/// Data for EnableAutoCompound and UpdateAutoCompound, `max_tip_bps` is left off when `None`.
+pub fn compound_settings(pool: &TestPool, frequency_hours: u32, min_amount: u64, max_tip_bps: Option<u16>) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&pool.pool_id.to_le_bytes());
+    data.extend_from_slice(&frequency_hours.to_le_bytes());
+    data.extend_from_slice(&min_amount.to_le_bytes());
+    if let Some(max_tip_bps) = max_tip_bps {
+        data.extend_from_slice(&max_tip_bps.to_le_bytes());
+    }
+    data
+}
+
+/// EnableAutoCompound or UpdateAutoCompound on one of the staker's positions.
+pub fn auto_compound_instruction(
+    instruction_type: StakingInstructions,
+    pool: &TestPool,
+    staker: &TestStaker,
+    stake_position: &Pubkey,
+    settings: &[u8],
+) -> Instruction {
+    instruction(
+        instruction_type,
+        vec![
+            AccountMeta::new_readonly(staker.user, true),
+            AccountMeta::new(staker.user_stake, false),
+            AccountMeta::new(*stake_position, false),
+            AccountMeta::new_readonly(pool.staking_pool, false),
+        ],
+        settings,
+    )
+}
+
+/// ExecuteAutoCompound cranked by `executor` on a pool whose reward and stake mints are the same.
+pub fn execute_auto_compound(
+    context: &Context,
+    pool: &TestPool,
+    staker: &TestStaker,
+    stake_position: &Pubkey,
+    executor: &Pubkey,
+    executor_token_account: &Pubkey,
+    check: Check,
+) {
+    let config: GlobalConfig = read(context, &pool.global_config);
+
+    context.process_and_validate_instruction(
+        &instruction(
+            StakingInstructions::ExecuteAutoCompound,
+            vec![
+                AccountMeta::new_readonly(*executor, true),
+                AccountMeta::new_readonly(staker.user, false),
+                AccountMeta::new_readonly(pool.authority, false),
+                AccountMeta::new_readonly(pool.reward_mint, false),
+                AccountMeta::new_readonly(pool.stake_mint, false),
+                AccountMeta::new(pool.reward_vault, false),
+                AccountMeta::new(pool.stake_vault, false),
+                AccountMeta::new(pool.liquid_stake_mint, false),
+                AccountMeta::new_readonly(pool.global_config, false),
+                AccountMeta::new(pool.staking_pool, false),
+                AccountMeta::new(staker.user_stake, false),
+                AccountMeta::new(*stake_position, false),
+                AccountMeta::new(staker.lst_account, false),
+                AccountMeta::new(treasury_pda(&pool.reward_mint, &Pubkey::new_from_array(config.config_id)), false),
+                AccountMeta::new(*executor_token_account, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+            &pool.pool_id.to_le_bytes(),
+        ),
+        &[check],
+    );
+}
@@ -0,0 +1,96 @@
+//! Swap adapter stand-in for the staking program's Mollusk tests.
+//!
+//! Takes the staking program's adapter layout, moves `consume_bps` of the input into `sink` and pays
+//! `amount_in * rate / 1e9` out of `reserve`. Both come from a config account the test writes, so a
+//! test can play an honest adapter, a bad price or one that leaves input behind. `min_amount_out` is
+//! ignored on purpose, the staking program has to hold its own bounds.
+
+use pinocchio::{account_info::AccountInfo, instruction::Signer, program_error::ProgramError, pubkey::{self, Pubkey}, *};
+use pinocchio_token::instructions::Transfer;
+
+entrypoint!(process_instruction);
+
+/// First 8 bytes of a staking program swap call.
+pub const SWAP_DISCRIMINATOR: [u8; 8] = *b"SWAPEXIN";
+
+/// Fixed-point scale of the configured rate.
+pub const RATE_PRECISION: u128 = 1_000_000_000;
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8]
+) -> ProgramResult {
+    let [
+        swap_authority,    // Staking program's swap authority, signed by it
+        source,            // Swap input holding amount_in
+        destination,       // Pool's stake vault
+        _source_mint,      // Reward mint
+        _destination_mint, // Stake mint
+        _token_program,    // Token program
+        mock_config,       // rate (8), consume_bps (2), owned by this program
+        reserve_authority, // PDA of [b"reserve_authority"], owns reserve and sink
+        reserve,           // Stake tokens paid out
+        sink,              // Reward tokens taken in
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if instruction_data.len() < 26 || instruction_data[0..8] != SWAP_DISCRIMINATOR {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let amount_in = u64::from_le_bytes(
+        instruction_data[8..16].try_into().map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if !mock_config.is_owned_by(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let (rate, consume_bps) = {
+        let config = mock_config.try_borrow_data()?;
+
+        if config.len() < 10 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        (
+            u64::from_le_bytes(config[0..8].try_into().map_err(|_| ProgramError::InvalidAccountData)?),
+            u16::from_le_bytes(config[8..10].try_into().map_err(|_| ProgramError::InvalidAccountData)?)
+        )
+    };
+
+    let amount_taken = ((amount_in as u128) * consume_bps as u128 / 10000u128) as u64;
+    let amount_out = ((amount_in as u128) * rate as u128 / RATE_PRECISION) as u64;
+
+    if amount_taken > 0 {
+        Transfer {
+            from: source,
+            to: sink,
+            authority: swap_authority,
+            amount: amount_taken,
+        }.invoke()?;
+    }
+
+    let (reserve_authority_pda, reserve_authority_bump) = pubkey::find_program_address(&[b"reserve_authority"], program_id);
+
+    if *reserve_authority.key() != reserve_authority_pda {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if amount_out > 0 {
+        let bump_ref = &[reserve_authority_bump];
+        let seeds = seeds!(b"reserve_authority", bump_ref);
+        let signer_seeds = Signer::from(&seeds);
+
+        Transfer {
+            from: reserve,
+            to: destination,
+            authority: reserve_authority,
+            amount: amount_out,
+        }.invoke_signed(&[signer_seeds])?;
+    }
+
+    Ok(())
+}
@@ -31,6 +31,7 @@ describe('Staking Program Tests - Debug Version', function () {
     let liquidStakeMintPda: PublicKey;
     let rewardMint: PublicKey;
     let oracleConfigPda: PublicKey;
+    let rolesPda: PublicKey;
     let userTokenAccount: PublicKey;
     let userStakeAccount: PublicKey;
 
@@ -122,6 +123,12 @@ describe('Staking Program Tests - Debug Version', function () {
             programId
         );
 
+        // Admin calls check roles against this PDA, the config authority passes without one being created
+        [rolesPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("roles"), globalConfigAccountPda.toBuffer()],
+            programId
+        );
+
         console.log("Program ID:", programId.toString());
         console.log("Global Config PDA:", globalConfigAccountPda.toString());
         console.log("Treasury PDA:", treasuryAccountPda.toString());
@@ -302,14 +309,16 @@ describe('Staking Program Tests - Debug Version', function () {
         }
     });
 
-    it("Process Update Authority", async () => {
-        let newAuthority = provider.wallet.publicKey;
+    it("Process Propose Authority", async () => {
+        // Discriminator 1 (UpdateAuthority) is retired, transfers are ProposeAuthority (70) then AcceptAuthority (56).
+        // The proposal is cancelled (57) in the same transaction so the rest of the suite keeps its authority
+        let newAuthority = Keypair.generate().publicKey;
         let instructionData = Buffer.concat([
             newAuthority.toBuffer()
         ]);
 
         const finalInstructionData = Buffer.concat([
-            Buffer.from([1]),
+            Buffer.from([70]),
             instructionData
         ]);
 
@@ -322,7 +331,16 @@ describe('Staking Program Tests - Debug Version', function () {
             data: finalInstructionData
         });
 
-        const transaction = new Transaction().add(instruction);
+        const cancelInstruction = new TransactionInstruction({
+            programId: programId,
+            keys: [
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },    // authority
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: true },       // global_config_account
+            ],
+            data: Buffer.from([57])
+        });
+
+        const transaction = new Transaction().add(instruction, cancelInstruction);
 
         const { blockhash } = await connection.getLatestBlockhash();
         transaction.recentBlockhash = blockhash;
@@ -358,8 +376,9 @@ describe('Staking Program Tests - Debug Version', function () {
         const instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },     // authority
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },     // fee_manager
                 { pubkey: globalConfigAccountPda, isSigner: false, isWritable: true },       // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                    // roles_account
             ],
             data: finalInstructionData
         });
@@ -481,6 +500,7 @@ describe('Staking Program Tests - Debug Version', function () {
                 { pubkey: priceFeedAccount, isSigner: false, isWritable: false },             // price_feed_account
                 { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },      // system_program
                 { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },             // token_program
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                     // roles_account
             ],
             data: finalInstructionData
         });
@@ -523,7 +543,7 @@ describe('Staking Program Tests - Debug Version', function () {
         }
     });
 
-    it("Update Pool Config", async () => {
+    it("Queue Pool Config Change", async () => {
         const UPDATE_TYPE_DISCRIMINATOR = 0; // RewardRatePerSecond
         const NEW_REWARD_RATE = 200; // Must be > 0 for discriminator 0
 
@@ -544,7 +564,7 @@ describe('Staking Program Tests - Debug Version', function () {
         ]);
 
         const finalInstructionData = Buffer.concat([
-            Buffer.from([4]), // Instruction discriminator for UpdatePoolConfig
+            Buffer.from([60]), // Instruction discriminator for QueuePoolConfigChange, reward rate changes are timelocked
             instructionData
         ]);
 
@@ -557,12 +577,19 @@ describe('Staking Program Tests - Debug Version', function () {
 
         const priceFeedAccount = Keypair.generate().publicKey;
 
+        const [configChangePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("pool_config_change"), stakingPoolPda.toBuffer(), Buffer.from([UPDATE_TYPE_DISCRIMINATOR])],
+            programId
+        );
+
         const instruction = new TransactionInstruction({
             programId: programId,
             keys: [
                 { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },    // authority
-                { pubkey: stakingPoolPda, isSigner: false, isWritable: true },              // staking_pool_account
+                { pubkey: stakingPoolPda, isSigner: false, isWritable: false },             // staking_pool_account
                 { pubkey: priceFeedAccount, isSigner: false, isWritable: false },           // price_feed_account
+                { pubkey: configChangePda, isSigner: false, isWritable: true },             // config_change_account
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },    // system_program
             ],
             data: finalInstructionData
         });
@@ -657,10 +684,12 @@ describe('Staking Program Tests - Debug Version', function () {
         const instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },
-                { pubkey: oracleConfigPda, isSigner: false, isWritable: true }, 
-                { pubkey: priceFeedAccount, isSigner: false, isWritable: false },  
-                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },     
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },   // oracle_authority
+                { pubkey: oracleConfigPda, isSigner: false, isWritable: true },            // oracle_config_account
+                { pubkey: priceFeedAccount, isSigner: false, isWritable: false },          // price_feed_account
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },   // system_program
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: false },    // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                  // roles_account
             ],
             data: finalInstructionData
         });
@@ -707,8 +736,10 @@ describe('Staking Program Tests - Debug Version', function () {
         let instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },  // oracle_authority
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: true },  // oracle_manager
                 { pubkey: oracleConfigPda, isSigner: false, isWritable: true },          // oracle_config_account
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: false },  // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                // roles_account
             ],
             data: finalInstructionData
         });
@@ -783,8 +814,10 @@ describe('Staking Program Tests - Debug Version', function () {
         let instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: false, isWritable: false }, 
-                { pubkey: stakingPoolPda, isSigner: false, isWritable: true }, 
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },  // guardian
+                { pubkey: stakingPoolPda, isSigner: false, isWritable: true },            // staking_pool_account
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: false },   // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                 // roles_account
             ],
             data: finalInstructionData
         });
@@ -825,8 +858,10 @@ describe('Staking Program Tests - Debug Version', function () {
         let instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: false, isWritable: false }, 
-                { pubkey: stakingPoolPda, isSigner: false, isWritable: true }, 
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },  // guardian
+                { pubkey: stakingPoolPda, isSigner: false, isWritable: true },            // staking_pool_account
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: false },   // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                 // roles_account
             ],
             data: finalInstructionData
         });
@@ -868,8 +903,8 @@ describe('Staking Program Tests - Debug Version', function () {
         let instruction = new TransactionInstruction({
             programId: programId,
             keys: [
-                { pubkey: provider.wallet.publicKey, isSigner: false, isWritable: false }, 
-                { pubkey: stakingPoolPda, isSigner: false, isWritable: true }, 
+                { pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false },  // authority
+                { pubkey: stakingPoolPda, isSigner: false, isWritable: true },            // staking_pool_account
             ],
             data: finalInstructionData
         });
@@ -965,8 +1000,10 @@ describe('Staking Program Tests - Debug Version', function () {
                 { pubkey: authorityRewardTokenAccount, isSigner: false, isWritable: true }, 
                 { pubkey: rewardMint, isSigner: false, isWritable: false },                        
                 { pubkey: rewardTokenVaultPda, isSigner: false, isWritable: true },                
-                { pubkey: stakingPoolPda, isSigner: false, isWritable: false },                            
+                { pubkey: stakingPoolPda, isSigner: false, isWritable: true },                     // staking_pool_account
                 { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },                  
+                { pubkey: globalConfigAccountPda, isSigner: false, isWritable: false },            // global_config_account
+                { pubkey: rolesPda, isSigner: false, isWritable: false },                          // roles_account
             ],
             data: finalInstructionData
         });
@@ -1018,6 +1055,15 @@ describe('Staking Program Tests - Debug Version', function () {
             instructionData
         ]);
 
+        // First stake from a fresh user stake account lands at position index 0
+        const positionIndexBuffer = Buffer.alloc(8);
+        positionIndexBuffer.writeBigUInt64LE(BigInt(0));
+
+        const [stakePositionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("stake_position"), provider.wallet.publicKey.toBuffer(), stakingPoolPda.toBuffer(), positionIndexBuffer],
+            programId
+        );
+
         let instruction = new TransactionInstruction({
             programId: programId,
             keys: [
@@ -1031,7 +1077,9 @@ describe('Staking Program Tests - Debug Version', function () {
                 { pubkey: stakingPoolPda, isSigner: false, isWritable: true },               // staking_pool_account
                 { pubkey: userTokenAccount, isSigner: false, isWritable: true },             // user_token_account
                 { pubkey: userStakeAccount, isSigner: false, isWritable: true },             // user_stake_account
+                { pubkey: stakePositionPda, isSigner: false, isWritable: true },             // stake_position_account
                 { pubkey: userLstTokenAccount, isSigner: false, isWritable: true },          // user_lst_token_account
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },     // system_program
                 { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },            // token_program
             ],
             data: finalInstructionData